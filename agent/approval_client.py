@@ -13,6 +13,10 @@ Architecture:
 - Rust returns approval decision
 - Client returns boolean to Python agent
 
+Diff Cards are sent in the format described by the published JSON Schema
+(docs/schemas/diff-card.schema.json) and validated against it when the
+optional `jsonschema` package is installed.
+
 Features:
 - Works over SSH (no GUI dependencies)
 - Timeout mechanism (auto-reject after 5 minutes)
@@ -41,6 +45,67 @@ class ApprovalDecision(Enum):
     CANCELLED = "cancelled"
 
 
+# Diff Card schema version produced by this client
+SCHEMA_VERSION = 2
+
+# Published Diff Card JSON Schema (generated by `luminaguard schema`)
+SCHEMA_PATH = (
+    Path(__file__).parent.parent / "docs" / "schemas" / "diff-card.schema.json"
+)
+
+# Tool name keywords mapped to orchestrator action types (Red actions only;
+# Green actions never reach the approval UI)
+ACTION_TYPE_KEYWORDS = [
+    ("delete", "DeleteFile"),
+    ("remove", "DeleteFile"),
+    ("create", "CreateFile"),
+    ("write", "EditFile"),
+    ("edit", "EditFile"),
+    ("send", "SendEmail"),
+    ("transfer", "TransferAsset"),
+    ("execute", "ExecuteCommand"),
+    ("run", "RunScript"),
+    ("deploy", "Deploy"),
+    ("install", "Install"),
+    ("commit", "Commit"),
+    ("push", "Push"),
+    ("publish", "Publish"),
+]
+
+
+def action_type_for(tool_name: str) -> str:
+    """Map a tool name to an orchestrator action type (e.g. "EditFile")"""
+    name = tool_name.lower()
+    for keyword, action_type in ACTION_TYPE_KEYWORDS:
+        if keyword in name:
+            return action_type
+    return "Unknown"
+
+
+def validate_diff_card(data: Dict[str, Any]) -> None:
+    """
+    Validate a serialized Diff Card against the published JSON Schema.
+
+    Skipped if `jsonschema` is not installed or the schema file is missing
+    (e.g. the agent was installed without the repository's docs).
+
+    Raises:
+        ValueError: If the card does not match the schema
+    """
+    try:
+        import jsonschema
+    except ImportError:
+        return
+    if not SCHEMA_PATH.exists():
+        return
+
+    schema = json.loads(SCHEMA_PATH.read_text())
+    try:
+        jsonschema.validate(data, schema)
+    except jsonschema.ValidationError as e:
+        raise ValueError(f"Diff Card does not match the schema: {e.message}") from e
+
+
 @dataclass
 class Change:
     """A single change within an action"""
@@ -49,31 +114,62 @@ class Change:
     summary: str
     details: Dict[str, Any]
 
+    def to_dict(self) -> Dict[str, Any]:
+        """Convert to the schema's change format (e.g. {"FileEdit": {...}})"""
+        path = self.details.get("path", "")
+        if self.change_type == "FileCreate":
+            return {
+                "FileCreate": {
+                    "path": path,
+                    "content_preview": self.details.get("content", ""),
+                }
+            }
+        if self.change_type == "FileEdit":
+            # Full content: the orchestrator computes the line diff and keeps
+            # only a preview
+            return {
+                "FileEdit": {
+                    "path": path,
+                    "before": self.details.get("before", ""),
+                    "after": self.details.get("after", ""),
+                }
+            }
+        if self.change_type == "FileDelete":
+            return {
+                "FileDelete": {
+                    "path": path,
+                    "size_bytes": self.details.get("size_bytes", 0),
+                }
+            }
+        if self.change_type == "CommandExec":
+            return {
+                "CommandExec": {
+                    "command": self.details.get("command", ""),
+                    "args": [str(a) for a in self.details.get("args", [])],
+                    "env_vars": None,
+                }
+            }
+        return {"Custom": {"description": self.summary}}
+
 
 @dataclass
 class DiffCard:
     """Diff card showing exactly what will change"""
 
-    action_type: str
+    action_type: str  # tool name
     description: str
-    risk_level: str
+    risk_level: str  # lowercase (e.g. "high")
     changes: List[Change]
     timestamp: str
 
     def to_dict(self) -> Dict[str, Any]:
-        """Convert to dictionary for JSON serialization"""
+        """Convert to dictionary for JSON serialization (schema format)"""
         return {
-            "action_type": self.action_type,
+            "schema_version": SCHEMA_VERSION,
+            "action_type": action_type_for(self.action_type),
             "description": self.description,
-            "risk_level": self.risk_level,
-            "changes": [
-                {
-                    "change_type": c.change_type,
-                    "summary": c.summary,
-                    "details": c.details,
-                }
-                for c in self.changes
-            ],
+            "risk_level": self.risk_level.capitalize(),
+            "changes": [c.to_dict() for c in self.changes],
             "timestamp": self.timestamp,
         }
 
@@ -198,7 +294,9 @@ class ApprovalClient:
                     details={
                         "path": path,
                         "before": "",
-                        "after": content[:500],  # First 500 chars
+                        # Full content: the orchestrator computes the line diff
+                        # and keeps only a preview
+                        "after": content,
                     },
                 )
             )
@@ -269,9 +367,12 @@ class ApprovalClient:
         Raises:
             RuntimeError: If orchestrator fails
         """
+        data = diff_card.to_dict()
+        validate_diff_card(data)
+
         # Create temporary file for diff card JSON
         with tempfile.NamedTemporaryFile(mode="w", suffix=".json", delete=False) as f:
-            json.dump(diff_card.to_dict(), f, indent=2)
+            json.dump(data, f, indent=2)
             temp_path = f.name
 
         try:
@@ -458,4 +559,5 @@ __all__ = [
     "DiffCard",
     "ApprovalClient",
     "present_diff_card",
+    "validate_diff_card",
 ]
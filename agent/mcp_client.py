@@ -39,9 +39,39 @@ from enum import Enum
 
 
 class McpError(Exception):
-    """MCP protocol or connection error"""
+    """
+    MCP protocol or connection error
+
+    For JSON-RPC errors returned by the orchestrator, ``code`` holds the
+    numeric error code and ``data`` the machine-readable error data. ``kind``
+    names the error category (e.g. "tool_error", "approval_denied",
+    "budget_exceeded"); see docs/schemas/agent-rpc-error.schema.json.
+    """
 
-    pass
+    def __init__(
+        self,
+        message: str,
+        code: Optional[int] = None,
+        data: Optional[Dict[str, Any]] = None,
+    ):
+        super().__init__(message)
+        self.code = code
+        self.data = data or {}
+
+    @property
+    def kind(self) -> Optional[str]:
+        """Error category from the orchestrator, if known"""
+        return self.data.get("kind")
+
+    @property
+    def retryable(self) -> bool:
+        """Whether retrying the same request later may succeed"""
+        return bool(self.data.get("retryable", False))
+
+    @property
+    def approval_id(self) -> Optional[str]:
+        """Deferred approval to poll, for "approval_pending" errors"""
+        return self.data.get("approval_id")
 
 
 class McpState(Enum):
@@ -138,6 +168,10 @@ class McpClient:
         # Request/Response tracking
         self._request_id = 0
 
+        # JSON-RPC notifications received while waiting for responses
+        # (e.g. "notifications/approval_resolved" for deferred tool calls)
+        self.notifications: List[Dict[str, Any]] = []
+
     @property
     def state(self) -> McpState:
         """Get current client state"""
@@ -244,24 +278,40 @@ class McpClient:
         except (BrokenPipeError, OSError) as e:
             raise McpError(f"Failed to send request: {e}") from e
 
-        # Read response from stdout
-        try:
-            response_line = self._process.stdout.readline()
-            if not response_line:
-                raise McpError("No response from orchestrator (process died?)")
-        except OSError as e:
-            raise McpError(f"Failed to read response: {e}") from e
+        # Read response from stdout, collecting notifications sent before it
+        while True:
+            try:
+                response_line = self._process.stdout.readline()
+                if not response_line:
+                    raise McpError("No response from orchestrator (process died?)")
+            except OSError as e:
+                raise McpError(f"Failed to read response: {e}") from e
 
-        # Parse JSON-RPC 2.0 response
-        try:
-            response = json.loads(response_line)
-        except json.JSONDecodeError as e:
-            raise McpError(f"Invalid JSON response: {e}") from e
+            # Parse JSON-RPC 2.0 response
+            try:
+                response = json.loads(response_line)
+            except json.JSONDecodeError as e:
+                raise McpError(f"Invalid JSON response: {e}") from e
+
+            is_notification = (
+                isinstance(response, dict)
+                and "id" not in response
+                and "method" in response
+            )
+            if is_notification:
+                self.notifications.append(response)
+                continue
+            break
 
         # Check for JSON-RPC error
         if "error" in response:
             error = response["error"]
-            raise McpError(f"MCP error {error.get('code')}: {error.get('message')}")
+            data = error.get("data")
+            raise McpError(
+                f"MCP error {error.get('code')}: {error.get('message')}",
+                code=error.get("code"),
+                data=data if isinstance(data, dict) else None,
+            )
 
         return response
 
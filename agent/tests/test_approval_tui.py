@@ -140,10 +140,41 @@ class TestApprovalClient:
 
         data = diff_card.to_dict()
 
-        assert data["action_type"] == "write_file"
+        assert data["schema_version"] == 2
+        assert data["action_type"] == "EditFile"
         assert data["description"] == "Write to test file"
-        assert data["risk_level"] == "high"
+        assert data["risk_level"] == "High"
         assert len(data["changes"]) == 1
+        assert data["changes"][0]["FileEdit"]["path"] == "/tmp/test.txt"
+
+    def test_diff_card_matches_schema(self):
+        """Test that serialized Diff Cards match the orchestrator's JSON Schema"""
+        jsonschema = pytest.importorskip("jsonschema")
+        from approval_client import ApprovalClient, SCHEMA_PATH
+        from loop import ToolCall, ActionKind
+        import json
+
+        schema = json.loads(SCHEMA_PATH.read_text())
+        client = ApprovalClient()
+        for name, arguments in [
+            ("write_file", {"path": "/tmp/a.txt", "content": "hello"}),
+            ("delete_file", {"path": "/tmp/a.txt"}),
+            ("read_file", {"path": "/tmp/a.txt"}),
+            ("execute_command", {"args": ["ls", "-la"]}),
+            ("send_email", {"to": "bob@example.com"}),
+        ]:
+            action = ToolCall(
+                name=name, arguments=arguments, action_kind=ActionKind.RED
+            )
+            jsonschema.validate(client._create_diff_card(action).to_dict(), schema)
+
+    def test_invalid_diff_card_rejected(self):
+        """Test that cards not matching the schema are rejected"""
+        pytest.importorskip("jsonschema")
+        from approval_client import validate_diff_card
+
+        with pytest.raises(ValueError):
+            validate_diff_card({"schema_version": 2, "action_type": "write_file"})
 
     def test_green_action_auto_approves(self):
         """Test that Green actions auto-approve without prompting"""
@@ -547,6 +547,63 @@ class TestMcpClientSendRequest:
         with pytest.raises(McpError, match="MCP error -32600"):
             client._send_request("test/method")
 
+    @patch("subprocess.Popen")
+    def test_send_request_exposes_error_kind(self, mock_popen):
+        """Test that structured error data is exposed on McpError"""
+        mock_process = MagicMock()
+        mock_process.stdin = MagicMock()
+        mock_process.stdout = MagicMock()
+        mock_process.stdout.readline = MagicMock(
+            return_value='{"jsonrpc":"2.0","id":1,"error":{"code":-32010,"message":"Budget exceeded","data":{"kind":"budget_exceeded","retryable":false,"limit":"tool_calls","max":5}}}\n'
+        )
+        mock_process.stderr = MagicMock()
+        mock_popen.return_value = mock_process
+
+        client = McpClient("test", ["echo", "test"])
+        client._process = mock_process
+        client._state = McpState.INITIALIZED
+
+        with pytest.raises(McpError) as exc_info:
+            client._send_request("tools/call")
+
+        assert exc_info.value.code == -32010
+        assert exc_info.value.kind == "budget_exceeded"
+        assert exc_info.value.data["limit"] == "tool_calls"
+        assert not exc_info.value.retryable
+
+    @patch("subprocess.Popen")
+    def test_send_request_collects_notifications(self, mock_popen):
+        """Test that notifications before the response are collected"""
+        mock_process = MagicMock()
+        mock_process.stdin = MagicMock()
+        mock_process.stdout = MagicMock()
+        mock_process.stdout.readline = MagicMock(
+            side_effect=[
+                '{"jsonrpc":"2.0","method":"notifications/approval_resolved","params":{"approvalId":"abc","status":"approved"}}\n',
+                '{"jsonrpc":"2.0","id":1,"error":{"code":-32006,"message":"Action deferred","data":{"kind":"approval_pending","retryable":true,"approval_id":"def","expires_at":"2026-01-01T00:00:00Z"}}}\n',
+            ]
+        )
+        mock_process.stderr = MagicMock()
+        mock_popen.return_value = mock_process
+
+        client = McpClient("test", ["echo", "test"])
+        client._process = mock_process
+        client._state = McpState.INITIALIZED
+
+        with pytest.raises(McpError) as exc_info:
+            client._send_request("tools/call")
+
+        assert exc_info.value.kind == "approval_pending"
+        assert exc_info.value.retryable
+        assert exc_info.value.approval_id == "def"
+        assert client.notifications == [
+            {
+                "jsonrpc": "2.0",
+                "method": "notifications/approval_resolved",
+                "params": {"approvalId": "abc", "status": "approved"},
+            }
+        ]
+
 
 class TestMcpClientToolOperations:
     """Test MCP client tool operations (list_tools, call_tool)"""
@@ -842,3 +899,5 @@ class TestMcpError:
         error = McpError("Test message")
         assert str(error) == "Test message"
         assert "Test message" in error.args[0]
+        assert error.code is None
+        assert error.kind is None
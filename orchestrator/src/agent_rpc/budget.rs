@@ -0,0 +1,313 @@
+//! Per-Session Tool-Call Budgets
+//!
+//! A [`SessionBudget`] bounds how much an agent session may consume:
+//! - Total tool calls
+//! - Red-action tool calls (each one costs approver attention)
+//! - Wall-clock time since the session started
+//! - Bytes transferred to/from each MCP server
+//!
+//! The budget is checked on every `tools/call` request. Once a limit is
+//! reached, further calls fail with a [`BudgetExceeded`] error until the
+//! session is restarted. All limits are optional; `None` means unlimited.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Budget limits for an agent session
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BudgetLimits {
+    /// Maximum number of tool calls
+    pub max_tool_calls: Option<u64>,
+
+    /// Maximum number of red-action (approval-requiring) tool calls
+    pub max_red_actions: Option<u64>,
+
+    /// Maximum session duration in seconds
+    pub max_wall_clock_secs: Option<u64>,
+
+    /// Maximum bytes transferred per MCP server (arguments + results)
+    pub max_bytes_per_server: Option<u64>,
+}
+
+/// Which budget limit was exhausted
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "limit", rename_all = "snake_case")]
+pub enum BudgetExceeded {
+    /// Tool call limit reached
+    ToolCalls { max: u64 },
+
+    /// Red-action limit reached
+    RedActions { max: u64 },
+
+    /// Session ran longer than allowed
+    WallClock { max_secs: u64, elapsed_secs: u64 },
+
+    /// Byte budget for a server exhausted
+    Bytes { server: String, max: u64, used: u64 },
+}
+
+impl fmt::Display for BudgetExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BudgetExceeded::ToolCalls { max } => {
+                write!(f, "session tool call limit of {} reached", max)
+            }
+            BudgetExceeded::RedActions { max } => {
+                write!(f, "session red-action limit of {} reached", max)
+            }
+            BudgetExceeded::WallClock {
+                max_secs,
+                elapsed_secs,
+            } => write!(
+                f,
+                "session wall-clock limit of {}s exceeded ({}s elapsed)",
+                max_secs, elapsed_secs
+            ),
+            BudgetExceeded::Bytes { server, max, used } => write!(
+                f,
+                "byte budget for server '{}' exhausted ({} of {} bytes used)",
+                server, used, max
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BudgetExceeded {}
+
+/// Snapshot of budget counters (returned by `session/status`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BudgetStatus {
+    /// Configured limits
+    pub limits: BudgetLimits,
+
+    /// Tool calls made so far
+    pub tool_calls: u64,
+
+    /// Red-action tool calls made so far
+    pub red_actions: u64,
+
+    /// Seconds since the session started
+    pub elapsed_secs: u64,
+
+    /// Bytes transferred per server
+    pub bytes_by_server: HashMap<String, u64>,
+}
+
+/// Budget tracking for a single agent session
+#[derive(Debug, Clone)]
+pub struct SessionBudget {
+    /// Configured limits
+    limits: BudgetLimits,
+
+    /// When the session started
+    started_at: Instant,
+
+    /// Tool calls made so far
+    tool_calls: u64,
+
+    /// Red-action tool calls made so far
+    red_actions: u64,
+
+    /// Bytes transferred per server
+    bytes_by_server: HashMap<String, u64>,
+}
+
+impl SessionBudget {
+    /// Start a new session budget
+    pub fn new(limits: BudgetLimits) -> Self {
+        Self {
+            limits,
+            started_at: Instant::now(),
+            tool_calls: 0,
+            red_actions: 0,
+            bytes_by_server: HashMap::new(),
+        }
+    }
+
+    /// Time elapsed since the session started
+    pub fn elapsed(&self) -> Duration {
+        self.started_at.elapsed()
+    }
+
+    /// Check the budget for a new tool call and count it
+    ///
+    /// Counters are only incremented if the call is within budget.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BudgetExceeded`] describing the first exhausted limit.
+    pub fn check_call(&mut self, server: &str, is_red_action: bool) -> Result<(), BudgetExceeded> {
+        if let Some(max_secs) = self.limits.max_wall_clock_secs {
+            let elapsed_secs = self.elapsed().as_secs();
+            if elapsed_secs >= max_secs {
+                return Err(BudgetExceeded::WallClock {
+                    max_secs,
+                    elapsed_secs,
+                });
+            }
+        }
+
+        if let Some(max) = self.limits.max_tool_calls {
+            if self.tool_calls >= max {
+                return Err(BudgetExceeded::ToolCalls { max });
+            }
+        }
+
+        if is_red_action {
+            if let Some(max) = self.limits.max_red_actions {
+                if self.red_actions >= max {
+                    return Err(BudgetExceeded::RedActions { max });
+                }
+            }
+        }
+
+        if let Some(max) = self.limits.max_bytes_per_server {
+            let used = self.bytes_used(server);
+            if used >= max {
+                return Err(BudgetExceeded::Bytes {
+                    server: server.to_string(),
+                    max,
+                    used,
+                });
+            }
+        }
+
+        self.tool_calls += 1;
+        if is_red_action {
+            self.red_actions += 1;
+        }
+
+        Ok(())
+    }
+
+    /// Record bytes transferred to/from a server
+    pub fn record_bytes(&mut self, server: &str, bytes: u64) {
+        *self.bytes_by_server.entry(server.to_string()).or_insert(0) += bytes;
+    }
+
+    /// Bytes transferred to/from a server so far
+    pub fn bytes_used(&self, server: &str) -> u64 {
+        self.bytes_by_server.get(server).copied().unwrap_or(0)
+    }
+
+    /// Snapshot of the current counters
+    pub fn status(&self) -> BudgetStatus {
+        BudgetStatus {
+            limits: self.limits.clone(),
+            tool_calls: self.tool_calls,
+            red_actions: self.red_actions,
+            elapsed_secs: self.elapsed().as_secs(),
+            bytes_by_server: self.bytes_by_server.clone(),
+        }
+    }
+}
+
+impl Default for SessionBudget {
+    fn default() -> Self {
+        Self::new(BudgetLimits::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unlimited_budget() {
+        let mut budget = SessionBudget::default();
+        for _ in 0..1000 {
+            assert!(budget.check_call("fs", true).is_ok());
+        }
+        budget.record_bytes("fs", u64::MAX / 2);
+        assert!(budget.check_call("fs", false).is_ok());
+    }
+
+    #[test]
+    fn test_tool_call_limit() {
+        let mut budget = SessionBudget::new(BudgetLimits {
+            max_tool_calls: Some(2),
+            ..BudgetLimits::default()
+        });
+
+        assert!(budget.check_call("fs", false).is_ok());
+        assert!(budget.check_call("fs", false).is_ok());
+        assert_eq!(
+            budget.check_call("fs", false),
+            Err(BudgetExceeded::ToolCalls { max: 2 })
+        );
+        assert_eq!(budget.status().tool_calls, 2);
+    }
+
+    #[test]
+    fn test_red_action_limit_only_counts_red() {
+        let mut budget = SessionBudget::new(BudgetLimits {
+            max_red_actions: Some(1),
+            ..BudgetLimits::default()
+        });
+
+        assert!(budget.check_call("fs", true).is_ok());
+        assert!(budget.check_call("fs", false).is_ok());
+        assert_eq!(
+            budget.check_call("fs", true),
+            Err(BudgetExceeded::RedActions { max: 1 })
+        );
+        assert!(budget.check_call("fs", false).is_ok());
+
+        let status = budget.status();
+        assert_eq!(status.red_actions, 1);
+        assert_eq!(status.tool_calls, 3);
+    }
+
+    #[test]
+    fn test_wall_clock_limit() {
+        let mut budget = SessionBudget::new(BudgetLimits {
+            max_wall_clock_secs: Some(0),
+            ..BudgetLimits::default()
+        });
+
+        assert!(matches!(
+            budget.check_call("fs", false),
+            Err(BudgetExceeded::WallClock { max_secs: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn test_bytes_per_server() {
+        let mut budget = SessionBudget::new(BudgetLimits {
+            max_bytes_per_server: Some(100),
+            ..BudgetLimits::default()
+        });
+
+        budget.record_bytes("fs", 60);
+        assert!(budget.check_call("fs", false).is_ok());
+        budget.record_bytes("fs", 60);
+
+        let err = budget.check_call("fs", false).unwrap_err();
+        assert_eq!(
+            err,
+            BudgetExceeded::Bytes {
+                server: "fs".to_string(),
+                max: 100,
+                used: 120
+            }
+        );
+        // Other servers have their own budget
+        assert!(budget.check_call("github", false).is_ok());
+    }
+
+    #[test]
+    fn test_budget_exceeded_display() {
+        let err = BudgetExceeded::ToolCalls { max: 5 };
+        assert_eq!(err.to_string(), "session tool call limit of 5 reached");
+    }
+
+    #[test]
+    fn test_limits_deserialize_partial() {
+        let limits: BudgetLimits = serde_json::from_str(r#"{"max_tool_calls": 50}"#).unwrap();
+        assert_eq!(limits.max_tool_calls, Some(50));
+        assert_eq!(limits.max_red_actions, None);
+    }
+}
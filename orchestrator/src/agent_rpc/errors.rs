@@ -0,0 +1,438 @@
+//! Agent RPC Error Taxonomy
+//!
+//! Every error returned by the agent RPC server carries a stable numeric
+//! code and a machine-readable `data` object whose `kind` field names the
+//! error category, so agents can branch on failures without parsing
+//! messages.
+//!
+//! | Code   | Kind                     | Meaning                                      |
+//! |--------|--------------------------|----------------------------------------------|
+//! | -32700 | `parse_error`            | Request line is not valid JSON               |
+//! | -32600 | `invalid_request`        | Not a valid JSON-RPC 2.0 request             |
+//! | -32601 | `method_not_found`       | Unknown method                               |
+//! | -32602 | `invalid_params`         | Missing or malformed parameters              |
+//! | -32603 | `internal_error`         | Unexpected orchestrator failure              |
+//! | -32001 | `mcp_server_unavailable` | MCP server not connected or not responding   |
+//! | -32002 | `tool_error`             | MCP server returned an error for the tool    |
+//! | -32003 | `approval_denied`        | Action denied (by a human or by policy)      |
+//! | -32004 | `approval_timeout`       | No approval decision before the deadline     |
+//! | -32005 | `sandbox_failure`        | The sandbox (VM) failed to run the action    |
+//! | -32006 | `approval_pending`       | Action deferred; retry or poll for decision  |
+//! | -32010 | `budget_exceeded`        | A session budget limit was reached           |
+//!
+//! The same table is mirrored in `docs/schemas/agent-rpc-error.schema.json`.
+//! Codes are part of the agent protocol: never renumber an existing kind.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fmt;
+
+use super::budget::BudgetExceeded;
+use crate::mcp::{McpError, PolicyViolation};
+
+/// Category of an agent RPC error
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// Request line is not valid JSON
+    ParseError,
+    /// Not a valid JSON-RPC 2.0 request
+    InvalidRequest,
+    /// Unknown method
+    MethodNotFound,
+    /// Missing or malformed parameters
+    InvalidParams,
+    /// Unexpected orchestrator failure
+    InternalError,
+    /// MCP server not connected or not responding
+    McpServerUnavailable,
+    /// MCP server returned an error for the tool call
+    ToolError,
+    /// Action denied by an approver or by policy
+    ApprovalDenied,
+    /// No approval decision before the deadline
+    ApprovalTimeout,
+    /// The sandbox failed to run the action
+    SandboxFailure,
+    /// Action deferred for a later decision
+    ApprovalPending,
+    /// A session budget limit was reached
+    BudgetExceeded,
+}
+
+impl ErrorKind {
+    /// All error kinds, in code-table order
+    pub const ALL: [ErrorKind; 12] = [
+        ErrorKind::ParseError,
+        ErrorKind::InvalidRequest,
+        ErrorKind::MethodNotFound,
+        ErrorKind::InvalidParams,
+        ErrorKind::InternalError,
+        ErrorKind::McpServerUnavailable,
+        ErrorKind::ToolError,
+        ErrorKind::ApprovalDenied,
+        ErrorKind::ApprovalTimeout,
+        ErrorKind::SandboxFailure,
+        ErrorKind::ApprovalPending,
+        ErrorKind::BudgetExceeded,
+    ];
+
+    /// Stable JSON-RPC error code
+    pub fn code(self) -> i32 {
+        match self {
+            ErrorKind::ParseError => -32700,
+            ErrorKind::InvalidRequest => -32600,
+            ErrorKind::MethodNotFound => -32601,
+            ErrorKind::InvalidParams => -32602,
+            ErrorKind::InternalError => -32603,
+            ErrorKind::McpServerUnavailable => -32001,
+            ErrorKind::ToolError => -32002,
+            ErrorKind::ApprovalDenied => -32003,
+            ErrorKind::ApprovalTimeout => -32004,
+            ErrorKind::SandboxFailure => -32005,
+            ErrorKind::ApprovalPending => -32006,
+            ErrorKind::BudgetExceeded => -32010,
+        }
+    }
+
+    /// Look up a kind by its code
+    pub fn from_code(code: i32) -> Option<Self> {
+        Self::ALL.into_iter().find(|k| k.code() == code)
+    }
+
+    /// Name used in the `kind` field of error data
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorKind::ParseError => "parse_error",
+            ErrorKind::InvalidRequest => "invalid_request",
+            ErrorKind::MethodNotFound => "method_not_found",
+            ErrorKind::InvalidParams => "invalid_params",
+            ErrorKind::InternalError => "internal_error",
+            ErrorKind::McpServerUnavailable => "mcp_server_unavailable",
+            ErrorKind::ToolError => "tool_error",
+            ErrorKind::ApprovalDenied => "approval_denied",
+            ErrorKind::ApprovalTimeout => "approval_timeout",
+            ErrorKind::SandboxFailure => "sandbox_failure",
+            ErrorKind::ApprovalPending => "approval_pending",
+            ErrorKind::BudgetExceeded => "budget_exceeded",
+        }
+    }
+
+    /// Whether retrying the same request later may succeed
+    pub fn is_retryable(self) -> bool {
+        matches!(
+            self,
+            ErrorKind::McpServerUnavailable
+                | ErrorKind::ApprovalTimeout
+                | ErrorKind::SandboxFailure
+                | ErrorKind::ApprovalPending
+        )
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A classified agent RPC error
+///
+/// Handlers return this (wrapped in `anyhow::Error`) to control the code and
+/// data sent to the agent. Unclassified errors become `internal_error`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RpcError {
+    /// Error category
+    pub kind: ErrorKind,
+    /// Human-readable message
+    pub message: String,
+    /// Extra machine-readable fields merged into `data`
+    pub details: serde_json::Map<String, Value>,
+}
+
+impl RpcError {
+    /// Create an error without details
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+            details: serde_json::Map::new(),
+        }
+    }
+
+    /// Attach a detail field
+    pub fn with_detail(mut self, key: &str, value: impl Into<Value>) -> Self {
+        self.details.insert(key.to_string(), value.into());
+        self
+    }
+
+    /// Request line is not valid JSON
+    pub fn parse_error(msg: impl fmt::Display) -> Self {
+        Self::new(ErrorKind::ParseError, format!("Parse error: {}", msg))
+    }
+
+    /// Not a valid JSON-RPC 2.0 request
+    pub fn invalid_request(msg: impl fmt::Display) -> Self {
+        Self::new(
+            ErrorKind::InvalidRequest,
+            format!("Invalid request: {}", msg),
+        )
+    }
+
+    /// Unknown method
+    pub fn method_not_found(method: &str) -> Self {
+        Self::new(
+            ErrorKind::MethodNotFound,
+            format!("Method not found: {}", method),
+        )
+        .with_detail("method", method)
+    }
+
+    /// Missing or malformed parameter
+    pub fn invalid_params(param: &str, msg: impl fmt::Display) -> Self {
+        Self::new(ErrorKind::InvalidParams, format!("Invalid params: {}", msg))
+            .with_detail("param", param)
+    }
+
+    /// Unexpected orchestrator failure
+    pub fn internal_error(msg: impl fmt::Display) -> Self {
+        Self::new(ErrorKind::InternalError, format!("Internal error: {}", msg))
+    }
+
+    /// MCP server not connected or not responding
+    pub fn mcp_server_unavailable(server: &str, msg: impl fmt::Display) -> Self {
+        Self::new(
+            ErrorKind::McpServerUnavailable,
+            format!("MCP server '{}' unavailable: {}", server, msg),
+        )
+        .with_detail("server", server)
+    }
+
+    /// MCP server returned an error for a tool call
+    pub fn tool_error(tool: &str, error: &McpError) -> Self {
+        let mut err = Self::new(
+            ErrorKind::ToolError,
+            format!("Tool '{}' failed: {}", tool, error.message),
+        )
+        .with_detail("tool", tool)
+        .with_detail("mcp_code", error.code);
+        if let Some(data) = &error.data {
+            err = err.with_detail("mcp_data", data.clone());
+        }
+        err
+    }
+
+    /// Action denied; `source` is who denied it (`"policy"`, `"approver"`)
+    pub fn approval_denied(source: &str, reason: impl fmt::Display) -> Self {
+        let reason = reason.to_string();
+        Self::new(
+            ErrorKind::ApprovalDenied,
+            format!("Action denied by {}: {}", source, reason),
+        )
+        .with_detail("source", source)
+        .with_detail("reason", reason)
+    }
+
+    /// No approval decision within `timeout_secs`
+    pub fn approval_timeout(timeout_secs: u64) -> Self {
+        Self::new(
+            ErrorKind::ApprovalTimeout,
+            format!("Approval timed out after {}s", timeout_secs),
+        )
+        .with_detail("timeout_secs", timeout_secs)
+    }
+
+    /// Action deferred; the agent may retry the call or poll
+    /// `approvals/status` with `approval_id`
+    pub fn approval_pending(approval_id: &str, expires_at: DateTime<Utc>) -> Self {
+        Self::new(
+            ErrorKind::ApprovalPending,
+            format!("Action deferred for a later decision ({})", approval_id),
+        )
+        .with_detail("approval_id", approval_id)
+        .with_detail("expires_at", expires_at.to_rfc3339())
+    }
+
+    /// The sandbox failed to run the action
+    pub fn sandbox_failure(msg: impl fmt::Display) -> Self {
+        Self::new(
+            ErrorKind::SandboxFailure,
+            format!("Sandbox failure: {}", msg),
+        )
+    }
+
+    /// Classify a handler error
+    ///
+    /// Recognizes [`RpcError`], [`BudgetExceeded`] and [`PolicyViolation`]
+    /// anywhere in the error chain; anything else is an internal error.
+    pub fn from_anyhow(e: &anyhow::Error) -> Self {
+        if let Some(err) = e.downcast_ref::<RpcError>() {
+            return err.clone();
+        }
+        if let Some(exceeded) = e.downcast_ref::<BudgetExceeded>() {
+            return exceeded.into();
+        }
+        if let Some(violation) = e.downcast_ref::<PolicyViolation>() {
+            return violation.into();
+        }
+        Self::internal_error(e)
+    }
+
+    /// Machine-readable `data` object: `kind`, `retryable` and details
+    pub fn data(&self) -> Value {
+        let mut data = self.details.clone();
+        data.insert("kind".to_string(), json!(self.kind));
+        data.insert("retryable".to_string(), json!(self.kind.is_retryable()));
+        Value::Object(data)
+    }
+}
+
+impl fmt::Display for RpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for RpcError {}
+
+impl From<&BudgetExceeded> for RpcError {
+    fn from(exceeded: &BudgetExceeded) -> Self {
+        let mut err = Self::new(
+            ErrorKind::BudgetExceeded,
+            format!("Budget exceeded: {}", exceeded),
+        );
+        if let Ok(Value::Object(fields)) = serde_json::to_value(exceeded) {
+            err.details.extend(fields);
+        }
+        err
+    }
+}
+
+impl From<&PolicyViolation> for RpcError {
+    fn from(violation: &PolicyViolation) -> Self {
+        let mut err = Self::approval_denied("policy", &violation.reason)
+            .with_detail("server", violation.server.as_str())
+            .with_detail("tool", violation.tool.as_str());
+        if let Some(argument) = &violation.argument {
+            err = err.with_detail("argument", argument.as_str());
+        }
+        err
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codes_are_unique_and_round_trip() {
+        for kind in ErrorKind::ALL {
+            assert_eq!(ErrorKind::from_code(kind.code()), Some(kind));
+            assert_eq!(json!(kind), json!(kind.as_str()));
+        }
+        assert_eq!(ErrorKind::from_code(-1), None);
+    }
+
+    #[test]
+    fn test_data_includes_kind_and_details() {
+        let err = RpcError::method_not_found("tools/run");
+
+        let data = err.data();
+        assert_eq!(err.kind.code(), -32601);
+        assert_eq!(data["kind"], "method_not_found");
+        assert_eq!(data["method"], "tools/run");
+        assert_eq!(data["retryable"], false);
+    }
+
+    #[test]
+    fn test_from_anyhow_finds_rpc_error_in_chain() {
+        let e = anyhow::Error::new(RpcError::invalid_params("name", "missing"))
+            .context("while handling tools/call");
+
+        let err = RpcError::from_anyhow(&e);
+        assert_eq!(err.kind, ErrorKind::InvalidParams);
+        assert_eq!(err.data()["param"], "name");
+    }
+
+    #[test]
+    fn test_from_anyhow_budget_exceeded() {
+        let e = anyhow::Error::new(BudgetExceeded::RedActions { max: 3 });
+
+        let err = RpcError::from_anyhow(&e);
+        assert_eq!(err.kind.code(), -32010);
+        let data = err.data();
+        assert_eq!(data["kind"], "budget_exceeded");
+        assert_eq!(data["limit"], "red_actions");
+        assert_eq!(data["max"], 3);
+    }
+
+    #[test]
+    fn test_from_anyhow_policy_violation() {
+        let e = anyhow::Error::new(PolicyViolation {
+            server: "fs".to_string(),
+            tool: "write_file".to_string(),
+            argument: Some("path".to_string()),
+            reason: "outside allowed roots".to_string(),
+        });
+
+        let data = RpcError::from_anyhow(&e).data();
+        assert_eq!(data["kind"], "approval_denied");
+        assert_eq!(data["source"], "policy");
+        assert_eq!(data["argument"], "path");
+        assert_eq!(data["reason"], "outside allowed roots");
+    }
+
+    #[test]
+    fn test_unclassified_is_internal() {
+        let err = RpcError::from_anyhow(&anyhow::anyhow!("boom"));
+        assert_eq!(err.kind, ErrorKind::InternalError);
+        assert!(err.message.contains("boom"));
+    }
+
+    #[test]
+    fn test_tool_error_keeps_mcp_code() {
+        let err = RpcError::tool_error("read_file", &McpError::new(-32602, "no such file"));
+
+        let data = err.data();
+        assert_eq!(err.kind.code(), -32002);
+        assert_eq!(data["tool"], "read_file");
+        assert_eq!(data["mcp_code"], -32602);
+    }
+
+    #[test]
+    fn test_schema_mirrors_taxonomy() {
+        let schema: Value = serde_json::from_str(include_str!(
+            "../../../docs/schemas/agent-rpc-error.schema.json"
+        ))
+        .unwrap();
+
+        let codes: Vec<i64> = ErrorKind::ALL.iter().map(|k| k.code() as i64).collect();
+        let kinds: Vec<&str> = ErrorKind::ALL.iter().map(|k| k.as_str()).collect();
+        assert_eq!(schema["properties"]["code"]["enum"], json!(codes));
+        assert_eq!(
+            schema["properties"]["data"]["properties"]["kind"]["enum"],
+            json!(kinds)
+        );
+
+        let variants = schema["$defs"]["codeMatchesKind"]["oneOf"]
+            .as_array()
+            .unwrap();
+        for (variant, kind) in variants.iter().zip(ErrorKind::ALL) {
+            assert_eq!(variant["properties"]["code"]["const"], kind.code());
+            assert_eq!(
+                variant["properties"]["data"]["properties"]["kind"]["const"],
+                kind.as_str()
+            );
+        }
+        assert_eq!(variants.len(), ErrorKind::ALL.len());
+    }
+
+    #[test]
+    fn test_retryable_kinds() {
+        assert!(ErrorKind::McpServerUnavailable.is_retryable());
+        assert!(!ErrorKind::ApprovalDenied.is_retryable());
+        assert!(!ErrorKind::BudgetExceeded.is_retryable());
+        assert!(ErrorKind::ApprovalPending.is_retryable());
+    }
+}
@@ -0,0 +1,1934 @@
+//! Agent RPC Module
+//!
+//! This module provides JSON-RPC 2.0 communication between the Python agent loop
+//! and the Rust orchestrator over stdin/stdout.
+//!
+//! # Architecture
+//!
+//! ```text
+//! Python Agent Loop (loop.py)
+//!      ↓ (JSON-RPC over stdin/stdout)
+//! Rust Orchestrator (this module)
+//!      ↓ (MCP over stdio/HTTP)
+//! MCP Server (filesystem, github, etc.)
+//! ```
+//!
+//! # Protocol
+//!
+//! The orchestrator acts as an MCP proxy, exposing standard MCP methods:
+//! - `initialize`: Initialize the orchestrator
+//! - `tools/list`: List available tools from connected MCP servers
+//! - `tools/call`: Execute a tool call
+//! - `session/status`: Report session budget counters and cache metrics
+//! - `approvals/status`: Report the state of a deferred tool call
+//! - `plans/submit`: Submit an ordered plan of Red tool calls for approval
+//!
+//! # Errors
+//!
+//! Failures are reported with the stable codes listed in [`errors`]. Each
+//! error's `data` object carries a `kind` (e.g. `"tool_error"`,
+//! `"approval_denied"`), a `retryable` flag and kind-specific fields, so
+//! agents never need to parse error messages.
+//!
+//! Every tool call is checked against the session's [`SessionBudget`]; when a
+//! limit is exhausted the call fails with a `budget_exceeded` error whose
+//! `data` names the exhausted limit.
+//!
+//! Tool calls are checked against the configured [`ToolPolicy`] before they
+//! reach the MCP server; violations are recorded in the approval audit trail
+//! as automatic denials and returned as `approval_denied` errors with
+//! `"source": "policy"`. `tools/list` only shows tools permitted by policy.
+//!
+//! Tool results are passed through a [`ToolResultSanitizer`] before being
+//! returned to the agent. When the sanitizer modified the content, the
+//! response carries `"contentAltered": true` and a `sanitization` report.
+//!
+//! When an approval backend is configured, Red tool calls wait for a human
+//! decision. Approvers may amend the call's arguments; the amended arguments
+//! are what reach the MCP server, and the response carries them as
+//! `amendedArguments`. Amended arguments are checked against the tool
+//! policy again before the call runs. Approved calls carry the approval's `ticketId`; the
+//! call's outcome (success or failure, result digest, duration) is attached
+//! to that ticket in the audit trail. A call the approver defers fails with a retryable
+//! `approval_pending` error carrying an `approval_id`; the agent can carry
+//! on with other work, poll `approvals/status`, and retry the call once it
+//! is decided (e.g. with `luminaguard approvals approve <id>`). When a
+//! deferred call is decided or expires, the orchestrator sends a
+//! `notifications/approval_resolved` JSON-RPC notification.
+//!
+//! With [`AgentConfig::with_preview`], a Red tool call first runs against a
+//! disposable copy of the workspace, using a second instance of the MCP
+//! server pointed at the copy. The file changes it made there are shown on
+//! the Diff Card. Once approved, the call runs against the real workspace,
+//! or the previewed changes are committed instead ([`PreviewCommit`]).
+//!
+//! Right before an approved Red tool call runs, the files named in its
+//! arguments are checkpointed; `luminaguard rollback <approval-id>` restores
+//! them. The response's `checkpoint` field lists the files captured, or says
+//! why nothing was. Relative paths are resolved against the preview
+//! workspace; without one, calls naming them are refused.
+//!
+//! `plans/submit` puts an ordered list of Red tool calls
+//! (`{"description", "steps": [{"name", "arguments"}]}`) to approvers as one
+//! plan. The response gives each step's status: `approved`, `denied`,
+//! `deferred`, or `pending` for steps that need more than one approver.
+//! While the plan has steps left, `tools/call` only runs Red calls that are
+//! its next step, with exactly the submitted arguments; a pending step is
+//! put to its approvers when it is called. Submitting a plan replaces the
+//! active one.
+//!
+//! Approvers sign their decisions with their own keys; the orchestrator
+//! holds no approver keys. Once a trust list exists (`luminaguard keys
+//! trust`), Red tool calls whose approval is not signed with a trusted key
+//! are refused.
+//!
+//! When enabled, results of Green tools are served from a
+//! [`ToolResultCache`]; cached responses carry `"cached": true`. Executing a
+//! Red action invalidates the cached results for that server.
+//!
+//! # Usage
+//!
+//! Start orchestrator in agent mode:
+//! ```bash
+//! luminaguard agent-mode --server filesystem --command "npx -y @modelcontextprotocol/server-filesystem /tmp"
+//! ```
+
+pub mod budget;
+pub mod errors;
+
+pub use budget::{BudgetExceeded, BudgetLimits, BudgetStatus, SessionBudget};
+pub use errors::{ErrorKind, RpcError};
+
+use crate::approval::{
+    argument_paths, ActionPlan, ActionType, ApprovalBackend, ApprovalDecision, ApprovalHistory,
+    ApprovalManager, ApprovalTicket, Change, Checkpoint, CheckpointStore, DeferredAction,
+    DeferredQueue, DeferredStatus, ExecutionOutcome, Overlay, PlanStep, PlanTicket, PreviewCommit,
+    PreviewConfig, TrustList,
+};
+use crate::mcp::{
+    CacheConfig, McpClient, McpError, SanitizerConfig, ServerCapabilities, ServerInfo,
+    StdioTransport, ToolPolicy, ToolResultCache, ToolResultSanitizer,
+};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{debug, error, info, warn};
+
+/// JSON-RPC 2.0 Request
+#[derive(Debug, Deserialize)]
+struct JsonRequest {
+    /// JSON-RPC version (must be "2.0")
+    jsonrpc: String,
+    /// Method name (e.g., "initialize", "tools/list", "tools/call")
+    method: String,
+    /// Method parameters (optional)
+    #[serde(default)]
+    params: Option<serde_json::Value>,
+    /// Request ID
+    id: serde_json::Value,
+}
+
+/// JSON-RPC 2.0 Response
+#[derive(Debug, Serialize)]
+struct JsonResponse {
+    /// JSON-RPC version
+    jsonrpc: &'static str,
+    /// Result (if successful)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<serde_json::Value>,
+    /// Error (if failed)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<JsonRpcError>,
+    /// Request ID (must match request)
+    id: serde_json::Value,
+}
+
+/// JSON-RPC 2.0 Notification (no ID, no response expected)
+#[derive(Debug, Serialize)]
+struct JsonNotification {
+    /// JSON-RPC version
+    jsonrpc: &'static str,
+    /// Notification name
+    method: &'static str,
+    /// Notification parameters
+    params: serde_json::Value,
+}
+
+/// JSON-RPC 2.0 Error
+#[derive(Debug, Serialize)]
+struct JsonRpcError {
+    /// Error code
+    code: i32,
+    /// Error message
+    message: String,
+    /// Additional data (optional)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data: Option<serde_json::Value>,
+}
+
+impl From<&RpcError> for JsonRpcError {
+    fn from(err: &RpcError) -> Self {
+        Self {
+            code: err.kind.code(),
+            message: err.message.clone(),
+            data: Some(err.data()),
+        }
+    }
+}
+
+impl JsonRpcError {
+    /// Map a handler error to a JSON-RPC error
+    fn from_handler_error(e: &anyhow::Error) -> Self {
+        Self::from(&RpcError::from_anyhow(e))
+    }
+}
+
+/// Agent RPC server configuration
+#[derive(Debug, Clone)]
+pub struct AgentConfig {
+    /// MCP server name (for logging)
+    pub server_name: String,
+
+    /// Command to spawn MCP server
+    pub command: Vec<String>,
+
+    /// Tool result limits and sanitization settings
+    pub sanitizer: SanitizerConfig,
+
+    /// Tool allowlists and argument constraints
+    pub tool_policy: ToolPolicy,
+
+    /// Session budget limits
+    pub budget: BudgetLimits,
+
+    /// Result cache for Green tools (disabled by default)
+    pub cache: CacheConfig,
+
+    /// Backend asked to approve Red tool calls (no approval gating if None)
+    pub approval_backend: Option<Arc<dyn ApprovalBackend>>,
+
+    /// Dry-run Red tool calls before approval (no previews if None)
+    pub preview: Option<PreviewConfig>,
+}
+
+impl AgentConfig {
+    /// Create new agent configuration
+    pub fn new(server_name: String, command: Vec<String>) -> Self {
+        Self {
+            server_name,
+            command,
+            sanitizer: SanitizerConfig::default(),
+            tool_policy: ToolPolicy::default(),
+            budget: BudgetLimits::default(),
+            cache: CacheConfig::default(),
+            approval_backend: None,
+            preview: None,
+        }
+    }
+
+    /// Set the tool result sanitizer configuration
+    pub fn with_sanitizer(mut self, sanitizer: SanitizerConfig) -> Self {
+        self.sanitizer = sanitizer;
+        self
+    }
+
+    /// Set the tool policy
+    pub fn with_tool_policy(mut self, tool_policy: ToolPolicy) -> Self {
+        self.tool_policy = tool_policy;
+        self
+    }
+
+    /// Set the session budget limits
+    pub fn with_budget(mut self, budget: BudgetLimits) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    /// Set the tool result cache configuration
+    pub fn with_cache(mut self, cache: CacheConfig) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Require approval of Red tool calls through `backend`
+    ///
+    /// stdin carries JSON-RPC in agent mode, so the stdin prompt cannot be
+    /// used here.
+    pub fn with_approval_backend(mut self, backend: Arc<dyn ApprovalBackend>) -> Self {
+        self.approval_backend = Some(backend);
+        self
+    }
+
+    /// Preview approval-gated Red tool calls in a copy of a workspace
+    ///
+    /// Only has an effect together with an approval backend.
+    pub fn with_preview(mut self, preview: PreviewConfig) -> Self {
+        self.preview = Some(preview);
+        self
+    }
+}
+
+/// A Red tool call's run against a workspace overlay
+struct ToolPreview {
+    /// Overlay the call ran in (None if it could not be created)
+    overlay: Option<Overlay>,
+
+    /// Changes observed in the overlay, and why the preview is incomplete
+    changes: Vec<Change>,
+
+    /// The call's result, if it succeeded
+    result: Option<serde_json::Value>,
+}
+
+impl ToolPreview {
+    /// A preview that did not run
+    fn unavailable(reason: &anyhow::Error) -> Self {
+        Self {
+            overlay: None,
+            changes: vec![Change::Custom {
+                description: format!("Preview unavailable: {:#}", reason),
+            }],
+            result: None,
+        }
+    }
+}
+
+/// Agent RPC server state
+struct AgentServer {
+    /// MCP client (initialized when ready)
+    mcp_client: Option<McpClient<StdioTransport>>,
+    /// Server capabilities
+    capabilities: Option<ServerCapabilities>,
+    /// Server info
+    server_info: Option<ServerInfo>,
+    /// Tool result sanitizer (configured on initialize)
+    sanitizer: ToolResultSanitizer,
+    /// Approval manager (audit trail, approval of Red tool calls)
+    approvals: ApprovalManager,
+    /// Session budget (reset on initialize)
+    budget: SessionBudget,
+    /// Green tool result cache (reset on initialize)
+    cache: ToolResultCache,
+    /// Plan whose steps Red tool calls must follow (see "plans/submit")
+    plan: Option<PlanTicket>,
+}
+
+impl AgentServer {
+    /// Create new agent server
+    fn new() -> Self {
+        Self {
+            mcp_client: None,
+            capabilities: None,
+            server_info: None,
+            sanitizer: ToolResultSanitizer::default(),
+            approvals: ApprovalManager::new(),
+            budget: SessionBudget::default(),
+            cache: ToolResultCache::default(),
+            plan: None,
+        }
+    }
+
+    /// Initialize MCP connection
+    async fn initialize(&mut self, config: &AgentConfig) -> Result<()> {
+        info!(
+            "🔌 Initializing MCP connection to {}...",
+            config.server_name
+        );
+
+        let client = connect(&config.server_name, &config.command).await?;
+
+        // Get server info
+        let server_info = ServerInfo {
+            name: config.server_name.clone(),
+            version: "1.0.0".to_string(),
+        };
+
+        // Create capabilities with tools support
+        let capabilities = ServerCapabilities {
+            protocol_version: "2024-11-05".to_string(),
+            capabilities: json!({
+                "tools": {},
+            }),
+            server_info: server_info.clone(),
+        };
+
+        self.mcp_client = Some(client);
+        self.server_info = Some(server_info);
+        self.capabilities = Some(capabilities);
+        self.sanitizer = ToolResultSanitizer::new(config.sanitizer.clone());
+        self.budget = SessionBudget::new(config.budget.clone());
+        self.cache = ToolResultCache::new(config.cache.clone());
+        if let Some(backend) = &config.approval_backend {
+            let mut approvals = ApprovalManager::new()
+                .with_backend(backend.clone())
+                .with_deferred_queue(DeferredQueue::open(DeferredQueue::default_path())?)
+                .with_history(ApprovalHistory::open(ApprovalHistory::default_path())?)
+                .with_checkpoints(CheckpointStore::open(CheckpointStore::default_path()));
+            // Signatures are enforced once a trust list is set up
+            if TrustList::default_path().exists() {
+                approvals = approvals.with_trust_list(TrustList::open(TrustList::default_path())?);
+            }
+            self.approvals = approvals;
+        }
+
+        info!("✅ MCP connection initialized");
+
+        Ok(())
+    }
+
+    /// Handle "initialize" method
+    async fn handle_initialize(
+        &mut self,
+        config: &AgentConfig,
+        _params: Option<serde_json::Value>,
+    ) -> Result<serde_json::Value> {
+        info!("📋 Handling initialize request");
+
+        // Initialize MCP connection
+        self.initialize(config).await?;
+
+        // Return initialized response
+        Ok(json!({
+            "protocolVersion": "2024-11-05",
+            "capabilities": self.capabilities,
+            "serverInfo": self.server_info,
+        }))
+    }
+
+    /// Handle "tools/list" method
+    async fn handle_tools_list(
+        &mut self,
+        config: &AgentConfig,
+        _params: Option<serde_json::Value>,
+    ) -> Result<serde_json::Value> {
+        info!("🔍 Handling tools/list request");
+
+        let client = self.client(config)?;
+
+        let tools = client.list_tools().await.map_err(|e| {
+            RpcError::mcp_server_unavailable(
+                &config.server_name,
+                format!("failed to list tools: {:#}", e),
+            )
+        })?;
+        let tools = config.tool_policy.filter_tools(&config.server_name, tools);
+
+        let tools_json: Vec<serde_json::Value> = tools
+            .into_iter()
+            .map(|t| {
+                json!({
+                    "name": t.name,
+                    "description": t.description,
+                    "inputSchema": t.input_schema,
+                })
+            })
+            .collect();
+
+        Ok(json!({ "tools": tools_json }))
+    }
+
+    /// Handle "tools/call" method
+    async fn handle_tools_call(
+        &mut self,
+        config: &AgentConfig,
+        params: Option<serde_json::Value>,
+    ) -> Result<serde_json::Value> {
+        let params = params.ok_or_else(|| RpcError::invalid_params("params", "missing params"))?;
+
+        let tool_name = params
+            .get("name")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| RpcError::invalid_params("name", "missing or invalid 'name'"))?;
+
+        let arguments = params
+            .get("arguments")
+            .ok_or_else(|| RpcError::invalid_params("arguments", "missing 'arguments'"))?;
+
+        info!("🔧 Handling tools/call request: {}", tool_name);
+
+        let is_red_action = ActionType::from_description(tool_name).requires_approval();
+        self.budget.check_call(&config.server_name, is_red_action)?;
+
+        self.enforce_tool_policy(config, tool_name, arguments)?;
+
+        if let Some(result) = self.cache.get(&config.server_name, tool_name, arguments) {
+            debug!("Cache hit for {}", tool_name);
+            let mut response = self.build_tool_response(tool_name, result);
+            response["cached"] = json!(true);
+            return Ok(response);
+        }
+
+        let gated = is_red_action && config.approval_backend.is_some();
+        // Steps of the active plan were approved with it
+        let planned = gated && self.plan.as_ref().is_some_and(|p| !p.is_finished());
+        let mut preview = match &config.preview {
+            Some(preview) if gated && !planned => {
+                Some(preview_tool_call(config, preview, tool_name, arguments).await)
+            }
+            _ => None,
+        };
+
+        // The approver may have amended the arguments
+        let (ticket_id, amended) = if planned {
+            self.authorize_plan_call(config, tool_name, arguments)
+                .await?
+        } else if gated {
+            let observed = preview
+                .as_mut()
+                .map(|p| std::mem::take(&mut p.changes))
+                .unwrap_or_default();
+            self.approve_tool_call(config, tool_name, arguments, observed)
+                .await?
+        } else {
+            (None, None)
+        };
+        let arguments = amended.as_ref().unwrap_or(arguments);
+        if amended.is_some() {
+            // Amendments must stay within the tool policy too
+            self.enforce_tool_policy(config, tool_name, arguments)?;
+        }
+
+        // An approved preview may be committed instead of running the call
+        // again (amended calls differ from what was previewed)
+        let commit_overlay = amended.is_none()
+            && config
+                .preview
+                .as_ref()
+                .is_some_and(|p| p.on_approval == PreviewCommit::CommitOverlay);
+        let committable = match preview {
+            Some(ToolPreview {
+                overlay: Some(overlay),
+                result: Some(result),
+                ..
+            }) if commit_overlay => Some((overlay, result)),
+            _ => None,
+        };
+
+        if is_red_action {
+            // The action may change what Green tools on this server return
+            self.cache.invalidate_server(&config.server_name);
+        }
+
+        // Snapshot the files as the call finds them (plan steps were
+        // checkpointed when authorized)
+        let checkpoint = match &ticket_id {
+            Some(ticket_id) if planned => Some(self.plan_checkpoint(ticket_id)),
+            Some(ticket_id) => Some(self.checkpoint_tool_call(config, ticket_id, arguments)?),
+            None => None,
+        };
+
+        let client = self.client(config)?;
+
+        let started = Instant::now();
+        let result = match committable {
+            Some((overlay, result)) => overlay.commit().map(|applied| {
+                info!(
+                    "📦 Committed {} previewed file change(s) of {}",
+                    applied, tool_name
+                );
+                result
+            }),
+            None => client.call_tool(tool_name, arguments.clone()).await,
+        };
+        if let Some(ticket_id) = &ticket_id {
+            self.record_tool_outcome(ticket_id, &result, started.elapsed());
+        }
+
+        let result = result.map_err(|e| match e.downcast_ref::<McpError>() {
+            Some(mcp_error) => RpcError::tool_error(tool_name, mcp_error),
+            None => RpcError::mcp_server_unavailable(&config.server_name, format!("{:#}", e)),
+        })?;
+
+        let transferred = serde_json::to_vec(arguments).map_or(0, |v| v.len())
+            + serde_json::to_vec(&result).map_or(0, |v| v.len());
+        self.budget
+            .record_bytes(&config.server_name, transferred as u64);
+
+        self.cache
+            .insert(&config.server_name, tool_name, arguments, result.clone());
+
+        let mut response = self.build_tool_response(tool_name, result);
+        if let Some(amended) = amended {
+            response["amendedArguments"] = amended;
+        }
+        if let Some(ticket_id) = ticket_id {
+            response["ticketId"] = json!(ticket_id);
+        }
+        if let Some(checkpoint) = checkpoint {
+            response["checkpoint"] = checkpoint;
+        }
+        Ok(response)
+    }
+
+    /// Checkpoint the files an approved tool call names
+    ///
+    /// Returns the response's `checkpoint` field: the files captured, or an
+    /// explicit marker that nothing was checkpointed. If the files cannot be
+    /// snapshotted, the call is refused and the failure recorded as the
+    /// ticket's outcome.
+    ///
+    /// Relative paths are relative to the MCP server, not the orchestrator:
+    /// they are resolved against the preview workspace if one is configured,
+    /// and refused otherwise.
+    fn checkpoint_tool_call(
+        &mut self,
+        config: &AgentConfig,
+        ticket_id: &str,
+        arguments: &serde_json::Value,
+    ) -> Result<serde_json::Value> {
+        let workspace = config.preview.as_ref().map(|p| p.workspace.as_path());
+        // Directories have no content to snapshot
+        let paths: Vec<PathBuf> = argument_paths(arguments)
+            .into_iter()
+            .map(|path| match workspace {
+                Some(workspace) => workspace.join(path),
+                None => PathBuf::from(path),
+            })
+            .filter(|path| !path.is_dir())
+            .collect();
+
+        match self.approvals.checkpoint(ticket_id, &paths) {
+            Ok(Some(checkpoint)) => Ok(checkpoint_marker(Some(&checkpoint), "")),
+            Ok(None) if paths.is_empty() => {
+                Ok(checkpoint_marker(None, "no file paths in arguments"))
+            }
+            Ok(None) => Ok(checkpoint_marker(None, "checkpoints disabled")),
+            Err(e) => {
+                let e = e.context("Checkpoint failed");
+                warn!("⚠️  Not running ticket {}: {:#}", ticket_id, e);
+                let message = format!("{:#}", e);
+                self.record_tool_outcome(ticket_id, &Err(e), Duration::ZERO);
+                Err(RpcError::internal_error(message).into())
+            }
+        }
+    }
+
+    /// Attach a tool call's outcome to its approval ticket
+    ///
+    /// Failing to record the outcome is logged, not returned: the call has
+    /// already run.
+    fn record_tool_outcome(
+        &mut self,
+        ticket_id: &str,
+        result: &Result<serde_json::Value>,
+        duration: Duration,
+    ) {
+        let outcome = match result {
+            Ok(value) => ExecutionOutcome::new(ticket_id, true, duration).with_result(value),
+            Err(e) => {
+                ExecutionOutcome::new(ticket_id, false, duration).with_detail(format!("{:#}", e))
+            }
+        };
+        if let Err(e) = self.approvals.record_outcome(outcome) {
+            warn!(
+                "⚠️  Failed to record outcome of ticket {}: {:#}",
+                ticket_id, e
+            );
+        }
+    }
+
+    /// The `checkpoint` field of a plan step's response
+    fn plan_checkpoint(&self, ticket_id: &str) -> serde_json::Value {
+        match self.approvals.checkpoint_of(ticket_id) {
+            Ok(checkpoint) => {
+                checkpoint_marker(checkpoint.as_ref(), "no files checkpointed for this step")
+            }
+            Err(e) => checkpoint_marker(None, &format!("{:#}", e)),
+        }
+    }
+
+    /// Handle "plans/submit" method
+    ///
+    /// Every step must be a Red tool call permitted by the tool policy.
+    async fn handle_plans_submit(
+        &mut self,
+        config: &AgentConfig,
+        params: Option<serde_json::Value>,
+    ) -> Result<serde_json::Value> {
+        if config.approval_backend.is_none() {
+            return Err(RpcError::invalid_request("no approval backend for plans").into());
+        }
+        let params = params.ok_or_else(|| RpcError::invalid_params("params", "missing params"))?;
+
+        let description = params
+            .get("description")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| {
+                RpcError::invalid_params("description", "missing or invalid 'description'")
+            })?;
+        let steps = params
+            .get("steps")
+            .and_then(|v| v.as_array())
+            .filter(|steps| !steps.is_empty())
+            .ok_or_else(|| RpcError::invalid_params("steps", "missing or empty 'steps'"))?;
+
+        let mut plan = ActionPlan::new(description);
+        for (i, step) in steps.iter().enumerate() {
+            let tool_name = step.get("name").and_then(|v| v.as_str()).ok_or_else(|| {
+                RpcError::invalid_params("steps", format!("step {} has no 'name'", i + 1))
+            })?;
+            let arguments = step.get("arguments").ok_or_else(|| {
+                RpcError::invalid_params("steps", format!("step {} has no 'arguments'", i + 1))
+            })?;
+            if !ActionType::from_description(tool_name).requires_approval() {
+                return Err(RpcError::invalid_params(
+                    "steps",
+                    format!(
+                        "step {} ({}) needs no approval; call it directly",
+                        i + 1,
+                        tool_name
+                    ),
+                )
+                .into());
+            }
+            self.enforce_tool_policy(config, tool_name, arguments)?;
+            plan = plan.with_step(PlanStep::tool_call(
+                &config.server_name,
+                tool_name,
+                arguments.clone(),
+            ));
+        }
+
+        info!(
+            "🗂️  Submitting plan of {} step(s): {}",
+            plan.steps.len(),
+            description
+        );
+        let ticket = self.approvals.check_and_approve_plan(plan).await?;
+        let status = plan_status(&ticket);
+        self.plan = Some(ticket);
+        Ok(status)
+    }
+
+    /// Handle "session/status" method
+    fn handle_session_status(&self) -> Result<serde_json::Value> {
+        Ok(json!({
+            "budget": self.budget.status(),
+            "cache": self.cache.metrics(),
+        }))
+    }
+
+    /// Handle "approvals/status" method
+    fn handle_approval_status(
+        &mut self,
+        params: Option<serde_json::Value>,
+    ) -> Result<serde_json::Value> {
+        let id = params
+            .as_ref()
+            .and_then(|p| p.get("approvalId"))
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| {
+                RpcError::invalid_params("approvalId", "missing or invalid 'approvalId'")
+            })?;
+
+        let item = self.approvals.deferred_action(id)?.ok_or_else(|| {
+            RpcError::invalid_params("approvalId", format!("no deferred action {}", id))
+        })?;
+        Ok(approval_status(&item))
+    }
+
+    /// Notifications for deferred calls decided or expired since last asked
+    fn take_approval_notifications(&mut self) -> Vec<JsonNotification> {
+        match self.approvals.take_deferred_notifications() {
+            Ok(items) => items
+                .iter()
+                .map(|item| JsonNotification {
+                    jsonrpc: "2.0",
+                    method: "notifications/approval_resolved",
+                    params: approval_status(item),
+                })
+                .collect(),
+            Err(e) => {
+                warn!("⚠️  Failed to read deferred approvals: {:#}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Connected MCP client, or an `mcp_server_unavailable` error
+    fn client(&mut self, config: &AgentConfig) -> Result<&mut McpClient<StdioTransport>> {
+        self.mcp_client.as_mut().ok_or_else(|| {
+            RpcError::mcp_server_unavailable(&config.server_name, "MCP client not initialized")
+                .into()
+        })
+    }
+
+    /// Ask the approval backend to decide a Red tool call
+    ///
+    /// `observed` are the changes seen in a preview run, if any. Returns the
+    /// approval's ticket ID and the amended arguments if the approver
+    /// modified the call. Denied calls fail with an `approval_denied` error,
+    /// deferred ones with `approval_pending`.
+    async fn approve_tool_call(
+        &mut self,
+        config: &AgentConfig,
+        tool_name: &str,
+        arguments: &serde_json::Value,
+        observed: Vec<Change>,
+    ) -> Result<(Option<String>, Option<serde_json::Value>)> {
+        let ticket = self
+            .approvals
+            .check_and_approve_previewed_tool_call(
+                &config.server_name,
+                tool_name,
+                arguments,
+                observed,
+            )
+            .await?;
+        self.ticket_outcome(tool_name, ticket, "approver")
+    }
+
+    /// Authorize a tool call as the active plan's next step
+    ///
+    /// Finished plans are dropped, so later calls are approved one by one.
+    async fn authorize_plan_call(
+        &mut self,
+        config: &AgentConfig,
+        tool_name: &str,
+        arguments: &serde_json::Value,
+    ) -> Result<(Option<String>, Option<serde_json::Value>)> {
+        let Some(mut plan) = self.plan.take() else {
+            return Ok((None, None));
+        };
+        let step = PlanStep::tool_call(&config.server_name, tool_name, arguments.clone());
+        let ticket = self.approvals.authorize_plan_step(&mut plan, &step).await;
+        if !plan.is_finished() {
+            self.plan = Some(plan);
+        }
+        self.ticket_outcome(tool_name, ticket?, "plan")
+    }
+
+    /// What an approval ticket means for a tool call: its ticket ID and
+    /// amended arguments if approved, the error to return otherwise
+    fn ticket_outcome(
+        &mut self,
+        tool_name: &str,
+        ticket: ApprovalTicket,
+        source: &str,
+    ) -> Result<(Option<String>, Option<serde_json::Value>)> {
+        match ticket.decision {
+            ApprovalDecision::Approved => Ok((ticket.id, None)),
+            ApprovalDecision::ApprovedWithModifications(amendment) => {
+                info!("✏️  Tool call {} amended by approver", tool_name);
+                Ok((ticket.id, amendment.arguments))
+            }
+            ApprovalDecision::Denied => match ticket.timeout {
+                Some(timeout) => Err(RpcError::approval_timeout(timeout.after_secs).into()),
+                None => Err(RpcError::approval_denied(
+                    source,
+                    ticket.justification.as_deref().unwrap_or("denied"),
+                )
+                .into()),
+            },
+            ApprovalDecision::DeferredToLater => {
+                let item = match &ticket.deferred_id {
+                    Some(id) => self.approvals.deferred_action(id)?,
+                    None => None,
+                };
+                match item {
+                    Some(item) => Err(RpcError::approval_pending(&item.id, item.expires_at).into()),
+                    None => {
+                        Err(RpcError::approval_denied(source, "deferred for later decision").into())
+                    }
+                }
+            }
+        }
+    }
+
+    /// Check a tool call against the tool policy
+    ///
+    /// Violations are recorded in the approval audit trail as automatic
+    /// denials before the error is returned.
+    fn enforce_tool_policy(
+        &mut self,
+        config: &AgentConfig,
+        tool_name: &str,
+        arguments: &serde_json::Value,
+    ) -> Result<()> {
+        if let Err(violation) =
+            config
+                .tool_policy
+                .check_call(&config.server_name, tool_name, arguments)
+        {
+            warn!("🚫 Tool call denied by policy: {}", violation);
+            self.approvals.record_automatic_denial(
+                format!("Call tool {} on {}", tool_name, config.server_name),
+                format!("Policy violation: {}", violation),
+            )?;
+            return Err(RpcError::from(&violation).into());
+        }
+        Ok(())
+    }
+
+    /// Sanitize a raw tool result and wrap it in a `tools/call` response
+    fn build_tool_response(&self, tool_name: &str, result: serde_json::Value) -> serde_json::Value {
+        // Tools report their own failures in the result
+        let is_error = result.get("isError") == Some(&json!(true));
+        let (content, report) = self.sanitizer.sanitize(tool_name, result);
+
+        if report.has_prompt_injection() {
+            warn!(
+                "⚠️  Possible prompt injection in result of tool {}",
+                tool_name
+            );
+        }
+        if report.has_secrets() {
+            warn!("⚠️  Secret detected in result of tool {}", tool_name);
+        }
+
+        let mut response = json!({
+            "content": content,
+            "isError": is_error,
+            "contentAltered": report.altered,
+        });
+
+        if !report.findings.is_empty() {
+            response["sanitization"] = json!(report);
+        }
+
+        response
+    }
+}
+
+/// Spawn and initialize an MCP server, or an `mcp_server_unavailable` error
+async fn connect(server_name: &str, command: &[String]) -> Result<McpClient<StdioTransport>> {
+    // Split command into program and args
+    let Some((program, args)) = command.split_first() else {
+        return Err(
+            RpcError::mcp_server_unavailable(server_name, "command cannot be empty").into(),
+        );
+    };
+    let args: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+
+    debug!("Spawning MCP server: {} {:?}", program, args);
+
+    // Spawn MCP server via stdio transport
+    let transport = StdioTransport::spawn(program, &args).await.map_err(|e| {
+        RpcError::mcp_server_unavailable(server_name, format!("failed to spawn: {:#}", e))
+    })?;
+
+    // Create and initialize MCP client
+    let mut client = McpClient::new(transport);
+
+    client.initialize().await.map_err(|e| {
+        RpcError::mcp_server_unavailable(server_name, format!("failed to initialize: {:#}", e))
+    })?;
+
+    Ok(client)
+}
+
+/// Run a Red tool call against a copy of the workspace
+///
+/// A separate MCP server is spawned for the preview, with workspace paths in
+/// its command and in the call's path arguments pointed at the copy. A preview
+/// that fails still goes to the approver, with the reason on the Diff Card.
+async fn preview_tool_call(
+    config: &AgentConfig,
+    preview: &PreviewConfig,
+    tool_name: &str,
+    arguments: &serde_json::Value,
+) -> ToolPreview {
+    // The preview really runs the call, so it must stay inside the overlay
+    if let Err(e) = preview.check_previewable(tool_name, arguments) {
+        info!("🔬 Not previewing {}: {:#}", tool_name, e);
+        return ToolPreview::unavailable(&e);
+    }
+    let overlay = match Overlay::create(&preview.workspace) {
+        Ok(overlay) => overlay,
+        Err(e) => {
+            warn!("⚠️  Could not preview {}: {:#}", tool_name, e);
+            return ToolPreview::unavailable(&e);
+        }
+    };
+
+    info!(
+        "🔬 Previewing {} in {}",
+        tool_name,
+        overlay.path().display()
+    );
+    let command: Vec<String> = config
+        .command
+        .iter()
+        .map(|arg| overlay.rewrite(arg))
+        .collect();
+    let result = match connect(&config.server_name, &command).await {
+        Ok(mut client) => {
+            client
+                .call_tool(tool_name, overlay.rewrite_json(arguments))
+                .await
+        }
+        Err(e) => Err(e),
+    };
+
+    let mut changes = overlay.changes().unwrap_or_else(|e| {
+        vec![Change::Custom {
+            description: format!("Preview changes unavailable: {:#}", e),
+        }]
+    });
+    let result = match result {
+        Ok(result) => Some(overlay.restore_json(&result)),
+        Err(e) => {
+            warn!("⚠️  Preview of {} failed: {:#}", tool_name, e);
+            changes.push(Change::Custom {
+                description: format!("Preview run failed: {:#}", e),
+            });
+            None
+        }
+    };
+    ToolPreview {
+        overlay: Some(overlay),
+        changes,
+        result,
+    }
+}
+
+/// State of a deferred action as reported to the agent
+fn approval_status(item: &DeferredAction) -> serde_json::Value {
+    let mut status = json!({
+        "approvalId": item.id,
+        "status": item.status.label(),
+        "description": item.description,
+        "expiresAt": item.expires_at,
+    });
+    if let DeferredStatus::Decided {
+        decided_by,
+        justification,
+        ..
+    } = &item.status
+    {
+        status["decidedBy"] = json!(decided_by);
+        status["justification"] = json!(justification);
+    }
+    status
+}
+
+/// Decisions on a submitted plan as reported to the agent
+fn plan_status(plan: &PlanTicket) -> serde_json::Value {
+    let steps: Vec<serde_json::Value> = plan
+        .steps
+        .iter()
+        .enumerate()
+        .map(|(i, step)| {
+            let status = match &step.ticket.decision {
+                _ if step.pending => "pending",
+                decision if decision.is_approved() => "approved",
+                ApprovalDecision::DeferredToLater => "deferred",
+                _ => "denied",
+            };
+            let mut status = json!({ "number": i + 1, "status": status });
+            if let Some(id) = &step.ticket.id {
+                status["ticketId"] = json!(id);
+            }
+            if let Some(reason) = step.note.as_ref().or(step.ticket.justification.as_ref()) {
+                status["reason"] = json!(reason);
+            }
+            status
+        })
+        .collect();
+    json!({ "planId": plan.plan_id, "steps": steps })
+}
+
+/// The `checkpoint` field of an approved call's response
+///
+/// Without a checkpoint, `reason` says why nothing was captured.
+fn checkpoint_marker(checkpoint: Option<&Checkpoint>, reason: &str) -> serde_json::Value {
+    match checkpoint {
+        Some(checkpoint) => json!({
+            "captured": true,
+            "files": checkpoint
+                .files
+                .iter()
+                .map(|file| file.path.to_string_lossy())
+                .collect::<Vec<_>>(),
+        }),
+        None => json!({ "captured": false, "reason": reason }),
+    }
+}
+
+/// Run the agent RPC server (synchronous wrapper)
+///
+/// This is a convenience function that creates a tokio runtime and blocks on
+/// the async `run_agent_rpc_server` function.
+///
+/// # Errors
+///
+/// Returns an error if runtime creation or server execution fails
+pub fn run_agent_rpc_server_sync(config: AgentConfig) -> Result<()> {
+    let rt = tokio::runtime::Runtime::new().context("Failed to create tokio runtime")?;
+
+    rt.block_on(run_agent_rpc_server(config))
+}
+
+/// Run the agent RPC server
+///
+/// This function:
+/// 1. Reads JSON-RPC requests from stdin
+/// 2. Routes to appropriate handler
+/// 3. Writes JSON-RPC responses to stdout
+///
+/// # Errors
+///
+/// Returns an error if:
+/// - stdin/stdout communication fails
+/// - JSON parsing fails
+/// - MCP operations fail
+pub async fn run_agent_rpc_server(config: AgentConfig) -> Result<()> {
+    info!("🤖 LuminaGuard Agent RPC Server starting...");
+    info!("📦 MCP server: {}", config.server_name);
+    info!("🔧 Command: {:?}", config.command);
+
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    let mut stdin_lock = BufReader::new(stdin.lock());
+    let mut stdout_lock = stdout.lock();
+
+    let mut server = AgentServer::new();
+
+    info!("✅ Ready to receive requests");
+
+    // Read line by line from stdin
+    let mut line = String::new();
+    loop {
+        line.clear();
+
+        // Read request from stdin
+        let bytes_read = stdin_lock
+            .read_line(&mut line)
+            .context("Failed to read from stdin")?;
+
+        if bytes_read == 0 {
+            info!("📭 EOF received, shutting down");
+            break;
+        }
+
+        let line = line.trim();
+        if line.is_empty() {
+            continue; // Skip empty lines
+        }
+
+        debug!("📨 Received: {}", line);
+
+        // Parse JSON-RPC request
+        let request: JsonRequest = match serde_json::from_str(line) {
+            Ok(req) => req,
+            Err(e) => {
+                error!("❌ Failed to parse JSON: {}", e);
+                let error_response = JsonResponse {
+                    jsonrpc: "2.0",
+                    result: None,
+                    error: Some(JsonRpcError::from(&RpcError::parse_error(e))),
+                    id: json!(null),
+                };
+                write_response(&mut stdout_lock, &error_response);
+                continue;
+            }
+        };
+
+        // Verify JSON-RPC version
+        if request.jsonrpc != "2.0" {
+            warn!("⚠️  Unsupported JSON-RPC version: {}", request.jsonrpc);
+            let error_response = JsonResponse {
+                jsonrpc: "2.0",
+                result: None,
+                error: Some(JsonRpcError::from(&RpcError::invalid_request(
+                    "unsupported JSON-RPC version",
+                ))),
+                id: request.id,
+            };
+            write_response(&mut stdout_lock, &error_response);
+            continue;
+        }
+
+        // Route to handler (async)
+        let result = match request.method.as_str() {
+            "initialize" => server.handle_initialize(&config, request.params).await,
+            "tools/list" => server.handle_tools_list(&config, request.params).await,
+            "tools/call" => server.handle_tools_call(&config, request.params).await,
+            "session/status" => server.handle_session_status(),
+            "approvals/status" => server.handle_approval_status(request.params),
+            "plans/submit" => server.handle_plans_submit(&config, request.params).await,
+            _ => {
+                error!("❌ Unknown method: {}", request.method);
+                Err(RpcError::method_not_found(&request.method).into())
+            }
+        };
+
+        // Build response
+        let response = match result {
+            Ok(result_value) => JsonResponse {
+                jsonrpc: "2.0",
+                result: Some(result_value),
+                error: None,
+                id: request.id,
+            },
+            Err(e) => {
+                error!("❌ Error handling request: {}", e);
+                JsonResponse {
+                    jsonrpc: "2.0",
+                    result: None,
+                    error: Some(JsonRpcError::from_handler_error(&e)),
+                    id: request.id,
+                }
+            }
+        };
+
+        // Tell the agent about deferred calls that were decided meanwhile
+        for notification in server.take_approval_notifications() {
+            write_notification(&mut stdout_lock, &notification);
+        }
+
+        // Write response to stdout
+        write_response(&mut stdout_lock, &response);
+    }
+
+    info!("👋 Agent RPC server shutting down");
+    Ok(())
+}
+
+/// Write JSON-RPC response to stdout
+fn write_response(stdout: &mut io::StdoutLock<'_>, response: &JsonResponse) {
+    let json = serde_json::to_string(response).unwrap_or_else(|e| {
+        error!("❌ Failed to serialize response: {}", e);
+        r#"{"jsonrpc":"2.0","error":{"code":-32603,"message":"Failed to serialize response"},"id":null}"#.to_string()
+    });
+
+    write_line(stdout, &json);
+}
+
+/// Write JSON-RPC notification to stdout
+fn write_notification(stdout: &mut io::StdoutLock<'_>, notification: &JsonNotification) {
+    match serde_json::to_string(notification) {
+        Ok(json) => write_line(stdout, &json),
+        Err(e) => error!("❌ Failed to serialize notification: {}", e),
+    }
+}
+
+/// Write one JSON message line to stdout
+fn write_line(stdout: &mut io::StdoutLock<'_>, json: &str) {
+    if let Err(e) = writeln!(stdout, "{}", json) {
+        error!("❌ Failed to write to stdout: {}", e);
+    }
+
+    if let Err(e) = stdout.flush() {
+        error!("❌ Failed to flush stdout: {}", e);
+    }
+
+    debug!("📤 Sent: {}", json);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::approval::{ApprovalRequest, ApprovalTimedOut, BackendDecision, Modification};
+
+    #[test]
+    fn test_agent_config_creation() {
+        let config = AgentConfig::new(
+            "filesystem".to_string(),
+            vec!["npx".to_string(), "-y".to_string(), "@server".to_string()],
+        );
+
+        assert_eq!(config.server_name, "filesystem");
+        assert_eq!(config.command.len(), 3);
+    }
+
+    #[test]
+    fn test_json_rpc_error_creation() {
+        let err = JsonRpcError::from(&RpcError::method_not_found("test_method"));
+        assert_eq!(err.code, -32601);
+        assert!(err.message.contains("test_method"));
+        assert_eq!(err.data.unwrap()["kind"], "method_not_found");
+    }
+
+    #[test]
+    fn test_agent_server_creation() {
+        let server = AgentServer::new();
+        assert!(server.mcp_client.is_none());
+        assert!(server.capabilities.is_none());
+    }
+
+    #[test]
+    fn test_policy_violation_recorded_as_denial() {
+        use crate::approval::ApprovalDecision;
+        use crate::mcp::{ArgumentConstraint, ServerToolPolicy};
+        use std::collections::HashMap;
+
+        let mut constraints = HashMap::new();
+        constraints.insert(
+            "write_file".to_string(),
+            vec![ArgumentConstraint::PathUnder {
+                argument: "path".to_string(),
+                roots: vec!["/tmp/out".to_string()],
+            }],
+        );
+        let policy = ToolPolicy::new().with_server(
+            "filesystem",
+            ServerToolPolicy {
+                argument_constraints: constraints,
+                ..ServerToolPolicy::default()
+            },
+        );
+        let config = AgentConfig::new("filesystem".to_string(), vec!["npx".to_string()])
+            .with_tool_policy(policy);
+        let mut server = AgentServer::new();
+
+        assert!(server
+            .enforce_tool_policy(&config, "write_file", &json!({"path": "/tmp/out/a.txt"}))
+            .is_ok());
+        assert!(server.approvals.get_history().is_empty());
+
+        let err = server
+            .enforce_tool_policy(&config, "write_file", &json!({"path": "/etc/passwd"}))
+            .unwrap_err();
+        assert!(err.to_string().contains("denied by policy"));
+        let data = JsonRpcError::from_handler_error(&err).data.unwrap();
+        assert_eq!(data["kind"], "approval_denied");
+        assert_eq!(data["source"], "policy");
+
+        let history = server.approvals.get_history();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].decision, ApprovalDecision::Denied);
+        assert_eq!(history[0].approved_by, "system");
+    }
+
+    #[tokio::test]
+    async fn test_budget_checked_before_tool_call() {
+        let config = AgentConfig::new("filesystem".to_string(), vec!["npx".to_string()])
+            .with_budget(BudgetLimits {
+                max_tool_calls: Some(0),
+                ..BudgetLimits::default()
+            });
+        let mut server = AgentServer::new();
+        server.budget = SessionBudget::new(config.budget.clone());
+
+        let err = server
+            .handle_tools_call(
+                &config,
+                Some(json!({"name": "read_file", "arguments": {"path": "/tmp/a"}})),
+            )
+            .await
+            .unwrap_err();
+
+        let rpc_error = JsonRpcError::from_handler_error(&err);
+        assert_eq!(rpc_error.code, -32010);
+        assert_eq!(rpc_error.data.unwrap()["limit"], "tool_calls");
+    }
+
+    #[tokio::test]
+    async fn test_tools_call_error_kinds() {
+        let config = AgentConfig::new("filesystem".to_string(), vec!["npx".to_string()]);
+        let mut server = AgentServer::new();
+
+        let err = server
+            .handle_tools_call(&config, Some(json!({"arguments": {}})))
+            .await
+            .unwrap_err();
+        let rpc_error = JsonRpcError::from_handler_error(&err);
+        assert_eq!(rpc_error.code, -32602);
+        assert_eq!(rpc_error.data.unwrap()["param"], "name");
+
+        let err = server
+            .handle_tools_call(
+                &config,
+                Some(json!({"name": "read_file", "arguments": {"path": "/tmp/a"}})),
+            )
+            .await
+            .unwrap_err();
+        let rpc_error = JsonRpcError::from_handler_error(&err);
+        assert_eq!(rpc_error.code, -32001);
+        let data = rpc_error.data.unwrap();
+        assert_eq!(data["kind"], "mcp_server_unavailable");
+        assert_eq!(data["retryable"], true);
+    }
+
+    #[test]
+    fn test_session_status() {
+        let mut server = AgentServer::new();
+        server.budget.check_call("filesystem", true).unwrap();
+
+        let status = server.handle_session_status().unwrap();
+
+        assert_eq!(status["budget"]["tool_calls"], 1);
+        assert_eq!(status["budget"]["red_actions"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_cache_hit_skips_mcp_client() {
+        let config = AgentConfig::new("filesystem".to_string(), vec!["npx".to_string()])
+            .with_cache(CacheConfig::enabled());
+        let mut server = AgentServer::new();
+        server.cache = ToolResultCache::new(config.cache.clone());
+        let arguments = json!({"path": "/tmp/a"});
+        let result = json!({ "content": [{ "type": "text", "text": "cached" }] });
+        server
+            .cache
+            .insert("filesystem", "read_file", &arguments, result.clone());
+
+        // No MCP client is connected, so only a cache hit can succeed
+        let response = server
+            .handle_tools_call(
+                &config,
+                Some(json!({"name": "read_file", "arguments": arguments})),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response["content"], result);
+        assert_eq!(response["cached"], true);
+        let status = server.handle_session_status().unwrap();
+        assert_eq!(status["cache"]["hits"], 1);
+        assert_eq!(status["budget"]["tool_calls"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_red_action_invalidates_cache() {
+        let config = AgentConfig::new("filesystem".to_string(), vec!["npx".to_string()])
+            .with_cache(CacheConfig::enabled());
+        let mut server = AgentServer::new();
+        server.cache = ToolResultCache::new(config.cache.clone());
+        server.cache.insert(
+            "filesystem",
+            "read_file",
+            &json!({"path": "/tmp/a"}),
+            json!({ "content": [] }),
+        );
+
+        // Fails without an MCP client, but only after invalidation
+        let _ = server
+            .handle_tools_call(
+                &config,
+                Some(json!({"name": "write_file", "arguments": {"path": "/tmp/a"}})),
+            )
+            .await;
+
+        assert!(server.cache.is_empty());
+        assert_eq!(server.cache.metrics().invalidations, 1);
+    }
+
+    /// Backend that always gives the same decision
+    #[derive(Debug)]
+    struct FixedBackend(BackendDecision);
+
+    #[async_trait::async_trait]
+    impl ApprovalBackend for FixedBackend {
+        fn name(&self) -> &str {
+            "fixed"
+        }
+
+        async fn request_approval(&self, _: &ApprovalRequest) -> anyhow::Result<BackendDecision> {
+            Ok(self.0.clone())
+        }
+    }
+
+    fn gated_server(decision: BackendDecision) -> (AgentConfig, AgentServer) {
+        let backend: Arc<dyn ApprovalBackend> = Arc::new(FixedBackend(decision));
+        let config = AgentConfig::new("filesystem".to_string(), vec!["npx".to_string()])
+            .with_approval_backend(backend.clone());
+        let mut server = AgentServer::new();
+        server.approvals = ApprovalManager::new().with_backend(backend);
+        (config, server)
+    }
+
+    fn plan_params(steps: &[(&str, serde_json::Value)]) -> Option<serde_json::Value> {
+        let steps: Vec<_> = steps
+            .iter()
+            .map(|(name, arguments)| json!({"name": name, "arguments": arguments}))
+            .collect();
+        Some(json!({"description": "Update configs", "steps": steps}))
+    }
+
+    #[tokio::test]
+    async fn test_tool_calls_follow_submitted_plan() {
+        let (config, mut server) =
+            gated_server(BackendDecision::local_user(ApprovalDecision::Approved));
+        let (first, second) = (json!({"path": "/tmp/a"}), json!({"path": "/tmp/b"}));
+
+        let status = server
+            .handle_plans_submit(
+                &config,
+                plan_params(&[
+                    ("write_file", first.clone()),
+                    ("write_file", second.clone()),
+                ]),
+            )
+            .await
+            .unwrap();
+        assert_eq!(status["steps"][0]["status"], "approved");
+        assert_eq!(status["steps"][1]["status"], "approved");
+
+        // Out of order, or with other arguments
+        for arguments in [&second, &json!({"path": "/etc/passwd"})] {
+            let err = server
+                .authorize_plan_call(&config, "write_file", arguments)
+                .await
+                .unwrap_err();
+            let data = JsonRpcError::from_handler_error(&err).data.unwrap();
+            assert_eq!(data["kind"], "approval_denied");
+            assert_eq!(data["source"], "plan");
+        }
+
+        let (ticket_id, amended) = server
+            .authorize_plan_call(&config, "write_file", &first)
+            .await
+            .unwrap();
+        assert_eq!(json!(ticket_id), status["steps"][0]["ticketId"]);
+        assert!(amended.is_none());
+        server
+            .authorize_plan_call(&config, "write_file", &second)
+            .await
+            .unwrap();
+        assert!(server.plan.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_plan_step_needing_quorum_pending() {
+        let (config, mut server) =
+            gated_server(BackendDecision::local_user(ApprovalDecision::Approved));
+        let quorum = crate::approval::QuorumConfig::new()
+            .with_group(crate::approval::ApproverGroup::new("ops", ["alice", "bob"]))
+            .with_rule(crate::approval::QuorumRule::new(
+                ActionType::DeleteFile.risk_level(),
+                "ops",
+                2,
+            ));
+        // Quorums need a trust list; the local user approves with a trusted key
+        let dir = tempfile::tempdir().unwrap();
+        let keyring = crate::approval::Keyring::open(dir.path());
+        let user = BackendDecision::local_user(ApprovalDecision::Approved).approved_by;
+        let mut trust = TrustList::in_memory();
+        trust.add(&user, &keyring.generate(&user).unwrap()).unwrap();
+        let prompt = crate::approval::PromptBackend::new(crate::approval::ApprovalPromptConfig {
+            interactive: false,
+            default_decision: ApprovalDecision::Approved,
+            ..Default::default()
+        })
+        .with_keyring(Some(keyring));
+        server.approvals = ApprovalManager::new()
+            .with_backend(Arc::new(prompt))
+            .with_trust_list(trust)
+            .with_quorum(quorum, crate::approval::QuorumStore::in_memory())
+            .unwrap();
+
+        let status = server
+            .handle_plans_submit(
+                &config,
+                plan_params(&[
+                    ("write_file", json!({"path": "/tmp/a"})),
+                    ("delete_file", json!({"path": "/tmp/b"})),
+                ]),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(status["steps"][0]["status"], "approved");
+        assert_eq!(status["steps"][1]["status"], "pending");
+        assert!(status["steps"][1]["reason"]
+            .as_str()
+            .unwrap()
+            .contains("more than one approver"));
+    }
+
+    #[tokio::test]
+    async fn test_plan_of_green_calls_refused() {
+        let (config, mut server) =
+            gated_server(BackendDecision::local_user(ApprovalDecision::Approved));
+
+        let err = server
+            .handle_plans_submit(&config, plan_params(&[("read_file", json!({}))]))
+            .await
+            .unwrap_err();
+
+        let data = JsonRpcError::from_handler_error(&err).data.unwrap();
+        assert_eq!(data["kind"], "invalid_params");
+        assert!(server.plan.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_tool_call_checkpointed_before_run() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("a.txt");
+        std::fs::write(&file, "old").unwrap();
+        let store = CheckpointStore::open(dir.path().join("checkpoints"));
+        let (config, mut server) =
+            gated_server(BackendDecision::local_user(ApprovalDecision::Approved));
+        server.approvals = std::mem::take(&mut server.approvals).with_checkpoints(store.clone());
+        let arguments = json!({"path": file.to_string_lossy(), "content": "new"});
+
+        let (ticket_id, _) = server
+            .approve_tool_call(&config, "write_file", &arguments, Vec::new())
+            .await
+            .unwrap();
+        let ticket_id = ticket_id.unwrap();
+        assert!(store.get(&ticket_id).unwrap().is_none());
+
+        // The file changed between approval and the call
+        std::fs::write(&file, "newer").unwrap();
+        let marker = server
+            .checkpoint_tool_call(&config, &ticket_id, &arguments)
+            .unwrap();
+        assert_eq!(marker["captured"], true);
+        assert_eq!(marker["files"], json!([file.to_string_lossy()]));
+        let checkpoint = store.get(&ticket_id).unwrap().unwrap();
+        assert_eq!(checkpoint.files[0].before.as_ref().unwrap().size, 5);
+
+        let marker = server
+            .checkpoint_tool_call(&config, &ticket_id, &json!({"content": "new"}))
+            .unwrap();
+        assert_eq!(
+            marker,
+            json!({"captured": false, "reason": "no file paths in arguments"})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_relative_paths_checkpointed_in_workspace() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.txt"), "old").unwrap();
+        let store = CheckpointStore::open(dir.path().join("checkpoints"));
+        let (config, mut server) =
+            gated_server(BackendDecision::local_user(ApprovalDecision::Approved));
+        server.approvals = std::mem::take(&mut server.approvals).with_checkpoints(store.clone());
+        let arguments = json!({"path": "a.txt", "content": "new"});
+
+        // Without a workspace the path's meaning is unknown
+        let (ticket_id, _) = server
+            .approve_tool_call(&config, "write_file", &arguments, Vec::new())
+            .await
+            .unwrap();
+        let err = server
+            .checkpoint_tool_call(&config, &ticket_id.unwrap(), &arguments)
+            .unwrap_err();
+        assert!(format!("{:#}", err).contains("relative path a.txt"));
+
+        let config = config.with_preview(PreviewConfig::new(dir.path()));
+        let (ticket_id, _) = server
+            .approve_tool_call(&config, "write_file", &arguments, Vec::new())
+            .await
+            .unwrap();
+        let marker = server
+            .checkpoint_tool_call(&config, &ticket_id.unwrap(), &arguments)
+            .unwrap();
+        let file = dir.path().canonicalize().unwrap().join("a.txt");
+        assert_eq!(marker["files"], json!([file.to_string_lossy()]));
+    }
+
+    #[tokio::test]
+    async fn test_red_tool_call_arguments_amended() {
+        let (config, mut server) = gated_server(BackendDecision {
+            modifications: vec![Modification::SetArgument {
+                pointer: "/path".to_string(),
+                value: json!("/tmp/out/a.txt"),
+            }],
+            ..BackendDecision::local_user(ApprovalDecision::Approved)
+        });
+        let arguments = json!({"path": "/etc/passwd", "content": "x"});
+
+        let (ticket_id, amended) = server
+            .approve_tool_call(&config, "write_file", &arguments, Vec::new())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            amended,
+            Some(json!({"path": "/tmp/out/a.txt", "content": "x"}))
+        );
+        let history = server.approvals.get_history();
+        assert_eq!(ticket_id.as_deref(), Some(history[0].id.as_str()));
+        let amendment = history[0].decision.amendment().unwrap();
+        assert_eq!(amendment.original_arguments.as_ref(), Some(&arguments));
+    }
+
+    #[tokio::test]
+    async fn test_amended_arguments_checked_against_policy() {
+        use crate::mcp::{ArgumentConstraint, ServerToolPolicy};
+        use std::collections::HashMap;
+
+        let (config, mut server) = gated_server(BackendDecision {
+            modifications: vec![Modification::SetArgument {
+                pointer: "/path".to_string(),
+                value: json!("/etc/passwd"),
+            }],
+            ..BackendDecision::local_user(ApprovalDecision::Approved)
+        });
+        let mut constraints = HashMap::new();
+        constraints.insert(
+            "write_file".to_string(),
+            vec![ArgumentConstraint::PathUnder {
+                argument: "path".to_string(),
+                roots: vec!["/tmp/out".to_string()],
+            }],
+        );
+        let config = config.with_tool_policy(ToolPolicy::new().with_server(
+            "filesystem",
+            ServerToolPolicy {
+                argument_constraints: constraints,
+                ..ServerToolPolicy::default()
+            },
+        ));
+
+        // No MCP client is connected, so reaching it would fail differently
+        let err = server
+            .handle_tools_call(
+                &config,
+                Some(json!({"name": "write_file", "arguments": {"path": "/tmp/out/a.txt"}})),
+            )
+            .await
+            .unwrap_err();
+
+        let data = JsonRpcError::from_handler_error(&err).data.unwrap();
+        assert_eq!(data["kind"], "approval_denied");
+        assert_eq!(data["source"], "policy");
+    }
+
+    #[tokio::test]
+    async fn test_tool_outcome_recorded_on_ticket() {
+        let (config, mut server) =
+            gated_server(BackendDecision::local_user(ApprovalDecision::Approved));
+        let arguments = json!({"path": "/tmp/a"});
+
+        let (ticket_id, _) = server
+            .approve_tool_call(&config, "write_file", &arguments, Vec::new())
+            .await
+            .unwrap();
+        let ticket_id = ticket_id.unwrap();
+        server.record_tool_outcome(
+            &ticket_id,
+            &Err(anyhow::anyhow!("disk full")),
+            Duration::from_millis(3),
+        );
+
+        let summary = server.approvals.audit_summary(&ticket_id).unwrap();
+        assert!(summary.contains("in 3 ms: failure (disk full)"));
+    }
+
+    #[tokio::test]
+    async fn test_failed_preview_shown_to_approver() {
+        let workspace = tempfile::tempdir().unwrap();
+        std::fs::write(workspace.path().join("a.txt"), "old").unwrap();
+        let preview = PreviewConfig::new(workspace.path());
+        let config = AgentConfig::new(
+            "filesystem".to_string(),
+            vec!["/nonexistent/mcp-server".to_string()],
+        )
+        .with_preview(preview.clone());
+
+        let path = workspace.path().join("a.txt");
+        let args = json!({"path": path.to_string_lossy()});
+        let run = preview_tool_call(&config, &preview, "write_file", &args).await;
+
+        assert!(run.result.is_none());
+        let overlay = run.overlay.unwrap();
+        assert!(overlay.path().join("a.txt").exists());
+        assert!(matches!(
+            &run.changes[..],
+            [Change::Custom { description }] if description.starts_with("Preview run failed")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_unconfined_call_not_previewed() {
+        let workspace = tempfile::tempdir().unwrap();
+        let preview = PreviewConfig::new(workspace.path());
+        let config = AgentConfig::new(
+            "filesystem".to_string(),
+            vec!["/nonexistent/mcp-server".to_string()],
+        )
+        .with_preview(preview.clone());
+
+        for (tool, args) in [
+            ("write_file", json!({"path": "/etc/hosts"})),
+            ("send_email", json!({"to": "a@example.com"})),
+        ] {
+            let run = preview_tool_call(&config, &preview, tool, &args).await;
+
+            assert!(run.overlay.is_none());
+            assert!(run.result.is_none());
+            assert!(matches!(
+                &run.changes[..],
+                [Change::Custom { description }] if description.starts_with("Preview unavailable")
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_denied_tool_call_never_reaches_server() {
+        let (config, mut server) = gated_server(BackendDecision {
+            justification: Some("not today".to_string()),
+            ..BackendDecision::local_user(ApprovalDecision::Denied)
+        });
+
+        // No MCP client is connected, so reaching it would fail differently
+        let err = server
+            .handle_tools_call(
+                &config,
+                Some(json!({"name": "write_file", "arguments": {"path": "/tmp/a"}})),
+            )
+            .await
+            .unwrap_err();
+
+        let data = JsonRpcError::from_handler_error(&err).data.unwrap();
+        assert_eq!(data["kind"], "approval_denied");
+        assert_eq!(data["source"], "approver");
+        assert_eq!(data["reason"], "not today");
+    }
+
+    /// Backend nobody answers
+    #[derive(Debug)]
+    struct SilentBackend;
+
+    #[async_trait::async_trait]
+    impl ApprovalBackend for SilentBackend {
+        fn name(&self) -> &str {
+            "silent"
+        }
+
+        async fn request_approval(&self, _: &ApprovalRequest) -> anyhow::Result<BackendDecision> {
+            Err(ApprovalTimedOut { timeout_secs: 5 }.into())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_timed_out_tool_call_reports_timeout() {
+        let backend: Arc<dyn ApprovalBackend> = Arc::new(SilentBackend);
+        let config = AgentConfig::new("filesystem".to_string(), vec!["npx".to_string()])
+            .with_approval_backend(backend.clone());
+        let mut server = AgentServer::new();
+        server.approvals = ApprovalManager::new().with_backend(backend);
+
+        let err = server
+            .handle_tools_call(
+                &config,
+                Some(json!({"name": "write_file", "arguments": {"path": "/tmp/a"}})),
+            )
+            .await
+            .unwrap_err();
+
+        let rpc_error = JsonRpcError::from_handler_error(&err);
+        assert_eq!(rpc_error.code, -32004);
+        let data = rpc_error.data.unwrap();
+        assert_eq!(data["kind"], "approval_timeout");
+        assert_eq!(data["timeout_secs"], 5);
+    }
+
+    #[tokio::test]
+    async fn test_deferred_tool_call_is_pending() {
+        let (config, mut server) = gated_server(BackendDecision::local_user(
+            ApprovalDecision::DeferredToLater,
+        ));
+        let call = json!({"name": "write_file", "arguments": {"path": "/tmp/a"}});
+
+        let err = server
+            .handle_tools_call(&config, Some(call.clone()))
+            .await
+            .unwrap_err();
+        let rpc_error = JsonRpcError::from_handler_error(&err);
+        assert_eq!(rpc_error.code, -32006);
+        let data = rpc_error.data.unwrap();
+        assert_eq!(data["kind"], "approval_pending");
+        assert_eq!(data["retryable"], true);
+        let id = data["approval_id"].as_str().unwrap().to_string();
+
+        let status = server
+            .handle_approval_status(Some(json!({ "approvalId": id })))
+            .unwrap();
+        assert_eq!(status["status"], "pending");
+        assert!(server.take_approval_notifications().is_empty());
+
+        // Retrying while pending reports the same approval, even after other
+        // actions were recorded
+        server
+            .approvals
+            .record_automatic_denial("Other action".to_string(), "policy".to_string())
+            .unwrap();
+        let err = server
+            .handle_tools_call(&config, Some(call))
+            .await
+            .unwrap_err();
+        let data = JsonRpcError::from_handler_error(&err).data.unwrap();
+        assert_eq!(data["approval_id"], id.as_str());
+
+        assert!(server
+            .handle_approval_status(Some(json!({ "approvalId": "missing" })))
+            .is_err());
+    }
+
+    #[test]
+    fn test_approval_status_of_decided_action() {
+        let item = DeferredAction {
+            status: DeferredStatus::Decided {
+                decision: ApprovalDecision::Denied,
+                decided_by: "bob".to_string(),
+                justification: Some("not now".to_string()),
+                decided_at: chrono::Utc::now(),
+                signature: None,
+            },
+            ..DeferredAction::new(
+                ActionType::EditFile,
+                "Call tool write_file on filesystem",
+                vec![],
+                "session",
+                "alice",
+            )
+        };
+
+        let status = approval_status(&item);
+
+        assert_eq!(status["approvalId"], item.id.as_str());
+        assert_eq!(status["status"], "denied");
+        assert_eq!(status["decidedBy"], "bob");
+        assert_eq!(status["justification"], "not now");
+    }
+
+    #[test]
+    fn test_handler_error_defaults_to_internal() {
+        let rpc_error = JsonRpcError::from_handler_error(&anyhow::anyhow!("boom"));
+        assert_eq!(rpc_error.code, -32603);
+        assert_eq!(rpc_error.data.unwrap()["kind"], "internal_error");
+    }
+
+    #[test]
+    fn test_tool_response_unaltered() {
+        let server = AgentServer::new();
+        let result = json!({ "content": [{ "type": "text", "text": "hello" }] });
+
+        let response = server.build_tool_response("read_file", result.clone());
+
+        assert_eq!(response["content"], result);
+        assert_eq!(response["contentAltered"], false);
+        assert!(response.get("sanitization").is_none());
+    }
+
+    #[test]
+    fn test_tool_response_error_propagated() {
+        let server = AgentServer::new();
+        let result = json!({
+            "content": [{ "type": "text", "text": "no such file" }],
+            "isError": true,
+        });
+
+        let response = server.build_tool_response("read_file", result);
+
+        assert_eq!(response["isError"], true);
+        let ok = server.build_tool_response("read_file", json!({ "content": [] }));
+        assert_eq!(ok["isError"], false);
+    }
+
+    #[test]
+    fn test_tool_response_altered() {
+        let server = AgentServer::new();
+        let result = json!({ "content": [{ "type": "text", "text": "\u{1b}[1mbold\u{1b}[0m" }] });
+
+        let response = server.build_tool_response("fetch", result);
+
+        assert_eq!(response["content"]["content"][0]["text"], "bold");
+        assert_eq!(response["contentAltered"], true);
+        assert_eq!(
+            response["sanitization"]["findings"][0]["kind"],
+            "control_chars_stripped"
+        );
+    }
+}
@@ -3,11 +3,13 @@
 //! This module classifies actions as "Green" (autonomous, safe) or "Red" (requires approval).
 //! Unknown actions default to RED for safety (fail-secure principle).
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::fmt;
+use std::str::FromStr;
 
 /// Action type with detailed classification
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
 pub enum ActionType {
     // Green Actions (Autonomous - no approval needed)
     /// Read file contents
@@ -74,6 +76,39 @@ pub enum ActionType {
 }
 
 impl ActionType {
+    /// All action types
+    pub const ALL: [ActionType; 29] = [
+        ActionType::ReadFile,
+        ActionType::ListDirectory,
+        ActionType::SearchWeb,
+        ActionType::CheckLogs,
+        ActionType::GetSystemInfo,
+        ActionType::ViewFile,
+        ActionType::DisplayInfo,
+        ActionType::Find,
+        ActionType::Query,
+        ActionType::Fetch,
+        ActionType::Inspect,
+        ActionType::Examine,
+        ActionType::Monitor,
+        ActionType::Status,
+        ActionType::CreateFile,
+        ActionType::EditFile,
+        ActionType::DeleteFile,
+        ActionType::ExecuteCommand,
+        ActionType::SendEmail,
+        ActionType::TransferAsset,
+        ActionType::ModifySystem,
+        ActionType::ExternalCall,
+        ActionType::RunScript,
+        ActionType::Deploy,
+        ActionType::Install,
+        ActionType::Commit,
+        ActionType::Push,
+        ActionType::Publish,
+        ActionType::Unknown,
+    ];
+
     /// Determine if this action type requires approval
     ///
     /// Returns:
@@ -262,8 +297,22 @@ impl fmt::Display for ActionType {
     }
 }
 
+impl FromStr for ActionType {
+    type Err = anyhow::Error;
+
+    /// Parse an action type name (e.g. "DeleteFile"), ignoring case
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|a| a.to_string().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| anyhow::anyhow!("Unknown action type '{}'", s))
+    }
+}
+
 /// Risk level of an action
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema,
+)]
 pub enum RiskLevel {
     /// No risk (green actions, read-only)
     None = 0,
@@ -289,6 +338,22 @@ impl fmt::Display for RiskLevel {
     }
 }
 
+impl FromStr for RiskLevel {
+    type Err = anyhow::Error;
+
+    /// Parse a risk level name (e.g. "high"), ignoring case
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "none" => Ok(RiskLevel::None),
+            "low" => Ok(RiskLevel::Low),
+            "medium" => Ok(RiskLevel::Medium),
+            "high" => Ok(RiskLevel::High),
+            "critical" => Ok(RiskLevel::Critical),
+            _ => anyhow::bail!("Unknown risk level '{}'", s),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -414,4 +479,19 @@ mod tests {
         assert!(RiskLevel::Medium < RiskLevel::High);
         assert!(RiskLevel::High < RiskLevel::Critical);
     }
+
+    #[test]
+    fn test_parse_names() {
+        for action in ActionType::ALL {
+            assert_eq!(action.to_string().parse::<ActionType>().unwrap(), action);
+        }
+        assert_eq!(
+            "deletefile".parse::<ActionType>().unwrap(),
+            ActionType::DeleteFile
+        );
+        assert!("Teleport".parse::<ActionType>().is_err());
+
+        assert_eq!("HIGH".parse::<RiskLevel>().unwrap(), RiskLevel::High);
+        assert!("severe".parse::<RiskLevel>().is_err());
+    }
 }
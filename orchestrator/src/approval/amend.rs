@@ -0,0 +1,579 @@
+//! Approve-with-Modifications
+//!
+//! Instead of approving a Diff Card as-is, an approver can amend it first:
+//! narrow a command's arguments, change an email recipient, reduce an asset
+//! transfer, drop individual changes from a batch, or edit the arguments of
+//! an MCP tool call. The result is an [`Amendment`], carried by
+//! [`ApprovalDecision::ApprovedWithModifications`](super::ApprovalDecision),
+//! which keeps both the original and the amended action for the audit trail.
+//!
+//! Command arguments can only be narrowed and amounts only reduced, so an
+//! amendment never widens what was requested.
+
+use super::diff::Change;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt;
+
+/// A single edit made by the approver
+///
+/// Change indices refer to the original list of changes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Modification {
+    /// Drop a change from the batch
+    DropChange {
+        /// Index of the change
+        index: usize,
+    },
+
+    /// Replace a command's arguments with a subsequence of them
+    NarrowArgs {
+        /// Index of the `CommandExec` change
+        index: usize,
+        /// Remaining arguments
+        args: Vec<String>,
+    },
+
+    /// Change an email/message recipient
+    SetRecipient {
+        /// Index of the `EmailSend` change
+        index: usize,
+        /// New recipient
+        to: String,
+    },
+
+    /// Reduce an asset transfer amount
+    ReduceAmount {
+        /// Index of the `AssetTransfer` change
+        index: usize,
+        /// New amount (not more than the original)
+        amount: String,
+    },
+
+    /// Set a tool call argument (JSON pointer, e.g. `/options/limit`)
+    SetArgument {
+        /// JSON pointer to the argument
+        pointer: String,
+        /// New value
+        value: serde_json::Value,
+    },
+
+    /// Remove a tool call argument (JSON pointer)
+    RemoveArgument {
+        /// JSON pointer to the argument
+        pointer: String,
+    },
+}
+
+impl Modification {
+    /// Whether the modification edits tool call arguments (rather than a
+    /// change)
+    pub fn is_argument(&self) -> bool {
+        matches!(
+            self,
+            Modification::SetArgument { .. } | Modification::RemoveArgument { .. }
+        )
+    }
+
+    /// Parse the prompt syntax
+    ///
+    /// - `drop <index>`
+    /// - `args <index> [arg...]`
+    /// - `to <index> <recipient>`
+    /// - `amount <index> <amount>`
+    /// - `set <pointer> <json value>` (bare words are taken as strings)
+    /// - `unset <pointer>`
+    pub fn parse(input: &str) -> Result<Self> {
+        let mut words = input.split_whitespace();
+        let op = words.next().context("Empty modification")?;
+        let mut index = || -> Result<usize> {
+            words
+                .next()
+                .context("Missing change index")?
+                .parse()
+                .context("Change index must be a number")
+        };
+
+        let modification = match op {
+            "drop" => Modification::DropChange { index: index()? },
+            "args" => Modification::NarrowArgs {
+                index: index()?,
+                args: words.map(str::to_string).collect(),
+            },
+            "to" => Modification::SetRecipient {
+                index: index()?,
+                to: words.next().context("Missing recipient")?.to_string(),
+            },
+            "amount" => Modification::ReduceAmount {
+                index: index()?,
+                amount: words.next().context("Missing amount")?.to_string(),
+            },
+            "set" => {
+                let pointer = words.next().context("Missing argument pointer")?;
+                let raw = words.collect::<Vec<_>>().join(" ");
+                if raw.is_empty() {
+                    anyhow::bail!("Missing argument value");
+                }
+                let value = serde_json::from_str(&raw).unwrap_or(serde_json::Value::String(raw));
+                Modification::SetArgument {
+                    pointer: pointer.to_string(),
+                    value,
+                }
+            }
+            "unset" => Modification::RemoveArgument {
+                pointer: words
+                    .next()
+                    .context("Missing argument pointer")?
+                    .to_string(),
+            },
+            other => anyhow::bail!("Unknown modification '{}'", other),
+        };
+        Ok(modification)
+    }
+}
+
+impl fmt::Display for Modification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Modification::DropChange { index } => write!(f, "drop change #{}", index),
+            Modification::NarrowArgs { index, args } => {
+                write!(
+                    f,
+                    "narrow args of change #{} to [{}]",
+                    index,
+                    args.join(" ")
+                )
+            }
+            Modification::SetRecipient { index, to } => {
+                write!(f, "set recipient of change #{} to {}", index, to)
+            }
+            Modification::ReduceAmount { index, amount } => {
+                write!(f, "reduce amount of change #{} to {}", index, amount)
+            }
+            Modification::SetArgument { pointer, value } => {
+                write!(f, "set argument {} to {}", pointer, value)
+            }
+            Modification::RemoveArgument { pointer } => write!(f, "remove argument {}", pointer),
+        }
+    }
+}
+
+/// An action as amended by the approver, with the original kept for audit
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Amendment {
+    /// Edits made by the approver
+    pub modifications: Vec<Modification>,
+
+    /// Changes as requested
+    pub original_changes: Vec<Change>,
+
+    /// Changes as amended
+    pub changes: Vec<Change>,
+
+    /// Tool call arguments as requested (tool calls only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original_arguments: Option<serde_json::Value>,
+
+    /// Tool call arguments as amended (tool calls only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub arguments: Option<serde_json::Value>,
+}
+
+impl Amendment {
+    /// Apply modifications to an action
+    ///
+    /// Drops are applied last, so indices always refer to the original
+    /// changes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a modification does not fit the action (bad
+    /// index, wrong change type, widened arguments or amount, missing
+    /// argument, a change of a tool call, which runs with its arguments
+    /// whatever its changes say) or if every change would be dropped.
+    pub fn apply(
+        changes: &[Change],
+        arguments: Option<&serde_json::Value>,
+        modifications: Vec<Modification>,
+    ) -> Result<Self> {
+        if modifications.is_empty() {
+            anyhow::bail!("No modifications given");
+        }
+
+        let mut amended = changes.to_vec();
+        let mut amended_arguments = arguments.cloned();
+        let mut dropped = Vec::new();
+
+        for modification in &modifications {
+            if arguments.is_some() && !modification.is_argument() {
+                anyhow::bail!(
+                    "Tool calls can only be amended through their arguments (set/unset), not '{}'",
+                    modification
+                );
+            }
+            match modification {
+                Modification::DropChange { index } => {
+                    change_at(&mut amended, *index)?;
+                    dropped.push(*index);
+                }
+                Modification::NarrowArgs { index, args } => {
+                    match change_at(&mut amended, *index)? {
+                        Change::CommandExec { args: current, .. } => {
+                            if !is_subsequence(args, current) {
+                                anyhow::bail!(
+                                "Arguments of change #{} can only be narrowed, not added or reordered",
+                                index
+                            );
+                            }
+                            *current = args.clone();
+                        }
+                        other => anyhow::bail!(wrong_type(*index, "command", other)),
+                    }
+                }
+                Modification::SetRecipient { index, to } => {
+                    match change_at(&mut amended, *index)? {
+                        Change::EmailSend { to: current, .. } => *current = to.clone(),
+                        other => anyhow::bail!(wrong_type(*index, "email/message", other)),
+                    }
+                }
+                Modification::ReduceAmount { index, amount } => {
+                    match change_at(&mut amended, *index)? {
+                        Change::AssetTransfer {
+                            amount: current, ..
+                        } => {
+                            match compare_amounts(amount, current) {
+                                Some(Ordering::Less | Ordering::Equal) => {}
+                                Some(Ordering::Greater) => anyhow::bail!(
+                                    "Amount of change #{} can only be reduced ({} > {})",
+                                    index,
+                                    amount,
+                                    current
+                                ),
+                                None => anyhow::bail!("Invalid amount '{}'", amount),
+                            }
+                            *current = amount.clone();
+                        }
+                        other => anyhow::bail!(wrong_type(*index, "asset transfer", other)),
+                    }
+                }
+                Modification::SetArgument { pointer, value } => {
+                    let arguments = amended_arguments
+                        .as_mut()
+                        .context("Only tool calls have arguments to modify")?;
+                    set_pointer(arguments, pointer, value.clone())?;
+                }
+                Modification::RemoveArgument { pointer } => {
+                    let arguments = amended_arguments
+                        .as_mut()
+                        .context("Only tool calls have arguments to modify")?;
+                    remove_pointer(arguments, pointer)?;
+                }
+            }
+        }
+
+        let amended: Vec<Change> = amended
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| !dropped.contains(i))
+            .map(|(_, c)| c)
+            .collect();
+        if amended.is_empty() && !changes.is_empty() {
+            anyhow::bail!("Amendment drops every change; deny the action instead");
+        }
+
+        Ok(Self {
+            modifications,
+            original_changes: changes.to_vec(),
+            changes: amended,
+            original_arguments: arguments.cloned(),
+            arguments: amended_arguments,
+        })
+    }
+}
+
+fn change_at(changes: &mut [Change], index: usize) -> Result<&mut Change> {
+    let len = changes.len();
+    changes
+        .get_mut(index)
+        .with_context(|| format!("No change #{} (action has {} changes)", index, len))
+}
+
+fn wrong_type(index: usize, expected: &str, change: &Change) -> String {
+    format!(
+        "Change #{} is a {}, not a {} change",
+        index,
+        change.change_type(),
+        expected
+    )
+}
+
+/// Check that `narrowed` keeps `original`'s order and adds nothing
+fn is_subsequence(narrowed: &[String], original: &[String]) -> bool {
+    let mut remaining = original.iter();
+    narrowed.iter().all(|arg| remaining.any(|o| o == arg))
+}
+
+/// Compare non-negative decimal strings exactly
+fn compare_amounts(a: &str, b: &str) -> Option<Ordering> {
+    fn split(s: &str) -> Option<(&str, &str)> {
+        let s = s.trim();
+        let (int, frac) = s.split_once('.').unwrap_or((s, ""));
+        let valid = !(int.is_empty() && frac.is_empty())
+            && int.bytes().all(|b| b.is_ascii_digit())
+            && frac.bytes().all(|b| b.is_ascii_digit());
+        valid.then(|| (int.trim_start_matches('0'), frac.trim_end_matches('0')))
+    }
+
+    let (a_int, a_frac) = split(a)?;
+    let (b_int, b_frac) = split(b)?;
+    Some(
+        a_int
+            .len()
+            .cmp(&b_int.len())
+            .then_with(|| a_int.cmp(b_int))
+            .then_with(|| a_frac.cmp(b_frac)),
+    )
+}
+
+/// Split a JSON pointer into its parent and (unescaped) last token
+fn split_pointer(pointer: &str) -> Result<(&str, String)> {
+    let (parent, last) = pointer
+        .rsplit_once('/')
+        .filter(|_| pointer.starts_with('/'))
+        .with_context(|| format!("Invalid argument pointer '{}'", pointer))?;
+    Ok((parent, last.replace("~1", "/").replace("~0", "~")))
+}
+
+fn set_pointer(
+    root: &mut serde_json::Value,
+    pointer: &str,
+    value: serde_json::Value,
+) -> Result<()> {
+    let (parent, key) = split_pointer(pointer)?;
+    match root.pointer_mut(parent) {
+        Some(serde_json::Value::Object(map)) => {
+            map.insert(key, value);
+        }
+        Some(serde_json::Value::Array(items)) => {
+            let slot = key
+                .parse::<usize>()
+                .ok()
+                .and_then(|i| items.get_mut(i))
+                .with_context(|| format!("No array element at {}", pointer))?;
+            *slot = value;
+        }
+        _ => anyhow::bail!("No argument object at {}", pointer),
+    }
+    Ok(())
+}
+
+fn remove_pointer(root: &mut serde_json::Value, pointer: &str) -> Result<()> {
+    let (parent, key) = split_pointer(pointer)?;
+    let removed = match root.pointer_mut(parent) {
+        Some(serde_json::Value::Object(map)) => map.remove(&key).is_some(),
+        Some(serde_json::Value::Array(items)) => match key.parse::<usize>() {
+            Ok(i) if i < items.len() => {
+                items.remove(i);
+                true
+            }
+            _ => false,
+        },
+        _ => false,
+    };
+    if !removed {
+        anyhow::bail!("No argument at {}", pointer);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn batch() -> Vec<Change> {
+        vec![
+            Change::CommandExec {
+                command: "rm".to_string(),
+                args: vec!["-r".to_string(), "-f".to_string(), "build".to_string()],
+                env_vars: None,
+            },
+            Change::EmailSend {
+                to: "all@example.com".to_string(),
+                subject: "Release".to_string(),
+                preview: "Shipping".to_string(),
+            },
+            Change::AssetTransfer {
+                from: "hot".to_string(),
+                to: "cold".to_string(),
+                amount: "10.5".to_string(),
+                currency: "ETH".to_string(),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_apply_change_modifications() {
+        let amendment = Amendment::apply(
+            &batch(),
+            None,
+            vec![
+                Modification::NarrowArgs {
+                    index: 0,
+                    args: vec!["-r".to_string(), "build".to_string()],
+                },
+                Modification::SetRecipient {
+                    index: 1,
+                    to: "team@example.com".to_string(),
+                },
+                Modification::ReduceAmount {
+                    index: 2,
+                    amount: "2".to_string(),
+                },
+                Modification::DropChange { index: 1 },
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(amendment.original_changes, batch());
+        assert_eq!(amendment.changes.len(), 2);
+        assert!(matches!(
+            &amendment.changes[0],
+            Change::CommandExec { args, .. } if args == &["-r", "build"]
+        ));
+        assert!(matches!(
+            &amendment.changes[1],
+            Change::AssetTransfer { amount, .. } if amount == "2"
+        ));
+    }
+
+    #[test]
+    fn test_rejects_widening() {
+        let add_arg = Modification::NarrowArgs {
+            index: 0,
+            args: vec!["-r".to_string(), "/".to_string()],
+        };
+        let reorder = Modification::NarrowArgs {
+            index: 0,
+            args: vec!["build".to_string(), "-r".to_string()],
+        };
+        let raise = Modification::ReduceAmount {
+            index: 2,
+            amount: "10.50001".to_string(),
+        };
+        let wrong_type = Modification::SetRecipient {
+            index: 0,
+            to: "x".to_string(),
+        };
+        let out_of_range = Modification::DropChange { index: 9 };
+
+        for modification in [add_arg, reorder, raise, wrong_type, out_of_range] {
+            assert!(
+                Amendment::apply(&batch(), None, vec![modification.clone()]).is_err(),
+                "{} should be rejected",
+                modification
+            );
+        }
+    }
+
+    #[test]
+    fn test_rejects_dropping_everything() {
+        let drops = (0..3)
+            .map(|index| Modification::DropChange { index })
+            .collect();
+        assert!(Amendment::apply(&batch(), None, drops).is_err());
+    }
+
+    #[test]
+    fn test_apply_argument_modifications() {
+        let arguments = json!({"path": "/data", "options": {"limit": 100, "force": true}});
+        let amendment = Amendment::apply(
+            &[],
+            Some(&arguments),
+            vec![
+                Modification::SetArgument {
+                    pointer: "/options/limit".to_string(),
+                    value: json!(10),
+                },
+                Modification::RemoveArgument {
+                    pointer: "/options/force".to_string(),
+                },
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(amendment.original_arguments, Some(arguments));
+        assert_eq!(
+            amendment.arguments,
+            Some(json!({"path": "/data", "options": {"limit": 10}}))
+        );
+
+        let missing = Modification::RemoveArgument {
+            pointer: "/nope".to_string(),
+        };
+        assert!(Amendment::apply(&[], Some(&json!({})), vec![missing.clone()]).is_err());
+        assert!(Amendment::apply(&batch(), None, vec![missing]).is_err());
+    }
+
+    #[test]
+    fn test_tool_call_changes_not_amendable() {
+        let arguments = json!({"path": "/data/a"});
+        for modification in [
+            Modification::DropChange { index: 0 },
+            Modification::NarrowArgs {
+                index: 0,
+                args: Vec::new(),
+            },
+        ] {
+            assert!(
+                Amendment::apply(&batch(), Some(&arguments), vec![modification.clone()]).is_err(),
+                "{} should be rejected",
+                modification
+            );
+        }
+    }
+
+    #[test]
+    fn test_compare_amounts() {
+        assert_eq!(compare_amounts("2", "10.5"), Some(Ordering::Less));
+        assert_eq!(compare_amounts("10.50", "10.5"), Some(Ordering::Equal));
+        assert_eq!(compare_amounts("010.6", "10.5"), Some(Ordering::Greater));
+        assert_eq!(compare_amounts(".5", "0.25"), Some(Ordering::Greater));
+        assert_eq!(compare_amounts("-1", "10"), None);
+        assert_eq!(compare_amounts("1e3", "10"), None);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            Modification::parse("drop 2").unwrap(),
+            Modification::DropChange { index: 2 }
+        );
+        assert_eq!(
+            Modification::parse("args 0 -r build").unwrap(),
+            Modification::NarrowArgs {
+                index: 0,
+                args: vec!["-r".to_string(), "build".to_string()]
+            }
+        );
+        assert_eq!(
+            Modification::parse("set /limit 10").unwrap(),
+            Modification::SetArgument {
+                pointer: "/limit".to_string(),
+                value: json!(10)
+            }
+        );
+        assert_eq!(
+            Modification::parse("set /name hello world").unwrap(),
+            Modification::SetArgument {
+                pointer: "/name".to_string(),
+                value: json!("hello world")
+            }
+        );
+        assert!(Modification::parse("drop x").is_err());
+        assert!(Modification::parse("explode 1").is_err());
+        assert!(Modification::parse("").is_err());
+    }
+}
@@ -0,0 +1,684 @@
+//! Anomaly Detection on Approval Patterns
+//!
+//! Builds baselines from the approval history and flags actions that do not
+//! fit them:
+//! - Per agent (the session owner requesting actions, see
+//!   [`ApprovalRecord::requested_by`]): action types requested, directories
+//!   touched, hours of the day and recent deletes
+//! - Per user (approver): action types decided, hours of the day and
+//!   approval latency
+//!
+//! Flags raised by the request (first-ever action type, unfamiliar
+//! directory, unusual hour, burst of deletes) are shown on the Diff Card.
+//! Flags raised by an approval (approved in under a second, an approver
+//! deciding an action type they never have before) are recorded with the
+//! decision. Optionally, flags escalate the risk score
+//! ([`AnomalyConfig::escalation_points`]) or require a second, different
+//! approver ([`AnomalyConfig::second_approver`]).
+//!
+//! "Never seen before" checks only apply once a baseline has
+//! [`AnomalyConfig::min_history`] records, so a new installation is not
+//! flagged constantly.
+
+use super::action::ActionType;
+use super::diff::DiffCard;
+use super::history::ApprovalRecord;
+use super::risk;
+use chrono::{DateTime, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::fmt;
+use std::path::Path;
+use std::time::Duration;
+
+/// Most unfamiliar directories listed in one flag
+const MAX_LISTED_DIRECTORIES: usize = 3;
+
+/// Anomaly detection configuration
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnomalyConfig {
+    /// Whether actions are checked at all
+    pub enabled: bool,
+
+    /// Records a baseline needs before "never seen before" checks apply
+    pub min_history: usize,
+
+    /// Window for counting deletes (seconds)
+    pub burst_window_secs: u64,
+
+    /// Deletes from one agent within the window that count as a burst
+    /// (including the current one)
+    pub burst_deletes: usize,
+
+    /// Approvals faster than this are flagged as rubber-stamping
+    /// (milliseconds)
+    pub rubber_stamp_ms: u64,
+
+    /// Risk score added per flag (0: flags are informational only)
+    pub escalation_points: u8,
+
+    /// Whether flagged approvals need a second, different approver
+    pub second_approver: bool,
+}
+
+impl Default for AnomalyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_history: 20,
+            burst_window_secs: 300,
+            burst_deletes: 5,
+            rubber_stamp_ms: 1000,
+            escalation_points: 0,
+            second_approver: false,
+        }
+    }
+}
+
+/// Kind of anomaly
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnomalyKind {
+    /// Action type never requested by the agent (or decided by the user)
+    NewActionType,
+
+    /// Files in directories the agent never touched
+    UnfamiliarPath,
+
+    /// Request or decision outside the usual hours
+    UnusualHour,
+
+    /// Many deletes from one agent in a short time
+    DeleteBurst,
+
+    /// Approval given too quickly to have been reviewed
+    RubberStamp,
+}
+
+/// A flagged deviation from the baseline
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Anomaly {
+    /// Kind of anomaly
+    pub kind: AnomalyKind,
+
+    /// Human-readable explanation (shown on the Diff Card)
+    pub detail: String,
+}
+
+impl Anomaly {
+    fn new(kind: AnomalyKind, detail: String) -> Self {
+        Self { kind, detail }
+    }
+}
+
+impl fmt::Display for Anomaly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.detail)
+    }
+}
+
+/// Usual behaviour of one agent or user (or everyone)
+#[derive(Debug, Clone, Default)]
+pub struct Baseline {
+    /// Records the baseline was built from
+    pub records: usize,
+
+    /// Number of records per action type
+    pub action_types: HashMap<ActionType, usize>,
+
+    /// Directories of the files touched
+    pub directories: BTreeSet<String>,
+
+    /// Number of records per hour of the day (UTC)
+    pub hours: [usize; 24],
+
+    /// Number of approvals per latency (milliseconds)
+    pub latencies_ms: BTreeMap<u64, usize>,
+}
+
+impl Baseline {
+    fn add(&mut self, record: &ApprovalRecord, action_type: ActionType) {
+        self.records += 1;
+        *self.action_types.entry(action_type).or_default() += 1;
+        self.directories
+            .extend(record.paths.iter().filter_map(|p| directory(p)));
+        self.hours[record.timestamp.hour() as usize] += 1;
+        if let Some(latency) = record.latency_ms {
+            *self.latencies_ms.entry(latency).or_default() += 1;
+        }
+    }
+
+    /// Median approval latency, if any latencies were recorded
+    pub fn median_latency(&self) -> Option<Duration> {
+        let n: usize = self.latencies_ms.values().sum();
+        let mut below = 0;
+        for (latency, count) in &self.latencies_ms {
+            below += count;
+            if below > n / 2 {
+                return Some(Duration::from_millis(*latency));
+            }
+        }
+        None
+    }
+}
+
+/// Baselines built from an approval history
+#[derive(Debug, Clone)]
+pub struct Baselines {
+    /// Every classified decision
+    pub overall: Baseline,
+
+    /// Decisions by the agent that requested them
+    pub by_agent: BTreeMap<String, Baseline>,
+
+    /// Decisions by the user that made them (automatic decisions excluded)
+    pub by_user: BTreeMap<String, Baseline>,
+
+    /// Times of each agent's delete requests within the burst window of
+    /// their latest one, oldest first
+    deletes: BTreeMap<String, VecDeque<DateTime<Utc>>>,
+
+    /// How long deletes are kept for burst checks
+    burst_window: chrono::Duration,
+}
+
+impl Default for Baselines {
+    fn default() -> Self {
+        Self::new(&AnomalyConfig::default())
+    }
+}
+
+impl Baselines {
+    /// Empty baselines for checks with `config`
+    pub fn new(config: &AnomalyConfig) -> Self {
+        Self {
+            overall: Baseline::default(),
+            by_agent: BTreeMap::new(),
+            by_user: BTreeMap::new(),
+            deletes: BTreeMap::new(),
+            burst_window: chrono::Duration::seconds(config.burst_window_secs as i64),
+        }
+    }
+
+    /// Build baselines for checks with `config` from history records
+    ///
+    /// Records without an action type (policy denials) are skipped.
+    pub fn build<'a>(
+        config: &AnomalyConfig,
+        records: impl IntoIterator<Item = &'a ApprovalRecord>,
+    ) -> Self {
+        let mut baselines = Self::new(config);
+        for record in records {
+            baselines.add(record);
+        }
+        baselines
+    }
+
+    /// Add a newly recorded decision
+    ///
+    /// Records without an action type (policy denials) are skipped.
+    pub fn add(&mut self, record: &ApprovalRecord) {
+        let Some(action_type) = record.action_type else {
+            return;
+        };
+        self.overall.add(record, action_type);
+
+        if let Some(agent) = &record.requested_by {
+            self.by_agent
+                .entry(agent.clone())
+                .or_default()
+                .add(record, action_type);
+            if action_type == ActionType::DeleteFile {
+                // Records may come in any order (history is newest first)
+                let deletes = self.deletes.entry(agent.clone()).or_default();
+                let at = deletes.partition_point(|t| *t <= record.timestamp);
+                deletes.insert(at, record.timestamp);
+                let latest = *deletes.back().unwrap_or(&record.timestamp);
+                while deletes
+                    .front()
+                    .is_some_and(|t| *t < latest - self.burst_window)
+                {
+                    deletes.pop_front();
+                }
+            }
+        }
+
+        // Standing grants and automatic decisions are not user decisions
+        if record.origin_record_id.is_some() {
+            return;
+        }
+        let users: Vec<&str> = if record.votes.is_empty() {
+            vec![record.approved_by.as_str()]
+        } else {
+            record.votes.iter().map(|v| v.approver.as_str()).collect()
+        };
+        for user in users.into_iter().filter(|u| *u != "system") {
+            self.by_user
+                .entry(user.to_string())
+                .or_default()
+                .add(record, action_type);
+        }
+    }
+}
+
+impl AnomalyConfig {
+    /// Flags raised by an action `agent` requests at `now`
+    pub fn check_request(
+        &self,
+        baselines: &Baselines,
+        card: &DiffCard,
+        agent: &str,
+        now: DateTime<Utc>,
+    ) -> Vec<Anomaly> {
+        if !self.enabled {
+            return Vec::new();
+        }
+        let mut anomalies = Vec::new();
+        let action = card.action_type;
+        let established = baselines
+            .by_agent
+            .get(agent)
+            .filter(|b| b.records >= self.min_history);
+
+        if baselines.overall.records >= self.min_history
+            && !baselines.overall.action_types.contains_key(&action)
+        {
+            anomalies.push(Anomaly::new(
+                AnomalyKind::NewActionType,
+                format!("First-ever {} action", action),
+            ));
+        } else if established.is_some_and(|b| !b.action_types.contains_key(&action)) {
+            anomalies.push(Anomaly::new(
+                AnomalyKind::NewActionType,
+                format!("First {} action requested by {}", action, agent),
+            ));
+        }
+
+        if let Some(baseline) = established.filter(|b| !b.directories.is_empty()) {
+            let unfamiliar: BTreeSet<String> = card
+                .changes
+                .iter()
+                .filter_map(|c| c.path().and_then(directory))
+                .filter(|d| !baseline.directories.contains(d))
+                .collect();
+            if !unfamiliar.is_empty() {
+                let mut listed: Vec<String> = unfamiliar
+                    .iter()
+                    .take(MAX_LISTED_DIRECTORIES)
+                    .cloned()
+                    .collect();
+                if unfamiliar.len() > MAX_LISTED_DIRECTORIES {
+                    listed.push(format!(
+                        "{} more",
+                        unfamiliar.len() - MAX_LISTED_DIRECTORIES
+                    ));
+                }
+                anomalies.push(Anomaly::new(
+                    AnomalyKind::UnfamiliarPath,
+                    format!(
+                        "Touches directories {} has not touched before: {}",
+                        agent,
+                        listed.join(", ")
+                    ),
+                ));
+            }
+        }
+
+        if established.is_some_and(|b| b.hours[now.hour() as usize] == 0) {
+            anomalies.push(Anomaly::new(
+                AnomalyKind::UnusualHour,
+                format!(
+                    "Requested at {:02}:00 UTC, outside {}'s usual hours",
+                    now.hour(),
+                    agent
+                ),
+            ));
+        }
+
+        // As recorded deletes are counted (records keep no changes)
+        if action == ActionType::DeleteFile {
+            let since = now - chrono::Duration::seconds(self.burst_window_secs as i64);
+            let recent = baselines.deletes.get(agent).map_or(0, |deletes| {
+                deletes.iter().filter(|at| **at >= since).count()
+            }) + 1;
+            if recent >= self.burst_deletes {
+                anomalies.push(Anomaly::new(
+                    AnomalyKind::DeleteBurst,
+                    format!(
+                        "{} delete actions from {} in the last {} minute(s)",
+                        recent,
+                        agent,
+                        self.burst_window_secs.div_ceil(60)
+                    ),
+                ));
+            }
+        }
+
+        anomalies
+    }
+
+    /// Flags raised by `approver` approving an action after `latency`
+    pub fn check_approval(
+        &self,
+        baselines: &Baselines,
+        action_type: ActionType,
+        approver: &str,
+        latency: Duration,
+        now: DateTime<Utc>,
+    ) -> Vec<Anomaly> {
+        if !self.enabled {
+            return Vec::new();
+        }
+        let mut anomalies = Vec::new();
+        let baseline = baselines.by_user.get(approver);
+
+        if latency < Duration::from_millis(self.rubber_stamp_ms) {
+            let usual = baseline
+                .and_then(Baseline::median_latency)
+                .map(|median| format!(" (usually {:.1}s)", median.as_secs_f64()))
+                .unwrap_or_default();
+            anomalies.push(Anomaly::new(
+                AnomalyKind::RubberStamp,
+                format!(
+                    "Approved by {} in {:.1}s{}",
+                    approver,
+                    latency.as_secs_f64(),
+                    usual
+                ),
+            ));
+        }
+
+        if let Some(baseline) = baseline.filter(|b| b.records >= self.min_history) {
+            if !baseline.action_types.contains_key(&action_type) {
+                anomalies.push(Anomaly::new(
+                    AnomalyKind::NewActionType,
+                    format!(
+                        "{} has not decided a {} action before",
+                        approver, action_type
+                    ),
+                ));
+            }
+            if baseline.hours[now.hour() as usize] == 0 {
+                anomalies.push(Anomaly::new(
+                    AnomalyKind::UnusualHour,
+                    format!(
+                        "Approved at {:02}:00 UTC, outside {}'s usual hours",
+                        now.hour(),
+                        approver
+                    ),
+                ));
+            }
+        }
+
+        anomalies
+    }
+
+    /// Show flags on a Diff Card, escalating its risk if configured
+    pub fn flag(&self, card: &mut DiffCard, anomalies: &[Anomaly]) {
+        if anomalies.is_empty() {
+            return;
+        }
+        card.anomalies
+            .extend(anomalies.iter().map(ToString::to_string));
+
+        if self.escalation_points > 0 {
+            let points = (anomalies.len() * self.escalation_points as usize).min(100) as u8;
+            card.risk_score = card.risk_score.saturating_add(points).min(100);
+            card.risk_level = card.risk_level.max(risk::level_for(card.risk_score));
+            card.risk_reasons
+                .push(format!("{} anomaly flag(s) (+{})", anomalies.len(), points));
+        }
+    }
+}
+
+/// Directory of a file path
+fn directory(path: &str) -> Option<String> {
+    Path::new(path)
+        .parent()
+        .map(|dir| dir.to_string_lossy().into_owned())
+        .filter(|dir| !dir.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::approval::diff::Change;
+    use crate::approval::history::ApprovalDecision;
+    use chrono::TimeZone;
+
+    fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 3, 2, hour, minute, 0).unwrap()
+    }
+
+    fn record(action_type: ActionType, path: &str, timestamp: DateTime<Utc>) -> ApprovalRecord {
+        ApprovalRecord {
+            id: uuid::Uuid::new_v4().to_string(),
+            timestamp,
+            action_description: format!("{} {}", action_type, path),
+            action_type: Some(action_type),
+            decision: ApprovalDecision::Approved,
+            approved_by: "alice".to_string(),
+            justification: None,
+            execution_result: None,
+            votes: Vec::new(),
+            grant_id: None,
+            origin_record_id: None,
+            deferred_id: None,
+            plan_id: None,
+            requested_by: Some("agent".to_string()),
+            session_id: None,
+            paths: vec![path.to_string()],
+            command: None,
+            latency_ms: Some(8000),
+            anomalies: Vec::new(),
+            timeout: None,
+            diff_card_digest: None,
+            signatures: Vec::new(),
+        }
+    }
+
+    /// Edits in /work during office hours
+    fn history() -> Vec<ApprovalRecord> {
+        (0..30)
+            .map(|i| record(ActionType::EditFile, "/work/src/main.rs", at(9 + i % 8, 0)))
+            .collect()
+    }
+
+    fn card(action_type: ActionType, changes: Vec<Change>) -> DiffCard {
+        DiffCard::new(action_type, "test".to_string(), changes)
+    }
+
+    #[test]
+    fn test_no_flags_for_usual_action() {
+        let baselines = Baselines::build(&AnomalyConfig::default(), &history());
+        let card = card(
+            ActionType::EditFile,
+            vec![Change::file_edit("/work/src/lib.rs", "a", "b")],
+        );
+
+        let config = AnomalyConfig::default();
+        assert!(config
+            .check_request(&baselines, &card, "agent", at(10, 30))
+            .is_empty());
+        assert!(config
+            .check_approval(
+                &baselines,
+                ActionType::EditFile,
+                "alice",
+                Duration::from_secs(5),
+                at(10, 30)
+            )
+            .is_empty());
+    }
+
+    #[test]
+    fn test_request_flags() {
+        let baselines = Baselines::build(&AnomalyConfig::default(), &history());
+        let card = card(
+            ActionType::TransferAsset,
+            vec![Change::AssetTransfer {
+                from: "ops".to_string(),
+                to: "unknown".to_string(),
+                amount: "10".to_string(),
+                currency: "ETH".to_string(),
+            }],
+        );
+
+        let anomalies =
+            AnomalyConfig::default().check_request(&baselines, &card, "agent", at(3, 0));
+        let details: Vec<String> = anomalies.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            details,
+            [
+                "First-ever TransferAsset action",
+                "Requested at 03:00 UTC, outside agent's usual hours"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unfamiliar_directories() {
+        let baselines = Baselines::build(&AnomalyConfig::default(), &history());
+        let card = card(
+            ActionType::EditFile,
+            vec![
+                Change::file_edit("/work/src/lib.rs", "a", "b"),
+                Change::file_edit("/home/alice/.ssh/config", "a", "b"),
+            ],
+        );
+
+        let anomalies =
+            AnomalyConfig::default().check_request(&baselines, &card, "agent", at(10, 0));
+        assert_eq!(anomalies.len(), 1);
+        assert_eq!(anomalies[0].kind, AnomalyKind::UnfamiliarPath);
+        assert!(anomalies[0].detail.ends_with("/home/alice/.ssh"));
+    }
+
+    #[test]
+    fn test_delete_burst() {
+        let mut records = history();
+        for minute in 0..4 {
+            records.push(record(
+                ActionType::DeleteFile,
+                "/work/tmp/x",
+                at(10, minute),
+            ));
+        }
+        let baselines = Baselines::build(&AnomalyConfig::default(), &records);
+        let delete = card(
+            ActionType::DeleteFile,
+            vec![Change::FileDelete {
+                path: "/work/tmp/y".to_string(),
+                size_bytes: 1,
+            }],
+        );
+
+        let config = AnomalyConfig::default();
+        let anomalies = config.check_request(&baselines, &delete, "agent", at(10, 4));
+        assert_eq!(anomalies.len(), 1);
+        assert_eq!(
+            anomalies[0].detail,
+            "5 delete actions from agent in the last 5 minute(s)"
+        );
+
+        // Only delete actions count, as only they are recorded as deletes
+        let command = card(ActionType::ExecuteCommand, delete.changes.clone());
+        assert!(config
+            .check_request(&baselines, &command, "agent", at(10, 4))
+            .iter()
+            .all(|a| a.kind != AnomalyKind::DeleteBurst));
+
+        // The window has passed; other agents are counted separately
+        assert!(config
+            .check_request(&baselines, &delete, "agent", at(10, 30))
+            .is_empty());
+        assert!(config
+            .check_request(&baselines, &delete, "other", at(10, 4))
+            .is_empty());
+
+        // Only deletes within the window of the latest are kept, whatever
+        // order the records come in
+        let mut later = records.clone();
+        later.push(record(ActionType::DeleteFile, "/work/tmp/z", at(10, 20)));
+        later.reverse();
+        let baselines = Baselines::build(&config, &later);
+        assert_eq!(baselines.deletes["agent"], [at(10, 20)]);
+    }
+
+    #[test]
+    fn test_baselines_updated_per_record() {
+        let mut records = history();
+        for (record, latency) in records.iter_mut().zip([500, 9000, 3000, 3000]) {
+            record.latency_ms = Some(latency);
+        }
+
+        let mut baselines = Baselines::default();
+        for record in &records {
+            baselines.add(record);
+        }
+        let built = Baselines::build(&AnomalyConfig::default(), &records);
+        assert_eq!(baselines.overall.records, built.overall.records);
+        assert_eq!(baselines.overall.latencies_ms, built.overall.latencies_ms);
+        assert_eq!(
+            baselines.by_user["alice"].hours,
+            built.by_user["alice"].hours
+        );
+
+        // 500, 3000, 3000, then 26 x 8000
+        assert_eq!(
+            baselines.overall.median_latency(),
+            Some(Duration::from_millis(8000))
+        );
+        let first: Vec<_> = records[..4].iter().collect();
+        assert_eq!(
+            Baselines::build(&AnomalyConfig::default(), first)
+                .overall
+                .median_latency(),
+            Some(Duration::from_millis(3000))
+        );
+    }
+
+    #[test]
+    fn test_rubber_stamp_and_new_approver_action() {
+        let baselines = Baselines::build(&AnomalyConfig::default(), &history());
+
+        let anomalies = AnomalyConfig::default().check_approval(
+            &baselines,
+            ActionType::DeleteFile,
+            "alice",
+            Duration::from_millis(400),
+            at(10, 0),
+        );
+        let details: Vec<String> = anomalies.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            details,
+            [
+                "Approved by alice in 0.4s (usually 8.0s)",
+                "alice has not decided a DeleteFile action before"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_flags_escalate_risk() {
+        let mut card = card(ActionType::EditFile, vec![]);
+        let score = card.risk_score;
+        let anomalies = [Anomaly::new(
+            AnomalyKind::UnusualHour,
+            "Requested at 03:00 UTC".to_string(),
+        )];
+
+        AnomalyConfig::default().flag(&mut card, &anomalies);
+        assert_eq!(card.anomalies, ["Requested at 03:00 UTC"]);
+        assert_eq!(card.risk_score, score);
+
+        let config = AnomalyConfig {
+            escalation_points: 20,
+            ..AnomalyConfig::default()
+        };
+        config.flag(&mut card, &anomalies);
+        assert_eq!(card.risk_score, score + 20);
+        assert_eq!(card.risk_level, risk::level_for(score + 20));
+        assert_eq!(card.risk_reasons.last().unwrap(), "1 anomaly flag(s) (+20)");
+    }
+}
@@ -0,0 +1,502 @@
+//! Audit Trail Queries and Export
+//!
+//! An [`AuditQuery`] selects approval records by time range, user, action
+//! type, risk level and decision. Matching records can be summarized per
+//! day ([`DailyStats`]) or exported for SIEM ingestion in one of the
+//! [`AuditFormat`]s:
+//!
+//! - `jsonl`: one JSON object per line (the record and its execution outcome)
+//! - `csv`: one row per record, after a header row
+//! - `cef`: RFC 5424 syslog messages carrying ArcSight CEF events
+//!
+//! Queries run against [`ApprovalHistory`](super::ApprovalHistory) (see
+//! [`ApprovalHistory::query`](super::ApprovalHistory::query)), and from the
+//! command line with `luminaguard audit query|stats|export`.
+
+use super::action::{ActionType, RiskLevel};
+use super::history::{ApprovalDecision, ApprovalRecord, ExecutionOutcome};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+use std::fmt;
+use std::str::FromStr;
+
+/// Syslog facility of exported CEF events (13: log audit)
+const SYSLOG_FACILITY: u8 = 13;
+
+/// Decision filter (amended approvals count as approved)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DecisionKind {
+    /// Approved, as-is or with modifications
+    Approved,
+    /// Denied
+    Denied,
+    /// Deferred for later decision
+    Deferred,
+}
+
+impl DecisionKind {
+    /// Kind of a decision
+    pub fn of(decision: &ApprovalDecision) -> Self {
+        match decision {
+            ApprovalDecision::Approved | ApprovalDecision::ApprovedWithModifications(_) => {
+                DecisionKind::Approved
+            }
+            ApprovalDecision::Denied => DecisionKind::Denied,
+            ApprovalDecision::DeferredToLater => DecisionKind::Deferred,
+        }
+    }
+
+    /// Lowercase label ("approved", "denied", "deferred")
+    pub fn label(self) -> &'static str {
+        match self {
+            DecisionKind::Approved => "approved",
+            DecisionKind::Denied => "denied",
+            DecisionKind::Deferred => "deferred",
+        }
+    }
+}
+
+impl fmt::Display for DecisionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+impl FromStr for DecisionKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "approved" | "approve" => Ok(DecisionKind::Approved),
+            "denied" | "deny" => Ok(DecisionKind::Denied),
+            "deferred" | "defer" => Ok(DecisionKind::Deferred),
+            _ => anyhow::bail!("Unknown decision '{}' (approved, denied or deferred)", s),
+        }
+    }
+}
+
+/// Filters selecting approval records
+///
+/// Unset filters match every record. Records without an action type (e.g.
+/// policy denials) never match an action type or risk level filter.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AuditQuery {
+    /// Only records at or after this time
+    pub since: Option<DateTime<Utc>>,
+
+    /// Only records before this time
+    pub until: Option<DateTime<Utc>>,
+
+    /// Only records decided by (or with a vote from) this user
+    pub user: Option<String>,
+
+    /// Only records of this action type
+    pub action_type: Option<ActionType>,
+
+    /// Only records of this risk level
+    pub risk_level: Option<RiskLevel>,
+
+    /// Only records with this decision
+    pub decision: Option<DecisionKind>,
+}
+
+impl AuditQuery {
+    /// Query matching every record
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only records at or after `since`
+    pub fn with_since(mut self, since: DateTime<Utc>) -> Self {
+        self.since = Some(since);
+        self
+    }
+
+    /// Only records before `until`
+    pub fn with_until(mut self, until: DateTime<Utc>) -> Self {
+        self.until = Some(until);
+        self
+    }
+
+    /// Only records decided by (or with a vote from) `user`
+    pub fn with_user(mut self, user: impl Into<String>) -> Self {
+        self.user = Some(user.into());
+        self
+    }
+
+    /// Only records of `action_type`
+    pub fn with_action_type(mut self, action_type: ActionType) -> Self {
+        self.action_type = Some(action_type);
+        self
+    }
+
+    /// Only records of `risk_level`
+    pub fn with_risk_level(mut self, risk_level: RiskLevel) -> Self {
+        self.risk_level = Some(risk_level);
+        self
+    }
+
+    /// Only records with `decision`
+    pub fn with_decision(mut self, decision: DecisionKind) -> Self {
+        self.decision = Some(decision);
+        self
+    }
+
+    /// Whether a record passes every filter
+    pub fn matches(&self, record: &ApprovalRecord) -> bool {
+        self.since.is_none_or(|t| record.timestamp >= t)
+            && self.until.is_none_or(|t| record.timestamp < t)
+            && self.user.as_deref().is_none_or(|user| {
+                record.approved_by == user || record.votes.iter().any(|v| v.approver == user)
+            })
+            && self
+                .action_type
+                .is_none_or(|a| record.action_type == Some(a))
+            && self
+                .risk_level
+                .is_none_or(|r| record.risk_level() == Some(r))
+            && self
+                .decision
+                .is_none_or(|d| DecisionKind::of(&record.decision) == d)
+    }
+}
+
+/// Decision counts for one day (UTC)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct DailyStats {
+    /// The day
+    pub day: NaiveDate,
+
+    /// Approved decisions (including amended approvals)
+    pub approved: usize,
+
+    /// Denied decisions
+    pub denied: usize,
+
+    /// Deferred decisions
+    pub deferred: usize,
+}
+
+impl DailyStats {
+    /// All decisions of the day
+    pub fn total(&self) -> usize {
+        self.approved + self.denied + self.deferred
+    }
+}
+
+/// Export format for SIEM ingestion
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditFormat {
+    /// JSON lines: one record (with its outcome) per line
+    Jsonl,
+    /// Comma-separated values with a header row
+    Csv,
+    /// RFC 5424 syslog messages with a CEF payload, one per line
+    Cef,
+}
+
+impl fmt::Display for AuditFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuditFormat::Jsonl => write!(f, "jsonl"),
+            AuditFormat::Csv => write!(f, "csv"),
+            AuditFormat::Cef => write!(f, "cef"),
+        }
+    }
+}
+
+impl FromStr for AuditFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "jsonl" | "json-lines" => Ok(AuditFormat::Jsonl),
+            "csv" => Ok(AuditFormat::Csv),
+            "cef" | "syslog" => Ok(AuditFormat::Cef),
+            _ => anyhow::bail!("Unknown audit format '{}' (jsonl, csv or cef)", s),
+        }
+    }
+}
+
+/// Audit log entry: a decision and its outcome
+#[derive(Debug, Serialize)]
+pub(super) struct AuditEntry<'a> {
+    #[serde(flatten)]
+    pub record: &'a ApprovalRecord,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub outcome: Option<&'a ExecutionOutcome>,
+}
+
+/// Format entries, one line per entry (plus a header for CSV)
+pub(super) fn export(entries: &[AuditEntry<'_>], format: AuditFormat) -> anyhow::Result<String> {
+    let mut out = String::new();
+    if format == AuditFormat::Csv {
+        out.push_str(CSV_HEADER);
+        out.push('\n');
+    }
+    for entry in entries {
+        let line = match format {
+            AuditFormat::Jsonl => serde_json::to_string(entry)?,
+            AuditFormat::Csv => csv_row(entry),
+            AuditFormat::Cef => cef_event(entry),
+        };
+        out.push_str(&line);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+const CSV_HEADER: &str = "id,timestamp,action_type,risk_level,decision,approved_by,\
+action_description,justification,executed_at,success,duration_ms,result_digest";
+
+fn csv_row(entry: &AuditEntry<'_>) -> String {
+    let record = entry.record;
+    let outcome = entry.outcome;
+    let fields = [
+        record.id.clone(),
+        record.timestamp.to_rfc3339(),
+        record
+            .action_type
+            .map(|a| a.to_string())
+            .unwrap_or_default(),
+        record
+            .risk_level()
+            .map(|r| r.to_string())
+            .unwrap_or_default(),
+        DecisionKind::of(&record.decision).to_string(),
+        record.approved_by.clone(),
+        record.action_description.clone(),
+        record.justification.clone().unwrap_or_default(),
+        outcome
+            .map(|o| o.executed_at.to_rfc3339())
+            .unwrap_or_default(),
+        outcome.map(|o| o.success.to_string()).unwrap_or_default(),
+        outcome
+            .map(|o| o.duration_ms.to_string())
+            .unwrap_or_default(),
+        outcome
+            .and_then(|o| o.result_digest.clone())
+            .unwrap_or_default(),
+    ];
+    fields
+        .iter()
+        .map(|f| csv_field(f))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Quote a CSV field if needed (RFC 4180)
+///
+/// Fields that a spreadsheet would read as a formula (starting with `=`,
+/// `+`, `-`, `@`, tab or CR) are prefixed with `'`.
+fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+fn cef_event(entry: &AuditEntry<'_>) -> String {
+    let record = entry.record;
+    let kind = DecisionKind::of(&record.decision);
+
+    // Syslog severity: warning for denials, notice for approvals
+    let syslog_severity = match kind {
+        DecisionKind::Denied => 4,
+        DecisionKind::Approved => 5,
+        DecisionKind::Deferred => 6,
+    };
+    let severity = match record.risk_level() {
+        Some(RiskLevel::None) => 0,
+        Some(RiskLevel::Low) => 3,
+        Some(RiskLevel::Medium) | None => 5,
+        Some(RiskLevel::High) => 8,
+        Some(RiskLevel::Critical) => 10,
+    };
+
+    let mut extensions = vec![
+        ("rt", record.timestamp.timestamp_millis().to_string()),
+        ("externalId", record.id.clone()),
+        ("suser", record.approved_by.clone()),
+        ("act", kind.to_string()),
+        ("msg", record.action_description.clone()),
+    ];
+    if let Some(action_type) = record.action_type {
+        extensions.push(("cs1Label", "actionType".to_string()));
+        extensions.push(("cs1", action_type.to_string()));
+    }
+    if let Some(justification) = &record.justification {
+        extensions.push(("reason", justification.clone()));
+    }
+    if let Some(outcome) = entry.outcome {
+        extensions.push(("end", outcome.executed_at.timestamp_millis().to_string()));
+        let result = if outcome.success {
+            "success"
+        } else {
+            "failure"
+        };
+        extensions.push(("outcome", result.to_string()));
+        if let Some(digest) = &outcome.result_digest {
+            extensions.push(("cs2Label", "resultDigest".to_string()));
+            extensions.push(("cs2", digest.clone()));
+        }
+    }
+    let extensions: Vec<String> = extensions
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, cef_extension_value(value)))
+        .collect();
+
+    format!(
+        "<{}>1 {} - luminaguard - - - CEF:0|LuminaGuard|Orchestrator|{}|approval:{}|{}|{}|{}",
+        SYSLOG_FACILITY * 8 + syslog_severity,
+        record.timestamp.to_rfc3339(),
+        cef_header_value(env!("CARGO_PKG_VERSION")),
+        kind,
+        cef_header_value(&format!("Action {}", kind)),
+        severity,
+        extensions.join(" ")
+    )
+}
+
+fn cef_header_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('|', "\\|")
+}
+
+fn cef_extension_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('=', "\\=")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn record(
+        id: &str,
+        day: u32,
+        action_type: ActionType,
+        decision: ApprovalDecision,
+    ) -> ApprovalRecord {
+        ApprovalRecord {
+            id: id.to_string(),
+            timestamp: Utc.with_ymd_and_hms(2026, 3, day, 12, 0, 0).unwrap(),
+            action_description: format!("{} #{}", action_type, id),
+            action_type: Some(action_type),
+            decision,
+            approved_by: "alice".to_string(),
+            justification: None,
+            execution_result: None,
+            votes: Vec::new(),
+            grant_id: None,
+            origin_record_id: None,
+            deferred_id: None,
+            plan_id: None,
+            requested_by: None,
+            session_id: None,
+            paths: Vec::new(),
+            command: None,
+            latency_ms: None,
+            anomalies: Vec::new(),
+            timeout: None,
+            diff_card_digest: None,
+            signatures: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_query_filters() {
+        let deleted = record("1", 1, ActionType::DeleteFile, ApprovalDecision::Approved);
+        let mut edited = record("2", 2, ActionType::EditFile, ApprovalDecision::Denied);
+        edited.approved_by = "bob".to_string();
+
+        let march_2 = Utc.with_ymd_and_hms(2026, 3, 2, 0, 0, 0).unwrap();
+        assert!(AuditQuery::new().matches(&deleted));
+        assert!(!AuditQuery::new().with_since(march_2).matches(&deleted));
+        assert!(AuditQuery::new().with_until(march_2).matches(&deleted));
+        assert!(AuditQuery::new().with_user("bob").matches(&edited));
+        assert!(!AuditQuery::new().with_user("bob").matches(&deleted));
+        assert!(AuditQuery::new()
+            .with_risk_level(RiskLevel::Critical)
+            .matches(&deleted));
+        assert!(!AuditQuery::new()
+            .with_action_type(ActionType::DeleteFile)
+            .matches(&edited));
+        assert!(AuditQuery::new()
+            .with_decision(DecisionKind::Denied)
+            .matches(&edited));
+    }
+
+    #[test]
+    fn test_parse_filters() {
+        assert_eq!(
+            "deny".parse::<DecisionKind>().unwrap(),
+            DecisionKind::Denied
+        );
+        assert_eq!("CSV".parse::<AuditFormat>().unwrap(), AuditFormat::Csv);
+        assert!("xml".parse::<AuditFormat>().is_err());
+    }
+
+    #[test]
+    fn test_export_csv_quotes_fields() {
+        let mut r = record("1", 1, ActionType::SendEmail, ApprovalDecision::Approved);
+        r.action_description = "Send \"hi\", to bob".to_string();
+        let out = export(
+            &[AuditEntry {
+                record: &r,
+                outcome: None,
+            }],
+            AuditFormat::Csv,
+        )
+        .unwrap();
+
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines[0], CSV_HEADER);
+        assert!(lines[1].contains(",\"Send \"\"hi\"\", to bob\","));
+        assert!(lines[1].contains(",SendEmail,Medium,approved,alice,"));
+
+        // Cells a spreadsheet would evaluate are not formulas
+        r.action_description = "=HYPERLINK(\"http://evil\")".to_string();
+        r.justification = Some("@SUM(A1)".to_string());
+        r.approved_by = "-2+3".to_string();
+        let out = export(
+            &[AuditEntry {
+                record: &r,
+                outcome: None,
+            }],
+            AuditFormat::Csv,
+        )
+        .unwrap();
+        let line = out.lines().nth(1).unwrap();
+        assert!(line.contains(",'-2+3,\"'=HYPERLINK(\"\"http://evil\"\")\",'@SUM(A1),"));
+    }
+
+    #[test]
+    fn test_export_cef_event() {
+        let mut r = record("1", 1, ActionType::DeleteFile, ApprovalDecision::Denied);
+        r.justification = Some("a=b".to_string());
+        let out = export(
+            &[AuditEntry {
+                record: &r,
+                outcome: None,
+            }],
+            AuditFormat::Cef,
+        )
+        .unwrap();
+
+        assert!(out.starts_with("<108>1 2026-03-01T12:00:00+00:00 - luminaguard - - - CEF:0|"));
+        assert!(out.contains("|approval:denied|Action denied|10|"));
+        assert!(out.contains("suser=alice"));
+        assert!(out.contains("reason=a\\=b"));
+        assert_eq!(out.lines().count(), 1);
+    }
+}
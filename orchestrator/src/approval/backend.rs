@@ -0,0 +1,490 @@
+//! Pluggable Approval Backends
+//!
+//! An [`ApprovalBackend`] decides how a pending Red action reaches a human:
+//! - [`PromptBackend`]: stdin prompt at the orchestrator's terminal
+//! - [`TuiBackend`]: full-screen terminal UI
+//! - [`WebApprovalBackend`](super::web::WebApprovalBackend): localhost HTTP
+//!   API and web page, for reviewers who are not at the orchestrator's
+//!   terminal
+//! - [`WebhookApprovalBackend`](super::webhook::WebhookApprovalBackend):
+//!   signed webhook to chat/ticket integrations
+//!
+//! [`ApprovalManager`](super::ApprovalManager) uses the configured backend
+//! for every Red action and records the decision in the audit trail.
+//!
+//! Requests carry the time they expire ([`ApprovalRequest::expires_at`],
+//! from the risk level's timeout). Backends should give up then with an
+//! [`ApprovalTimedOut`] error; [`request_with_reminders`] enforces the
+//! deadline regardless and sends [`ApprovalBackend::remind`] notifications
+//! before it.
+//!
+//! Requests also carry the header of the record their decision will be
+//! recorded as ([`ApprovalRequest::record`]); approvers sign its
+//! [`digest`](ApprovalRequest::digest). The prompt and TUI backends sign
+//! with the local user's key when given their [`Keyring`].
+
+use super::amend::Modification;
+use super::diff::DiffCard;
+use super::grants::GrantScope;
+use super::history::ApprovalDecision;
+use super::plan::{PlanRequest, PlanVerdict};
+use super::signing::{plan_digest, Keyring, PlanStepDigest, RecordHeader, RecordSignature};
+use super::tui::{approval_timeout, present_tui_approval_with_timeout, TuiResult};
+use super::ui::{ApprovalPrompt, ApprovalPromptConfig};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Duration;
+use tracing::warn;
+
+/// A Red action waiting for a decision
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApprovalRequest {
+    /// Unique request ID
+    pub id: String,
+
+    /// Diff Card describing the action
+    pub diff_card: DiffCard,
+
+    /// When the request was created
+    pub requested_at: DateTime<Utc>,
+
+    /// When the request expires without a decision (None: never)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+
+    /// Record the decision will be recorded as
+    pub record: RecordHeader,
+
+    /// Steps of the plan decided as a whole (plan requests only)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub plan: Vec<PlanStepDigest>,
+}
+
+impl ApprovalRequest {
+    /// Create a request for a Diff Card
+    pub fn new(diff_card: DiffCard) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            record: RecordHeader::for_card(&diff_card),
+            diff_card,
+            requested_at: Utc::now(),
+            expires_at: None,
+            plan: Vec::new(),
+        }
+    }
+
+    /// Record the decision as the record with `header`
+    pub fn for_record(mut self, header: RecordHeader) -> Self {
+        self.record = header;
+        self
+    }
+
+    /// Decide the plan with these steps as a whole
+    pub fn for_plan(mut self, steps: Vec<PlanStepDigest>) -> Self {
+        self.plan = steps;
+        self
+    }
+
+    /// Expire the request `timeout` after it was created (None: never)
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.expires_at = timeout
+            .and_then(|t| chrono::Duration::from_std(t).ok())
+            .map(|t| self.requested_at + t);
+        self
+    }
+
+    /// Time left before the request expires (None if it never does)
+    pub fn time_left(&self) -> Option<Duration> {
+        self.expires_at
+            .map(|at| (at - Utc::now()).to_std().unwrap_or_default())
+    }
+
+    /// Digest approvers sign (`sha256:<hex>`): of the plan's steps for plan
+    /// requests, otherwise of the record
+    pub fn digest(&self) -> anyhow::Result<String> {
+        if self.plan.is_empty() {
+            self.record.digest()
+        } else {
+            plan_digest(&self.plan)
+        }
+    }
+
+    /// Time to wait for a decision, at most `limit` (a backend's own timeout)
+    pub fn wait_limit(&self, limit: Duration) -> Duration {
+        self.time_left().map_or(limit, |left| left.min(limit))
+    }
+}
+
+/// Decision returned by a backend
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackendDecision {
+    /// The decision
+    pub decision: ApprovalDecision,
+
+    /// Who made the decision
+    pub approved_by: String,
+
+    /// Optional reason given by the approver
+    pub justification: Option<String>,
+
+    /// Standing grant requested along with an approval
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grant: Option<GrantScope>,
+
+    /// Edits to apply before execution (approvals only)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub modifications: Vec<Modification>,
+
+    /// The approver's signature over the decision, made with their own key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<RecordSignature>,
+}
+
+impl BackendDecision {
+    /// Decision attributed to the local user (`$USER`)
+    pub fn local_user(decision: ApprovalDecision) -> Self {
+        Self {
+            decision,
+            approved_by: super::local_user(),
+            justification: None,
+            grant: None,
+            modifications: Vec::new(),
+            signature: None,
+        }
+    }
+
+    /// Decision made automatically, with the reason as justification
+    pub fn system(decision: ApprovalDecision, reason: impl Into<String>) -> Self {
+        Self {
+            decision,
+            approved_by: "system".to_string(),
+            justification: Some(reason.into()),
+            grant: None,
+            modifications: Vec::new(),
+            signature: None,
+        }
+    }
+}
+
+/// No decision was made before the backend's deadline
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ApprovalTimedOut {
+    /// Timeout that expired, in seconds
+    pub timeout_secs: u64,
+}
+
+impl fmt::Display for ApprovalTimedOut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "no approval decision within {}s", self.timeout_secs)
+    }
+}
+
+impl std::error::Error for ApprovalTimedOut {}
+
+/// A way of asking a human to approve a Red action
+///
+/// Implementations block (asynchronously) until a decision is made. A
+/// backend that gives up waiting returns an [`ApprovalTimedOut`] error.
+#[async_trait]
+pub trait ApprovalBackend: Send + Sync + fmt::Debug {
+    /// Backend name (for logs)
+    fn name(&self) -> &str;
+
+    /// Ask for a decision on a request
+    async fn request_approval(&self, request: &ApprovalRequest) -> anyhow::Result<BackendDecision>;
+
+    /// Remind approvers that a request expires in `remaining`
+    ///
+    /// Called while [`request_approval`](Self::request_approval) is waiting.
+    /// The default does nothing (e.g. for UIs that show a countdown).
+    async fn remind(&self, _request: &ApprovalRequest, _remaining: Duration) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Ask for a decision on a plan (see [`super::plan`])
+    ///
+    /// By default the plan's combined card is decided as a whole:
+    /// approving it approves every step. Under a trust list, a partial
+    /// selection must be signed with [`Keyring::sign_steps`].
+    async fn request_plan_approval(&self, plan: &PlanRequest) -> anyhow::Result<PlanVerdict> {
+        let decision = self.request_approval(&plan.request).await?;
+        Ok(PlanVerdict::whole(decision))
+    }
+}
+
+/// Ask a backend for a decision, enforcing the request's deadline
+///
+/// Reminders are sent `reminders` before the request expires (reminders
+/// longer than the whole timeout are skipped); a failed reminder is logged
+/// and does not affect the request.
+///
+/// # Errors
+///
+/// Returns [`ApprovalTimedOut`] if the request expires without a decision,
+/// or the backend's error.
+pub async fn request_with_reminders(
+    backend: &dyn ApprovalBackend,
+    request: &ApprovalRequest,
+    reminders: &[Duration],
+) -> anyhow::Result<BackendDecision> {
+    with_reminders(
+        backend,
+        request,
+        reminders,
+        backend.request_approval(request),
+    )
+    .await
+}
+
+/// Ask a backend for a decision on a plan, enforcing its deadline
+///
+/// Like [`request_with_reminders`], for the plan's combined request.
+pub async fn request_plan_with_reminders(
+    backend: &dyn ApprovalBackend,
+    plan: &PlanRequest,
+    reminders: &[Duration],
+) -> anyhow::Result<PlanVerdict> {
+    let request = &plan.request;
+    with_reminders(
+        backend,
+        request,
+        reminders,
+        backend.request_plan_approval(plan),
+    )
+    .await
+}
+
+/// Wait for `decision` until `request` expires, sending reminders
+async fn with_reminders<T>(
+    backend: &dyn ApprovalBackend,
+    request: &ApprovalRequest,
+    reminders: &[Duration],
+    decision: impl std::future::Future<Output = anyhow::Result<T>>,
+) -> anyhow::Result<T> {
+    let Some(time_left) = request.time_left() else {
+        return decision.await;
+    };
+    let timeout_secs = request
+        .expires_at
+        .map(|at| (at - request.requested_at).num_seconds().max(0) as u64)
+        .unwrap_or_default();
+    let start = tokio::time::Instant::now();
+    let deadline = start + time_left;
+
+    // Soonest reminder last, so they can be popped in order
+    let mut pending: Vec<Duration> = reminders
+        .iter()
+        .copied()
+        .filter(|before| *before < time_left)
+        .collect();
+    pending.sort();
+
+    tokio::pin!(decision);
+    loop {
+        let next_reminder = pending.last().map(|before| deadline - *before);
+        tokio::select! {
+            result = &mut decision => return result,
+            _ = tokio::time::sleep_until(deadline) => {
+                warn!("No decision on request {} within {}s", request.id, timeout_secs);
+                return Err(ApprovalTimedOut { timeout_secs }.into());
+            }
+            _ = tokio::time::sleep_until(next_reminder.unwrap_or(deadline)),
+                if next_reminder.is_some() =>
+            {
+                let before = pending.pop().unwrap_or_default();
+                if let Err(e) = backend.remind(request, before).await {
+                    warn!("Failed to send reminder for request {}: {:#}", request.id, e);
+                }
+            }
+        }
+    }
+}
+
+/// Sign a decision made at the orchestrator's terminal with the local
+/// user's keyring (left unsigned without one, or without the user's key)
+fn sign_locally(
+    keyring: Option<&Keyring>,
+    decision: &mut BackendDecision,
+    digest: &str,
+) -> anyhow::Result<()> {
+    if let Some(keyring) = keyring {
+        decision.signature = keyring.sign(
+            &decision.approved_by,
+            digest,
+            &decision.decision,
+            &decision.modifications,
+            decision.grant.as_ref(),
+        )?;
+    }
+    Ok(())
+}
+
+/// Stdin prompt at the orchestrator's terminal
+#[derive(Debug, Clone, Default)]
+pub struct PromptBackend {
+    config: ApprovalPromptConfig,
+    keyring: Option<Keyring>,
+}
+
+impl PromptBackend {
+    /// Create with a prompt configuration
+    pub fn new(config: ApprovalPromptConfig) -> Self {
+        Self {
+            config,
+            keyring: None,
+        }
+    }
+
+    /// Sign decisions with the local user's key from `keyring`
+    pub fn with_keyring(mut self, keyring: Option<Keyring>) -> Self {
+        self.keyring = keyring;
+        self
+    }
+}
+
+#[async_trait]
+impl ApprovalBackend for PromptBackend {
+    fn name(&self) -> &str {
+        "prompt"
+    }
+
+    async fn request_approval(&self, request: &ApprovalRequest) -> anyhow::Result<BackendDecision> {
+        let prompt = ApprovalPrompt::with_config(self.config.clone());
+        let mut decision = prompt.ask_for_decision(&request.diff_card).await?;
+        sign_locally(self.keyring.as_ref(), &mut decision, &request.digest()?)?;
+        Ok(decision)
+    }
+
+    async fn remind(&self, _request: &ApprovalRequest, remaining: Duration) -> anyhow::Result<()> {
+        ApprovalPrompt::with_config(self.config.clone()).remind(remaining);
+        Ok(())
+    }
+
+    async fn request_plan_approval(&self, plan: &PlanRequest) -> anyhow::Result<PlanVerdict> {
+        let prompt = ApprovalPrompt::with_config(self.config.clone());
+        let mut verdict = prompt.ask_for_plan_decision(plan).await?;
+        if let Some(keyring) = &self.keyring {
+            verdict.decision.signature = keyring.sign_steps(
+                &verdict.decision.approved_by,
+                &plan.request.digest()?,
+                &verdict.decision.decision,
+                &verdict.steps,
+            )?;
+        }
+        Ok(verdict)
+    }
+}
+
+/// Full-screen terminal UI
+#[derive(Debug, Clone, Default)]
+pub struct TuiBackend {
+    keyring: Option<Keyring>,
+}
+
+impl TuiBackend {
+    /// Sign decisions with the local user's key from `keyring`
+    pub fn with_keyring(mut self, keyring: Option<Keyring>) -> Self {
+        self.keyring = keyring;
+        self
+    }
+}
+
+#[async_trait]
+impl ApprovalBackend for TuiBackend {
+    fn name(&self) -> &str {
+        "tui"
+    }
+
+    async fn request_approval(&self, request: &ApprovalRequest) -> anyhow::Result<BackendDecision> {
+        let timeout = request.wait_limit(approval_timeout());
+        let decision = match present_tui_approval_with_timeout(&request.diff_card, timeout).await? {
+            TuiResult::Approved => ApprovalDecision::Approved,
+            TuiResult::Rejected => ApprovalDecision::Denied,
+            TuiResult::Cancelled => ApprovalDecision::DeferredToLater,
+            TuiResult::TimedOut => {
+                return Err(ApprovalTimedOut {
+                    timeout_secs: timeout.as_secs(),
+                }
+                .into())
+            }
+        };
+        let mut decision = BackendDecision::local_user(decision);
+        sign_locally(self.keyring.as_ref(), &mut decision, &request.digest()?)?;
+        Ok(decision)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::approval::action::ActionType;
+
+    #[tokio::test]
+    async fn test_prompt_backend_non_interactive() {
+        let backend = PromptBackend::new(ApprovalPromptConfig {
+            interactive: false,
+            auto_approve_green: true,
+            default_decision: ApprovalDecision::Approved,
+            ..ApprovalPromptConfig::default()
+        });
+        let request = ApprovalRequest::new(DiffCard::new(
+            ActionType::DeleteFile,
+            "Delete /tmp/x".to_string(),
+            vec![],
+        ));
+
+        let decision = backend.request_approval(&request).await.unwrap();
+
+        assert_eq!(decision.decision, ApprovalDecision::Approved);
+        assert_eq!(backend.name(), "prompt");
+    }
+
+    /// Backend nobody answers, recording reminders
+    #[derive(Debug, Default)]
+    struct SilentBackend(std::sync::Mutex<Vec<Duration>>);
+
+    #[async_trait]
+    impl ApprovalBackend for SilentBackend {
+        fn name(&self) -> &str {
+            "silent"
+        }
+
+        async fn request_approval(&self, _: &ApprovalRequest) -> anyhow::Result<BackendDecision> {
+            std::future::pending().await
+        }
+
+        async fn remind(&self, _: &ApprovalRequest, remaining: Duration) -> anyhow::Result<()> {
+            self.0.lock().unwrap().push(remaining);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_request_expires_after_reminders() {
+        let backend = SilentBackend::default();
+        let request = ApprovalRequest::new(DiffCard::new(
+            ActionType::DeleteFile,
+            "Delete /tmp/x".to_string(),
+            vec![],
+        ))
+        .with_timeout(Some(Duration::from_millis(300)));
+        assert!(request.wait_limit(Duration::from_secs(60)) <= Duration::from_millis(300));
+
+        let reminders = [Duration::from_secs(1), Duration::from_millis(100)];
+        let err = request_with_reminders(&backend, &request, &reminders)
+            .await
+            .unwrap_err();
+
+        assert!(err.downcast_ref::<ApprovalTimedOut>().is_some());
+        // The reminder longer than the whole timeout is skipped
+        assert_eq!(*backend.0.lock().unwrap(), [Duration::from_millis(100)]);
+    }
+
+    #[test]
+    fn test_timed_out_display() {
+        let err = ApprovalTimedOut { timeout_secs: 300 };
+        assert_eq!(err.to_string(), "no approval decision within 300s");
+    }
+}
@@ -0,0 +1,526 @@
+//! Checkpoints of Files Changed by Approved Actions
+//!
+//! Before an approved action that creates, edits or deletes files runs, the
+//! affected paths are snapshotted (content and metadata) into a local
+//! content-addressed store, keyed by the approval record ID. When the
+//! action's outcome is recorded, the checkpoint is sealed with the files'
+//! new state; `luminaguard rollback <approval-id>` restores the snapshot,
+//! refusing if any of the files changed since.
+//!
+//! Layout of the store (`<data dir>/checkpoints`):
+//! - `objects/<sha256>`: file contents, shared between checkpoints
+//! - `<approval id>.json`: checkpoint manifests
+//!
+//! Each new checkpoint triggers garbage collection: checkpoints beyond the
+//! [`Retention`] limits are removed, then objects no checkpoint refers to.
+
+use super::hex::hex_encode;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::info;
+
+/// How many checkpoints are kept by default
+pub const DEFAULT_MAX_CHECKPOINTS: usize = 100;
+
+/// How long checkpoints are kept by default (30 days)
+pub const DEFAULT_MAX_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// Limits on the checkpoints kept in a store
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Retention {
+    /// Most checkpoints kept (the newest ones)
+    pub max_checkpoints: usize,
+
+    /// Checkpoints older than this are removed
+    pub max_age: Duration,
+}
+
+impl Default for Retention {
+    fn default() -> Self {
+        Self {
+            max_checkpoints: DEFAULT_MAX_CHECKPOINTS,
+            max_age: DEFAULT_MAX_AGE,
+        }
+    }
+}
+
+/// Content and metadata of a file at one point in time
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileState {
+    /// Content digest (`sha256:<hex>`), also the object it is stored as
+    pub digest: String,
+
+    /// Size in bytes
+    pub size: u64,
+
+    /// Unix permission bits
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<u32>,
+
+    /// Last modification time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified: Option<DateTime<Utc>>,
+}
+
+/// One file in a checkpoint
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileCheckpoint {
+    /// Absolute path of the file
+    pub path: PathBuf,
+
+    /// State before the action (None if the file did not exist)
+    pub before: Option<FileState>,
+
+    /// State after the action (None if the file did not exist); only
+    /// meaningful once the checkpoint is sealed
+    #[serde(default)]
+    pub after: Option<FileState>,
+}
+
+/// Files snapshotted before an approved action ran
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Approval record the checkpoint belongs to
+    pub approval_id: String,
+
+    /// When the files were snapshotted
+    pub created_at: DateTime<Utc>,
+
+    /// When the action's outcome was recorded (the files' state after the
+    /// action is known from then on)
+    #[serde(default)]
+    pub sealed_at: Option<DateTime<Utc>>,
+
+    /// When the checkpoint was restored
+    #[serde(default)]
+    pub rolled_back_at: Option<DateTime<Utc>>,
+
+    /// Snapshotted files
+    pub files: Vec<FileCheckpoint>,
+}
+
+/// What garbage collection removed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GcReport {
+    /// Checkpoints removed
+    pub checkpoints: usize,
+
+    /// Objects removed
+    pub objects: usize,
+}
+
+/// Content-addressed store of checkpoints
+#[derive(Debug, Clone)]
+pub struct CheckpointStore {
+    /// Store directory
+    dir: PathBuf,
+
+    /// Limits enforced by garbage collection
+    retention: Retention,
+}
+
+impl CheckpointStore {
+    /// Store in `dir` (created when the first checkpoint is taken)
+    pub fn open(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            retention: Retention::default(),
+        }
+    }
+
+    /// Default store location (`<data dir>/checkpoints`)
+    pub fn default_path() -> PathBuf {
+        super::data_dir().join("checkpoints")
+    }
+
+    /// Set the retention limits
+    pub fn with_retention(mut self, retention: Retention) -> Self {
+        self.retention = retention;
+        self
+    }
+
+    /// Snapshot `paths` before the action approved by `approval_id` runs
+    ///
+    /// Paths that do not exist yet are recorded as absent (rolling back
+    /// removes them). Old checkpoints are garbage collected afterwards.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a path is relative (it would be resolved against
+    /// the orchestrator's working directory, not the tool's), is not a
+    /// regular file or cannot be read.
+    pub fn capture(&self, approval_id: &str, paths: &[PathBuf]) -> Result<Checkpoint> {
+        let mut files: Vec<FileCheckpoint> = Vec::new();
+        for path in paths {
+            if path.is_relative() {
+                anyhow::bail!("Cannot checkpoint relative path {}", path.display());
+            }
+            if files.iter().any(|file| &file.path == path) {
+                continue;
+            }
+            files.push(FileCheckpoint {
+                before: self.snapshot(path)?,
+                after: None,
+                path: path.clone(),
+            });
+        }
+
+        let checkpoint = Checkpoint {
+            approval_id: approval_id.to_string(),
+            created_at: Utc::now(),
+            sealed_at: None,
+            rolled_back_at: None,
+            files,
+        };
+        self.save(&checkpoint)?;
+
+        let report = self.gc()?;
+        if report.checkpoints > 0 {
+            info!(
+                "Removed {} old checkpoint(s) and {} object(s)",
+                report.checkpoints, report.objects
+            );
+        }
+        Ok(checkpoint)
+    }
+
+    /// Record the files' state after the action ran
+    ///
+    /// Returns false if there is no checkpoint for `approval_id`.
+    pub fn seal(&self, approval_id: &str) -> Result<bool> {
+        let Some(mut checkpoint) = self.load(&self.manifest_path(approval_id)?)? else {
+            return Ok(false);
+        };
+        for file in &mut checkpoint.files {
+            file.after = current_state(&file.path)?;
+        }
+        checkpoint.sealed_at = Some(Utc::now());
+        self.save(&checkpoint)?;
+        Ok(true)
+    }
+
+    /// All checkpoints, oldest first
+    pub fn list(&self) -> Result<Vec<Checkpoint>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut checkpoints = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)
+            .with_context(|| format!("Failed to read {}", self.dir.display()))?
+        {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                checkpoints.extend(self.load(&path)?);
+            }
+        }
+        checkpoints.sort_by_key(|c| c.created_at);
+        Ok(checkpoints)
+    }
+
+    /// Get a checkpoint by approval ID (or unique ID prefix)
+    pub fn get(&self, approval_id: &str) -> Result<Option<Checkpoint>> {
+        let mut matching: Vec<Checkpoint> = self
+            .list()?
+            .into_iter()
+            .filter(|c| c.approval_id.starts_with(approval_id))
+            .collect();
+
+        match matching.len() {
+            0 => Ok(None),
+            1 => Ok(matching.pop()),
+            _ => anyhow::bail!("Approval ID prefix '{}' is ambiguous", approval_id),
+        }
+    }
+
+    /// Restore the files of a checkpoint to their state before the action
+    ///
+    /// Returns the checkpoint as restored.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no such checkpoint, it is not sealed or
+    /// already rolled back, or any of its files changed since the action ran.
+    /// Nothing is restored in that case.
+    pub fn rollback(&self, approval_id: &str) -> Result<Checkpoint> {
+        let mut checkpoint = self
+            .get(approval_id)?
+            .with_context(|| format!("No checkpoint for approval {}", approval_id))?;
+        if let Some(at) = checkpoint.rolled_back_at {
+            anyhow::bail!(
+                "Checkpoint {} was already rolled back at {}",
+                checkpoint.approval_id,
+                at
+            );
+        }
+        if checkpoint.sealed_at.is_none() {
+            anyhow::bail!(
+                "The outcome of approval {} was never recorded, so changes since cannot be detected",
+                checkpoint.approval_id
+            );
+        }
+
+        let changed: Vec<String> = checkpoint
+            .files
+            .iter()
+            .filter_map(|file| match current_state(&file.path) {
+                Ok(state) if digest_of(&state) == digest_of(&file.after) => None,
+                _ => Some(file.path.display().to_string()),
+            })
+            .collect();
+        if !changed.is_empty() {
+            anyhow::bail!(
+                "Refusing to roll back {}: changed since the action ran: {}",
+                checkpoint.approval_id,
+                changed.join(", ")
+            );
+        }
+
+        for file in &checkpoint.files {
+            self.restore(&file.path, file.before.as_ref())?;
+        }
+        checkpoint.rolled_back_at = Some(Utc::now());
+        self.save(&checkpoint)?;
+        Ok(checkpoint)
+    }
+
+    /// Remove checkpoints beyond the retention limits, then unused objects
+    pub fn gc(&self) -> Result<GcReport> {
+        let mut report = GcReport::default();
+        let oldest = Utc::now()
+            - chrono::Duration::from_std(self.retention.max_age)
+                .context("Checkpoint retention too long")?;
+
+        let mut checkpoints = self.list()?;
+        checkpoints.reverse();
+        let mut referenced = HashSet::new();
+        for (i, checkpoint) in checkpoints.iter().enumerate() {
+            if i < self.retention.max_checkpoints && checkpoint.created_at >= oldest {
+                let states = checkpoint
+                    .files
+                    .iter()
+                    .flat_map(|file| [&file.before, &file.after]);
+                referenced.extend(states.flatten().map(|state| state.digest.clone()));
+            } else {
+                let path = self.manifest_path(&checkpoint.approval_id)?;
+                std::fs::remove_file(&path)
+                    .with_context(|| format!("Failed to remove {}", path.display()))?;
+                report.checkpoints += 1;
+            }
+        }
+
+        let objects = self.dir.join("objects");
+        if objects.exists() {
+            for entry in std::fs::read_dir(&objects)
+                .with_context(|| format!("Failed to read {}", objects.display()))?
+            {
+                let path = entry?.path();
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                if !referenced.contains(&format!("sha256:{}", name)) {
+                    std::fs::remove_file(&path)
+                        .with_context(|| format!("Failed to remove {}", path.display()))?;
+                    report.objects += 1;
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    /// Store a file's content and return its state (None if absent)
+    fn snapshot(&self, path: &Path) -> Result<Option<FileState>> {
+        let Some(state) = current_state(path)? else {
+            return Ok(None);
+        };
+
+        let object = self.object_path(&state.digest);
+        if !object.exists() {
+            let content = std::fs::read(path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            let objects = self.dir.join("objects");
+            std::fs::create_dir_all(&objects)
+                .with_context(|| format!("Failed to create {}", objects.display()))?;
+            let tmp = object.with_extension("tmp");
+            std::fs::write(&tmp, content)
+                .with_context(|| format!("Failed to write {}", tmp.display()))?;
+            std::fs::rename(&tmp, &object)
+                .with_context(|| format!("Failed to write {}", object.display()))?;
+        }
+        Ok(Some(state))
+    }
+
+    /// Put a file back into `state` (remove it if None)
+    fn restore(&self, path: &Path, state: Option<&FileState>) -> Result<()> {
+        let Some(state) = state else {
+            if path.exists() {
+                std::fs::remove_file(path)
+                    .with_context(|| format!("Failed to remove {}", path.display()))?;
+            }
+            return Ok(());
+        };
+
+        let object = self.object_path(&state.digest);
+        let content = std::fs::read(&object)
+            .with_context(|| format!("Checkpoint object {} is missing", object.display()))?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        std::fs::write(path, content)
+            .with_context(|| format!("Failed to restore {}", path.display()))?;
+
+        #[cfg(unix)]
+        if let Some(mode) = state.mode {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+                .with_context(|| format!("Failed to restore permissions of {}", path.display()))?;
+        }
+        if let Some(modified) = state.modified {
+            std::fs::File::options()
+                .write(true)
+                .open(path)
+                .and_then(|file| file.set_modified(modified.into()))
+                .with_context(|| format!("Failed to restore mtime of {}", path.display()))?;
+        }
+        Ok(())
+    }
+
+    fn object_path(&self, digest: &str) -> PathBuf {
+        let hex = digest.strip_prefix("sha256:").unwrap_or(digest);
+        self.dir.join("objects").join(hex)
+    }
+
+    fn manifest_path(&self, approval_id: &str) -> Result<PathBuf> {
+        if approval_id.is_empty()
+            || !approval_id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            anyhow::bail!("Invalid approval ID '{}'", approval_id);
+        }
+        Ok(self.dir.join(format!("{}.json", approval_id)))
+    }
+
+    fn load(&self, path: &Path) -> Result<Option<Checkpoint>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read checkpoint {}", path.display()))?;
+        let checkpoint = serde_json::from_str(&json)
+            .with_context(|| format!("Failed to parse checkpoint {}", path.display()))?;
+        Ok(Some(checkpoint))
+    }
+
+    fn save(&self, checkpoint: &Checkpoint) -> Result<()> {
+        super::write_json_atomic(&self.manifest_path(&checkpoint.approval_id)?, checkpoint)
+    }
+}
+
+/// Current state of a file (None if it does not exist)
+fn current_state(path: &Path) -> Result<Option<FileState>> {
+    let metadata = match std::fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("Failed to stat {}", path.display())),
+    };
+    if !metadata.is_file() {
+        anyhow::bail!("{} is not a regular file", path.display());
+    }
+
+    let content =
+        std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    #[cfg(unix)]
+    let mode = Some(std::os::unix::fs::PermissionsExt::mode(&metadata.permissions()) & 0o7777);
+    #[cfg(not(unix))]
+    let mode = None;
+
+    Ok(Some(FileState {
+        digest: format!("sha256:{}", hex_encode(&Sha256::digest(&content))),
+        size: content.len() as u64,
+        mode,
+        modified: metadata.modified().ok().map(DateTime::<Utc>::from),
+    }))
+}
+
+fn digest_of(state: &Option<FileState>) -> Option<&str> {
+    state.as_ref().map(|s| s.digest.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capture_seal_rollback() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = CheckpointStore::open(dir.path().join("checkpoints"));
+        let edited = dir.path().join("edited.txt");
+        let created = dir.path().join("created.txt");
+        std::fs::write(&edited, "before").unwrap();
+
+        store
+            .capture("a1", &[edited.clone(), created.clone()])
+            .unwrap();
+        std::fs::write(&edited, "after").unwrap();
+        std::fs::write(&created, "new").unwrap();
+
+        // Not sealed: the action's effects are unknown
+        assert!(store.rollback("a1").is_err());
+        assert!(store.seal("a1").unwrap());
+        assert!(!store.seal("missing").unwrap());
+
+        let checkpoint = store.rollback("a").unwrap();
+        assert_eq!(checkpoint.files.len(), 2);
+        assert_eq!(std::fs::read_to_string(&edited).unwrap(), "before");
+        assert!(!created.exists());
+
+        let err = store.rollback("a1").unwrap_err();
+        assert!(err.to_string().contains("already rolled back"));
+    }
+
+    #[test]
+    fn test_rollback_refused_after_later_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = CheckpointStore::open(dir.path().join("checkpoints"));
+        let file = dir.path().join("a.txt");
+        std::fs::write(&file, "v1").unwrap();
+
+        store.capture("a1", std::slice::from_ref(&file)).unwrap();
+        std::fs::write(&file, "v2").unwrap();
+        store.seal("a1").unwrap();
+        std::fs::write(&file, "v3").unwrap();
+
+        let err = store.rollback("a1").unwrap_err();
+        assert!(err.to_string().contains("changed since"));
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "v3");
+    }
+
+    #[test]
+    fn test_gc_enforces_retention() {
+        let dir = tempfile::tempdir().unwrap();
+        let store =
+            CheckpointStore::open(dir.path().join("checkpoints")).with_retention(Retention {
+                max_checkpoints: 1,
+                ..Retention::default()
+            });
+        let file = dir.path().join("a.txt");
+
+        std::fs::write(&file, "v1").unwrap();
+        store.capture("a1", std::slice::from_ref(&file)).unwrap();
+        std::fs::write(&file, "v2").unwrap();
+        store.capture("a2", std::slice::from_ref(&file)).unwrap();
+
+        let checkpoints = store.list().unwrap();
+        assert_eq!(checkpoints.len(), 1);
+        assert_eq!(checkpoints[0].approval_id, "a2");
+        let objects = std::fs::read_dir(dir.path().join("checkpoints").join("objects"))
+            .unwrap()
+            .count();
+        assert_eq!(objects, 1);
+    }
+}
@@ -3,15 +3,34 @@
 //! This module generates "Diff Cards" that show exactly what will change
 //! before the user approves an action. Diff cards are color-coded by risk level
 //! and include timestamps for audit trails.
+//!
+//! Cards carry a `schema_version`; see [`super::schema`] for the published
+//! JSON Schema and how older cards are upgraded.
 
 use super::action::{ActionType, RiskLevel};
+use super::line_diff::{DiffOptions, UnifiedDiff};
+use super::risk::RiskConfig;
 use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
+/// Maximum characters kept in content previews
+pub const PREVIEW_CHARS: usize = 500;
+
+/// Current Diff Card schema version
+///
+/// Version 1 cards (before versioning, including the Python agent's
+/// `change_type`/`summary`/`details` format) have no `schema_version` field.
+pub const SCHEMA_VERSION: u32 = 2;
+
 /// A Diff Card showing the exact changes an action will make
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct DiffCard {
+    /// Schema version (1 if absent)
+    #[serde(default = "legacy_schema_version")]
+    pub schema_version: u32,
+
     /// Action type being performed
     pub action_type: ActionType,
 
@@ -21,6 +40,19 @@ pub struct DiffCard {
     /// Risk level (None, Low, Medium, High, Critical)
     pub risk_level: RiskLevel,
 
+    /// Risk score from 0 to 100 (see [`RiskConfig`])
+    #[serde(default)]
+    #[schemars(range(max = 100))]
+    pub risk_score: u8,
+
+    /// Why the score differs from the action type's baseline
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub risk_reasons: Vec<String>,
+
+    /// Deviations from usual approval patterns (see [`super::anomaly`])
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub anomalies: Vec<String>,
+
     /// List of specific changes that will be made
     pub changes: Vec<Change>,
 
@@ -29,7 +61,7 @@ pub struct DiffCard {
 }
 
 /// A single change within an action
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub enum Change {
     /// File creation
     FileCreate {
@@ -45,6 +77,9 @@ pub enum Change {
         before: String,
         /// After content (preview, first 500 chars)
         after: String,
+        /// Line-level diff computed from the full content
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        diff: Option<UnifiedDiff>,
     },
 
     /// File deletion (show size)
@@ -104,6 +139,65 @@ pub enum Change {
         new_value: String,
     },
 
+    /// Git operation (commit, push, merge, ...)
+    GitOperation {
+        /// Operation (commit, push, merge, rebase, ...)
+        operation: String,
+        /// Branch operated on
+        branch: String,
+        /// Remote (push/pull/fetch only)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        remote: Option<String>,
+        /// Commits involved (short hash and subject)
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        commits: Vec<String>,
+        /// Whether history is rewritten (e.g. `push --force`)
+        #[serde(default)]
+        force: bool,
+    },
+
+    /// Database statement
+    DatabaseStatement {
+        /// Database connection (URL or name, without credentials)
+        connection: String,
+        /// Statement to execute
+        statement: String,
+        /// Estimated number of affected rows, if known
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        estimated_rows: Option<u64>,
+    },
+
+    /// Package installation
+    PackageInstall {
+        /// Package manager (npm, pip, cargo, apt, ...)
+        manager: String,
+        /// Package name
+        package: String,
+        /// Requested version (latest if None)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        version: Option<String>,
+        /// Registry, URL or path installed from (default registry if None)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        source: Option<String>,
+    },
+
+    /// File permission or ownership change
+    PermissionChange {
+        path: String,
+        /// Old mode in octal (e.g. "644"), if known
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        old_mode: Option<String>,
+        /// New mode in octal (unchanged if None)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        new_mode: Option<String>,
+        /// Old owner (`user` or `user:group`), if known
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        old_owner: Option<String>,
+        /// New owner (unchanged if None)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        new_owner: Option<String>,
+    },
+
     /// Generic/custom change
     Custom {
         /// Description of the change
@@ -112,6 +206,37 @@ pub enum Change {
 }
 
 impl Change {
+    /// Create a file creation from the full content (keeps a preview)
+    pub fn file_create(path: impl Into<String>, content: &str) -> Self {
+        Change::FileCreate {
+            path: path.into(),
+            content_preview: preview(content),
+        }
+    }
+
+    /// Create a file edit from the full before/after content
+    ///
+    /// Computes a line-level diff from the full content and keeps only
+    /// previews of the content itself.
+    pub fn file_edit(path: impl Into<String>, before: &str, after: &str) -> Self {
+        Change::FileEdit {
+            path: path.into(),
+            before: preview(before),
+            after: preview(after),
+            diff: Some(UnifiedDiff::compute(before, after, &DiffOptions::default())),
+        }
+    }
+
+    /// File path touched by this change, if any
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            Change::FileCreate { path, .. }
+            | Change::FileEdit { path, .. }
+            | Change::FileDelete { path, .. } => Some(path),
+            _ => None,
+        }
+    }
+
     /// Get a human-readable name for this change type
     pub fn change_type(&self) -> &'static str {
         match self {
@@ -123,6 +248,10 @@ impl Change {
             Change::ExternalCall { .. } => "External API Call",
             Change::AssetTransfer { .. } => "Asset Transfer",
             Change::ConfigChange { .. } => "Configuration Change",
+            Change::GitOperation { .. } => "Git Operation",
+            Change::DatabaseStatement { .. } => "Database Statement",
+            Change::PackageInstall { .. } => "Package Install",
+            Change::PermissionChange { .. } => "Permission Change",
             Change::Custom { .. } => "Custom Change",
         }
     }
@@ -154,6 +283,69 @@ impl Change {
             Change::ConfigChange { key, new_value, .. } => {
                 format!("Change {} to {}", key, new_value)
             }
+            Change::GitOperation {
+                operation,
+                branch,
+                remote,
+                commits,
+                force,
+            } => {
+                let mut summary = format!("git {} {}", operation, branch);
+                if let Some(remote) = remote {
+                    summary.push_str(&format!(" to {}", remote));
+                }
+                if !commits.is_empty() {
+                    summary.push_str(&format!(" ({} commit(s))", commits.len()));
+                }
+                if *force {
+                    summary.push_str(" [force]");
+                }
+                summary
+            }
+            Change::DatabaseStatement {
+                connection,
+                statement,
+                estimated_rows,
+            } => {
+                let mut summary = format!("Run on {}: {}", connection, truncate(statement, 60));
+                if let Some(rows) = estimated_rows {
+                    summary.push_str(&format!(" (~{} rows)", rows));
+                }
+                summary
+            }
+            Change::PackageInstall {
+                manager,
+                package,
+                version,
+                source,
+            } => {
+                let mut summary = format!("Install {} package {}", manager, package);
+                if let Some(version) = version {
+                    summary.push_str(&format!(" {}", version));
+                }
+                if let Some(source) = source {
+                    summary.push_str(&format!(" from {}", source));
+                }
+                summary
+            }
+            Change::PermissionChange {
+                path,
+                old_mode,
+                new_mode,
+                old_owner,
+                new_owner,
+            } => {
+                let mut parts = Vec::new();
+                if let Some(mode) = new_mode {
+                    let old = old_mode.as_deref().unwrap_or("?");
+                    parts.push(format!("mode {} -> {}", old, mode));
+                }
+                if let Some(owner) = new_owner {
+                    let old = old_owner.as_deref().unwrap_or("?");
+                    parts.push(format!("owner {} -> {}", old, owner));
+                }
+                format!("Change permissions of {}: {}", path, parts.join(", "))
+            }
             Change::Custom { description } => description.clone(),
         }
     }
@@ -161,16 +353,30 @@ impl Change {
 
 impl DiffCard {
     /// Create a new diff card for an action
+    ///
+    /// The risk is scored with the default [`RiskConfig`].
     pub fn new(action_type: ActionType, description: String, changes: Vec<Change>) -> Self {
-        let risk_level = action_type.risk_level();
-
         Self {
+            schema_version: SCHEMA_VERSION,
             action_type,
             description,
-            risk_level,
+            risk_level: action_type.risk_level(),
+            risk_score: 0,
+            risk_reasons: Vec::new(),
+            anomalies: Vec::new(),
             changes,
             timestamp: Utc::now(),
         }
+        .with_risk_config(&RiskConfig::default())
+    }
+
+    /// Score the risk with custom thresholds
+    pub fn with_risk_config(mut self, config: &RiskConfig) -> Self {
+        let assessment = config.assess(self.action_type, &self.changes);
+        self.risk_level = assessment.level;
+        self.risk_score = assessment.score;
+        self.risk_reasons = assessment.reasons;
+        self
     }
 
     /// Generate human-readable output (CLI format)
@@ -184,17 +390,22 @@ impl DiffCard {
         let mut output = String::new();
 
         // Header with risk level
-        let risk_str = match self.risk_level {
-            RiskLevel::None => "🟢 [GREEN]",
-            RiskLevel::Low => "🟡 [LOW]",
-            RiskLevel::Medium => "🟠 [MEDIUM]",
-            RiskLevel::High => "🔴 [HIGH]",
-            RiskLevel::Critical => "🔴🔴 [CRITICAL]",
-        };
-
-        output.push_str(&format!("{} {} Action\n", risk_str, self.action_type));
+        output.push_str(&format!(
+            "{} {} Action\n",
+            risk_badge(self.risk_level),
+            self.action_type
+        ));
         output.push_str("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n");
 
+        // Risk score and the reasons for it
+        output.push_str(&format!("Risk score: {}/100\n", self.risk_score));
+        for reason in &self.risk_reasons {
+            output.push_str(&format!("  - {}\n", reason));
+        }
+        for anomaly in &self.anomalies {
+            output.push_str(&format!("⚠ Anomaly: {}\n", anomaly));
+        }
+
         // Description
         output.push_str(&format!("Description: {}\n", self.description));
 
@@ -216,6 +427,11 @@ impl DiffCard {
                 ));
 
                 match change {
+                    Change::FileEdit {
+                        diff: Some(diff), ..
+                    } => {
+                        push_diff(&mut output, diff);
+                    }
                     Change::FileEdit { before, after, .. } => {
                         output.push_str(&format!("     Before: {}\n", truncate(before, 60)));
                         output.push_str(&format!("     After:  {}\n", truncate(after, 60)));
@@ -258,6 +474,17 @@ impl DiffCard {
                         output.push_str(&format!("     Old: {}\n", old_value));
                         output.push_str(&format!("     New: {}\n", new_value));
                     }
+                    Change::GitOperation { commits, .. } => {
+                        for commit in commits {
+                            output.push_str(&format!("     Commit: {}\n", commit));
+                        }
+                    }
+                    Change::DatabaseStatement { statement, .. } => {
+                        output.push_str(&format!("     Statement: {}\n", statement));
+                    }
+                    Change::PackageInstall { .. } | Change::PermissionChange { .. } => {
+                        // Already in summary
+                    }
                     Change::Custom { .. } => {
                         // Already in summary
                     }
@@ -274,6 +501,60 @@ impl DiffCard {
     }
 }
 
+/// Risk level label with its color emoji (e.g. "🔴 [HIGH]")
+pub(super) fn risk_badge(level: RiskLevel) -> &'static str {
+    match level {
+        RiskLevel::None => "🟢 [GREEN]",
+        RiskLevel::Low => "🟡 [LOW]",
+        RiskLevel::Medium => "🟠 [MEDIUM]",
+        RiskLevel::High => "🔴 [HIGH]",
+        RiskLevel::Critical => "🔴🔴 [CRITICAL]",
+    }
+}
+
+/// Append a line-level diff (indented, with line numbers) to CLI output
+fn push_diff(output: &mut String, diff: &UnifiedDiff) {
+    if diff.binary {
+        output.push_str("     Binary file changed\n");
+        return;
+    }
+
+    output.push_str(&format!(
+        "     {} line(s) added, {} line(s) removed\n",
+        diff.added, diff.removed
+    ));
+    for hunk in &diff.hunks {
+        output.push_str(&format!("     {}\n", hunk.header()));
+        for line in &hunk.lines {
+            let number = line
+                .new_line
+                .or(line.old_line)
+                .map_or(String::new(), |n| n.to_string());
+            output.push_str(&format!(
+                "     {:>5} {}{}\n",
+                number,
+                line.kind.prefix(),
+                line.text
+            ));
+        }
+    }
+    if diff.omitted_hunks > 0 {
+        output.push_str(&format!(
+            "     ... {} more hunk(s) not shown\n",
+            diff.omitted_hunks
+        ));
+    }
+}
+
+fn legacy_schema_version() -> u32 {
+    1
+}
+
+/// Content preview (first PREVIEW_CHARS characters)
+fn preview(s: &str) -> String {
+    s.chars().take(PREVIEW_CHARS).collect()
+}
+
 /// Truncate a string to a maximum length, adding "..." if truncated
 fn truncate(s: &str, max_len: usize) -> String {
     if s.len() > max_len {
@@ -308,10 +589,53 @@ mod tests {
             path: "/tmp/test.txt".to_string(),
             before: "old".to_string(),
             after: "new".to_string(),
+            diff: None,
         };
         assert_eq!(change.summary(), "Edit: /tmp/test.txt");
     }
 
+    #[test]
+    fn test_file_edit_computes_diff_from_full_content() {
+        let before = format!("{}\nport = 80\n", "x".repeat(600));
+        let after = format!("{}\nport = 8080\n", "x".repeat(600));
+
+        let change = Change::file_edit("/etc/app.conf", &before, &after);
+
+        let Change::FileEdit {
+            before: preview,
+            diff: Some(diff),
+            ..
+        } = &change
+        else {
+            panic!("expected FileEdit with diff");
+        };
+        assert_eq!(preview.chars().count(), PREVIEW_CHARS);
+        assert_eq!((diff.added, diff.removed), (1, 1));
+    }
+
+    #[test]
+    fn test_human_readable_shows_line_diff() {
+        let card = DiffCard::new(
+            ActionType::EditFile,
+            "Change port".to_string(),
+            vec![Change::file_edit(
+                "/etc/app.conf",
+                "host = a\nport = 80\n",
+                "host = a\nport = 8080\n",
+            )],
+        );
+
+        let output = card.to_human_readable();
+
+        assert!(output.contains("🔴🔴 [CRITICAL] EditFile Action"));
+        assert!(output.contains("Risk score: 90/100"));
+        assert!(output.contains("  - Sensitive path /etc/app.conf (+25)"));
+        assert!(output.contains("1 line(s) added, 1 line(s) removed"));
+        assert!(output.contains("@@ -1,2 +1,2 @@"));
+        assert!(output.contains("    2 -port = 80"));
+        assert!(output.contains("    2 +port = 8080"));
+    }
+
     #[test]
     fn test_file_delete_change_summary() {
         let change = Change::FileDelete {
@@ -351,6 +675,75 @@ mod tests {
         assert!(change.summary().contains("POST"));
     }
 
+    fn structured_changes() -> Vec<Change> {
+        vec![
+            Change::GitOperation {
+                operation: "push".to_string(),
+                branch: "main".to_string(),
+                remote: Some("origin".to_string()),
+                commits: vec!["abc123 Fix login".to_string(), "def456 Bump".to_string()],
+                force: true,
+            },
+            Change::DatabaseStatement {
+                connection: "postgres://db/app".to_string(),
+                statement: "DELETE FROM sessions WHERE expired".to_string(),
+                estimated_rows: Some(1200),
+            },
+            Change::PackageInstall {
+                manager: "npm".to_string(),
+                package: "left-pad".to_string(),
+                version: Some("1.3.0".to_string()),
+                source: None,
+            },
+            Change::PermissionChange {
+                path: "/srv/app/run.sh".to_string(),
+                old_mode: Some("644".to_string()),
+                new_mode: Some("755".to_string()),
+                old_owner: None,
+                new_owner: None,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_structured_change_summaries() {
+        let summaries: Vec<String> = structured_changes().iter().map(Change::summary).collect();
+        assert_eq!(
+            summaries,
+            vec![
+                "git push main to origin (2 commit(s)) [force]",
+                "Run on postgres://db/app: DELETE FROM sessions WHERE expired (~1200 rows)",
+                "Install npm package left-pad 1.3.0",
+                "Change permissions of /srv/app/run.sh: mode 644 -> 755",
+            ]
+        );
+
+        let card = DiffCard::new(
+            ActionType::Push,
+            "Release".to_string(),
+            structured_changes(),
+        );
+        let readable = card.to_human_readable();
+        assert!(readable.contains("Git Operation - git push main"));
+        assert!(readable.contains("     Commit: def456 Bump"));
+        assert!(readable.contains("     Statement: DELETE FROM sessions WHERE expired"));
+    }
+
+    #[test]
+    fn test_structured_changes_json_round_trip() {
+        for change in structured_changes() {
+            let json = serde_json::to_string(&change).unwrap();
+            assert_eq!(serde_json::from_str::<Change>(&json).unwrap(), change);
+        }
+
+        // Optional fields may be left out
+        let change: Change = serde_json::from_value(serde_json::json!({
+            "GitOperation": {"operation": "commit", "branch": "dev"}
+        }))
+        .unwrap();
+        assert_eq!(change.summary(), "git commit dev");
+    }
+
     #[test]
     fn test_asset_transfer_change_summary() {
         let change = Change::AssetTransfer {
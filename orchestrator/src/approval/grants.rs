@@ -0,0 +1,552 @@
+//! Scoped Standing Grants
+//!
+//! When approving a Red action, the approver can choose to also approve
+//! similar actions for a while instead of being prompted every time:
+//! - [`GrantScope::Session`]: similar actions for the rest of the session
+//! - [`GrantScope::Minutes`]: similar actions for N minutes
+//! - [`GrantScope::PathPrefix`]: actions on paths under a prefix, for the
+//!   rest of the session
+//!
+//! A [`Grant`] is an action type, a [`ChangeMatcher`] over the action's
+//! changes and an optional expiry. Grants never apply to actions that need
+//! multi-party approval, and are bound to the session that created them.
+//!
+//! Grants live in a [`GrantStore`]; a file-backed store is re-read before
+//! every lookup so that `luminaguard grants revoke` takes effect
+//! immediately in a running orchestrator.
+
+use super::action::ActionType;
+use super::diff::Change;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::path::{Component, Path, PathBuf};
+
+/// How far an approval extends (chosen by the approver)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GrantScope {
+    /// Similar actions for the rest of the session
+    Session,
+
+    /// Similar actions for N minutes
+    Minutes(u64),
+
+    /// Actions on paths under a prefix, for the rest of the session
+    PathPrefix(String),
+}
+
+impl std::fmt::Display for GrantScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GrantScope::Session => write!(f, "this session"),
+            GrantScope::Minutes(minutes) => write!(f, "{} minutes", minutes),
+            GrantScope::PathPrefix(prefix) => write!(f, "paths under {}", prefix),
+        }
+    }
+}
+
+/// Which changes a grant covers
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ChangeMatcher {
+    /// Any changes
+    Any,
+
+    /// Every change touches a file under this prefix
+    PathPrefix {
+        /// Path prefix (matched per path component)
+        prefix: String,
+    },
+
+    /// Every change runs this command
+    Command {
+        /// Command name
+        command: String,
+    },
+}
+
+impl ChangeMatcher {
+    /// Matcher for actions "similar" to these changes
+    ///
+    /// Commands match by command name; anything else matches any changes
+    /// of the same action type.
+    pub fn similar_to(changes: &[Change]) -> Self {
+        let mut commands = changes.iter().map(|c| match c {
+            Change::CommandExec { command, .. } => Some(command.as_str()),
+            _ => None,
+        });
+        match commands.next().flatten() {
+            Some(first) if commands.all(|c| c == Some(first)) => ChangeMatcher::Command {
+                command: first.to_string(),
+            },
+            _ => ChangeMatcher::Any,
+        }
+    }
+
+    /// Check whether the matcher covers all of `changes`
+    pub fn matches(&self, changes: &[Change]) -> bool {
+        match self {
+            ChangeMatcher::Any => true,
+            ChangeMatcher::PathPrefix { prefix } => {
+                !changes.is_empty()
+                    && changes.iter().all(|c| {
+                        c.path()
+                            .is_some_and(|p| is_under(Path::new(p), Path::new(prefix)))
+                    })
+            }
+            ChangeMatcher::Command { command } => {
+                !changes.is_empty()
+                    && changes.iter().all(
+                        |c| matches!(c, Change::CommandExec { command: cmd, .. } if cmd == command),
+                    )
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for ChangeMatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChangeMatcher::Any => write!(f, "any"),
+            ChangeMatcher::PathPrefix { prefix } => write!(f, "path under {}", prefix),
+            ChangeMatcher::Command { command } => write!(f, "command {}", command),
+        }
+    }
+}
+
+/// Deepest directory containing every changed path
+///
+/// Returns `None` if a change has no path or the only common ancestor is
+/// the filesystem root (too broad to offer as a grant).
+pub fn common_parent(changes: &[Change]) -> Option<String> {
+    let paths: Option<Vec<&str>> = changes.iter().map(Change::path).collect();
+    common_directory(paths?)
+}
+
+/// Deepest directory containing every path (see [`common_parent`])
+pub fn common_directory<'a>(paths: impl IntoIterator<Item = &'a str>) -> Option<String> {
+    let mut common: Option<PathBuf> = None;
+    for path in paths {
+        let parent = Path::new(path).parent()?;
+        common = Some(match common {
+            None => parent.to_path_buf(),
+            Some(common) => common
+                .components()
+                .zip(parent.components())
+                .take_while(|(a, b)| a == b)
+                .map(|(a, _)| a)
+                .collect(),
+        });
+    }
+
+    let common = common?;
+    let specific = common
+        .components()
+        .any(|c| matches!(c, Component::Normal(_)))
+        && !common
+            .components()
+            .any(|c| matches!(c, Component::ParentDir));
+    specific.then(|| common.to_string_lossy().into_owned())
+}
+
+/// Component-wise prefix check that rejects `..` escapes
+pub(super) fn is_under(path: &Path, prefix: &Path) -> bool {
+    let has_parent_dir = |p: &Path| p.components().any(|c| matches!(c, Component::ParentDir));
+    !has_parent_dir(path) && !has_parent_dir(prefix) && path.starts_with(prefix)
+}
+
+/// A standing approval for similar actions
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Grant {
+    /// Unique grant ID
+    pub id: String,
+
+    /// Action type covered
+    pub action_type: ActionType,
+
+    /// Changes covered
+    pub matcher: ChangeMatcher,
+
+    /// Session the grant belongs to
+    pub session_id: String,
+
+    /// When the grant expires (None = end of session)
+    pub expires_at: Option<DateTime<Utc>>,
+
+    /// Who created the grant
+    pub granted_by: String,
+
+    /// Audit record of the decision that created the grant
+    pub origin_record_id: String,
+
+    /// When the grant was created (UTC)
+    pub created_at: DateTime<Utc>,
+}
+
+impl Grant {
+    /// Create a grant for the scope chosen when approving an action
+    pub fn from_scope(
+        scope: &GrantScope,
+        action_type: ActionType,
+        changes: &[Change],
+        session_id: &str,
+        granted_by: &str,
+        origin_record_id: &str,
+    ) -> Self {
+        let now = Utc::now();
+        let (matcher, expires_at) = match scope {
+            GrantScope::Session => (ChangeMatcher::similar_to(changes), None),
+            GrantScope::Minutes(minutes) => (
+                ChangeMatcher::similar_to(changes),
+                Some(now + chrono::Duration::minutes(*minutes as i64)),
+            ),
+            GrantScope::PathPrefix(prefix) => (
+                ChangeMatcher::PathPrefix {
+                    prefix: prefix.clone(),
+                },
+                None,
+            ),
+        };
+
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            action_type,
+            matcher,
+            session_id: session_id.to_string(),
+            expires_at,
+            granted_by: granted_by.to_string(),
+            origin_record_id: origin_record_id.to_string(),
+            created_at: now,
+        }
+    }
+
+    /// Check whether the grant has expired
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|t| now >= t)
+    }
+
+    /// Check whether the grant covers an action in a session
+    pub fn covers(
+        &self,
+        action_type: ActionType,
+        changes: &[Change],
+        session_id: &str,
+        now: DateTime<Utc>,
+    ) -> bool {
+        self.action_type == action_type
+            && self.session_id == session_id
+            && !self.is_expired(now)
+            && self.matcher.matches(changes)
+    }
+}
+
+/// Standing grants, optionally persisted to a JSON file
+#[derive(Debug, Default)]
+pub struct GrantStore {
+    path: Option<PathBuf>,
+    grants: Vec<Grant>,
+}
+
+impl GrantStore {
+    /// Store that is not persisted
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    /// Open (or create) a store backed by a JSON file
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let mut store = Self {
+            path: Some(path.into()),
+            grants: Vec::new(),
+        };
+        store.reload()?;
+        Ok(store)
+    }
+
+    /// Default store location (`<data dir>/grants.json`)
+    pub fn default_path() -> PathBuf {
+        super::data_dir().join("grants.json")
+    }
+
+    /// All grants, including expired ones not yet pruned
+    pub fn list(&self) -> &[Grant] {
+        &self.grants
+    }
+
+    /// Add a grant
+    pub fn add(&mut self, grant: Grant) -> Result<()> {
+        let _lock = self.lock()?;
+        self.reload()?;
+        self.grants.push(grant);
+        self.save()
+    }
+
+    /// Revoke a grant by ID (or unique ID prefix)
+    ///
+    /// Returns the revoked grant, or `None` if no grant matched.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the prefix matches more than one grant.
+    pub fn revoke(&mut self, id: &str) -> Result<Option<Grant>> {
+        let _lock = self.lock()?;
+        self.reload()?;
+        let matching: Vec<usize> = self
+            .grants
+            .iter()
+            .enumerate()
+            .filter(|(_, g)| g.id.starts_with(id))
+            .map(|(i, _)| i)
+            .collect();
+
+        match matching.as_slice() {
+            [] => Ok(None),
+            [index] => {
+                let grant = self.grants.remove(*index);
+                self.save()?;
+                Ok(Some(grant))
+            }
+            _ => anyhow::bail!("Grant ID prefix '{}' is ambiguous", id),
+        }
+    }
+
+    /// Find a live grant covering an action
+    ///
+    /// Re-reads the backing file first so revocations made elsewhere apply.
+    pub fn find(
+        &mut self,
+        action_type: ActionType,
+        changes: &[Change],
+        session_id: &str,
+    ) -> Result<Option<Grant>> {
+        self.reload()?;
+        let now = Utc::now();
+        Ok(self
+            .grants
+            .iter()
+            .find(|g| g.covers(action_type, changes, session_id, now))
+            .cloned())
+    }
+
+    /// Drop expired grants
+    pub fn prune_expired(&mut self) -> Result<usize> {
+        let _lock = self.lock()?;
+        self.reload()?;
+        let now = Utc::now();
+        let before = self.grants.len();
+        self.grants.retain(|g| !g.is_expired(now));
+        let pruned = before - self.grants.len();
+        if pruned > 0 {
+            self.save()?;
+        }
+        Ok(pruned)
+    }
+
+    /// Lock the backing file (if any) until the guard is dropped
+    fn lock(&self) -> Result<Option<File>> {
+        self.path.as_deref().map(super::lock_store).transpose()
+    }
+
+    fn reload(&mut self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        self.grants = if path.exists() {
+            let json = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read grant store {}", path.display()))?;
+            serde_json::from_str(&json)
+                .with_context(|| format!("Failed to parse grant store {}", path.display()))?
+        } else {
+            Vec::new()
+        };
+        Ok(())
+    }
+
+    fn save(&self) -> Result<()> {
+        match &self.path {
+            Some(path) => super::write_json_atomic(path, &self.grants),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn delete(path: &str) -> Change {
+        Change::FileDelete {
+            path: path.to_string(),
+            size_bytes: 1,
+        }
+    }
+
+    fn exec(command: &str) -> Change {
+        Change::CommandExec {
+            command: command.to_string(),
+            args: vec![],
+            env_vars: None,
+        }
+    }
+
+    #[test]
+    fn test_path_prefix_matcher() {
+        let matcher = ChangeMatcher::PathPrefix {
+            prefix: "/tmp/build".to_string(),
+        };
+
+        assert!(matcher.matches(&[delete("/tmp/build/a.o"), delete("/tmp/build/b/c.o")]));
+        assert!(!matcher.matches(&[delete("/tmp/build/a.o"), delete("/etc/passwd")]));
+        assert!(!matcher.matches(&[delete("/tmp/buildx/a.o")]));
+        assert!(!matcher.matches(&[delete("/tmp/build/../../etc/passwd")]));
+        assert!(!matcher.matches(&[exec("rm")]));
+        assert!(!matcher.matches(&[]));
+    }
+
+    #[test]
+    fn test_common_parent() {
+        assert_eq!(
+            common_parent(&[delete("/tmp/build/a.o"), delete("/tmp/build/sub/b.o")]).as_deref(),
+            Some("/tmp/build")
+        );
+        assert_eq!(common_parent(&[delete("/tmp/a"), delete("/etc/b")]), None);
+        assert_eq!(common_parent(&[delete("/tmp/a"), exec("rm")]), None);
+        assert_eq!(common_parent(&[]), None);
+    }
+
+    #[test]
+    fn test_similar_to() {
+        assert_eq!(
+            ChangeMatcher::similar_to(&[exec("cargo"), exec("cargo")]),
+            ChangeMatcher::Command {
+                command: "cargo".to_string()
+            }
+        );
+        assert_eq!(
+            ChangeMatcher::similar_to(&[exec("cargo"), exec("rm")]),
+            ChangeMatcher::Any
+        );
+        assert_eq!(
+            ChangeMatcher::similar_to(&[delete("/tmp/x")]),
+            ChangeMatcher::Any
+        );
+    }
+
+    #[test]
+    fn test_grant_covers() {
+        let grant = Grant::from_scope(
+            &GrantScope::Session,
+            ActionType::ExecuteCommand,
+            &[exec("cargo")],
+            "s1",
+            "alice",
+            "r1",
+        );
+        let now = Utc::now();
+
+        assert!(grant.covers(ActionType::ExecuteCommand, &[exec("cargo")], "s1", now));
+        assert!(!grant.covers(ActionType::ExecuteCommand, &[exec("rm")], "s1", now));
+        assert!(!grant.covers(ActionType::ExecuteCommand, &[exec("cargo")], "s2", now));
+        assert!(!grant.covers(ActionType::DeleteFile, &[exec("cargo")], "s1", now));
+    }
+
+    #[test]
+    fn test_minutes_grant_expires() {
+        let grant = Grant::from_scope(
+            &GrantScope::Minutes(5),
+            ActionType::EditFile,
+            &[],
+            "s1",
+            "alice",
+            "r1",
+        );
+        let now = Utc::now();
+
+        assert!(!grant.is_expired(now));
+        assert!(grant.is_expired(now + chrono::Duration::minutes(6)));
+        assert!(!grant.covers(
+            ActionType::EditFile,
+            &[],
+            "s1",
+            now + chrono::Duration::minutes(6)
+        ));
+    }
+
+    #[test]
+    fn test_scope_serialization() {
+        assert_eq!(
+            serde_json::to_value(GrantScope::Session).unwrap(),
+            "session"
+        );
+        let scope: GrantScope = serde_json::from_str(r#"{"minutes": 15}"#).unwrap();
+        assert_eq!(scope, GrantScope::Minutes(15));
+        let scope: GrantScope = serde_json::from_str(r#"{"path_prefix": "/tmp"}"#).unwrap();
+        assert_eq!(scope, GrantScope::PathPrefix("/tmp".to_string()));
+    }
+
+    #[test]
+    fn test_revocation_visible_to_other_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("grants.json");
+
+        let mut orchestrator = GrantStore::open(&path).unwrap();
+        let grant = Grant::from_scope(
+            &GrantScope::PathPrefix("/tmp".to_string()),
+            ActionType::DeleteFile,
+            &[],
+            "s1",
+            "alice",
+            "r1",
+        );
+        let id = grant.id.clone();
+        orchestrator.add(grant).unwrap();
+        assert!(orchestrator
+            .find(ActionType::DeleteFile, &[delete("/tmp/x")], "s1")
+            .unwrap()
+            .is_some());
+
+        // Revoked from the CLI (another process)
+        let mut cli = GrantStore::open(&path).unwrap();
+        assert_eq!(cli.list().len(), 1);
+        assert!(cli.revoke(&id[..8]).unwrap().is_some());
+        assert!(cli.revoke(&id).unwrap().is_none());
+
+        assert!(orchestrator
+            .find(ActionType::DeleteFile, &[delete("/tmp/x")], "s1")
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_concurrent_adds_not_lost() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("grants.json");
+
+        let writers: Vec<_> = (0..4)
+            .map(|_| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    let mut store = GrantStore::open(&path).unwrap();
+                    for _ in 0..10 {
+                        let grant = Grant::from_scope(
+                            &GrantScope::Session,
+                            ActionType::DeleteFile,
+                            &[],
+                            "s1",
+                            "alice",
+                            "r1",
+                        );
+                        store.add(grant).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        assert_eq!(GrantStore::open(&path).unwrap().list().len(), 40);
+    }
+}
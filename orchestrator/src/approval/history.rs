@@ -2,9 +2,30 @@
 //!
 //! This module records all approval decisions for compliance and auditing.
 //! Decisions are immutable and include timestamps and user information.
-
-use chrono::{DateTime, Utc};
+//!
+//! What happened when an approved action ran is attached afterwards as an
+//! append-only [`ExecutionOutcome`] follow-up, linked to the decision by its
+//! ticket ID (the record ID). The exported audit log shows each decision
+//! together with its outcome.
+//!
+//! A history opened from a file ([`ApprovalHistory::open`]) appends every
+//! decision and outcome to a JSON-lines log, so the audit trail outlives the
+//! session and can be queried later (see [`super::audit`]).
+
+use super::action::{ActionType, RiskLevel};
+use super::amend::Amendment;
+use super::audit::{self, AuditEntry, AuditFormat, AuditQuery, DailyStats};
+use super::quorum::Vote;
+use super::signing::RecordSignature;
+use super::ui::TimeoutAction;
+use anyhow::Context;
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Duration;
 
 /// A single approval decision
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -12,12 +33,16 @@ pub struct ApprovalRecord {
     /// Unique identifier for this record (UUID)
     pub id: String,
 
-    /// When this decision was made (UTC)
+    /// When approval was requested (UTC)
     pub timestamp: DateTime<Utc>,
 
     /// Description of the action that was approved/rejected
     pub action_description: String,
 
+    /// Type of the action (None if not classified, e.g. policy denials)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action_type: Option<ActionType>,
+
     /// The decision made (Approved, Denied, Deferred)
     pub decision: ApprovalDecision,
 
@@ -30,14 +55,121 @@ pub struct ApprovalRecord {
     /// Result of execution (if approved and executed)
     /// Example: "Success: File created", "Error: Access denied"
     pub execution_result: Option<String>,
+
+    /// Individual votes (multi-party approvals only)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub votes: Vec<Vote>,
+
+    /// Standing grant created by, or used for, this decision
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grant_id: Option<String>,
+
+    /// Decision that created the grant (records approved by a grant)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin_record_id: Option<String>,
+
+    /// Deferred queue item this decision parked or came from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deferred_id: Option<String>,
+
+    /// Plan this decision is a step of (see [`super::plan`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plan_id: Option<String>,
+
+    /// Session owner the action was requested by (the agent's user)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requested_by: Option<String>,
+
+    /// Agent session the action was requested in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+
+    /// Files the action touches
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub paths: Vec<String>,
+
+    /// Command every change of the action runs, if they all run the same one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+
+    /// Time from presenting the Diff Card to the decision (prompted
+    /// decisions only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+
+    /// Anomalies flagged on the action or its approval (see
+    /// [`super::anomaly`])
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub anomalies: Vec<String>,
+
+    /// What happened when nobody decided in time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<TimeoutEvent>,
+
+    /// Digest of the Diff Card that was decided (see
+    /// [`super::signing::RecordHeader`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diff_card_digest: Option<String>,
+
+    /// Approvers' signatures over this record
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub signatures: Vec<RecordSignature>,
+}
+
+impl ApprovalRecord {
+    /// Risk level of the action, if its type is known
+    pub fn risk_level(&self) -> Option<RiskLevel> {
+        self.action_type.map(ActionType::risk_level)
+    }
+}
+
+/// An approval request that expired without a decision
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeoutEvent {
+    /// How long the request waited (seconds)
+    pub after_secs: u64,
+
+    /// What was done about it
+    pub action: TimeoutAction,
+
+    /// Backend the request was escalated to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub escalated_to: Option<String>,
+}
+
+impl TimeoutEvent {
+    /// Create an event without escalation
+    pub fn new(after: Duration, action: TimeoutAction) -> Self {
+        Self {
+            after_secs: after.as_secs(),
+            action,
+            escalated_to: None,
+        }
+    }
+}
+
+impl std::fmt::Display for TimeoutEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "timed out after {}s, ", self.after_secs)?;
+        match (&self.action, &self.escalated_to) {
+            (TimeoutAction::Escalate, Some(to)) => write!(f, "escalated to {}", to),
+            (TimeoutAction::Escalate, None) => write!(f, "no escalation backend"),
+            (TimeoutAction::Deny, _) => write!(f, "denied"),
+            (TimeoutAction::Defer, _) => write!(f, "deferred"),
+        }
+    }
 }
 
 /// The decision made on an approval request
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ApprovalDecision {
     /// Action was approved and may proceed
     Approved,
 
+    /// Action was approved after the approver amended it; only the amended
+    /// action may proceed
+    ApprovedWithModifications(Box<Amendment>),
+
     /// Action was denied and will not execute
     Denied,
 
@@ -49,17 +181,175 @@ impl std::fmt::Display for ApprovalDecision {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             ApprovalDecision::Approved => write!(f, "Approved"),
+            ApprovalDecision::ApprovedWithModifications(_) => write!(f, "Approved (modified)"),
             ApprovalDecision::Denied => write!(f, "Denied"),
             ApprovalDecision::DeferredToLater => write!(f, "Deferred"),
         }
     }
 }
 
+impl ApprovalDecision {
+    /// Whether the action may proceed (as-is or amended)
+    pub fn is_approved(&self) -> bool {
+        matches!(
+            self,
+            ApprovalDecision::Approved | ApprovalDecision::ApprovedWithModifications(_)
+        )
+    }
+
+    /// The approver's amendment, if the action was modified
+    pub fn amendment(&self) -> Option<&Amendment> {
+        match self {
+            ApprovalDecision::ApprovedWithModifications(amendment) => Some(amendment),
+            _ => None,
+        }
+    }
+}
+
+/// Result of an approval request, with the ticket to report the outcome to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApprovalTicket {
+    /// ID of the audit record (None if nothing was recorded, e.g. for
+    /// Green actions)
+    pub id: Option<String>,
+
+    /// The decision
+    pub decision: ApprovalDecision,
+
+    /// Reason given for the decision, if any
+    pub justification: Option<String>,
+
+    /// Queue ID of the deferred action, if the action was deferred
+    pub deferred_id: Option<String>,
+
+    /// Timeout that decided the action (None if an approver decided it,
+    /// including one the request was escalated to)
+    pub timeout: Option<TimeoutEvent>,
+}
+
+impl ApprovalTicket {
+    /// Ticket for a decision that was not recorded
+    pub fn unrecorded(decision: ApprovalDecision) -> Self {
+        Self {
+            id: None,
+            decision,
+            justification: None,
+            deferred_id: None,
+            timeout: None,
+        }
+    }
+
+    /// Ticket for `record`, with its justification, deferral and timeout
+    pub fn for_record(record: &ApprovalRecord) -> Self {
+        Self {
+            id: Some(record.id.clone()),
+            decision: record.decision.clone(),
+            justification: record.justification.clone(),
+            deferred_id: record.deferred_id.clone(),
+            timeout: record
+                .timeout
+                .clone()
+                .filter(|t| t.escalated_to.is_none() || record.approved_by == "system"),
+        }
+    }
+
+    /// Attach the queue ID of the deferred action
+    pub fn with_deferred_id(mut self, deferred_id: impl Into<String>) -> Self {
+        self.deferred_id = Some(deferred_id.into());
+        self
+    }
+}
+
+/// What happened when an approved action ran (append-only follow-up)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExecutionOutcome {
+    /// Ticket (approval record ID) this outcome belongs to
+    pub ticket_id: String,
+
+    /// When execution finished (UTC)
+    pub executed_at: DateTime<Utc>,
+
+    /// Whether the action succeeded
+    pub success: bool,
+
+    /// How long execution took, in milliseconds
+    pub duration_ms: u64,
+
+    /// SHA-256 digest of the result (`sha256:<hex>`), if there was one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result_digest: Option<String>,
+
+    /// Short result summary or error message
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl ExecutionOutcome {
+    /// Outcome of an action that just finished
+    pub fn new(ticket_id: impl Into<String>, success: bool, duration: Duration) -> Self {
+        Self {
+            ticket_id: ticket_id.into(),
+            executed_at: Utc::now(),
+            success,
+            duration_ms: u64::try_from(duration.as_millis()).unwrap_or(u64::MAX),
+            result_digest: None,
+            detail: None,
+        }
+    }
+
+    /// Record a digest of the action's result
+    pub fn with_result(mut self, result: &serde_json::Value) -> Self {
+        let digest = Sha256::digest(result.to_string().as_bytes());
+        let hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+        self.result_digest = Some(format!("sha256:{}", hex));
+        self
+    }
+
+    /// Attach a short summary or error message
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+}
+
+impl std::fmt::Display for ExecutionOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "executed at {} in {} ms: {}",
+            self.executed_at.format("%Y-%m-%d %H:%M:%S UTC"),
+            self.duration_ms,
+            if self.success { "success" } else { "failure" }
+        )?;
+        if let Some(detail) = &self.detail {
+            write!(f, " ({})", detail)?;
+        }
+        if let Some(digest) = &self.result_digest {
+            write!(f, " [{}]", digest)?;
+        }
+        Ok(())
+    }
+}
+
+/// Line of the persisted history log
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "entry", rename_all = "snake_case")]
+enum LogEntry {
+    Decision(Box<ApprovalRecord>),
+    Outcome(ExecutionOutcome),
+}
+
 /// Storage for approval history
 #[derive(Debug, Clone)]
 pub struct ApprovalHistory {
     /// All approval records (immutable)
     records: Vec<ApprovalRecord>,
+
+    /// Execution outcomes of approved actions (append-only)
+    outcomes: Vec<ExecutionOutcome>,
+
+    /// JSON-lines log new entries are appended to (None: in memory only)
+    path: Option<PathBuf>,
 }
 
 impl ApprovalHistory {
@@ -67,17 +357,130 @@ impl ApprovalHistory {
     pub fn new() -> Self {
         Self {
             records: Vec::new(),
+            outcomes: Vec::new(),
+            path: None,
         }
     }
 
+    /// Open (or create) a history backed by a JSON-lines log
+    pub fn open(path: impl Into<PathBuf>) -> anyhow::Result<Self> {
+        let path = path.into();
+        let mut history = Self::new();
+        if path.exists() {
+            let log = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read approval history {}", path.display()))?;
+            for (n, line) in log.lines().enumerate() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let entry = serde_json::from_str(line).with_context(|| {
+                    format!("Failed to parse {} line {}", path.display(), n + 1)
+                })?;
+                match entry {
+                    LogEntry::Decision(record) => history.records.push(*record),
+                    LogEntry::Outcome(outcome) => history.outcomes.push(outcome),
+                }
+            }
+        }
+        history.path = Some(path);
+        Ok(history)
+    }
+
+    /// Default history location (`<data dir>/history.jsonl`)
+    pub fn default_path() -> PathBuf {
+        super::data_dir().join("history.jsonl")
+    }
+
     /// Record a new approval decision (immutable - cannot be changed)
     pub fn record_decision(&mut self, record: ApprovalRecord) -> anyhow::Result<()> {
-        // In Phase 3+, this would be written to persistent storage (SQLite)
-        // For now, keep in memory
-        self.records.push(record);
+        let entry = LogEntry::Decision(Box::new(record));
+        self.append(&entry)?;
+        if let LogEntry::Decision(record) = entry {
+            self.records.push(*record);
+        }
+        Ok(())
+    }
+
+    /// Attach the execution outcome to an approved decision
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the ticket is unknown, was not approved, or
+    /// already has an outcome.
+    pub fn record_outcome(&mut self, outcome: ExecutionOutcome) -> anyhow::Result<()> {
+        let Some(record) = self.records.iter().find(|r| r.id == outcome.ticket_id) else {
+            anyhow::bail!("No approval record for ticket {}", outcome.ticket_id);
+        };
+        if !record.decision.is_approved() {
+            anyhow::bail!(
+                "Ticket {} was not approved ({})",
+                outcome.ticket_id,
+                record.decision
+            );
+        }
+        if self.outcome(&outcome.ticket_id).is_some() {
+            anyhow::bail!("Ticket {} already has an outcome", outcome.ticket_id);
+        }
+
+        let entry = LogEntry::Outcome(outcome);
+        self.append(&entry)?;
+        if let LogEntry::Outcome(outcome) = entry {
+            self.outcomes.push(outcome);
+        }
+        Ok(())
+    }
+
+    /// Append an entry to the log file (if any)
+    fn append(&self, entry: &LogEntry) -> anyhow::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open approval history {}", path.display()))?;
+        writeln!(file, "{}", serde_json::to_string(entry)?)
+            .with_context(|| format!("Failed to append to {}", path.display()))?;
         Ok(())
     }
 
+    /// Decision recorded with an id
+    pub fn get(&self, id: &str) -> Option<&ApprovalRecord> {
+        self.records.iter().find(|r| r.id == id)
+    }
+
+    /// Execution outcome recorded for a ticket
+    pub fn outcome(&self, ticket_id: &str) -> Option<&ExecutionOutcome> {
+        self.outcomes.iter().find(|o| o.ticket_id == ticket_id)
+    }
+
+    /// One-line audit summary of a ticket
+    ///
+    /// E.g. "Delete /tmp/a: Approved by alice because cleanup; executed
+    /// at ... in 12 ms: success".
+    pub fn audit_summary(&self, ticket_id: &str) -> Option<String> {
+        let record = self.records.iter().find(|r| r.id == ticket_id)?;
+        let mut summary = format!(
+            "{}: {} by {}",
+            record.action_description, record.decision, record.approved_by
+        );
+        if let Some(justification) = &record.justification {
+            summary.push_str(&format!(" because {}", justification));
+        }
+        match self.outcome(ticket_id) {
+            Some(outcome) => summary.push_str(&format!("; {}", outcome)),
+            None if record.decision.is_approved() => summary.push_str("; not executed yet"),
+            None => {}
+        }
+        Some(summary)
+    }
+
     /// Get the most recent decisions (optionally limited)
     pub fn get_history(&self, limit: Option<usize>) -> Vec<&ApprovalRecord> {
         let mut records: Vec<_> = self.records.iter().collect();
@@ -99,41 +502,70 @@ impl ApprovalHistory {
             .collect()
     }
 
-    /// Get all decisions by a specific user
+    /// Get all decisions by a specific user (including votes cast)
     pub fn get_by_user(&self, user: &str) -> Vec<&ApprovalRecord> {
         self.records
             .iter()
-            .filter(|r| r.approved_by == user)
+            .filter(|r| r.approved_by == user || r.votes.iter().any(|v| v.approver == user))
             .collect()
     }
 
     /// Count decisions by type
     pub fn count_by_decision(&self) -> (usize, usize, usize) {
-        let approved = self
-            .records
-            .iter()
-            .filter(|r| r.decision == ApprovalDecision::Approved)
-            .count();
-        let denied = self
-            .records
-            .iter()
-            .filter(|r| r.decision == ApprovalDecision::Denied)
-            .count();
-        let deferred = self
-            .records
-            .iter()
-            .filter(|r| r.decision == ApprovalDecision::DeferredToLater)
-            .count();
+        count_decisions(&self.records.iter().collect::<Vec<_>>())
+    }
 
-        (approved, denied, deferred)
+    /// Records matching a query, oldest first
+    pub fn query(&self, query: &AuditQuery) -> Vec<&ApprovalRecord> {
+        self.records.iter().filter(|r| query.matches(r)).collect()
+    }
+
+    /// Decision counts per day (UTC) of the records matching a query
+    pub fn daily_stats(&self, query: &AuditQuery) -> Vec<DailyStats> {
+        let mut days: BTreeMap<NaiveDate, Vec<&ApprovalRecord>> = BTreeMap::new();
+        for record in self.query(query) {
+            days.entry(record.timestamp.date_naive())
+                .or_default()
+                .push(record);
+        }
+
+        days.into_iter()
+            .map(|(day, records)| {
+                let (approved, denied, deferred) = count_decisions(&records);
+                DailyStats {
+                    day,
+                    approved,
+                    denied,
+                    deferred,
+                }
+            })
+            .collect()
+    }
+
+    /// Export the records matching a query (with their outcomes)
+    pub fn export(&self, query: &AuditQuery, format: AuditFormat) -> anyhow::Result<String> {
+        audit::export(&self.audit_entries(self.query(query)), format)
     }
 
     /// Export history as JSON string (for auditing)
+    ///
+    /// Each record carries its execution outcome (if any) as `outcome`.
     pub fn export_audit_log(&self) -> anyhow::Result<String> {
-        let json = serde_json::to_string_pretty(&self.records)?;
+        let entries = self.audit_entries(self.records.iter().collect());
+        let json = serde_json::to_string_pretty(&entries)?;
         Ok(json)
     }
 
+    fn audit_entries<'a>(&'a self, records: Vec<&'a ApprovalRecord>) -> Vec<AuditEntry<'a>> {
+        records
+            .into_iter()
+            .map(|record| AuditEntry {
+                record,
+                outcome: self.outcome(&record.id),
+            })
+            .collect()
+    }
+
     /// Get total number of records
     pub fn record_count(&self) -> usize {
         self.records.len()
@@ -143,9 +575,25 @@ impl ApprovalHistory {
     #[cfg(test)]
     pub fn clear(&mut self) {
         self.records.clear();
+        self.outcomes.clear();
     }
 }
 
+/// Count (approved, denied, deferred) decisions
+fn count_decisions(records: &[&ApprovalRecord]) -> (usize, usize, usize) {
+    let approved = records.iter().filter(|r| r.decision.is_approved()).count();
+    let denied = records
+        .iter()
+        .filter(|r| r.decision == ApprovalDecision::Denied)
+        .count();
+    let deferred = records
+        .iter()
+        .filter(|r| r.decision == ApprovalDecision::DeferredToLater)
+        .count();
+
+    (approved, denied, deferred)
+}
+
 impl Default for ApprovalHistory {
     fn default() -> Self {
         Self::new()
@@ -155,16 +603,32 @@ impl Default for ApprovalHistory {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::approval::audit::DecisionKind;
 
     fn create_test_record(id: &str, action: &str, decision: ApprovalDecision) -> ApprovalRecord {
         ApprovalRecord {
             id: id.to_string(),
             timestamp: Utc::now(),
             action_description: action.to_string(),
+            action_type: None,
             decision,
             approved_by: "test_user".to_string(),
             justification: None,
             execution_result: None,
+            votes: Vec::new(),
+            grant_id: None,
+            origin_record_id: None,
+            deferred_id: None,
+            plan_id: None,
+            requested_by: None,
+            session_id: None,
+            paths: Vec::new(),
+            command: None,
+            latency_ms: None,
+            anomalies: Vec::new(),
+            timeout: None,
+            diff_card_digest: None,
+            signatures: Vec::new(),
         }
     }
 
@@ -348,4 +812,109 @@ mod tests {
             .unwrap()
             .contains("Success"));
     }
+
+    #[test]
+    fn test_record_outcome_links_to_ticket() {
+        let mut history = ApprovalHistory::new();
+        let mut record = create_test_record("t1", "Delete /tmp/a", ApprovalDecision::Approved);
+        record.justification = Some("cleanup".to_string());
+        history.record_decision(record).unwrap();
+
+        let outcome = ExecutionOutcome::new("t1", true, Duration::from_millis(12))
+            .with_result(&serde_json::json!({"ok": true}));
+        history.record_outcome(outcome).unwrap();
+
+        let stored = history.outcome("t1").unwrap();
+        assert_eq!(stored.duration_ms, 12);
+        assert!(stored
+            .result_digest
+            .as_ref()
+            .unwrap()
+            .starts_with("sha256:"));
+
+        let summary = history.audit_summary("t1").unwrap();
+        assert!(summary.contains("Approved by test_user because cleanup"));
+        assert!(summary.contains("in 12 ms: success"));
+
+        let log = history.export_audit_log().unwrap();
+        assert!(log.contains("\"outcome\""));
+        assert!(log.contains("\"ticket_id\": \"t1\""));
+    }
+
+    #[test]
+    fn test_history_persisted_across_opens() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit").join("history.jsonl");
+
+        let mut history = ApprovalHistory::open(&path).unwrap();
+        let mut record = create_test_record("t1", "Delete /tmp/a", ApprovalDecision::Approved);
+        record.action_type = Some(ActionType::DeleteFile);
+        history.record_decision(record).unwrap();
+        history
+            .record_outcome(ExecutionOutcome::new("t1", true, Duration::from_millis(7)))
+            .unwrap();
+        history
+            .record_decision(create_test_record("t2", "Edit b", ApprovalDecision::Denied))
+            .unwrap();
+
+        let reopened = ApprovalHistory::open(&path).unwrap();
+        assert_eq!(reopened.record_count(), 2);
+        assert_eq!(reopened.outcome("t1").unwrap().duration_ms, 7);
+        assert_eq!(
+            reopened.query(&AuditQuery::new().with_risk_level(RiskLevel::Critical))[0].id,
+            "t1"
+        );
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 3);
+    }
+
+    #[test]
+    fn test_daily_stats() {
+        let mut history = ApprovalHistory::new();
+        let day = |d: u32| chrono::NaiveDate::from_ymd_opt(2026, 3, d).unwrap();
+        for (id, d, decision) in [
+            ("1", 1, ApprovalDecision::Approved),
+            ("2", 1, ApprovalDecision::Denied),
+            ("3", 2, ApprovalDecision::Approved),
+            ("4", 2, ApprovalDecision::DeferredToLater),
+        ] {
+            let mut record = create_test_record(id, "Action", decision);
+            record.timestamp = day(d).and_hms_opt(9, 0, 0).unwrap().and_utc();
+            history.record_decision(record).unwrap();
+        }
+
+        let stats = history.daily_stats(&AuditQuery::new());
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].day, day(1));
+        assert_eq!((stats[0].approved, stats[0].denied), (1, 1));
+        assert_eq!((stats[1].approved, stats[1].deferred), (1, 1));
+        assert_eq!(stats[1].total(), 2);
+
+        let approved = AuditQuery::new().with_decision(DecisionKind::Approved);
+        let stats = history.daily_stats(&approved);
+        assert_eq!(stats.iter().map(DailyStats::total).sum::<usize>(), 2);
+
+        let jsonl = history.export(&approved, AuditFormat::Jsonl).unwrap();
+        assert_eq!(jsonl.lines().count(), 2);
+    }
+
+    #[test]
+    fn test_record_outcome_is_append_only() {
+        let mut history = ApprovalHistory::new();
+        history
+            .record_decision(create_test_record("t1", "A", ApprovalDecision::Approved))
+            .unwrap();
+        history
+            .record_decision(create_test_record("t2", "B", ApprovalDecision::Denied))
+            .unwrap();
+
+        let outcome = || ExecutionOutcome::new("t1", false, Duration::ZERO);
+        history.record_outcome(outcome()).unwrap();
+        assert!(history.record_outcome(outcome()).is_err());
+        assert!(history
+            .record_outcome(ExecutionOutcome::new("t2", true, Duration::ZERO))
+            .is_err());
+        assert!(history
+            .record_outcome(ExecutionOutcome::new("missing", true, Duration::ZERO))
+            .is_err());
+    }
 }
@@ -0,0 +1,624 @@
+//! Line-Level Unified Diffs for File Edits
+//!
+//! Computes a unified diff (hunks with context and line numbers) between the
+//! full before/after content of a file, so approvers see exactly which lines
+//! an edit changes instead of truncated previews.
+//!
+//! The diff uses Myers' algorithm on lines after trimming the common prefix
+//! and suffix. Large inputs are bounded:
+//! - If the edit distance exceeds `max_edit_distance`, the changed region is
+//!   shown as a single replacement instead of a minimal diff
+//! - At most `max_hunks` hunks are kept (counts still cover the whole diff)
+//! - Lines longer than `max_line_chars` are truncated
+//! - Binary content (NUL bytes or invalid UTF-8) produces no hunks
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+
+/// Bytes inspected for binary detection (same heuristic as git)
+const BINARY_SNIFF_BYTES: usize = 8000;
+
+/// Options controlling diff computation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiffOptions {
+    /// Unchanged lines shown around each change
+    pub context_lines: usize,
+
+    /// Maximum number of hunks kept
+    pub max_hunks: usize,
+
+    /// Maximum edit distance for a minimal diff
+    pub max_edit_distance: usize,
+
+    /// Maximum characters kept per line
+    pub max_line_chars: usize,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        Self {
+            context_lines: 3,
+            max_hunks: 50,
+            max_edit_distance: 1000,
+            max_line_chars: 400,
+        }
+    }
+}
+
+/// Kind of a diff line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffLineKind {
+    /// Unchanged line
+    Context,
+    /// Line only in the new content
+    Added,
+    /// Line only in the old content
+    Removed,
+}
+
+impl DiffLineKind {
+    /// Unified diff prefix character
+    pub fn prefix(self) -> char {
+        match self {
+            DiffLineKind::Context => ' ',
+            DiffLineKind::Added => '+',
+            DiffLineKind::Removed => '-',
+        }
+    }
+}
+
+/// A single line in a hunk
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct DiffLine {
+    /// Line kind
+    pub kind: DiffLineKind,
+
+    /// Line number in the old content (1-based; None for added lines)
+    pub old_line: Option<usize>,
+
+    /// Line number in the new content (1-based; None for removed lines)
+    pub new_line: Option<usize>,
+
+    /// Line text (without newline)
+    pub text: String,
+}
+
+/// A contiguous group of changes with surrounding context
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct DiffHunk {
+    /// First old line covered (1-based; 0 if the hunk covers no old lines)
+    pub old_start: usize,
+
+    /// Number of old lines covered
+    pub old_len: usize,
+
+    /// First new line covered (1-based; 0 if the hunk covers no new lines)
+    pub new_start: usize,
+
+    /// Number of new lines covered
+    pub new_len: usize,
+
+    /// Lines in the hunk
+    pub lines: Vec<DiffLine>,
+}
+
+impl DiffHunk {
+    /// Unified diff hunk header (`@@ -a,b +c,d @@`)
+    pub fn header(&self) -> String {
+        format!(
+            "@@ -{},{} +{},{} @@",
+            self.old_start, self.old_len, self.new_start, self.new_len
+        )
+    }
+}
+
+/// Line-level diff between two versions of a file
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct UnifiedDiff {
+    /// Hunks (at most `max_hunks`)
+    pub hunks: Vec<DiffHunk>,
+
+    /// Total added lines
+    pub added: usize,
+
+    /// Total removed lines
+    pub removed: usize,
+
+    /// Content is binary; no hunks are computed
+    #[serde(default)]
+    pub binary: bool,
+
+    /// Hunks dropped because of the hunk limit
+    #[serde(default)]
+    pub omitted_hunks: usize,
+}
+
+/// Edit operation (indices into old/new lines)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Equal(usize, usize),
+    Delete(usize),
+    Insert(usize),
+}
+
+impl UnifiedDiff {
+    /// Diff two texts
+    pub fn compute(before: &str, after: &str, options: &DiffOptions) -> Self {
+        if is_binary(before.as_bytes()) || is_binary(after.as_bytes()) {
+            return Self::binary();
+        }
+
+        let old: Vec<&str> = before.lines().collect();
+        let new: Vec<&str> = after.lines().collect();
+        let ops = diff_lines(&old, &new, options.max_edit_distance);
+
+        let added = ops.iter().filter(|op| matches!(op, Op::Insert(_))).count();
+        let removed = ops.iter().filter(|op| matches!(op, Op::Delete(_))).count();
+
+        let mut hunks = build_hunks(&ops, &old, &new, options);
+        let omitted_hunks = hunks.len().saturating_sub(options.max_hunks);
+        hunks.truncate(options.max_hunks);
+
+        Self {
+            hunks,
+            added,
+            removed,
+            binary: false,
+            omitted_hunks,
+        }
+    }
+
+    /// Diff two byte buffers, treating non-UTF-8 content as binary
+    pub fn compute_bytes(before: &[u8], after: &[u8], options: &DiffOptions) -> Self {
+        match (std::str::from_utf8(before), std::str::from_utf8(after)) {
+            (Ok(before), Ok(after)) => Self::compute(before, after, options),
+            _ => Self::binary(),
+        }
+    }
+
+    fn binary() -> Self {
+        Self {
+            binary: true,
+            ..Self::default()
+        }
+    }
+
+    /// True if the contents are identical (or the diff is binary)
+    pub fn is_empty(&self) -> bool {
+        self.hunks.is_empty()
+    }
+
+    /// One-line statistics, e.g. `+3 -1`
+    pub fn stats(&self) -> String {
+        if self.binary {
+            "binary".to_string()
+        } else {
+            format!("+{} -{}", self.added, self.removed)
+        }
+    }
+
+    /// Render as a unified diff for `path`
+    pub fn to_unified_string(&self, path: &str) -> String {
+        let mut out = String::new();
+        if self.binary {
+            let _ = writeln!(out, "Binary file {} differs", path);
+            return out;
+        }
+
+        let _ = writeln!(out, "--- a/{}", path);
+        let _ = writeln!(out, "+++ b/{}", path);
+        for hunk in &self.hunks {
+            let _ = writeln!(out, "{}", hunk.header());
+            for line in &hunk.lines {
+                let _ = writeln!(out, "{}{}", line.kind.prefix(), line.text);
+            }
+        }
+        if self.omitted_hunks > 0 {
+            let _ = writeln!(out, "... {} more hunks omitted", self.omitted_hunks);
+        }
+        out
+    }
+}
+
+/// NUL byte in the first few KB means binary
+fn is_binary(bytes: &[u8]) -> bool {
+    bytes[..bytes.len().min(BINARY_SNIFF_BYTES)].contains(&0)
+}
+
+/// Compute edit operations between two line lists
+fn diff_lines(old: &[&str], new: &[&str], max_edit_distance: usize) -> Vec<Op> {
+    let prefix = old
+        .iter()
+        .zip(new.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+
+    let mut ops: Vec<Op> = (0..prefix).map(|i| Op::Equal(i, i)).collect();
+
+    let middle = myers(old_mid, new_mid, max_edit_distance).unwrap_or_else(|| {
+        // Too many differences for a minimal diff: replace the whole region
+        (0..old_mid.len())
+            .map(Op::Delete)
+            .chain((0..new_mid.len()).map(Op::Insert))
+            .collect()
+    });
+    ops.extend(middle.into_iter().map(|op| match op {
+        Op::Equal(a, b) => Op::Equal(a + prefix, b + prefix),
+        Op::Delete(a) => Op::Delete(a + prefix),
+        Op::Insert(b) => Op::Insert(b + prefix),
+    }));
+
+    let old_tail = old.len() - suffix;
+    let new_tail = new.len() - suffix;
+    ops.extend((0..suffix).map(|i| Op::Equal(old_tail + i, new_tail + i)));
+    ops
+}
+
+/// Myers' O((N+M)D) diff; None if the edit distance exceeds `max_d`
+fn myers(old: &[&str], new: &[&str], max_d: usize) -> Option<Vec<Op>> {
+    let n = old.len() as isize;
+    let m = new.len() as isize;
+    let max = (n + m) as usize;
+    let offset = max as isize + 1;
+    let mut v = vec![0isize; 2 * max + 3];
+    // trace[d] holds v[-d-1..=d+1] as it was before step d
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+
+    for d in 0..=max.min(max_d) as isize {
+        trace.push(v[(offset - d - 1) as usize..=(offset + d + 1) as usize].to_vec());
+
+        let mut k = -d;
+        while k <= d {
+            let idx = (offset + k) as usize;
+            let mut x = if k == -d || (k != d && v[idx - 1] < v[idx + 1]) {
+                v[idx + 1]
+            } else {
+                v[idx - 1] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[idx] = x;
+
+            if x >= n && y >= m {
+                return Some(backtrack(&trace, n, m));
+            }
+            k += 2;
+        }
+    }
+
+    None
+}
+
+fn backtrack(trace: &[Vec<isize>], n: isize, m: isize) -> Vec<Op> {
+    let mut ops = Vec::new();
+    let (mut x, mut y) = (n, m);
+
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let get = |k: isize| v[(k + d + 1) as usize];
+        let k = x - y;
+        let prev_k = if k == -d || (k != d && get(k - 1) < get(k + 1)) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let prev_x = get(prev_k);
+        let prev_y = prev_x - prev_k;
+
+        while x > prev_x && y > prev_y {
+            ops.push(Op::Equal((x - 1) as usize, (y - 1) as usize));
+            x -= 1;
+            y -= 1;
+        }
+        if d > 0 {
+            if x == prev_x {
+                ops.push(Op::Insert(prev_y as usize));
+            } else {
+                ops.push(Op::Delete(prev_x as usize));
+            }
+        }
+        x = prev_x;
+        y = prev_y;
+    }
+
+    ops.reverse();
+    ops
+}
+
+/// Group edit operations into hunks with context
+fn build_hunks(ops: &[Op], old: &[&str], new: &[&str], options: &DiffOptions) -> Vec<DiffHunk> {
+    let changes: Vec<usize> = ops
+        .iter()
+        .enumerate()
+        .filter(|(_, op)| !matches!(op, Op::Equal(..)))
+        .map(|(i, _)| i)
+        .collect();
+
+    // Ranges of op indices, merging changes whose context would overlap
+    let context = options.context_lines;
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for &i in &changes {
+        let start = i.saturating_sub(context);
+        let end = (i + context + 1).min(ops.len());
+        match ranges.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => ranges.push((start, end)),
+        }
+    }
+
+    let line_text = |text: &str| -> String {
+        if text.chars().count() > options.max_line_chars {
+            let mut truncated: String = text.chars().take(options.max_line_chars).collect();
+            truncated.push('…');
+            truncated
+        } else {
+            text.to_string()
+        }
+    };
+
+    ranges
+        .into_iter()
+        .map(|(start, end)| {
+            // Old/new lines preceding the hunk
+            let (mut old_pos, mut new_pos) =
+                ops[..start].iter().fold((0, 0), |(o, n), op| match op {
+                    Op::Equal(..) => (o + 1, n + 1),
+                    Op::Delete(_) => (o + 1, n),
+                    Op::Insert(_) => (o, n + 1),
+                });
+            let (old_before, new_before) = (old_pos, new_pos);
+
+            let lines: Vec<DiffLine> = ops[start..end]
+                .iter()
+                .map(|op| match *op {
+                    Op::Equal(a, b) => {
+                        old_pos += 1;
+                        new_pos += 1;
+                        DiffLine {
+                            kind: DiffLineKind::Context,
+                            old_line: Some(a + 1),
+                            new_line: Some(b + 1),
+                            text: line_text(old[a]),
+                        }
+                    }
+                    Op::Delete(a) => {
+                        old_pos += 1;
+                        DiffLine {
+                            kind: DiffLineKind::Removed,
+                            old_line: Some(a + 1),
+                            new_line: None,
+                            text: line_text(old[a]),
+                        }
+                    }
+                    Op::Insert(b) => {
+                        new_pos += 1;
+                        DiffLine {
+                            kind: DiffLineKind::Added,
+                            old_line: None,
+                            new_line: Some(b + 1),
+                            text: line_text(new[b]),
+                        }
+                    }
+                })
+                .collect();
+
+            let old_len = old_pos - old_before;
+            let new_len = new_pos - new_before;
+            DiffHunk {
+                old_start: if old_len > 0 {
+                    old_before + 1
+                } else {
+                    old_before
+                },
+                old_len,
+                new_start: if new_len > 0 {
+                    new_before + 1
+                } else {
+                    new_before
+                },
+                new_len,
+                lines,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diff(before: &str, after: &str) -> UnifiedDiff {
+        UnifiedDiff::compute(before, after, &DiffOptions::default())
+    }
+
+    /// Prefix characters of all hunk lines
+    fn kinds(d: &UnifiedDiff) -> String {
+        d.hunks
+            .iter()
+            .flat_map(|h| h.lines.iter().map(|l| l.kind.prefix()))
+            .collect()
+    }
+
+    #[test]
+    fn test_identical_content() {
+        let d = diff("a\nb\nc\n", "a\nb\nc\n");
+        assert!(d.is_empty());
+        assert_eq!(d.stats(), "+0 -0");
+    }
+
+    #[test]
+    fn test_single_line_change() {
+        let d = diff("a\nb\nc\n", "a\nB\nc\n");
+
+        assert_eq!(d.added, 1);
+        assert_eq!(d.removed, 1);
+        assert_eq!(d.hunks.len(), 1);
+        assert_eq!(d.hunks[0].header(), "@@ -1,3 +1,3 @@");
+        assert_eq!(kinds(&d), " -+ ");
+        assert_eq!(d.hunks[0].lines[1].old_line, Some(2));
+        assert_eq!(d.hunks[0].lines[2].new_line, Some(2));
+    }
+
+    #[test]
+    fn test_minimal_diff_in_middle() {
+        let before = "fn main() {\n    let x = 1;\n    println!(\"{}\", x);\n}\n";
+        let after = "fn main() {\n    let x = 1;\n    let y = 2;\n    println!(\"{}\", x);\n}\n";
+
+        let d = diff(before, after);
+
+        assert_eq!((d.added, d.removed), (1, 0));
+        assert_eq!(d.hunks[0].header(), "@@ -1,4 +1,5 @@");
+        let added: Vec<_> = d.hunks[0]
+            .lines
+            .iter()
+            .filter(|l| l.kind == DiffLineKind::Added)
+            .collect();
+        assert_eq!(added[0].text, "    let y = 2;");
+        assert_eq!(added[0].new_line, Some(3));
+    }
+
+    #[test]
+    fn test_interleaved_changes_are_minimal() {
+        let d = diff("a\nb\nc\nd\ne\n", "a\nc\nd\nx\ne\n");
+        assert_eq!((d.added, d.removed), (1, 1));
+    }
+
+    #[test]
+    fn test_separate_hunks_with_context() {
+        let before: Vec<String> = (1..=30).map(|i| format!("line {}", i)).collect();
+        let mut after = before.clone();
+        after[1] = "changed 2".to_string();
+        after[25] = "changed 26".to_string();
+
+        let d = diff(&before.join("\n"), &after.join("\n"));
+
+        assert_eq!(d.hunks.len(), 2);
+        assert_eq!(d.hunks[0].header(), "@@ -1,5 +1,5 @@");
+        assert_eq!(d.hunks[1].header(), "@@ -23,7 +23,7 @@");
+    }
+
+    #[test]
+    fn test_new_file() {
+        let d = diff("", "one\ntwo\n");
+        assert_eq!(d.added, 2);
+        assert_eq!(d.hunks[0].header(), "@@ -0,0 +1,2 @@");
+    }
+
+    #[test]
+    fn test_hunk_limit() {
+        let before: Vec<String> = (0..100).map(|i| format!("line {}", i)).collect();
+        let after: Vec<String> = before
+            .iter()
+            .enumerate()
+            .map(|(i, l)| {
+                if i % 10 == 0 {
+                    format!("{} changed", l)
+                } else {
+                    l.clone()
+                }
+            })
+            .collect();
+        let options = DiffOptions {
+            max_hunks: 3,
+            ..DiffOptions::default()
+        };
+
+        let d = UnifiedDiff::compute(&before.join("\n"), &after.join("\n"), &options);
+
+        assert_eq!(d.hunks.len(), 3);
+        assert_eq!(d.omitted_hunks, 7);
+        assert_eq!(d.added, 10);
+        assert!(d.to_unified_string("f").contains("7 more hunks omitted"));
+    }
+
+    #[test]
+    fn test_edit_distance_fallback() {
+        let options = DiffOptions {
+            max_edit_distance: 2,
+            ..DiffOptions::default()
+        };
+
+        let d = UnifiedDiff::compute("a\nb\nc\nd\n", "w\nx\ny\nz\n", &options);
+
+        assert_eq!((d.added, d.removed), (4, 4));
+        assert_eq!(kinds(&d), "----++++");
+    }
+
+    #[test]
+    fn test_binary_detection() {
+        let d = diff("abc\0def", "abc");
+        assert!(d.binary);
+        assert!(d.is_empty());
+        assert_eq!(d.stats(), "binary");
+
+        let d = UnifiedDiff::compute_bytes(&[0xff, 0xfe], b"text", &DiffOptions::default());
+        assert!(d.binary);
+        assert!(d.to_unified_string("img.png").contains("Binary file"));
+    }
+
+    #[test]
+    fn test_long_lines_truncated() {
+        let long = "x".repeat(1000);
+        let d = diff("a\n", &format!("{}\n", long));
+        let added = &d.hunks[0].lines[1];
+        assert_eq!(added.text.chars().count(), 401);
+    }
+
+    #[test]
+    fn test_to_unified_string() {
+        let d = diff("a\nb\n", "a\nc\n");
+        assert_eq!(
+            d.to_unified_string("src/lib.rs"),
+            "--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1,2 +1,2 @@\n a\n-b\n+c\n"
+        );
+    }
+}
+
+#[cfg(test)]
+mod proptests {
+    use super::*;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn prop_diff_reconstructs_both_sides(
+            before in prop::collection::vec("[abc]", 0..40),
+            after in prop::collection::vec("[abc]", 0..40),
+        ) {
+            let options = DiffOptions {
+                context_lines: 100,
+                ..DiffOptions::default()
+            };
+            let d = UnifiedDiff::compute(&before.join("\n"), &after.join("\n"), &options);
+
+            let side = |skip: DiffLineKind| -> Vec<String> {
+                d.hunks
+                    .iter()
+                    .flat_map(|h| h.lines.iter())
+                    .filter(|l| l.kind != skip)
+                    .map(|l| l.text.clone())
+                    .collect()
+            };
+            if before != after {
+                prop_assert_eq!(side(DiffLineKind::Added), before);
+                prop_assert_eq!(side(DiffLineKind::Removed), after);
+            } else {
+                prop_assert!(d.is_empty());
+            }
+        }
+    }
+}
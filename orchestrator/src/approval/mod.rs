@@ -5,8 +5,23 @@
 //!
 //! Architecture:
 //! - `action.rs`: Classify actions as Green (safe) or Red (requires approval)
+//! - `amend.rs`: Approver edits to an action ("approve with modifications")
+//! - `anomaly.rs`: Baselines from approval history and anomaly flags
+//! - `audit.rs`: Audit trail queries, statistics and SIEM export
+//! - `checkpoint.rs`: File snapshots taken before approved actions, for rollback
 //! - `diff.rs`: Generate human-readable Diff Cards showing exact changes
+//! - `grants.rs`: Scoped standing grants ("approve similar actions")
 //! - `history.rs`: Record all approval decisions for audit trails
+//! - `plan.rs`: Multi-step plans approved together and executed in order
+//! - `policy.rs`: Reviewed rules that allow or deny Red actions without prompting
+//! - `preview.rs`: Dry-run red actions in a disposable workspace copy
+//! - `queue.rs`: Deferred actions waiting for a later decision
+//! - `quorum.rs`: Multi-party (N-of-M) approvals for critical actions
+//! - `render.rs`: Markdown and HTML Diff Cards for web and chat channels
+//! - `risk.rs`: Context-aware risk scores for Diff Cards
+//! - `schema.rs`: Published Diff Card JSON Schema and version upgrades
+//! - `signing.rs`: Ed25519-signed approval records and trust lists
+//! - `suggest.rs`: Policy rules suggested from approval history
 //! - `ui.rs`: CLI/interactive prompts for user approval
 //! - `mod.rs`: ApprovalManager - main entry point
 //!
@@ -24,18 +39,74 @@
 //! - Any destructive or external communication
 
 pub mod action;
+pub mod amend;
+pub mod anomaly;
+pub mod audit;
+pub mod backend;
+pub mod checkpoint;
 pub mod diff;
+pub mod grants;
+mod hex;
 pub mod history;
+pub mod line_diff;
+pub mod plan;
+pub mod policy;
+pub mod preview;
+pub mod queue;
+pub mod quorum;
+pub mod render;
+pub mod risk;
+pub mod schema;
+pub mod signing;
+pub mod suggest;
 pub mod tui;
 pub mod ui;
+pub mod web;
+pub mod webhook;
 
 pub use action::{ActionType, RiskLevel};
-pub use diff::{Change, DiffCard};
-pub use history::{ApprovalDecision, ApprovalHistory, ApprovalRecord};
+pub use amend::{Amendment, Modification};
+pub use anomaly::{Anomaly, AnomalyConfig, AnomalyKind, Baseline, Baselines};
+pub use audit::{AuditFormat, AuditQuery, DailyStats, DecisionKind};
+pub use backend::{
+    request_plan_with_reminders, request_with_reminders, ApprovalBackend, ApprovalRequest,
+    ApprovalTimedOut, BackendDecision, PromptBackend, TuiBackend,
+};
+pub use checkpoint::{Checkpoint, CheckpointStore, GcReport, Retention};
+pub use diff::{Change, DiffCard, SCHEMA_VERSION};
+pub use grants::{ChangeMatcher, Grant, GrantScope, GrantStore};
+pub use history::{
+    ApprovalDecision, ApprovalHistory, ApprovalRecord, ApprovalTicket, ExecutionOutcome,
+    TimeoutEvent,
+};
+pub use line_diff::{DiffOptions, UnifiedDiff};
+pub use plan::{
+    ActionPlan, PlanRequest, PlanStep, PlanStepCard, PlanStepTicket, PlanTicket, PlanVerdict,
+    StepSelection,
+};
+pub use policy::{ApprovalPolicy, PolicyEffect, PolicyRule};
+pub use preview::{argument_paths, Overlay, PreviewCommit, PreviewConfig, WorkspaceSnapshot};
+pub use queue::{DeferredAction, DeferredQueue, DeferredStatus};
+pub use quorum::{
+    ApproverGroup, PendingQuorum, QuorumConfig, QuorumRule, QuorumStatus, QuorumStore, Vote,
+};
+pub use risk::{AmountThresholds, RiskAssessment, RiskConfig};
+pub use schema::{diff_card_schema, parse_diff_card};
+pub use signing::{
+    verify_audit_log, Keyring, LogVerification, PlanStepDigest, RecordHeader, RecordSignature,
+    TrustList,
+};
+pub use suggest::{suggest_policy, suggested_policy, PatternStats, SuggestOptions, Suggestion};
 pub use tui::{present_tui_approval, TuiResult};
-pub use ui::{ApprovalPrompt, ApprovalPromptConfig};
+pub use ui::{ApprovalPrompt, ApprovalPromptConfig, ApprovalTimeouts, TimeoutAction};
+pub use web::{WebApprovalBackend, WebApprovalConfig, WebApprover};
+pub use webhook::{DecisionChannel, WebhookApprovalBackend, WebhookConfig};
 
-use tracing::info;
+use anyhow::Context;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
 
 /// Main Approval Manager - entry point for approval cliff workflow
 ///
@@ -55,6 +126,51 @@ pub struct ApprovalManager {
 
     /// UI configuration
     prompt_config: ApprovalPromptConfig,
+
+    /// Backend used for Red actions (stdin prompt if None)
+    backend: Option<Arc<dyn ApprovalBackend>>,
+
+    /// Backend asked when nobody decides in time (see [`TimeoutAction`])
+    escalation: Option<Arc<dyn ApprovalBackend>>,
+
+    /// Multi-party approval rules
+    quorum_config: QuorumConfig,
+
+    /// Pending multi-party approvals
+    quorum_store: QuorumStore,
+
+    /// User whose session requests actions (for separation of duties)
+    session_owner: String,
+
+    /// Standing grants consulted before prompting
+    grants: GrantStore,
+
+    /// Session ID that grants are bound to
+    session_id: String,
+
+    /// Deferred actions waiting for a decision
+    deferred: DeferredQueue,
+
+    /// Keys approvals must be signed with (not enforced if None)
+    trust: Option<TrustList>,
+
+    /// Local user's keys, for decisions made at this terminal
+    keyring: Option<Keyring>,
+
+    /// Snapshots of files approved actions change (none taken if None)
+    checkpoints: Option<CheckpointStore>,
+
+    /// Thresholds for scoring Diff Cards
+    risk_config: RiskConfig,
+
+    /// Checks against approval history baselines
+    anomaly_config: AnomalyConfig,
+
+    /// Baselines of the history, updated as decisions are recorded
+    baselines: Baselines,
+
+    /// Reviewed rules consulted before prompting
+    policy: ApprovalPolicy,
 }
 
 impl ApprovalManager {
@@ -64,16 +180,152 @@ impl ApprovalManager {
             history: ApprovalHistory::new(),
             enable_approval_cliff: true,
             prompt_config: ApprovalPromptConfig::default(),
+            backend: None,
+            escalation: None,
+            quorum_config: QuorumConfig::default(),
+            quorum_store: QuorumStore::in_memory(),
+            session_owner: local_user(),
+            grants: GrantStore::in_memory(),
+            session_id: uuid::Uuid::new_v4().to_string(),
+            deferred: DeferredQueue::in_memory(),
+            trust: None,
+            keyring: None,
+            checkpoints: None,
+            risk_config: RiskConfig::default(),
+            anomaly_config: AnomalyConfig::default(),
+            baselines: Baselines::default(),
+            policy: ApprovalPolicy::default(),
         }
     }
 
     /// Create with custom prompt configuration
     pub fn with_prompt_config(config: ApprovalPromptConfig) -> Self {
         Self {
-            history: ApprovalHistory::new(),
-            enable_approval_cliff: true,
             prompt_config: config,
+            ..Self::new()
+        }
+    }
+
+    /// Use an approval backend for Red actions
+    ///
+    /// Without a backend, Red actions are prompted for on stdin.
+    pub fn with_backend(mut self, backend: Arc<dyn ApprovalBackend>) -> Self {
+        self.backend = Some(backend);
+        self
+    }
+
+    /// Escalate requests nobody decided in time to `backend`
+    ///
+    /// Used when the prompt configuration's `on_timeout` is
+    /// [`TimeoutAction::Escalate`]; the escalated request gets a fresh
+    /// timeout and reminders.
+    pub fn with_escalation(mut self, backend: Arc<dyn ApprovalBackend>) -> Self {
+        self.escalation = Some(backend);
+        self
+    }
+
+    /// Require multi-party approval for actions matching quorum rules
+    ///
+    /// Votes are collected through the configured backend, one decision per
+    /// request, and kept in `store` until the quorum is decided. Votes only
+    /// count if signed, so a trust list must be set first
+    /// ([`with_trust_list`](Self::with_trust_list)).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the configuration is invalid, or has rules but no
+    /// trust list is set.
+    pub fn with_quorum(mut self, config: QuorumConfig, store: QuorumStore) -> anyhow::Result<Self> {
+        config.validate()?;
+        if !config.rules.is_empty() && self.trust.is_none() {
+            anyhow::bail!("Quorum rules need a trust list to authenticate votes");
         }
+        self.quorum_config = config;
+        self.quorum_store = store;
+        Ok(self)
+    }
+
+    /// Keep standing grants in `store` (e.g. a file shared with the CLI)
+    pub fn with_grants(mut self, store: GrantStore) -> Self {
+        self.grants = store;
+        self
+    }
+
+    /// Record decisions in `history` (e.g. a log file shared with the CLI)
+    pub fn with_history(mut self, history: ApprovalHistory) -> Self {
+        self.baselines = Baselines::build(&self.anomaly_config, history.get_history(None));
+        self.history = history;
+        self
+    }
+
+    /// Refuse approvals not signed by every approver with a trusted key
+    pub fn with_trust_list(mut self, trust: TrustList) -> Self {
+        self.trust = Some(trust);
+        self
+    }
+
+    /// Sign decisions made at this terminal (the stdin prompt and
+    /// [`check_and_approve_tui`](Self::check_and_approve_tui)) with the
+    /// local user's key from `keyring`
+    pub fn with_keyring(mut self, keyring: Keyring) -> Self {
+        self.keyring = Some(keyring);
+        self
+    }
+
+    /// Snapshot files approved actions will change into `store`
+    ///
+    /// Snapshots are taken by [`checkpoint`](Self::checkpoint) right before
+    /// an action runs, and sealed when the action's outcome is recorded, after
+    /// which it can be rolled back (see [`CheckpointStore::rollback`]).
+    pub fn with_checkpoints(mut self, store: CheckpointStore) -> Self {
+        self.checkpoints = Some(store);
+        self
+    }
+
+    /// Score the risk of Diff Cards with custom thresholds
+    pub fn with_risk_config(mut self, config: RiskConfig) -> Self {
+        self.risk_config = config;
+        self
+    }
+
+    /// Flag actions that deviate from approval history with custom settings
+    pub fn with_anomaly_config(mut self, config: AnomalyConfig) -> Self {
+        self.baselines = Baselines::build(&config, self.history.get_history(None));
+        self.anomaly_config = config;
+        self
+    }
+
+    /// Decide actions covered by `policy` rules without prompting
+    pub fn with_policy(mut self, policy: ApprovalPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Keep deferred actions in `queue` (e.g. a file shared with the CLI)
+    pub fn with_deferred_queue(mut self, queue: DeferredQueue) -> Self {
+        self.deferred = queue;
+        self
+    }
+
+    /// Look up a deferred action by ID (or unique ID prefix)
+    pub fn deferred_action(&mut self, id: &str) -> anyhow::Result<Option<DeferredAction>> {
+        self.deferred.get(id)
+    }
+
+    /// Deferred actions of this session decided or expired since last asked
+    pub fn take_deferred_notifications(&mut self) -> anyhow::Result<Vec<DeferredAction>> {
+        self.deferred.take_notifications(&self.session_id)
+    }
+
+    /// Session ID standing grants are bound to
+    pub fn session_id(&self) -> &str {
+        &self.session_id
+    }
+
+    /// Set the session owner (defaults to `$USER`)
+    pub fn with_session_owner(mut self, owner: impl Into<String>) -> Self {
+        self.session_owner = owner.into();
+        self
     }
 
     /// Check if an action requires approval and get user decision
@@ -83,49 +335,1048 @@ impl ApprovalManager {
     /// 2. If Green action, return Approved (auto-safe)
     /// 3. If Red action, present Diff Card and ask user
     /// 4. Record decision in audit trail
-    /// 5. Return decision
+    /// 5. Return decision, with the ticket to report the execution outcome
+    ///    to (see [`record_outcome`](Self::record_outcome))
     pub async fn check_and_approve(
         &mut self,
         action_type: ActionType,
         description: String,
         changes: Vec<Change>,
-    ) -> anyhow::Result<ApprovalDecision> {
+    ) -> anyhow::Result<ApprovalTicket> {
+        self.decide(action_type, description, changes, None).await
+    }
+
+    /// Check if an MCP tool call requires approval and get user decision
+    ///
+    /// Like [`check_and_approve`](Self::check_and_approve), but the Diff Card
+    /// shows the call's arguments and the approver may edit them. When the
+    /// decision is [`ApprovalDecision::ApprovedWithModifications`], only the
+    /// amended arguments (see [`Amendment::arguments`]) may be sent to the
+    /// server.
+    pub async fn check_and_approve_tool_call(
+        &mut self,
+        server_name: &str,
+        tool_name: &str,
+        arguments: &serde_json::Value,
+    ) -> anyhow::Result<ApprovalTicket> {
+        self.check_and_approve_previewed_tool_call(server_name, tool_name, arguments, Vec::new())
+            .await
+    }
+
+    /// Check an MCP tool call whose effects were observed in a dry run
+    ///
+    /// Like [`check_and_approve_tool_call`](Self::check_and_approve_tool_call),
+    /// with the `observed` changes from a preview run (see [`preview`]) shown
+    /// on the Diff Card after the call itself.
+    pub async fn check_and_approve_previewed_tool_call(
+        &mut self,
+        server_name: &str,
+        tool_name: &str,
+        arguments: &serde_json::Value,
+        observed: Vec<Change>,
+    ) -> anyhow::Result<ApprovalTicket> {
+        let action_type = ActionType::from_description(tool_name);
+        let description = format!("Call tool {} on {}", tool_name, server_name);
+        let mut changes = vec![Change::ExternalCall {
+            method: "tools/call".to_string(),
+            endpoint: format!("mcp://{}/{}", server_name, tool_name),
+            payload_preview: arguments.to_string(),
+        }];
+        changes.extend(observed);
+        self.decide(action_type, description, changes, Some(arguments))
+            .await
+    }
+
+    /// Ask for approval of a multi-step plan at once
+    ///
+    /// Approvers see one combined card and may approve every step, a
+    /// prefix, or all but some steps (see [`plan`] for how steps are
+    /// decided). Each step's decision is recorded with the plan ID. Check
+    /// every step with [`authorize_plan_step`](Self::authorize_plan_step)
+    /// before running it.
+    pub async fn check_and_approve_plan(&mut self, plan: ActionPlan) -> anyhow::Result<PlanTicket> {
+        let plan_id = uuid::Uuid::new_v4().to_string();
+        if !self.enable_approval_cliff {
+            info!(
+                "Approval cliff disabled, auto-approving plan: {}",
+                plan.description
+            );
+        }
+
+        // Decide what can be decided without the approver
+        let mut tickets = Vec::with_capacity(plan.steps.len());
+        let mut reviewed = Vec::new();
+        for (i, step) in plan.steps.iter().enumerate() {
+            if !self.enable_approval_cliff || !step.action_type.requires_approval() {
+                tickets.push((
+                    ApprovalTicket::unrecorded(ApprovalDecision::Approved),
+                    None,
+                    false,
+                ));
+                continue;
+            }
+
+            let mut card = DiffCard::new(
+                step.action_type,
+                step.description.clone(),
+                step.changes.clone(),
+            )
+            .with_risk_config(&self.risk_config);
+            let flags = self.anomaly_config.check_request(
+                &self.baselines,
+                &card,
+                &self.session_owner,
+                chrono::Utc::now(),
+            );
+            self.anomaly_config.flag(&mut card, &flags);
+
+            let rule = self
+                .policy
+                .rule_for(step.action_type, &card.changes)
+                .filter(|r| r.effect == PolicyEffect::Deny)
+                .cloned();
+            if let Some(rule) = rule {
+                let ticket = self.deny_automatically(
+                    step.description.clone(),
+                    Some(step.action_type),
+                    format!("Denied by policy rule ({})", rule),
+                )?;
+                tickets.push((ticket, None, false));
+                continue;
+            }
+
+            let separate = self.quorum_config.rule_for(card.risk_level).is_some()
+                || (self.anomaly_config.second_approver && !card.anomalies.is_empty());
+            if separate {
+                info!(
+                    "Plan step {} needs separate approval: {}",
+                    i + 1,
+                    step.description
+                );
+                let note = "Needs more than one approver; requested when reached".to_string();
+                tickets.push((
+                    ApprovalTicket::unrecorded(ApprovalDecision::DeferredToLater),
+                    Some(note),
+                    true,
+                ));
+                continue;
+            }
+
+            reviewed.push(PlanStepCard::new(i + 1, card));
+            tickets.push((
+                ApprovalTicket::unrecorded(ApprovalDecision::DeferredToLater),
+                None,
+                false,
+            ));
+        }
+
+        if !reviewed.is_empty() {
+            let request = PlanRequest::new(&plan.description, reviewed)?;
+
+            // Ask for the remaining steps at once
+            let started = std::time::Instant::now();
+            let timed = self.backend.is_some() || self.prompt_config.interactive;
+            let (verdict, latency, timeout) = match self.request_plan_decision(&request).await {
+                Ok(verdict) => (verdict, timed.then(|| started.elapsed()), None),
+                Err(e) => match e.downcast_ref::<ApprovalTimedOut>() {
+                    Some(timed_out) => {
+                        warn!("Plan approval timed out: {}", plan.description);
+                        let (verdict, event) =
+                            self.handle_plan_timeout(&request, *timed_out).await?;
+                        (verdict, None, Some(event))
+                    }
+                    None => return Err(e),
+                },
+            };
+
+            // Record each step's decision (signed unless excluded)
+            for step in request.steps {
+                let signature = verdict.decision.signature.clone();
+                let (decision, justification, signature) = if verdict.approves(step.number) {
+                    (
+                        ApprovalDecision::Approved,
+                        verdict.decision.justification.clone(),
+                        signature,
+                    )
+                } else if verdict.decision.decision.is_approved() {
+                    (
+                        ApprovalDecision::Denied,
+                        Some(format!("Excluded from plan (approved {})", verdict.steps)),
+                        None,
+                    )
+                } else {
+                    (
+                        verdict.decision.decision.clone(),
+                        verdict.decision.justification.clone(),
+                        signature,
+                    )
+                };
+                let record = ApprovalRecord {
+                    justification,
+                    plan_id: Some(plan_id.clone()),
+                    requested_by: Some(self.session_owner.clone()),
+                    latency_ms: latency.map(|l| l.as_millis() as u64),
+                    anomalies: step.card.anomalies.clone(),
+                    timeout: timeout.clone(),
+                    signatures: signature.into_iter().collect(),
+                    ..step
+                        .record
+                        .into_record(decision, verdict.decision.approved_by.clone())
+                };
+                tickets[step.number - 1].0 = self.commit(record)?;
+            }
+        }
+
+        let steps = plan
+            .steps
+            .into_iter()
+            .zip(tickets)
+            .map(|(step, (ticket, note, pending))| PlanStepTicket {
+                step,
+                ticket,
+                note,
+                pending,
+            })
+            .collect();
+        Ok(PlanTicket::new(plan_id, steps))
+    }
+
+    /// Check a plan step the agent is about to run
+    ///
+    /// Returns the step's ticket (to report the execution outcome to) if
+    /// `step` is the plan's next approved step, with the changes and
+    /// arguments that were approved. Anything else (steps out of order,
+    /// excluded or changed steps, steps run twice) is denied and recorded
+    /// as an automatic denial. A pending step is requested on its own now,
+    /// like a single action; until it is approved, it stays next.
+    ///
+    /// The files an approved step changes are checkpointed before the
+    /// ticket is returned; if they cannot be, the step is denied.
+    pub async fn authorize_plan_step(
+        &mut self,
+        plan: &mut PlanTicket,
+        step: &PlanStep,
+    ) -> anyhow::Result<ApprovalTicket> {
+        let ticket = match plan.next_step(step) {
+            Ok(ticket) if ticket.decision == ApprovalDecision::DeferredToLater => {
+                info!(
+                    "Requesting pending step of plan {}: {}",
+                    plan.plan_id, step.description
+                );
+                let ticket = self
+                    .decide(
+                        step.action_type,
+                        step.description.clone(),
+                        step.changes.clone(),
+                        step.arguments.as_ref(),
+                    )
+                    .await?;
+                plan.settle(ticket.clone());
+                ticket
+            }
+            Ok(ticket) => ticket,
+            Err(e) => {
+                warn!(
+                    "Refusing step of plan {}: {}",
+                    plan.plan_id, step.description
+                );
+                return self.deny_automatically(
+                    step.description.clone(),
+                    Some(step.action_type),
+                    format!(
+                        "Not the next approved step of plan {}: {:#}",
+                        plan.plan_id, e
+                    ),
+                );
+            }
+        };
+
+        if let (true, Some(id)) = (ticket.decision.is_approved(), &ticket.id) {
+            if let Err(e) = self.checkpoint(id, &step_paths(step, &ticket)) {
+                warn!("Refusing step without checkpoint: {}", step.description);
+                return self.deny_automatically(
+                    step.description.clone(),
+                    Some(step.action_type),
+                    format!("Checkpoint failed: {:#}", e),
+                );
+            }
+        }
+        Ok(ticket)
+    }
+
+    /// Approval flow shared by actions and tool calls
+    ///
+    /// `arguments` are the tool call arguments approvers may edit.
+    async fn decide(
+        &mut self,
+        action_type: ActionType,
+        description: String,
+        changes: Vec<Change>,
+        arguments: Option<&serde_json::Value>,
+    ) -> anyhow::Result<ApprovalTicket> {
         // If approval cliff disabled, auto-approve
         if !self.enable_approval_cliff {
             info!(
                 "Approval cliff disabled, auto-approving action: {}",
                 description
             );
-            return Ok(ApprovalDecision::Approved);
+            return Ok(ApprovalTicket::unrecorded(ApprovalDecision::Approved));
         }
 
         // Green actions skip approval
         if !action_type.requires_approval() {
             info!("Green action, auto-approving: {}", description);
-            return Ok(ApprovalDecision::Approved);
+            return Ok(ApprovalTicket::unrecorded(ApprovalDecision::Approved));
         }
 
-        // Generate Diff Card for Red action
-        let diff_card = DiffCard::new(action_type, description.clone(), changes);
+        // Generate Diff Card for Red action, flagged against past behaviour
+        let mut diff_card = DiffCard::new(action_type, description.clone(), changes)
+            .with_risk_config(&self.risk_config);
+        let flags = self.anomaly_config.check_request(
+            &self.baselines,
+            &diff_card,
+            &self.session_owner,
+            chrono::Utc::now(),
+        );
+        self.anomaly_config.flag(&mut diff_card, &flags);
+        let header = RecordHeader {
+            session_id: Some(self.session_id.clone()),
+            ..RecordHeader::for_card(&diff_card)
+        };
+
+        // Policy deny rules apply to every action
+        let rule = self
+            .policy
+            .rule_for(action_type, &diff_card.changes)
+            .cloned();
+        if let Some(rule) = rule.as_ref().filter(|r| r.effect == PolicyEffect::Deny) {
+            return self.deny_automatically(
+                description,
+                Some(action_type),
+                format!("Denied by policy rule ({})", rule),
+            );
+        }
+
+        // Critical actions may need several approvers
+        if let Some(rule) = self.quorum_config.rule_for(diff_card.risk_level).cloned() {
+            return self
+                .collect_quorum(rule, action_type, description, diff_card, arguments, header)
+                .await;
+        }
+
+        // Policy allow rules and standing grants skip the prompt, unless flags
+        // need a second approver
+        let flagged = self.anomaly_config.second_approver && !diff_card.anomalies.is_empty();
+        if let Some(rule) = rule.filter(|_| !flagged) {
+            info!("Approved by policy rule ({}): {}", rule, description);
+            // Signed rules approve on behalf of their signer
+            let signature = signing::rule_signature(&rule);
+            let approved_by = signature
+                .as_ref()
+                .map_or_else(|| "system".to_string(), |s| s.signer.clone());
+            let record = ApprovalRecord {
+                justification: Some(format!("Allowed by policy rule ({})", rule)),
+                requested_by: Some(self.session_owner.clone()),
+                anomalies: diff_card.anomalies.clone(),
+                signatures: signature.into_iter().collect(),
+                ..header.into_record(ApprovalDecision::Approved, approved_by)
+            };
+            return self.commit(record);
+        }
+        let grant = if flagged {
+            None
+        } else {
+            self.grants
+                .find(action_type, &diff_card.changes, &self.session_id)?
+        };
+        if let Some(grant) = grant {
+            info!("Approved by standing grant {}: {}", grant.id, description);
+            let record = ApprovalRecord {
+                justification: Some(format!("Standing grant ({})", grant.matcher)),
+                grant_id: Some(grant.id),
+                origin_record_id: Some(grant.origin_record_id),
+                requested_by: Some(self.session_owner.clone()),
+                anomalies: diff_card.anomalies.clone(),
+                ..header.into_record(ApprovalDecision::Approved, grant.granted_by)
+            };
+            return self.commit(record);
+        }
 
-        // Ask user for approval
-        let prompt = ApprovalPrompt::with_config(self.prompt_config.clone());
-        let decision = prompt.ask_for_approval(&diff_card).await?;
+        // Deferred actions are decided through the queue
+        let changes = diff_card.changes.clone();
+        if let Some(item) = self.deferred.claim(
+            action_type,
+            &description,
+            &changes,
+            arguments,
+            &self.session_id,
+        )? {
+            // Recorded as the record the approver signed
+            let queued = item.record_header();
+            match item.status {
+                DeferredStatus::Pending => {
+                    info!(
+                        "Still awaiting deferred decision {}: {}",
+                        item.id, description
+                    );
+                    return Ok(
+                        ApprovalTicket::unrecorded(ApprovalDecision::DeferredToLater)
+                            .with_deferred_id(item.id),
+                    );
+                }
+                DeferredStatus::Decided {
+                    decision,
+                    decided_by,
+                    justification,
+                    signature,
+                    ..
+                } => {
+                    info!("Deferred action {} decided by {}", item.id, decided_by);
+                    let record = ApprovalRecord {
+                        justification,
+                        deferred_id: Some(item.id),
+                        requested_by: Some(self.session_owner.clone()),
+                        anomalies: diff_card.anomalies,
+                        signatures: signature.map(|s| *s).into_iter().collect(),
+                        ..queued.into_record(decision, decided_by)
+                    };
+                    return self.commit(record);
+                }
+                DeferredStatus::Expired => {
+                    info!("Deferred action {} expired, asking again", item.id);
+                }
+            }
+        }
+
+        // Ask user for approval (non-interactive prompts decide without anyone)
+        let started = std::time::Instant::now();
+        let timed = self.backend.is_some() || self.prompt_config.interactive;
+        let mut timeout = None;
+        let (outcome, latency) = match self.request_decision(diff_card.clone(), &header).await {
+            Ok(outcome) => (outcome, timed.then(|| started.elapsed())),
+            Err(e) => match e.downcast_ref::<ApprovalTimedOut>() {
+                Some(timed_out) => {
+                    warn!("Approval timed out: {}", description);
+                    let (outcome, event) =
+                        self.handle_timeout(&diff_card, &header, *timed_out).await?;
+                    timeout = Some(event);
+                    (outcome, None)
+                }
+                None => return Err(e),
+            },
+        };
+
+        // Approvers may amend the action before approving it
+        let mut signatures: Vec<RecordSignature> = outcome.signature.into_iter().collect();
+        let mut decision = outcome.decision;
+        let mut justification = outcome.justification;
+        if decision == ApprovalDecision::Approved && !outcome.modifications.is_empty() {
+            match Amendment::apply(&changes, arguments, outcome.modifications) {
+                Ok(amendment) => {
+                    info!("Action amended by {}: {}", outcome.approved_by, description);
+                    decision = ApprovalDecision::ApprovedWithModifications(Box::new(amendment));
+                }
+                Err(e) => {
+                    warn!("Invalid modifications, denying action: {}", description);
+                    decision = ApprovalDecision::Denied;
+                    justification = Some(format!("Invalid modifications: {:#}", e));
+                }
+            }
+        }
+
+        // Approvals are checked too (e.g. given too quickly to be reviewed)
+        let mut anomalies = diff_card.anomalies.clone();
+        if let (true, Some(latency)) = (decision.is_approved(), latency) {
+            let flags = self.anomaly_config.check_approval(
+                &self.baselines,
+                action_type,
+                &outcome.approved_by,
+                latency,
+                chrono::Utc::now(),
+            );
+            anomalies.extend(flags.iter().map(ToString::to_string));
+        }
+
+        // Flagged approvals may need a second, different approver
+        let mut approved_by = outcome.approved_by;
+        let mut votes = Vec::new();
+        if decision.is_approved() && self.anomaly_config.second_approver && !anomalies.is_empty() {
+            info!("Flagged approval needs a second approver: {}", description);
+            let mut card = diff_card.clone();
+            card.anomalies.clone_from(&anomalies);
+            let refusal = match self.request_decision(card, &header).await {
+                Ok(second) if !second.decision.is_approved() => {
+                    Some(format!("not approved by {}", second.approved_by))
+                }
+                Ok(second) if second.approved_by == approved_by => {
+                    Some(format!("{} cannot approve twice", approved_by))
+                }
+                Ok(mut second) => {
+                    let mut first = Vote::new(approved_by.clone(), decision.clone());
+                    first.justification = justification.clone();
+                    approved_by = format!("{}, {}", approved_by, second.approved_by);
+                    signatures.extend(second.signature.take());
+                    votes = vec![first, Vote::from(second)];
+                    None
+                }
+                Err(e) => match e.downcast_ref::<ApprovalTimedOut>() {
+                    Some(timed_out) => Some(format!("timeout: {}", timed_out)),
+                    None => return Err(e),
+                },
+            };
+            if let Some(refusal) = refusal {
+                warn!("Second approval refused, denying action: {}", description);
+                decision = ApprovalDecision::Denied;
+                justification = Some(format!(
+                    "Second approver required for anomalous action ({})",
+                    refusal
+                ));
+            }
+        }
 
         // Record decision in history
+        let mut record = ApprovalRecord {
+            justification,
+            votes,
+            requested_by: Some(self.session_owner.clone()),
+            latency_ms: latency.map(|l| l.as_millis() as u64),
+            anomalies,
+            timeout,
+            signatures,
+            ..header.into_record(decision.clone(), approved_by)
+        };
+
+        // Approvals may extend to similar actions; under a trust list only if
+        // the approver signed the grant too
+        let grant = outcome.grant.as_ref().filter(|scope| {
+            let signed = self.trust.is_none()
+                || record
+                    .signatures
+                    .iter()
+                    .any(|s| s.signer == record.approved_by && s.grant.as_ref() == Some(*scope));
+            if !signed {
+                warn!(
+                    "Not creating standing grant ({}): grant is not signed",
+                    scope
+                );
+            }
+            signed
+        });
+        if let (ApprovalDecision::Approved, Some(scope)) = (&decision, grant) {
+            let grant = Grant::from_scope(
+                scope,
+                action_type,
+                &changes,
+                &self.session_id,
+                &record.approved_by,
+                &record.id,
+            );
+            info!("Created standing grant {} for {}", grant.id, scope);
+            record.grant_id = Some(grant.id.clone());
+            self.grants.add(grant)?;
+        }
+
+        // Deferred actions wait in the queue for a later decision
+        if decision == ApprovalDecision::DeferredToLater {
+            let item = DeferredAction::new(
+                action_type,
+                record.action_description.clone(),
+                changes.clone(),
+                self.session_id.clone(),
+                self.session_owner.clone(),
+            )
+            .with_arguments(arguments.cloned());
+            let item = self.deferred.enqueue(item)?;
+            info!("Deferred action queued as {}", item.id);
+            record.deferred_id = Some(item.id);
+        }
+
+        self.commit(record)
+    }
+
+    /// Ask the backend (or stdin prompt) for one decision, to be recorded
+    /// as the record with `header`
+    async fn request_decision(
+        &self,
+        diff_card: DiffCard,
+        header: &RecordHeader,
+    ) -> anyhow::Result<BackendDecision> {
+        match &self.backend {
+            Some(backend) => self.ask(backend.as_ref(), diff_card, header).await,
+            None => {
+                let prompt = PromptBackend::new(self.prompt_config.clone())
+                    .with_keyring(self.keyring.clone());
+                self.ask(&prompt, diff_card, header).await
+            }
+        }
+    }
+
+    /// Ask one backend, with the risk level's timeout and reminders
+    async fn ask(
+        &self,
+        backend: &dyn ApprovalBackend,
+        diff_card: DiffCard,
+        header: &RecordHeader,
+    ) -> anyhow::Result<BackendDecision> {
+        info!("Requesting approval via {} backend", backend.name());
+        let timeout = self.prompt_config.timeouts.for_level(diff_card.risk_level);
+        let request = ApprovalRequest::new(diff_card)
+            .for_record(header.clone())
+            .with_timeout(timeout);
+        request_with_reminders(backend, &request, &self.prompt_config.reminders).await
+    }
+
+    /// Ask the backend (or stdin prompt) for a decision on a plan
+    async fn request_plan_decision(&self, plan: &PlanRequest) -> anyhow::Result<PlanVerdict> {
+        match &self.backend {
+            Some(backend) => self.ask_plan(backend.as_ref(), plan).await,
+            None => {
+                let prompt = PromptBackend::new(self.prompt_config.clone())
+                    .with_keyring(self.keyring.clone());
+                self.ask_plan(&prompt, plan).await
+            }
+        }
+    }
+
+    /// Ask one backend about a plan, with its riskiest step's timeout
+    async fn ask_plan(
+        &self,
+        backend: &dyn ApprovalBackend,
+        plan: &PlanRequest,
+    ) -> anyhow::Result<PlanVerdict> {
+        info!("Requesting plan approval via {} backend", backend.name());
+        let risk = plan.request.diff_card.risk_level;
+        let mut plan = plan.clone();
+        plan.request = ApprovalRequest::new(plan.request.diff_card)
+            .for_plan(plan.request.plan)
+            .with_timeout(self.prompt_config.timeouts.for_level(risk));
+        let mut verdict =
+            request_plan_with_reminders(backend, &plan, &self.prompt_config.reminders).await?;
+
+        // Plans are approved as submitted, step by step
+        let decision = &verdict.decision;
+        if decision.decision.is_approved()
+            && (decision.decision.amendment().is_some()
+                || !decision.modifications.is_empty()
+                || decision.grant.is_some())
+        {
+            warn!("Refusing plan approval with modifications or a grant");
+            return Ok(PlanVerdict::whole(BackendDecision::system(
+                ApprovalDecision::Denied,
+                "Plan approvals cannot modify steps or grant similar actions".to_string(),
+            )));
+        }
+
+        // Signatures cover every step's record and the steps selected
+        if let Some(signature) = &mut verdict.decision.signature {
+            signature.plan.clone_from(&plan.request.plan);
+        }
+        Ok(verdict)
+    }
+
+    /// Apply the configured [`TimeoutAction`] to a plan nobody decided
+    ///
+    /// Plans are not queued: deferred steps are recorded as deferred, and
+    /// the plan has to be submitted again.
+    async fn handle_plan_timeout(
+        &self,
+        plan: &PlanRequest,
+        timed_out: ApprovalTimedOut,
+    ) -> anyhow::Result<(PlanVerdict, TimeoutEvent)> {
+        let action = self.prompt_config.on_timeout;
+        let mut event = TimeoutEvent::new(Duration::from_secs(timed_out.timeout_secs), action);
+        let reason = format!("Approval timeout: {}", timed_out);
+        let decision = match (action, &self.escalation) {
+            (TimeoutAction::Deny, _) => BackendDecision::system(ApprovalDecision::Denied, reason),
+            (TimeoutAction::Defer, _) => {
+                BackendDecision::system(ApprovalDecision::DeferredToLater, reason)
+            }
+            (TimeoutAction::Escalate, None) => {
+                warn!("No escalation backend configured, denying plan");
+                BackendDecision::system(
+                    ApprovalDecision::Denied,
+                    format!("{} (no escalation backend)", reason),
+                )
+            }
+            (TimeoutAction::Escalate, Some(escalation)) => {
+                event.escalated_to = Some(escalation.name().to_string());
+                match self.ask_plan(escalation.as_ref(), plan).await {
+                    Ok(verdict) => return Ok((verdict, event)),
+                    Err(e) => match e.downcast_ref::<ApprovalTimedOut>() {
+                        Some(again) => BackendDecision::system(
+                            ApprovalDecision::Denied,
+                            format!("{} (escalated to {}: {})", reason, escalation.name(), again),
+                        ),
+                        None => return Err(e),
+                    },
+                }
+            }
+        };
+        Ok((PlanVerdict::whole(decision), event))
+    }
+
+    /// Apply the configured [`TimeoutAction`] to a request nobody decided
+    async fn handle_timeout(
+        &self,
+        diff_card: &DiffCard,
+        header: &RecordHeader,
+        timed_out: ApprovalTimedOut,
+    ) -> anyhow::Result<(BackendDecision, TimeoutEvent)> {
+        let action = self.prompt_config.on_timeout;
+        let mut event = TimeoutEvent::new(Duration::from_secs(timed_out.timeout_secs), action);
+        let reason = format!("Approval timeout: {}", timed_out);
+        let outcome = match action {
+            TimeoutAction::Deny => BackendDecision::system(ApprovalDecision::Denied, reason),
+            TimeoutAction::Defer => {
+                BackendDecision::system(ApprovalDecision::DeferredToLater, reason)
+            }
+            TimeoutAction::Escalate => match &self.escalation {
+                None => {
+                    warn!("No escalation backend configured, denying action");
+                    BackendDecision::system(
+                        ApprovalDecision::Denied,
+                        format!("{} (no escalation backend)", reason),
+                    )
+                }
+                Some(escalation) => {
+                    event.escalated_to = Some(escalation.name().to_string());
+                    match self
+                        .ask(escalation.as_ref(), diff_card.clone(), header)
+                        .await
+                    {
+                        Ok(outcome) => outcome,
+                        Err(e) => match e.downcast_ref::<ApprovalTimedOut>() {
+                            Some(again) => BackendDecision::system(
+                                ApprovalDecision::Denied,
+                                format!(
+                                    "{} (escalated to {}: {})",
+                                    reason,
+                                    escalation.name(),
+                                    again
+                                ),
+                            ),
+                            None => return Err(e),
+                        },
+                    }
+                }
+            },
+        };
+        Ok((outcome, event))
+    }
+
+    /// Collect votes until a quorum is reached or becomes unreachable
+    ///
+    /// A vote only counts if the voter signed it with a trusted key that has
+    /// not voted under another name. If votes stop arriving (every request
+    /// produced an invalid, unauthenticated or duplicate vote), the approval
+    /// stays pending in the store and the action is deferred, with an item
+    /// in the deferred queue; asking again later resumes the same vote.
+    /// Standing grants and modifications requested along with votes are
+    /// ignored. A vote request that times out denies the action, whatever
+    /// the configured [`TimeoutAction`].
+    async fn collect_quorum(
+        &mut self,
+        rule: QuorumRule,
+        action_type: ActionType,
+        description: String,
+        diff_card: DiffCard,
+        arguments: Option<&serde_json::Value>,
+        header: RecordHeader,
+    ) -> anyhow::Result<ApprovalTicket> {
+        let id = self.quorum_store.open_request(
+            &self.quorum_config,
+            &rule,
+            &diff_card,
+            arguments,
+            &self.session_owner,
+            header,
+        )?;
+        let (mut status, eligible, header) = match self.quorum_store.get(&id) {
+            Some(pending) => (
+                pending.status(),
+                pending.eligible.len(),
+                pending.record.clone(),
+            ),
+            None => anyhow::bail!("Pending approval {} disappeared", id),
+        };
+        let digest = header.digest()?;
+
+        let mut timed_out = None;
+        for _ in 0..eligible * 2 {
+            let QuorumStatus::Pending {
+                approvals,
+                required,
+            } = status
+            else {
+                break;
+            };
+            info!(
+                "Quorum for '{}': {}/{} approvals from group '{}'",
+                description, approvals, required, rule.group
+            );
+
+            let outcome = match self.request_decision(diff_card.clone(), &header).await {
+                Ok(outcome) => outcome,
+                Err(e) => match e.downcast_ref::<ApprovalTimedOut>() {
+                    Some(t) => {
+                        warn!("Approval timed out, denying action: {}", description);
+                        timed_out = Some(*t);
+                        break;
+                    }
+                    None => return Err(e),
+                },
+            };
+            // With a trust list, only votes signed by the voter count
+            let vote = Vote::from(outcome);
+            if let Some(trust) = &self.trust {
+                let signature = vote.signature.as_ref();
+                if let Err(e) =
+                    trust.authenticate(&vote.approver, &digest, &vote.decision, signature)
+                {
+                    warn!("Ignoring unauthenticated vote on {}: {:#}", id, e);
+                    continue;
+                }
+            }
+            match self.quorum_store.vote(&id, vote) {
+                Ok(next) => status = next,
+                Err(e) => warn!("Ignoring vote on {}: {:#}", id, e),
+            }
+        }
+
+        let (decision, justification) = match status {
+            QuorumStatus::Approved => (
+                ApprovalDecision::Approved,
+                format!(
+                    "Quorum reached: {} of group '{}' required",
+                    rule.required, rule.group
+                ),
+            ),
+            QuorumStatus::Pending {
+                approvals,
+                required,
+            } => match timed_out {
+                Some(t) => (
+                    ApprovalDecision::Denied,
+                    format!(
+                        "Approval timeout: {} ({}/{} approvals from group '{}')",
+                        t, approvals, required, rule.group
+                    ),
+                ),
+                None => {
+                    info!("Quorum still pending for '{}', deferring", description);
+                    let item = match self.deferred.quorum_item(&id)? {
+                        Some(item) => item,
+                        None => self.deferred.enqueue(
+                            DeferredAction::new(
+                                action_type,
+                                description,
+                                diff_card.changes,
+                                self.session_id.clone(),
+                                self.session_owner.clone(),
+                            )
+                            .with_arguments(arguments.cloned())
+                            .with_quorum(&id),
+                        )?,
+                    };
+                    return Ok(
+                        ApprovalTicket::unrecorded(ApprovalDecision::DeferredToLater)
+                            .with_deferred_id(item.id),
+                    );
+                }
+            },
+            QuorumStatus::Denied => (
+                ApprovalDecision::Denied,
+                format!(
+                    "Quorum not reached: {} of group '{}' required",
+                    rule.required, rule.group
+                ),
+            ),
+        };
+
+        let mut votes = self
+            .quorum_store
+            .resolve(&id)?
+            .map(|p| p.votes)
+            .unwrap_or_default();
+        let deferred_id = match self.deferred.quorum_item(&id)? {
+            Some(item) => self.deferred.withdraw(&item.id)?.map(|item| item.id),
+            None => None,
+        };
+        let signatures = votes
+            .iter_mut()
+            .filter_map(|v| v.signature.take())
+            .collect();
+        let voters: Vec<&str> = votes.iter().map(|v| v.approver.as_str()).collect();
+        let approved_by = if voters.is_empty() {
+            "system".to_string()
+        } else {
+            voters.join(", ")
+        };
+
+        let record = ApprovalRecord {
+            justification: Some(justification),
+            votes,
+            requested_by: Some(self.session_owner.clone()),
+            anomalies: diff_card.anomalies.clone(),
+            timeout: timed_out.map(|t| {
+                TimeoutEvent::new(Duration::from_secs(t.timeout_secs), TimeoutAction::Deny)
+            }),
+            signatures,
+            deferred_id,
+            ..header.into_record(decision, approved_by)
+        };
+
+        self.commit(record)
+    }
+
+    /// Record an action that was denied automatically (without prompting)
+    ///
+    /// Used when the orchestrator rejects an action by policy, e.g. a tool
+    /// call that violates a tool allowlist or argument constraint. The record
+    /// is attributed to "system" and carries the reason as justification.
+    pub fn record_automatic_denial(
+        &mut self,
+        description: String,
+        reason: String,
+    ) -> anyhow::Result<()> {
+        self.deny_automatically(description, None, reason)
+            .map(|_| ())
+    }
+
+    /// Record an automatic denial, returning its ticket
+    fn deny_automatically(
+        &mut self,
+        description: String,
+        action_type: Option<ActionType>,
+        reason: String,
+    ) -> anyhow::Result<ApprovalTicket> {
+        info!("Automatically denying action: {} ({})", description, reason);
+
         let record = ApprovalRecord {
             id: uuid::Uuid::new_v4().to_string(),
             timestamp: chrono::Utc::now(),
             action_description: description,
-            decision,
-            approved_by: std::env::var("USER").unwrap_or_else(|_| "unknown".to_string()),
-            justification: None,
+            action_type,
+            decision: ApprovalDecision::Denied,
+            approved_by: "system".to_string(),
+            justification: Some(reason),
             execution_result: None,
+            votes: Vec::new(),
+            grant_id: None,
+            origin_record_id: None,
+            deferred_id: None,
+            plan_id: None,
+            requested_by: Some(self.session_owner.clone()),
+            session_id: Some(self.session_id.clone()),
+            paths: Vec::new(),
+            command: None,
+            latency_ms: None,
+            anomalies: Vec::new(),
+            timeout: None,
+            diff_card_digest: None,
+            signatures: Vec::new(),
+        };
+
+        let ticket = ApprovalTicket::for_record(&record);
+        self.record(record)?;
+        Ok(ticket)
+    }
+
+    /// Add a decision to the history and the baselines
+    fn record(&mut self, record: ApprovalRecord) -> anyhow::Result<()> {
+        self.history.record_decision(record.clone())?;
+        self.baselines.add(&record);
+        Ok(())
+    }
+
+    /// Record a decision made by approvers
+    ///
+    /// With a trust list configured, the approvers' signatures are verified
+    /// first (for standing grants, those of the approval that created the
+    /// grant); an approval whose signatures do not verify is recorded as
+    /// made, then refused with an automatic denial.
+    fn commit(&mut self, record: ApprovalRecord) -> anyhow::Result<ApprovalTicket> {
+        let rejected = match &self.trust {
+            Some(trust) if record.decision.is_approved() => {
+                let origin = record
+                    .origin_record_id
+                    .as_deref()
+                    .and_then(|id| self.history.get(id));
+                trust.verify_with(&record, origin).err()
+            }
+            _ => None,
+        };
+        let ticket = ApprovalTicket::for_record(&record);
+        let (description, action_type) = (record.action_description.clone(), record.action_type);
+        self.record(record)?;
+
+        if let Some(e) = rejected {
+            warn!("Refusing approval with invalid signature: {}", description);
+            return self.deny_automatically(
+                description,
+                action_type,
+                format!("Approval signature rejected: {:#}", e),
+            );
+        }
+        Ok(ticket)
+    }
+
+    /// Snapshot the files an approved action is about to change
+    ///
+    /// Call right before the action runs, so the checkpoint holds the files
+    /// as the action finds them; [`record_outcome`](Self::record_outcome)
+    /// seals it. Returns None if checkpoints are disabled or there are no
+    /// paths.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a file cannot be snapshotted; the action should
+    /// not run then.
+    pub fn checkpoint(
+        &self,
+        ticket_id: &str,
+        paths: &[PathBuf],
+    ) -> anyhow::Result<Option<Checkpoint>> {
+        let Some(store) = &self.checkpoints else {
+            return Ok(None);
         };
+        if paths.is_empty() {
+            return Ok(None);
+        }
+
+        let checkpoint = store.capture(ticket_id, paths)?;
+        info!(
+            "Checkpointed {} file(s) for approval {}",
+            checkpoint.files.len(),
+            ticket_id
+        );
+        Ok(Some(checkpoint))
+    }
+
+    /// The checkpoint taken for an approval, if any
+    pub fn checkpoint_of(&self, ticket_id: &str) -> anyhow::Result<Option<Checkpoint>> {
+        match &self.checkpoints {
+            Some(store) => store.get(ticket_id),
+            None => Ok(None),
+        }
+    }
 
-        self.history.record_decision(record)?;
+    /// Attach the execution outcome of an approved action to its ticket
+    ///
+    /// Outcomes are append-only: each ticket takes at most one, and only
+    /// approved decisions can have one.
+    pub fn record_outcome(&mut self, outcome: ExecutionOutcome) -> anyhow::Result<()> {
+        info!("Ticket {} {}", outcome.ticket_id, outcome);
+        let ticket_id = outcome.ticket_id.clone();
+        self.history.record_outcome(outcome)?;
 
-        Ok(decision)
+        // The files' new state is what a rollback must find unchanged
+        if let Some(store) = &self.checkpoints {
+            if let Err(e) = store.seal(&ticket_id) {
+                warn!("Failed to seal checkpoint of {}: {:#}", ticket_id, e);
+            }
+        }
+        Ok(())
     }
 
     /// Disable approval cliff (for testing only)
@@ -143,6 +1394,11 @@ impl ApprovalManager {
         self.history.get_history(Some(limit))
     }
 
+    /// One-line audit summary of a ticket (decision, approver, outcome)
+    pub fn audit_summary(&self, ticket_id: &str) -> Option<String> {
+        self.history.audit_summary(ticket_id)
+    }
+
     /// Export audit log as JSON
     pub fn export_audit_log(&self) -> anyhow::Result<String> {
         self.history.export_audit_log()
@@ -159,28 +1415,29 @@ impl ApprovalManager {
     /// * `changes` - The changes that will be made
     ///
     /// # Returns
-    /// * `Ok(ApprovalDecision::Approved)` if action was approved
-    /// * `Ok(ApprovalDecision::Denied)` if action was rejected
+    /// * A ticket with `ApprovalDecision::Approved` if action was approved
+    /// * A ticket with `ApprovalDecision::Denied` if action was rejected
     /// * `Err` if TUI operations fail
     pub async fn check_and_approve_tui(
         &mut self,
         action_type: ActionType,
         description: String,
         changes: Vec<Change>,
-    ) -> anyhow::Result<ApprovalDecision> {
+    ) -> anyhow::Result<ApprovalTicket> {
         if !self.enable_approval_cliff {
             // Testing mode: auto-approve everything
-            return Ok(ApprovalDecision::Approved);
+            return Ok(ApprovalTicket::unrecorded(ApprovalDecision::Approved));
         }
 
         // Green actions skip approval
         if !action_type.requires_approval() {
             info!("Green action, auto-approving: {}", description);
-            return Ok(ApprovalDecision::Approved);
+            return Ok(ApprovalTicket::unrecorded(ApprovalDecision::Approved));
         }
 
         // Generate Diff Card for Red action
-        let diff_card = DiffCard::new(action_type, description.clone(), changes);
+        let diff_card = DiffCard::new(action_type, description.clone(), changes)
+            .with_risk_config(&self.risk_config);
 
         // Present TUI for approval
         let tui_result = present_tui_approval(&diff_card).await?;
@@ -188,23 +1445,108 @@ impl ApprovalManager {
             TuiResult::Approved => ApprovalDecision::Approved,
             TuiResult::Rejected => ApprovalDecision::Denied,
             TuiResult::Cancelled => ApprovalDecision::DeferredToLater,
+            TuiResult::TimedOut => ApprovalDecision::Denied,
         };
+        let timeout = (tui_result == TuiResult::TimedOut)
+            .then(|| TimeoutEvent::new(tui::approval_timeout(), TimeoutAction::Deny));
 
-        // Record decision in history
+        // Record decision in history, signed with the local user's key
+        let header = RecordHeader::for_card(&diff_card);
+        let approver = local_user();
+        let signature = match &self.keyring {
+            Some(keyring) => keyring.sign(&approver, &header.digest()?, &decision, &[], None)?,
+            None => None,
+        };
         let record = ApprovalRecord {
-            id: uuid::Uuid::new_v4().to_string(),
-            timestamp: chrono::Utc::now(),
-            action_description: description,
-            decision,
-            approved_by: std::env::var("USER").unwrap_or_else(|_| "unknown".to_string()),
-            justification: None,
-            execution_result: None,
+            justification: timeout.as_ref().map(|t| format!("Approval {}", t)),
+            requested_by: Some(self.session_owner.clone()),
+            timeout,
+            signatures: signature.into_iter().collect(),
+            ..header.into_record(decision, approver)
         };
 
-        self.history.record_decision(record)?;
+        self.commit(record)
+    }
+}
+
+/// Files an approved plan step will change (as amended, if it was)
+fn step_paths(step: &PlanStep, ticket: &ApprovalTicket) -> Vec<PathBuf> {
+    let amendment = ticket.decision.amendment();
+    let changes = amendment.map_or(&step.changes[..], |a| &a.changes[..]);
+    let arguments = amendment
+        .and_then(|a| a.arguments.as_ref())
+        .or(step.arguments.as_ref());
+
+    // Directories named in arguments have no content to snapshot
+    let named = arguments
+        .map(argument_paths)
+        .unwrap_or_default()
+        .into_iter()
+        .map(PathBuf::from)
+        .filter(|path| !path.is_dir());
+    changes
+        .iter()
+        .filter_map(|change| change.path().map(PathBuf::from))
+        .chain(named)
+        .collect()
+}
+
+/// Current OS user (`$USER`), who decides at this machine's terminal
+pub fn local_user() -> String {
+    std::env::var("USER").unwrap_or_else(|_| "unknown".to_string())
+}
+
+/// Directory for persisted approval state
+///
+/// `$LUMINAGUARD_DATA_DIR`, or `~/.luminaguard` if unset.
+pub fn data_dir() -> PathBuf {
+    if let Some(dir) = std::env::var_os("LUMINAGUARD_DATA_DIR") {
+        return PathBuf::from(dir);
+    }
+    std::env::var_os("HOME")
+        .map(PathBuf::from)
+        .unwrap_or_default()
+        .join(".luminaguard")
+}
+
+/// Write a value as pretty JSON, atomically (via a temporary file)
+pub(crate) fn write_json_atomic<T: serde::Serialize + ?Sized>(
+    path: &Path,
+    value: &T,
+) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, serde_json::to_string_pretty(value)?)
+        .with_context(|| format!("Failed to write {}", tmp.display()))?;
+    std::fs::rename(&tmp, path).with_context(|| format!("Failed to replace {}", path.display()))?;
+    Ok(())
+}
 
-        Ok(decision)
+/// Lock a JSON store for a read-modify-write cycle
+///
+/// Holds an exclusive advisory lock on `<path>.lock` until the returned file
+/// is dropped, so processes sharing the store do not overwrite each other's
+/// changes.
+pub(crate) fn lock_store(path: &Path) -> anyhow::Result<std::fs::File> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
     }
+
+    let lock_path = path.with_extension("lock");
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(false)
+        .open(&lock_path)
+        .with_context(|| format!("Failed to open {}", lock_path.display()))?;
+    file.lock()
+        .with_context(|| format!("Failed to lock {}", lock_path.display()))?;
+    Ok(file)
 }
 
 impl Default for ApprovalManager {
@@ -231,7 +1573,8 @@ mod tests {
         let decision = manager
             .check_and_approve(ActionType::ReadFile, "Read test.txt".to_string(), vec![])
             .await
-            .unwrap();
+            .unwrap()
+            .decision;
 
         assert_eq!(decision, ApprovalDecision::Approved);
     }
@@ -242,6 +1585,7 @@ mod tests {
             interactive: false,
             auto_approve_green: true,
             default_decision: ApprovalDecision::Approved,
+            ..ApprovalPromptConfig::default()
         };
 
         let mut manager = ApprovalManager::with_prompt_config(config);
@@ -256,7 +1600,8 @@ mod tests {
                 }],
             )
             .await
-            .unwrap();
+            .unwrap()
+            .decision;
 
         assert_eq!(decision, ApprovalDecision::Approved);
         assert_eq!(manager.get_history().len(), 1);
@@ -274,7 +1619,8 @@ mod tests {
                 vec![],
             )
             .await
-            .unwrap();
+            .unwrap()
+            .decision;
 
         // Even dangerous actions are auto-approved when disabled
         assert_eq!(decision, ApprovalDecision::Approved);
@@ -286,6 +1632,7 @@ mod tests {
             interactive: false,
             auto_approve_green: true,
             default_decision: ApprovalDecision::Denied,
+            ..ApprovalPromptConfig::default()
         };
 
         let mut manager = ApprovalManager::with_prompt_config(config);
@@ -306,6 +1653,1631 @@ mod tests {
         assert_eq!(history[0].decision, ApprovalDecision::Denied);
     }
 
+    /// Backend returning a fixed outcome
+    #[derive(Debug)]
+    struct FixedBackend(Option<BackendDecision>);
+
+    #[async_trait::async_trait]
+    impl ApprovalBackend for FixedBackend {
+        fn name(&self) -> &str {
+            "fixed"
+        }
+
+        async fn request_approval(&self, _: &ApprovalRequest) -> anyhow::Result<BackendDecision> {
+            match &self.0 {
+                Some(decision) => Ok(decision.clone()),
+                None => Err(ApprovalTimedOut { timeout_secs: 5 }.into()),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_backend_decision_recorded() {
+        let mut manager =
+            ApprovalManager::new().with_backend(Arc::new(FixedBackend(Some(BackendDecision {
+                decision: ApprovalDecision::Approved,
+                approved_by: "alice".to_string(),
+                justification: Some("expected cleanup".to_string()),
+                grant: None,
+                modifications: Vec::new(),
+                signature: None,
+            }))));
+
+        let decision = manager
+            .check_and_approve(
+                ActionType::DeleteFile,
+                "Delete test.txt".to_string(),
+                vec![],
+            )
+            .await
+            .unwrap()
+            .decision;
+
+        assert_eq!(decision, ApprovalDecision::Approved);
+        let history = manager.get_history();
+        assert_eq!(history[0].approved_by, "alice");
+        assert_eq!(
+            history[0].justification.as_deref(),
+            Some("expected cleanup")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ticket_links_outcome() {
+        let mut manager =
+            ApprovalManager::new().with_backend(Arc::new(FixedBackend(Some(BackendDecision {
+                justification: Some("expected cleanup".to_string()),
+                ..BackendDecision::local_user(ApprovalDecision::Approved)
+            }))));
+
+        let ticket = manager
+            .check_and_approve(
+                ActionType::DeleteFile,
+                "Delete test.txt".to_string(),
+                vec![],
+            )
+            .await
+            .unwrap();
+        let ticket_id = ticket.id.unwrap();
+        assert_eq!(manager.get_history()[0].id, ticket_id);
+
+        let outcome = ExecutionOutcome::new(&ticket_id, true, std::time::Duration::from_millis(5));
+        manager.record_outcome(outcome.clone()).unwrap();
+        assert!(manager.record_outcome(outcome).is_err());
+
+        let summary = manager.audit_summary(&ticket_id).unwrap();
+        assert!(summary.contains("because expected cleanup"));
+        assert!(summary.contains("in 5 ms: success"));
+
+        // Green actions are not recorded, so there is no ticket
+        let ticket = manager
+            .check_and_approve(ActionType::ReadFile, "Read a.txt".to_string(), vec![])
+            .await
+            .unwrap();
+        assert_eq!(ticket.id, None);
+    }
+
+    #[tokio::test]
+    async fn test_approved_file_changes_checkpointed() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("config.toml");
+        std::fs::write(&file, "approved").unwrap();
+        let store = CheckpointStore::open(dir.path().join("checkpoints"));
+        let mut manager = ApprovalManager::new()
+            .with_backend(Arc::new(FixedBackend(Some(BackendDecision::local_user(
+                ApprovalDecision::Approved,
+            )))))
+            .with_checkpoints(store.clone());
+
+        let ticket = manager
+            .check_and_approve(
+                ActionType::EditFile,
+                "Edit config".to_string(),
+                vec![Change::file_edit(file.to_string_lossy(), "old", "new")],
+            )
+            .await
+            .unwrap();
+        let ticket_id = ticket.id.unwrap();
+        assert!(store.get(&ticket_id).unwrap().is_none());
+
+        // Taken when the action runs, not when it was approved
+        std::fs::write(&file, "old").unwrap();
+        assert!(manager.checkpoint(&ticket_id, &[]).unwrap().is_none());
+        let checkpoint = manager.checkpoint(&ticket_id, &[file.clone()]).unwrap();
+        assert_eq!(checkpoint.unwrap().files.len(), 1);
+
+        std::fs::write(&file, "new").unwrap();
+        manager
+            .record_outcome(ExecutionOutcome::new(
+                &ticket_id,
+                true,
+                std::time::Duration::from_millis(1),
+            ))
+            .unwrap();
+
+        store.rollback(&ticket_id).unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "old");
+    }
+
+    /// Approver signing their approvals with their own keyring, optionally
+    /// granting similar actions (signed along if `sign_grant`)
+    #[derive(Debug)]
+    struct SigningBackend {
+        keyring: Keyring,
+        approver: &'static str,
+        digest: Option<&'static str>,
+        grant: Option<GrantScope>,
+        sign_grant: bool,
+    }
+
+    #[async_trait::async_trait]
+    impl ApprovalBackend for SigningBackend {
+        fn name(&self) -> &str {
+            "signing"
+        }
+
+        async fn request_approval(
+            &self,
+            request: &ApprovalRequest,
+        ) -> anyhow::Result<BackendDecision> {
+            let digest = match self.digest {
+                Some(digest) => digest.to_string(),
+                None => request.digest()?,
+            };
+            let decision = ApprovalDecision::Approved;
+            let signed_grant = self.grant.as_ref().filter(|_| self.sign_grant);
+            Ok(BackendDecision {
+                approved_by: self.approver.to_string(),
+                signature: self.keyring.sign(
+                    self.approver,
+                    &digest,
+                    &decision,
+                    &[],
+                    signed_grant,
+                )?,
+                grant: self.grant.clone(),
+                ..BackendDecision::local_user(decision)
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unsigned_approval_refused_by_trust_list() {
+        let dir = tempfile::tempdir().unwrap();
+        let keyring = Keyring::open(dir.path());
+        let mut trust = TrustList::in_memory();
+        trust
+            .add("alice", &keyring.generate("alice").unwrap())
+            .unwrap();
+        keyring.generate("mallory").unwrap();
+
+        let delete = || {
+            vec![Change::FileDelete {
+                path: "test.txt".to_string(),
+                size_bytes: 100,
+            }]
+        };
+        let decide = |approver, digest| {
+            let backend = Arc::new(SigningBackend {
+                keyring: keyring.clone(),
+                approver,
+                digest,
+                grant: None,
+                sign_grant: false,
+            });
+            let mut manager = ApprovalManager::new()
+                .with_backend(backend)
+                .with_trust_list(trust.clone());
+            async move {
+                let ticket = manager
+                    .check_and_approve(
+                        ActionType::DeleteFile,
+                        "Delete test.txt".to_string(),
+                        delete(),
+                    )
+                    .await
+                    .unwrap();
+                let history: Vec<ApprovalRecord> =
+                    manager.get_history().into_iter().cloned().collect();
+                (ticket, history)
+            }
+        };
+
+        // Signed by the approver with a trusted key
+        let (ticket, history) = decide("alice", None).await;
+        assert_eq!(ticket.decision, ApprovalDecision::Approved);
+        assert_eq!(history[0].signatures[0].signer, "alice");
+        assert_eq!(history[0].id, ticket.id.unwrap());
+        assert!(history[0].diff_card_digest.is_some());
+
+        // Signed, but for another record or not with a trusted key
+        for (approver, digest) in [("alice", Some("sha256:00")), ("mallory", None)] {
+            let (ticket, history) = decide(approver, digest).await;
+            assert_eq!(ticket.decision, ApprovalDecision::Denied);
+            assert_eq!(history.len(), 2);
+            let denial = history
+                .iter()
+                .find(|r| Some(&r.id) == ticket.id.as_ref())
+                .unwrap();
+            assert_eq!(denial.approved_by, "system");
+            assert!(denial
+                .justification
+                .as_ref()
+                .unwrap()
+                .starts_with("Approval signature rejected"));
+        }
+    }
+
+    fn trust_alice(keyring: &Keyring) -> TrustList {
+        let mut trust = TrustList::in_memory();
+        trust
+            .add("alice", &keyring.generate("alice").unwrap())
+            .unwrap();
+        trust
+    }
+
+    fn delete_in(dir: &str) -> Vec<Change> {
+        vec![Change::FileDelete {
+            path: format!("{}/test.txt", dir),
+            size_bytes: 100,
+        }]
+    }
+
+    #[tokio::test]
+    async fn test_standing_grant_verified_by_trust_list() {
+        let dir = tempfile::tempdir().unwrap();
+        let keyring = Keyring::open(dir.path());
+        let trust = trust_alice(&keyring);
+
+        // Grants are only created if the approver signed them
+        for sign_grant in [true, false] {
+            let backend = Arc::new(SigningBackend {
+                keyring: keyring.clone(),
+                approver: "alice",
+                digest: None,
+                grant: Some(GrantScope::Session),
+                sign_grant,
+            });
+            let mut manager = ApprovalManager::new()
+                .with_backend(backend)
+                .with_trust_list(trust.clone());
+            for _ in 0..2 {
+                let ticket = manager
+                    .check_and_approve(
+                        ActionType::DeleteFile,
+                        "Delete test.txt".to_string(),
+                        delete_in("/tmp"),
+                    )
+                    .await
+                    .unwrap();
+                assert_eq!(ticket.decision, ApprovalDecision::Approved);
+            }
+            let granted: Vec<_> = manager
+                .get_history()
+                .into_iter()
+                .filter(|r| r.origin_record_id.is_some())
+                .cloned()
+                .collect();
+            assert_eq!(granted.len(), usize::from(sign_grant));
+            if sign_grant {
+                assert_eq!(granted[0].approved_by, "alice");
+                assert!(granted[0].signatures.is_empty());
+            }
+        }
+
+        // A grant without a signed origin approval is refused
+        let mut manager = ApprovalManager::new().with_trust_list(trust);
+        let session = manager.session_id().to_string();
+        manager
+            .grants
+            .add(Grant::from_scope(
+                &GrantScope::Session,
+                ActionType::DeleteFile,
+                &delete_in("/tmp"),
+                &session,
+                "alice",
+                "forged",
+            ))
+            .unwrap();
+        let ticket = manager
+            .check_and_approve(
+                ActionType::DeleteFile,
+                "Delete test.txt".to_string(),
+                delete_in("/tmp"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(ticket.decision, ApprovalDecision::Denied);
+    }
+
+    #[tokio::test]
+    async fn test_policy_rule_signed_for_trust_list() {
+        let dir = tempfile::tempdir().unwrap();
+        let keyring = Keyring::open(dir.path());
+        let trust = trust_alice(&keyring);
+        let rule = PolicyRule::new(
+            PolicyEffect::Allow,
+            ActionType::DeleteFile,
+            ChangeMatcher::PathPrefix {
+                prefix: "/tmp/out".to_string(),
+            },
+        );
+        let signed = PolicyRule {
+            signature: keyring.sign_rule("alice", &rule).unwrap(),
+            ..rule.clone()
+        };
+        // Widened after it was signed
+        let widened = PolicyRule {
+            changes: ChangeMatcher::Any,
+            ..signed.clone()
+        };
+
+        for (rule, expected) in [
+            (signed, ApprovalDecision::Approved),
+            (rule, ApprovalDecision::Denied),
+            (widened, ApprovalDecision::Denied),
+        ] {
+            let mut manager = ApprovalManager::new()
+                .with_backend(Arc::new(FixedBackend(None)))
+                .with_policy(ApprovalPolicy::new().with_rule(rule))
+                .with_trust_list(trust.clone());
+            let ticket = manager
+                .check_and_approve(
+                    ActionType::DeleteFile,
+                    "Delete test.txt".to_string(),
+                    delete_in("/tmp/out"),
+                )
+                .await
+                .unwrap();
+            assert_eq!(ticket.decision, expected);
+            if expected.is_approved() {
+                assert_eq!(manager.get_history()[0].approved_by, "alice");
+            }
+        }
+        assert!(keyring
+            .sign_rule(
+                "alice",
+                &PolicyRule::new(
+                    PolicyEffect::Deny,
+                    ActionType::DeleteFile,
+                    ChangeMatcher::Any
+                )
+            )
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_deferred_decision_signed_for_trust_list() {
+        let dir = tempfile::tempdir().unwrap();
+        let keyring = Keyring::open(dir.path());
+        let trust = trust_alice(&keyring);
+
+        for sign in [true, false] {
+            let backend = ScriptedBackend::new(&[("bob", ApprovalDecision::DeferredToLater)]);
+            let mut manager = ApprovalManager::new()
+                .with_backend(backend)
+                .with_trust_list(trust.clone());
+            async fn request(manager: &mut ApprovalManager) -> ApprovalTicket {
+                manager
+                    .check_and_approve(
+                        ActionType::ExecuteCommand,
+                        "cargo build".to_string(),
+                        cargo_build(),
+                    )
+                    .await
+                    .unwrap()
+            }
+            let ticket = request(&mut manager).await;
+            let id = ticket.deferred_id.unwrap();
+
+            // As `luminaguard approvals approve` signs it
+            let item = manager.deferred_action(&id).unwrap().unwrap();
+            let signature = keyring
+                .sign(
+                    "alice",
+                    &item.record_header().digest().unwrap(),
+                    &ApprovalDecision::Approved,
+                    &[],
+                    None,
+                )
+                .unwrap()
+                .filter(|_| sign);
+            manager
+                .deferred
+                .decide(&id, ApprovalDecision::Approved, "alice", None, signature)
+                .unwrap();
+
+            let ticket = request(&mut manager).await;
+            let expected = if sign {
+                ApprovalDecision::Approved
+            } else {
+                ApprovalDecision::Denied
+            };
+            assert_eq!(ticket.decision, expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_prompt_signs_with_local_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let keyring = Keyring::open(dir.path());
+        let mut trust = TrustList::in_memory();
+        trust
+            .add(&local_user(), &keyring.generate(&local_user()).unwrap())
+            .unwrap();
+        let config = ApprovalPromptConfig {
+            interactive: false,
+            default_decision: ApprovalDecision::Approved,
+            ..ApprovalPromptConfig::default()
+        };
+
+        for (keyring, expected) in [
+            (Some(keyring), ApprovalDecision::Approved),
+            (None, ApprovalDecision::Denied),
+        ] {
+            let mut manager =
+                ApprovalManager::with_prompt_config(config.clone()).with_trust_list(trust.clone());
+            if let Some(keyring) = keyring {
+                manager = manager.with_keyring(keyring);
+            }
+            let ticket = manager
+                .check_and_approve(
+                    ActionType::DeleteFile,
+                    "Delete test.txt".to_string(),
+                    delete_in("/tmp"),
+                )
+                .await
+                .unwrap();
+            assert_eq!(ticket.decision, expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_backend_timeout_denies() {
+        let mut manager = ApprovalManager::new().with_backend(Arc::new(FixedBackend(None)));
+
+        let decision = manager
+            .check_and_approve(
+                ActionType::DeleteFile,
+                "Delete test.txt".to_string(),
+                vec![],
+            )
+            .await
+            .unwrap()
+            .decision;
+
+        assert_eq!(decision, ApprovalDecision::Denied);
+        let history = manager.get_history();
+        assert_eq!(history[0].approved_by, "system");
+        assert!(history[0]
+            .justification
+            .as_deref()
+            .unwrap()
+            .contains("timeout"));
+        let timeout = history[0].timeout.as_ref().unwrap();
+        assert_eq!(timeout.action, TimeoutAction::Deny);
+        assert_eq!(timeout.to_string(), "timed out after 5s, denied");
+    }
+
+    #[tokio::test]
+    async fn test_timeout_defers_or_escalates() {
+        fn on_timeout(action: TimeoutAction) -> ApprovalManager {
+            ApprovalManager::with_prompt_config(ApprovalPromptConfig {
+                on_timeout: action,
+                ..ApprovalPromptConfig::default()
+            })
+            .with_backend(Arc::new(FixedBackend(None)))
+        }
+        async fn request(manager: &mut ApprovalManager) -> ApprovalDecision {
+            manager
+                .check_and_approve(
+                    ActionType::DeleteFile,
+                    "Delete test.txt".to_string(),
+                    vec![],
+                )
+                .await
+                .unwrap()
+                .decision
+        }
+
+        // Deferred: parked in the queue for a later decision
+        let mut manager = on_timeout(TimeoutAction::Defer);
+        assert_eq!(
+            request(&mut manager).await,
+            ApprovalDecision::DeferredToLater
+        );
+        let record = &manager.get_history()[0];
+        assert!(record.deferred_id.is_some());
+        assert_eq!(
+            record.timeout.as_ref().unwrap().action,
+            TimeoutAction::Defer
+        );
+
+        // Escalated: decided by the escalation backend
+        let mut manager = on_timeout(TimeoutAction::Escalate).with_escalation(
+            ScriptedBackend::new(&[("oncall", ApprovalDecision::Approved)]),
+        );
+        assert_eq!(request(&mut manager).await, ApprovalDecision::Approved);
+        let record = &manager.get_history()[0];
+        assert_eq!(record.approved_by, "oncall");
+        assert_eq!(
+            record.timeout.as_ref().unwrap().to_string(),
+            "timed out after 5s, escalated to scripted"
+        );
+
+        // Escalation that times out too (or is missing) denies
+        let mut manager =
+            on_timeout(TimeoutAction::Escalate).with_escalation(Arc::new(FixedBackend(None)));
+        assert_eq!(request(&mut manager).await, ApprovalDecision::Denied);
+        let mut manager = on_timeout(TimeoutAction::Escalate);
+        assert_eq!(request(&mut manager).await, ApprovalDecision::Denied);
+        assert!(manager.get_history()[0]
+            .justification
+            .as_deref()
+            .unwrap()
+            .contains("no escalation backend"));
+    }
+
+    /// Backend replaying scripted decisions (the last one repeats)
+    #[derive(Debug)]
+    struct ScriptedBackend(std::sync::Mutex<Vec<BackendDecision>>);
+
+    impl ScriptedBackend {
+        fn new(votes: &[(&str, ApprovalDecision)]) -> Arc<Self> {
+            Self::from_decisions(
+                votes
+                    .iter()
+                    .map(|(who, decision)| BackendDecision {
+                        decision: decision.clone(),
+                        approved_by: who.to_string(),
+                        justification: None,
+                        grant: None,
+                        modifications: Vec::new(),
+                        signature: None,
+                    })
+                    .collect(),
+            )
+        }
+
+        fn from_decisions(mut script: Vec<BackendDecision>) -> Arc<Self> {
+            script.reverse();
+            Arc::new(Self(std::sync::Mutex::new(script)))
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl ApprovalBackend for ScriptedBackend {
+        fn name(&self) -> &str {
+            "scripted"
+        }
+
+        async fn request_approval(&self, _: &ApprovalRequest) -> anyhow::Result<BackendDecision> {
+            let mut script = self.0.lock().unwrap();
+            if script.len() > 1 {
+                Ok(script.pop().unwrap())
+            } else {
+                Ok(script[0].clone())
+            }
+        }
+    }
+
+    fn finance_quorum() -> QuorumConfig {
+        QuorumConfig::new()
+            .with_group(ApproverGroup::new("finance", ["alice", "bob", "carol"]))
+            .with_rule(QuorumRule::new(RiskLevel::Critical, "finance", 2))
+    }
+
+    #[tokio::test]
+    async fn test_quorum_records_every_vote() {
+        let dir = tempfile::tempdir().unwrap();
+        let (keyring, trust) = trusted_keys(dir.path(), &["alice", "bob"]);
+        // alice's second vote is a duplicate, ignored
+        let backend = SignedVotes::new(&keyring, &["alice", "alice", "bob"]);
+        let mut manager = ApprovalManager::new()
+            .with_backend(backend)
+            .with_session_owner("alice")
+            .with_trust_list(trust)
+            .with_quorum(finance_quorum(), QuorumStore::in_memory())
+            .unwrap();
+
+        let decision = manager
+            .check_and_approve(ActionType::TransferAsset, "Send 1 BTC".to_string(), vec![])
+            .await
+            .unwrap()
+            .decision;
+
+        assert_eq!(decision, ApprovalDecision::Approved);
+        let history = manager.get_history();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].approved_by, "alice, bob");
+        assert_eq!(history[0].votes.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_quorum_does_not_apply_below_risk() {
+        let dir = tempfile::tempdir().unwrap();
+        let (keyring, trust) = trusted_keys(dir.path(), &["alice"]);
+        let mut manager = ApprovalManager::new()
+            .with_backend(SignedVotes::new(&keyring, &["alice"]))
+            .with_trust_list(trust)
+            .with_quorum(finance_quorum(), QuorumStore::in_memory())
+            .unwrap();
+
+        let decision = manager
+            .check_and_approve(ActionType::EditFile, "Edit a.txt".to_string(), vec![])
+            .await
+            .unwrap()
+            .decision;
+
+        assert_eq!(decision, ApprovalDecision::Approved);
+        assert!(manager.get_history()[0].votes.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_quorum_follows_card_risk() {
+        let dir = tempfile::tempdir().unwrap();
+        let (keyring, trust) = trusted_keys(dir.path(), &["alice"]);
+        let mut manager = ApprovalManager::new()
+            .with_backend(SignedVotes::new(&keyring, &["alice"]))
+            .with_session_owner("dave")
+            .with_trust_list(trust)
+            .with_quorum(finance_quorum(), QuorumStore::in_memory())
+            .unwrap();
+        let changes = vec![Change::FileEdit {
+            path: "/etc/sudoers".to_string(),
+            before: String::new(),
+            after: "ALL ALL=(ALL) NOPASSWD: ALL".to_string(),
+            diff: None,
+        }];
+        let card = DiffCard::new(
+            ActionType::EditFile,
+            "Edit sudoers".to_string(),
+            changes.clone(),
+        )
+        .with_risk_config(&RiskConfig::default());
+        assert_eq!(card.risk_level, RiskLevel::Critical);
+
+        // A High-risk action type, assessed Critical: one approval is not enough
+        let ticket = manager
+            .check_and_approve(
+                ActionType::EditFile,
+                "Edit sudoers".to_string(),
+                changes.clone(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(ticket.decision, ApprovalDecision::DeferredToLater);
+        let id = ticket.deferred_id.unwrap();
+        let item = manager.deferred_action(&id).unwrap().unwrap();
+        assert_eq!(item.status, DeferredStatus::Pending);
+        assert!(item.quorum_id.is_some());
+
+        // Still pending: the same queue item
+        let ticket = manager
+            .check_and_approve(ActionType::EditFile, "Edit sudoers".to_string(), changes)
+            .await
+            .unwrap();
+        assert_eq!(ticket.deferred_id, Some(id));
+        assert_eq!(manager.deferred.list().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_quorum_needs_trust_list() {
+        assert!(ApprovalManager::new()
+            .with_quorum(finance_quorum(), QuorumStore::in_memory())
+            .is_err());
+        assert!(ApprovalManager::new()
+            .with_quorum(QuorumConfig::new(), QuorumStore::in_memory())
+            .is_ok());
+    }
+
+    /// Group members voting in turn (the last one repeats), each signing
+    /// with their key if the keyring holds one
+    #[derive(Debug)]
+    struct SignedVotes {
+        keyring: Keyring,
+        voters: std::sync::Mutex<Vec<&'static str>>,
+    }
+
+    impl SignedVotes {
+        fn new(keyring: &Keyring, voters: &[&'static str]) -> Arc<Self> {
+            Arc::new(Self {
+                keyring: keyring.clone(),
+                voters: std::sync::Mutex::new(voters.to_vec()),
+            })
+        }
+    }
+
+    /// Keyring with a key for each approver, all trusted
+    fn trusted_keys(dir: &Path, approvers: &[&str]) -> (Keyring, TrustList) {
+        let keyring = Keyring::open(dir);
+        let mut trust = TrustList::in_memory();
+        for &approver in approvers {
+            trust
+                .add(approver, &keyring.generate(approver).unwrap())
+                .unwrap();
+        }
+        (keyring, trust)
+    }
+
+    #[async_trait::async_trait]
+    impl ApprovalBackend for SignedVotes {
+        fn name(&self) -> &str {
+            "signed-votes"
+        }
+
+        async fn request_approval(
+            &self,
+            request: &ApprovalRequest,
+        ) -> anyhow::Result<BackendDecision> {
+            let approver = {
+                let mut voters = self.voters.lock().unwrap();
+                if voters.len() > 1 {
+                    voters.remove(0)
+                } else {
+                    voters[0]
+                }
+            };
+            let decision = ApprovalDecision::Approved;
+            let digest = request.digest()?;
+            Ok(BackendDecision {
+                approved_by: approver.to_string(),
+                signature: self.keyring.sign(approver, &digest, &decision, &[], None)?,
+                ..BackendDecision::local_user(decision)
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_quorum_counts_authenticated_voters() {
+        let dir = tempfile::tempdir().unwrap();
+        let keyring = Keyring::open(dir.path());
+        let mut trust = TrustList::in_memory();
+        let alice = keyring.generate("alice").unwrap();
+        trust.add("alice", &alice).unwrap();
+
+        // bob holds alice's key (trusted for both); carol has no key
+        std::fs::copy(dir.path().join("alice.key"), dir.path().join("bob.key")).unwrap();
+        trust.add("bob", &alice).unwrap();
+
+        let backend = SignedVotes::new(&keyring, &["alice", "bob", "carol"]);
+        let store = QuorumStore::in_memory();
+        let mut manager = ApprovalManager::new()
+            .with_backend(backend)
+            .with_session_owner("dave")
+            .with_trust_list(trust.clone())
+            .with_quorum(finance_quorum(), store)
+            .unwrap();
+
+        let ticket = manager
+            .check_and_approve(ActionType::TransferAsset, "Send 1 BTC".to_string(), vec![])
+            .await
+            .unwrap();
+
+        // Only alice's vote counts
+        assert_eq!(ticket.decision, ApprovalDecision::DeferredToLater);
+        let deferred_id = ticket.deferred_id.unwrap();
+        let pending = manager.quorum_store.pending();
+        assert_eq!(pending[0].votes.len(), 1);
+        assert_eq!(pending[0].votes[0].approver, "alice");
+
+        // carol signs with her own trusted key
+        trust
+            .add("carol", &keyring.generate("carol").unwrap())
+            .unwrap();
+        manager.trust = Some(trust);
+        let decision = manager
+            .check_and_approve(ActionType::TransferAsset, "Send 1 BTC".to_string(), vec![])
+            .await
+            .unwrap()
+            .decision;
+
+        assert_eq!(decision, ApprovalDecision::Approved);
+        let history = manager.get_history();
+        assert_eq!(history[0].approved_by, "alice, carol");
+        assert_eq!(history[0].signatures.len(), 2);
+
+        // Decided by the votes: the queue item is withdrawn
+        assert_eq!(history[0].deferred_id, Some(deferred_id));
+        assert!(manager.deferred.list().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_quorum_pending_survives_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pending.json");
+        let (keyring, trust) = trusted_keys(&dir.path().join("keys"), &["alice", "carol"]);
+
+        // Only alice is around; mallory's votes are ignored
+        let mut manager = ApprovalManager::new()
+            .with_backend(SignedVotes::new(&keyring, &["alice", "mallory"]))
+            .with_session_owner("dave")
+            .with_trust_list(trust.clone())
+            .with_quorum(finance_quorum(), QuorumStore::open(&path).unwrap())
+            .unwrap();
+        let decision = manager
+            .check_and_approve(ActionType::DeleteFile, "Delete db".to_string(), vec![])
+            .await
+            .unwrap()
+            .decision;
+        assert_eq!(decision, ApprovalDecision::DeferredToLater);
+        drop(manager);
+
+        // After a restart, carol's vote completes the quorum
+        let mut manager = ApprovalManager::new()
+            .with_backend(SignedVotes::new(&keyring, &["carol"]))
+            .with_session_owner("dave")
+            .with_trust_list(trust)
+            .with_quorum(finance_quorum(), QuorumStore::open(&path).unwrap())
+            .unwrap();
+        let decision = manager
+            .check_and_approve(ActionType::DeleteFile, "Delete db".to_string(), vec![])
+            .await
+            .unwrap()
+            .decision;
+
+        assert_eq!(decision, ApprovalDecision::Approved);
+        assert_eq!(manager.get_history()[0].approved_by, "alice, carol");
+        assert!(QuorumStore::open(&path).unwrap().pending().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_quorum_votes_not_shared_across_arguments() {
+        let dir = tempfile::tempdir().unwrap();
+        let (keyring, trust) = trusted_keys(dir.path(), &["alice", "carol"]);
+        let quorum = QuorumConfig::new()
+            .with_group(ApproverGroup::new("ops", ["alice", "bob", "carol"]))
+            .with_rule(QuorumRule::new(RiskLevel::Low, "ops", 2));
+        let mut manager = ApprovalManager::new()
+            .with_backend(SignedVotes::new(&keyring, &["alice"]))
+            .with_session_owner("dave")
+            .with_trust_list(trust.clone())
+            .with_quorum(quorum, QuorumStore::in_memory())
+            .unwrap();
+
+        let harmless = serde_json::json!({"path": "/tmp/a"});
+        let ticket = manager
+            .check_and_approve_tool_call("fs", "delete_file", &harmless)
+            .await
+            .unwrap();
+        assert_eq!(ticket.decision, ApprovalDecision::DeferredToLater);
+
+        // The same tool with other arguments opens a vote of its own
+        manager.backend = Some(SignedVotes::new(&keyring, &["carol"]));
+        let important = serde_json::json!({"path": "/home/x/important"});
+        let ticket = manager
+            .check_and_approve_tool_call("fs", "delete_file", &important)
+            .await
+            .unwrap();
+        assert_eq!(ticket.decision, ApprovalDecision::DeferredToLater);
+        let pending = manager.quorum_store.pending();
+        assert_eq!(pending.len(), 2);
+        assert!(pending.iter().all(|p| p.votes.len() == 1));
+        assert_eq!(pending[1].arguments.as_ref(), Some(&important));
+        assert!(manager.get_history().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_quorum_timeout_denies() {
+        let mut manager = ApprovalManager::new()
+            .with_backend(Arc::new(FixedBackend(None)))
+            .with_trust_list(TrustList::in_memory())
+            .with_quorum(finance_quorum(), QuorumStore::in_memory())
+            .unwrap();
+
+        let decision = manager
+            .check_and_approve(ActionType::DeleteFile, "Delete db".to_string(), vec![])
+            .await
+            .unwrap()
+            .decision;
+
+        assert_eq!(decision, ApprovalDecision::Denied);
+        assert_eq!(manager.get_history()[0].approved_by, "system");
+    }
+
+    fn cargo_build() -> Vec<Change> {
+        vec![Change::CommandExec {
+            command: "cargo".to_string(),
+            args: vec!["build".to_string()],
+            env_vars: None,
+        }]
+    }
+
+    #[tokio::test]
+    async fn test_standing_grant_skips_prompt() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("grants.json");
+        let backend = ScriptedBackend::from_decisions(vec![
+            BackendDecision {
+                grant: Some(GrantScope::Session),
+                ..BackendDecision::local_user(ApprovalDecision::Approved)
+            },
+            BackendDecision::local_user(ApprovalDecision::Denied),
+        ]);
+        let mut manager = ApprovalManager::new()
+            .with_backend(backend)
+            .with_grants(GrantStore::open(&path).unwrap());
+
+        // First run prompts and creates the grant
+        let decision = manager
+            .check_and_approve(
+                ActionType::ExecuteCommand,
+                "cargo build".to_string(),
+                cargo_build(),
+            )
+            .await
+            .unwrap()
+            .decision;
+        assert_eq!(decision, ApprovalDecision::Approved);
+        let origin = manager.get_history()[0].clone();
+        let grant_id = origin.grant_id.clone().unwrap();
+
+        // Second run is approved by the grant, linked to the first decision
+        let decision = manager
+            .check_and_approve(
+                ActionType::ExecuteCommand,
+                "cargo build".to_string(),
+                cargo_build(),
+            )
+            .await
+            .unwrap()
+            .decision;
+        assert_eq!(decision, ApprovalDecision::Approved);
+        let history = manager.get_history();
+        let by_grant = history.iter().find(|r| r.id != origin.id).unwrap();
+        assert_eq!(by_grant.grant_id.as_deref(), Some(grant_id.as_str()));
+        assert_eq!(
+            by_grant.origin_record_id.as_deref(),
+            Some(origin.id.as_str())
+        );
+
+        // Revoked via another store handle (the CLI); the prompt is back
+        GrantStore::open(&path).unwrap().revoke(&grant_id).unwrap();
+        let decision = manager
+            .check_and_approve(
+                ActionType::ExecuteCommand,
+                "cargo build".to_string(),
+                cargo_build(),
+            )
+            .await
+            .unwrap()
+            .decision;
+        assert_eq!(decision, ApprovalDecision::Denied);
+    }
+
+    #[tokio::test]
+    async fn test_standing_grant_does_not_cover_other_actions() {
+        let backend = ScriptedBackend::from_decisions(vec![
+            BackendDecision {
+                grant: Some(GrantScope::Minutes(15)),
+                ..BackendDecision::local_user(ApprovalDecision::Approved)
+            },
+            BackendDecision::local_user(ApprovalDecision::Denied),
+        ]);
+        let mut manager = ApprovalManager::new().with_backend(backend);
+
+        manager
+            .check_and_approve(
+                ActionType::ExecuteCommand,
+                "cargo build".to_string(),
+                cargo_build(),
+            )
+            .await
+            .unwrap();
+        let decision = manager
+            .check_and_approve(
+                ActionType::ExecuteCommand,
+                "rm -rf target".to_string(),
+                vec![Change::CommandExec {
+                    command: "rm".to_string(),
+                    args: vec!["-rf".to_string(), "target".to_string()],
+                    env_vars: None,
+                }],
+            )
+            .await
+            .unwrap()
+            .decision;
+
+        assert_eq!(decision, ApprovalDecision::Denied);
+    }
+
+    #[tokio::test]
+    async fn test_approved_with_modifications() {
+        let modifications = vec![Modification::NarrowArgs {
+            index: 0,
+            args: vec!["-r".to_string(), "target".to_string()],
+        }];
+        let backend = ScriptedBackend::from_decisions(vec![BackendDecision {
+            modifications: modifications.clone(),
+            ..BackendDecision::local_user(ApprovalDecision::Approved)
+        }]);
+        let mut manager = ApprovalManager::new().with_backend(backend);
+        let original = vec![Change::CommandExec {
+            command: "rm".to_string(),
+            args: vec!["-rf".to_string(), "-r".to_string(), "target".to_string()],
+            env_vars: None,
+        }];
+
+        let decision = manager
+            .check_and_approve(
+                ActionType::ExecuteCommand,
+                "rm -rf target".to_string(),
+                original.clone(),
+            )
+            .await
+            .unwrap()
+            .decision;
+
+        assert!(decision.is_approved());
+        let amendment = decision.amendment().unwrap();
+        assert_eq!(amendment.modifications, modifications);
+        assert_eq!(amendment.original_changes, original);
+        assert!(matches!(
+            &amendment.changes[0],
+            Change::CommandExec { args, .. } if args == &["-r", "target"]
+        ));
+
+        // The audit record keeps both versions
+        let history = manager.get_history();
+        assert_eq!(history[0].decision, decision);
+        let json = manager.export_audit_log().unwrap();
+        assert!(json.contains("ApprovedWithModifications"));
+        assert!(json.contains("original_changes"));
+    }
+
+    #[tokio::test]
+    async fn test_previewed_tool_call_changes_not_amendable() {
+        // Dropping an observed change would not stop the call from running
+        let backend = ScriptedBackend::from_decisions(vec![BackendDecision {
+            modifications: vec![Modification::DropChange { index: 1 }],
+            ..BackendDecision::local_user(ApprovalDecision::Approved)
+        }]);
+        let mut manager = ApprovalManager::new().with_backend(backend);
+        let arguments = serde_json::json!({"path": "/tmp/a.txt"});
+
+        let decision = manager
+            .check_and_approve_previewed_tool_call(
+                "fs",
+                "delete_file",
+                &arguments,
+                delete_in("/tmp"),
+            )
+            .await
+            .unwrap()
+            .decision;
+
+        assert_eq!(decision, ApprovalDecision::Denied);
+        assert!(manager.get_history()[0]
+            .justification
+            .as_deref()
+            .unwrap()
+            .contains("only be amended through their arguments"));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_modifications_deny() {
+        let backend = ScriptedBackend::from_decisions(vec![BackendDecision {
+            modifications: vec![Modification::ReduceAmount {
+                index: 0,
+                amount: "5".to_string(),
+            }],
+            ..BackendDecision::local_user(ApprovalDecision::Approved)
+        }]);
+        let mut manager = ApprovalManager::new().with_backend(backend);
+
+        let decision = manager
+            .check_and_approve(
+                ActionType::ExecuteCommand,
+                "cargo build".to_string(),
+                cargo_build(),
+            )
+            .await
+            .unwrap()
+            .decision;
+
+        assert_eq!(decision, ApprovalDecision::Denied);
+        assert!(manager.get_history()[0]
+            .justification
+            .as_deref()
+            .unwrap()
+            .starts_with("Invalid modifications"));
+    }
+
+    #[tokio::test]
+    async fn test_tool_call_arguments_amended() {
+        let backend = ScriptedBackend::from_decisions(vec![BackendDecision {
+            modifications: vec![Modification::SetArgument {
+                pointer: "/to".to_string(),
+                value: serde_json::json!("team@example.com"),
+            }],
+            ..BackendDecision::local_user(ApprovalDecision::Approved)
+        }]);
+        let mut manager = ApprovalManager::new().with_backend(backend);
+        let arguments = serde_json::json!({"to": "all@example.com", "body": "hi"});
+
+        let decision = manager
+            .check_and_approve_tool_call("mail", "send_email", &arguments)
+            .await
+            .unwrap()
+            .decision;
+
+        let amendment = decision.amendment().unwrap();
+        assert_eq!(amendment.original_arguments.as_ref(), Some(&arguments));
+        assert_eq!(
+            amendment.arguments,
+            Some(serde_json::json!({"to": "team@example.com", "body": "hi"}))
+        );
+        assert_eq!(
+            manager.get_history()[0].action_description,
+            "Call tool send_email on mail"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rubber_stamp_approval_flagged() {
+        let backend = ScriptedBackend::new(&[("alice", ApprovalDecision::Approved)]);
+        let mut manager = ApprovalManager::new()
+            .with_backend(backend)
+            .with_session_owner("agent");
+
+        let decision = manager
+            .check_and_approve(
+                ActionType::DeleteFile,
+                "Delete a.txt".to_string(),
+                vec![Change::FileDelete {
+                    path: "/srv/app/a.txt".to_string(),
+                    size_bytes: 1,
+                }],
+            )
+            .await
+            .unwrap()
+            .decision;
+
+        assert_eq!(decision, ApprovalDecision::Approved);
+        let record = manager.get_history()[0];
+        assert_eq!(record.requested_by.as_deref(), Some("agent"));
+        assert_eq!(record.paths, vec!["/srv/app/a.txt".to_string()]);
+        assert!(record.latency_ms.is_some());
+        assert!(record.anomalies[0].starts_with("Approved by alice in 0.0s"));
+    }
+
+    #[tokio::test]
+    async fn test_flagged_approval_needs_second_approver() {
+        let config = AnomalyConfig {
+            second_approver: true,
+            ..AnomalyConfig::default()
+        };
+        async fn request(manager: &mut ApprovalManager) -> ApprovalDecision {
+            manager
+                .check_and_approve(
+                    ActionType::ExecuteCommand,
+                    "cargo build".to_string(),
+                    cargo_build(),
+                )
+                .await
+                .unwrap()
+                .decision
+        }
+
+        // The same approver cannot confirm their own flagged approval
+        let backend = ScriptedBackend::new(&[("alice", ApprovalDecision::Approved)]);
+        let mut manager = ApprovalManager::new()
+            .with_backend(backend)
+            .with_anomaly_config(config.clone());
+        assert_eq!(request(&mut manager).await, ApprovalDecision::Denied);
+        assert!(manager.get_history()[0]
+            .justification
+            .as_deref()
+            .unwrap()
+            .contains("alice cannot approve twice"));
+
+        let backend = ScriptedBackend::new(&[
+            ("alice", ApprovalDecision::Approved),
+            ("bob", ApprovalDecision::Approved),
+        ]);
+        let mut manager = ApprovalManager::new()
+            .with_backend(backend)
+            .with_anomaly_config(config);
+        assert_eq!(request(&mut manager).await, ApprovalDecision::Approved);
+        let record = manager.get_history()[0];
+        assert_eq!(record.approved_by, "alice, bob");
+        assert_eq!(record.votes.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_policy_rules_decide_without_prompting() {
+        let policy = ApprovalPolicy::new()
+            .with_rule(PolicyRule::new(
+                PolicyEffect::Allow,
+                ActionType::ExecuteCommand,
+                ChangeMatcher::Command {
+                    command: "cargo".to_string(),
+                },
+            ))
+            .with_rule(PolicyRule::new(
+                PolicyEffect::Deny,
+                ActionType::TransferAsset,
+                ChangeMatcher::Any,
+            ));
+        let backend = ScriptedBackend::new(&[("alice", ApprovalDecision::Denied)]);
+        let mut manager = ApprovalManager::new()
+            .with_backend(backend)
+            .with_policy(policy);
+
+        let allowed = manager
+            .check_and_approve(
+                ActionType::ExecuteCommand,
+                "cargo build".to_string(),
+                cargo_build(),
+            )
+            .await
+            .unwrap();
+        let denied = manager
+            .check_and_approve(ActionType::TransferAsset, "Send 1 BTC".to_string(), vec![])
+            .await
+            .unwrap();
+
+        assert_eq!(allowed.decision, ApprovalDecision::Approved);
+        assert_eq!(denied.decision, ApprovalDecision::Denied);
+        let history = manager.get_history(); // newest first
+        assert_eq!(history[1].approved_by, "system");
+        assert_eq!(
+            history[1].justification.as_deref(),
+            Some("Allowed by policy rule (allow ExecuteCommand, command cargo)")
+        );
+        assert_eq!(
+            history[0].justification.as_deref(),
+            Some("Denied by policy rule (deny TransferAsset)")
+        );
+    }
+
+    /// Backend approving plans for a selection of steps, signing the
+    /// selection given with its keyring
+    #[derive(Debug)]
+    struct PlanBackend(StepSelection, Option<(Keyring, StepSelection)>);
+
+    #[async_trait::async_trait]
+    impl ApprovalBackend for PlanBackend {
+        fn name(&self) -> &str {
+            "plan"
+        }
+
+        async fn request_approval(&self, _: &ApprovalRequest) -> anyhow::Result<BackendDecision> {
+            anyhow::bail!("Plans only")
+        }
+
+        async fn request_plan_approval(&self, plan: &PlanRequest) -> anyhow::Result<PlanVerdict> {
+            assert_eq!(plan.request.diff_card.risk_reasons.len(), plan.steps.len());
+            let signature = match &self.1 {
+                Some((keyring, signed)) => keyring.sign_steps(
+                    "alice",
+                    &plan.request.digest()?,
+                    &ApprovalDecision::Approved,
+                    signed,
+                )?,
+                None => None,
+            };
+            Ok(PlanVerdict {
+                decision: BackendDecision {
+                    approved_by: "alice".to_string(),
+                    signature,
+                    ..BackendDecision::local_user(ApprovalDecision::Approved)
+                },
+                steps: self.0.clone(),
+            })
+        }
+    }
+
+    fn refactor_plan() -> ActionPlan {
+        ActionPlan::new("Refactor")
+            .with_step(PlanStep::new(
+                ActionType::EditFile,
+                "Edit lib.rs",
+                vec![Change::file_edit("/tmp/lib.rs", "a", "b")],
+            ))
+            .with_step(PlanStep::new(
+                ActionType::DeleteFile,
+                "Delete old.rs",
+                vec![],
+            ))
+            .with_step(PlanStep::new(
+                ActionType::ReadFile,
+                "Read Cargo.toml",
+                vec![],
+            ))
+            .with_step(PlanStep::new(
+                ActionType::ExecuteCommand,
+                "cargo build",
+                cargo_build(),
+            ))
+    }
+
+    #[tokio::test]
+    async fn test_plan_steps_approved_and_run_in_order() {
+        let mut manager = ApprovalManager::new().with_backend(Arc::new(PlanBackend(
+            StepSelection::Excluding(vec![2]),
+            None,
+        )));
+        let plan = refactor_plan();
+
+        let mut ticket = manager.check_and_approve_plan(plan.clone()).await.unwrap();
+
+        assert_eq!(ticket.approved_steps(), [1, 3, 4]);
+        let history = manager.get_history();
+        assert_eq!(history.len(), 3);
+        assert!(history
+            .iter()
+            .all(|r| r.plan_id.as_deref() == Some(ticket.plan_id.as_str())));
+        let excluded = history
+            .iter()
+            .find(|r| r.action_description == "Delete old.rs")
+            .unwrap();
+        assert_eq!(excluded.decision, ApprovalDecision::Denied);
+        assert_eq!(
+            excluded.justification.as_deref(),
+            Some("Excluded from plan (approved all steps except 2)")
+        );
+
+        // Steps run in order; excluded or repeated steps are refused
+        let first = manager
+            .authorize_plan_step(&mut ticket, &plan.steps[0])
+            .await
+            .unwrap();
+        assert_eq!(first.decision, ApprovalDecision::Approved);
+        assert!(first.id.is_some());
+        let refused = manager
+            .authorize_plan_step(&mut ticket, &plan.steps[1])
+            .await
+            .unwrap();
+        assert_eq!(refused.decision, ApprovalDecision::Denied);
+        assert!(manager.get_history()[0]
+            .justification
+            .as_deref()
+            .unwrap()
+            .starts_with("Not the next approved step"));
+        for step in &plan.steps[2..] {
+            let ticket = manager
+                .authorize_plan_step(&mut ticket, step)
+                .await
+                .unwrap();
+            assert!(ticket.decision.is_approved());
+        }
+        let again = manager
+            .authorize_plan_step(&mut ticket, &plan.steps[3])
+            .await
+            .unwrap();
+        assert_eq!(again.decision, ApprovalDecision::Denied);
+    }
+
+    #[tokio::test]
+    async fn test_plan_selection_verified_by_trust_list() {
+        let dir = tempfile::tempdir().unwrap();
+        let keyring = Keyring::open(dir.path());
+        let trust = trust_alice(&keyring);
+        let selection = StepSelection::Through(2);
+
+        // The signature must cover the selection the verdict approves
+        for signed in [selection.clone(), StepSelection::Through(1)] {
+            let backend = PlanBackend(selection.clone(), Some((keyring.clone(), signed.clone())));
+            let mut manager = ApprovalManager::new()
+                .with_backend(Arc::new(backend))
+                .with_trust_list(trust.clone());
+
+            let ticket = manager
+                .check_and_approve_plan(refactor_plan())
+                .await
+                .unwrap();
+
+            // The Green step is approved automatically
+            let rejected = manager.get_history().iter().any(|r| {
+                r.justification
+                    .as_deref()
+                    .is_some_and(|j| j.starts_with("Approval signature rejected"))
+            });
+            if signed == selection {
+                assert_eq!(ticket.approved_steps(), [1, 2, 3]);
+                assert!(!rejected);
+            } else {
+                assert_eq!(ticket.approved_steps(), [1, 3]);
+                assert!(rejected);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_plan_approval_cannot_grant_or_modify() {
+        let modified = BackendDecision {
+            modifications: vec![Modification::SetArgument {
+                pointer: "/command".to_string(),
+                value: serde_json::json!("ls"),
+            }],
+            ..BackendDecision::local_user(ApprovalDecision::Approved)
+        };
+        let granted = BackendDecision {
+            grant: Some(GrantScope::Session),
+            ..BackendDecision::local_user(ApprovalDecision::Approved)
+        };
+
+        for decision in [modified, granted] {
+            let mut manager = ApprovalManager::new()
+                .with_backend(ScriptedBackend::from_decisions(vec![decision]));
+            let ticket = manager
+                .check_and_approve_plan(refactor_plan())
+                .await
+                .unwrap();
+
+            assert_eq!(ticket.approved_steps(), [3]);
+            let history = manager.get_history();
+            assert!(history.iter().any(|r| r.justification.as_deref()
+                == Some("Plan approvals cannot modify steps or grant similar actions")));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_plan_quorum_steps_and_timeout() {
+        let quorum = QuorumConfig::new()
+            .with_group(ApproverGroup::new("ops", ["alice", "bob"]))
+            .with_rule(QuorumRule::new(
+                ActionType::DeleteFile.risk_level(),
+                "ops",
+                2,
+            ));
+        let mut manager = ApprovalManager::new()
+            .with_backend(Arc::new(FixedBackend(None)))
+            .with_trust_list(TrustList::in_memory())
+            .with_quorum(quorum, QuorumStore::in_memory())
+            .unwrap();
+
+        let ticket = manager
+            .check_and_approve_plan(refactor_plan())
+            .await
+            .unwrap();
+
+        // Only the Green step may run; the quorum step is pending
+        assert_eq!(ticket.approved_steps(), [3]);
+        assert_eq!(ticket.pending_steps(), [2]);
+        assert!(ticket.steps[1].note.is_some());
+        let history = manager.get_history();
+        assert!(history
+            .iter()
+            .all(|r| r.decision == ApprovalDecision::Denied && r.timeout.is_some()));
+        assert!(!history
+            .iter()
+            .any(|r| r.action_description == "Delete old.rs"));
+    }
+
+    #[tokio::test]
+    async fn test_pending_plan_step_requested_when_reached() {
+        let quorum = QuorumConfig::new()
+            .with_group(ApproverGroup::new("ops", ["alice", "bob"]))
+            .with_rule(QuorumRule::new(
+                ActionType::DeleteFile.risk_level(),
+                "ops",
+                2,
+            ));
+        let dir = tempfile::tempdir().unwrap();
+        let (keyring, trust) = trusted_keys(dir.path(), &["alice", "bob", "carol"]);
+        let mut manager = ApprovalManager::new()
+            .with_backend(SignedVotes::new(&keyring, &["carol", "alice", "bob"]))
+            .with_trust_list(trust)
+            .with_quorum(quorum, QuorumStore::in_memory())
+            .unwrap();
+        let plan = refactor_plan();
+
+        let mut ticket = manager.check_and_approve_plan(plan.clone()).await.unwrap();
+        assert_eq!(ticket.approved_steps(), [1, 3, 4]);
+        assert_eq!(ticket.pending_steps(), [2]);
+
+        // Step 3 cannot jump the pending step
+        manager
+            .authorize_plan_step(&mut ticket, &plan.steps[0])
+            .await
+            .unwrap();
+        let refused = manager
+            .authorize_plan_step(&mut ticket, &plan.steps[2])
+            .await
+            .unwrap();
+        assert_eq!(refused.decision, ApprovalDecision::Denied);
+
+        let voted = manager
+            .authorize_plan_step(&mut ticket, &plan.steps[1])
+            .await
+            .unwrap();
+        assert_eq!(voted.decision, ApprovalDecision::Approved);
+        assert_eq!(manager.get_history()[0].votes.len(), 2);
+        for step in &plan.steps[2..] {
+            let ticket = manager
+                .authorize_plan_step(&mut ticket, step)
+                .await
+                .unwrap();
+            assert!(ticket.decision.is_approved());
+        }
+        assert!(ticket.is_finished());
+    }
+
+    #[tokio::test]
+    async fn test_plan_step_checkpointed_when_authorized() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("lib.rs");
+        std::fs::write(&file, "a").unwrap();
+        let store = CheckpointStore::open(dir.path().join("checkpoints"));
+        let mut manager = ApprovalManager::new()
+            .with_backend(Arc::new(PlanBackend(StepSelection::All, None)))
+            .with_checkpoints(store.clone());
+        let plan = ActionPlan::new("Edit").with_step(PlanStep::new(
+            ActionType::EditFile,
+            "Edit lib.rs",
+            vec![Change::file_edit(file.to_string_lossy(), "a", "b")],
+        ));
+
+        let mut ticket = manager.check_and_approve_plan(plan.clone()).await.unwrap();
+        let id = ticket.steps[0].ticket.id.clone().unwrap();
+        assert!(store.get(&id).unwrap().is_none());
+
+        std::fs::write(&file, "changed").unwrap();
+        manager
+            .authorize_plan_step(&mut ticket, &plan.steps[0])
+            .await
+            .unwrap();
+        let checkpoint = store.get(&id).unwrap().unwrap();
+        assert_eq!(checkpoint.files[0].before.as_ref().unwrap().size, 7);
+    }
+
+    #[tokio::test]
+    async fn test_deferred_action_decided_later() {
+        let backend = ScriptedBackend::new(&[("alice", ApprovalDecision::DeferredToLater)]);
+        let mut manager = ApprovalManager::new().with_backend(backend);
+        async fn request(manager: &mut ApprovalManager) -> anyhow::Result<ApprovalDecision> {
+            manager
+                .check_and_approve(
+                    ActionType::ExecuteCommand,
+                    "cargo build".to_string(),
+                    cargo_build(),
+                )
+                .await
+                .map(|ticket| ticket.decision)
+        }
+
+        assert_eq!(
+            request(&mut manager).await.unwrap(),
+            ApprovalDecision::DeferredToLater
+        );
+        let id = manager.get_history()[0].deferred_id.clone().unwrap();
+        assert_eq!(
+            manager.deferred_action(&id).unwrap().unwrap().status,
+            DeferredStatus::Pending
+        );
+
+        // Still pending: no new prompt, no new queue entry
+        assert_eq!(
+            request(&mut manager).await.unwrap(),
+            ApprovalDecision::DeferredToLater
+        );
+        assert!(manager.take_deferred_notifications().unwrap().is_empty());
+
+        manager
+            .deferred
+            .decide(&id, ApprovalDecision::Approved, "bob", None, None)
+            .unwrap();
+        let notifications = manager.take_deferred_notifications().unwrap();
+        assert_eq!(notifications.len(), 1);
+        assert_eq!(notifications[0].status.label(), "approved");
+
+        assert_eq!(
+            request(&mut manager).await.unwrap(),
+            ApprovalDecision::Approved
+        );
+        let history = manager.get_history();
+        let record = history
+            .iter()
+            .find(|r| r.decision == ApprovalDecision::Approved)
+            .unwrap();
+        assert_eq!(record.approved_by, "bob");
+        assert_eq!(record.deferred_id.as_deref(), Some(id.as_str()));
+        assert!(manager.deferred_action(&id).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_record_automatic_denial() {
+        let mut manager = ApprovalManager::new();
+        manager
+            .record_automatic_denial(
+                "Call write_file".to_string(),
+                "path not allowed".to_string(),
+            )
+            .unwrap();
+
+        let history = manager.get_history();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].decision, ApprovalDecision::Denied);
+        assert_eq!(history[0].approved_by, "system");
+        assert_eq!(
+            history[0].justification.as_deref(),
+            Some("path not allowed")
+        );
+    }
+
     #[test]
     fn test_export_audit_log() {
         let manager = ApprovalManager::new();
@@ -0,0 +1,588 @@
+//! Plan Approval (batches of Red actions)
+//!
+//! Agents often plan several Red actions at once (edit three files, run the
+//! tests, commit). Instead of one prompt per action, the agent submits an
+//! ordered [`ActionPlan`] through
+//! [`ApprovalManager::check_and_approve_plan`](super::ApprovalManager::check_and_approve_plan).
+//! Approvers see one combined card with every step and its risk, and approve
+//! all steps, only the first few, or all but some ([`StepSelection`]). Each
+//! step's decision is recorded separately, linked by the plan ID.
+//!
+//! The returned [`PlanTicket`] is consulted before each step runs
+//! ([`ApprovalManager::authorize_plan_step`](super::ApprovalManager::authorize_plan_step)):
+//! only approved steps may run, in plan order, with exactly the changes and
+//! arguments that were approved. Files a step changes are checkpointed when
+//! it is authorized, right before it runs.
+//!
+//! Steps are decided like single actions where it matters:
+//! - Green steps are approved without review
+//! - Policy deny rules deny matching steps
+//! - Steps that need more than one approver (quorum rules, or anomalies when
+//!   a second approver is required) are pending: they are requested on their
+//!   own when the agent reaches them
+//!
+//! Standing grants and policy allow rules do not apply to plans, and
+//! standing grants or modifications requested with a plan decision are
+//! ignored. Backends decide the combined card as a whole by default (see
+//! [`ApprovalBackend::request_plan_approval`](super::backend::ApprovalBackend::request_plan_approval));
+//! the stdin prompt also offers step selection.
+//!
+//! Each step is recorded as its own record ([`PlanStepCard::record`]); a
+//! signed plan decision covers the digests of all of them.
+
+use super::action::{ActionType, RiskLevel};
+use super::backend::{ApprovalRequest, BackendDecision};
+use super::diff::{Change, DiffCard};
+use super::history::{ApprovalDecision, ApprovalTicket};
+use super::signing::{PlanStepDigest, RecordHeader};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// One action of a plan
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanStep {
+    /// Type of the action
+    pub action_type: ActionType,
+
+    /// Human-readable description
+    pub description: String,
+
+    /// Changes the action makes
+    pub changes: Vec<Change>,
+
+    /// Tool call arguments (tool call steps only)
+    pub arguments: Option<serde_json::Value>,
+}
+
+impl PlanStep {
+    /// Create a step for an action
+    pub fn new(
+        action_type: ActionType,
+        description: impl Into<String>,
+        changes: Vec<Change>,
+    ) -> Self {
+        Self {
+            action_type,
+            description: description.into(),
+            changes,
+            arguments: None,
+        }
+    }
+
+    /// Create a step for an MCP tool call
+    ///
+    /// Classified and described like
+    /// [`check_and_approve_tool_call`](super::ApprovalManager::check_and_approve_tool_call).
+    pub fn tool_call(server_name: &str, tool_name: &str, arguments: serde_json::Value) -> Self {
+        Self {
+            action_type: ActionType::from_description(tool_name),
+            description: format!("Call tool {} on {}", tool_name, server_name),
+            changes: vec![Change::ExternalCall {
+                method: "tools/call".to_string(),
+                endpoint: format!("mcp://{}/{}", server_name, tool_name),
+                payload_preview: arguments.to_string(),
+            }],
+            arguments: Some(arguments),
+        }
+    }
+
+    /// Whether `other` performs the same action (descriptions may differ)
+    pub fn same_action(&self, other: &PlanStep) -> bool {
+        self.action_type == other.action_type
+            && self.changes == other.changes
+            && self.arguments == other.arguments
+    }
+}
+
+/// An ordered list of actions approved together
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ActionPlan {
+    /// What the plan achieves
+    pub description: String,
+
+    /// Steps, in execution order
+    pub steps: Vec<PlanStep>,
+}
+
+impl ActionPlan {
+    /// Create an empty plan
+    pub fn new(description: impl Into<String>) -> Self {
+        Self {
+            description: description.into(),
+            steps: Vec::new(),
+        }
+    }
+
+    /// Add a step
+    pub fn with_step(mut self, step: PlanStep) -> Self {
+        self.steps.push(step);
+        self
+    }
+}
+
+/// Steps an approver approved (numbered from 1, in plan order)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepSelection {
+    /// Every step
+    All,
+
+    /// Steps up to and including this one
+    Through(usize),
+
+    /// Every step except these
+    Excluding(Vec<usize>),
+}
+
+impl StepSelection {
+    /// Whether step `number` is selected
+    pub fn includes(&self, number: usize) -> bool {
+        match self {
+            StepSelection::All => true,
+            StepSelection::Through(last) => number <= *last,
+            StepSelection::Excluding(excluded) => !excluded.contains(&number),
+        }
+    }
+}
+
+impl fmt::Display for StepSelection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StepSelection::All => write!(f, "all steps"),
+            StepSelection::Through(last) => write!(f, "steps up to {}", last),
+            StepSelection::Excluding(excluded) => {
+                let numbers: Vec<String> = excluded.iter().map(ToString::to_string).collect();
+                write!(f, "all steps except {}", numbers.join(", "))
+            }
+        }
+    }
+}
+
+/// Parse step numbers such as `2, 4` or `2 4`
+pub fn parse_step_numbers(input: &str) -> Result<Vec<usize>> {
+    let numbers = input
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            s.parse::<usize>()
+                .with_context(|| format!("Invalid step number '{}'", s))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if numbers.is_empty() {
+        anyhow::bail!("No step numbers given");
+    }
+    Ok(numbers)
+}
+
+/// A plan step as shown to approvers
+#[derive(Debug, Clone)]
+pub struct PlanStepCard {
+    /// Position in the plan (from 1)
+    pub number: usize,
+
+    /// Diff Card of the step
+    pub card: DiffCard,
+
+    /// Record the step's decision will be recorded as
+    pub record: RecordHeader,
+}
+
+impl PlanStepCard {
+    /// Step `number` deciding `card`, as a new record
+    pub fn new(number: usize, card: DiffCard) -> Self {
+        Self {
+            number,
+            record: RecordHeader::for_card(&card),
+            card,
+        }
+    }
+}
+
+/// Steps of a plan waiting for a decision
+#[derive(Debug, Clone)]
+pub struct PlanRequest {
+    /// Request carrying the combined card (for backends that decide the
+    /// plan as a whole)
+    pub request: ApprovalRequest,
+
+    /// Steps to decide
+    pub steps: Vec<PlanStepCard>,
+}
+
+impl PlanRequest {
+    /// Create a request, combining the steps into one card
+    ///
+    /// The combined card takes the action type and risk of the riskiest
+    /// step, lists each step with its risk among the risk reasons, and
+    /// carries every step's changes and anomalies. Signatures cover the
+    /// steps' records.
+    pub fn new(description: &str, steps: Vec<PlanStepCard>) -> Result<Self> {
+        let riskiest = steps
+            .iter()
+            .max_by_key(|s| (s.card.risk_level, s.card.risk_score))
+            .map(|s| &s.card);
+        let action_type = riskiest.map_or(ActionType::Unknown, |c| c.action_type);
+
+        let mut card = DiffCard::new(
+            action_type,
+            format!("Plan: {} ({} steps)", description, steps.len()),
+            steps
+                .iter()
+                .flat_map(|s| s.card.changes.iter().cloned())
+                .collect(),
+        );
+        card.risk_level = riskiest.map_or(RiskLevel::None, |c| c.risk_level);
+        card.risk_score = riskiest.map_or(0, |c| c.risk_score);
+        card.risk_reasons = steps
+            .iter()
+            .map(|s| {
+                format!(
+                    "Step {} ({} {}): {}",
+                    s.number, s.card.risk_level, s.card.action_type, s.card.description
+                )
+            })
+            .collect();
+        card.anomalies = steps
+            .iter()
+            .flat_map(|s| {
+                s.card
+                    .anomalies
+                    .iter()
+                    .map(move |a| format!("Step {}: {}", s.number, a))
+            })
+            .collect();
+
+        let digests = steps
+            .iter()
+            .map(|s| {
+                Ok(PlanStepDigest {
+                    number: s.number,
+                    record_digest: s.record.digest()?,
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            request: ApprovalRequest::new(card).for_plan(digests),
+            steps,
+        })
+    }
+
+    /// Whether the plan has a step numbered `number`
+    pub fn has_step(&self, number: usize) -> bool {
+        self.steps.iter().any(|s| s.number == number)
+    }
+}
+
+/// Decision on a plan
+#[derive(Debug, Clone)]
+pub struct PlanVerdict {
+    /// The decision (approvals apply to the selected steps only)
+    pub decision: BackendDecision,
+
+    /// Steps approved
+    pub steps: StepSelection,
+}
+
+impl PlanVerdict {
+    /// Decide every step the same way
+    pub fn whole(decision: BackendDecision) -> Self {
+        Self {
+            decision,
+            steps: StepSelection::All,
+        }
+    }
+
+    /// Whether step `number` was approved
+    pub fn approves(&self, number: usize) -> bool {
+        self.decision.decision.is_approved() && self.steps.includes(number)
+    }
+}
+
+/// A plan step and its decision
+#[derive(Debug, Clone)]
+pub struct PlanStepTicket {
+    /// The step as approved
+    pub step: PlanStep,
+
+    /// Its decision
+    pub ticket: ApprovalTicket,
+
+    /// Why the step was not put to the approver, if it was not
+    pub note: Option<String>,
+
+    /// Whether the step still needs its own approval
+    pub pending: bool,
+}
+
+/// Decisions on a plan's steps, enforcing their order
+#[derive(Debug, Clone)]
+pub struct PlanTicket {
+    /// Plan ID (recorded with every step's decision)
+    pub plan_id: String,
+
+    /// Steps, in plan order
+    pub steps: Vec<PlanStepTicket>,
+
+    /// Index of the first step that may still run
+    next: usize,
+}
+
+impl PlanTicket {
+    /// Create a ticket for decided steps
+    pub fn new(plan_id: impl Into<String>, steps: Vec<PlanStepTicket>) -> Self {
+        Self {
+            plan_id: plan_id.into(),
+            steps,
+            next: 0,
+        }
+    }
+
+    /// Numbers of the approved steps (from 1)
+    pub fn approved_steps(&self) -> Vec<usize> {
+        self.steps
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| s.ticket.decision.is_approved())
+            .map(|(i, _)| i + 1)
+            .collect()
+    }
+
+    /// Numbers of the steps waiting for their own approval (from 1)
+    pub fn pending_steps(&self) -> Vec<usize> {
+        self.steps
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| s.pending)
+            .map(|(i, _)| i + 1)
+            .collect()
+    }
+
+    /// Whether no step may run any more
+    pub fn is_finished(&self) -> bool {
+        self.upcoming().is_none()
+    }
+
+    /// Index of the next step that may run (approved or pending)
+    fn upcoming(&self) -> Option<usize> {
+        self.steps[self.next..]
+            .iter()
+            .position(|s| s.pending || s.ticket.decision.is_approved())
+            .map(|offset| self.next + offset)
+    }
+
+    /// Claim the next approved step, which must be `step`
+    ///
+    /// Steps before it that were not approved are skipped; approved steps
+    /// cannot be skipped or run twice. A pending step is returned with its
+    /// pending ticket and stays next until [`settle`](Self::settle)d.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no approved steps are left or `step` is not the
+    /// next one (out of order, excluded, or with different changes or
+    /// arguments).
+    pub fn next_step(&mut self, step: &PlanStep) -> Result<ApprovalTicket> {
+        let index = self.upcoming().context("No approved steps left")?;
+
+        let expected = &self.steps[index];
+        if !expected.step.same_action(step) {
+            anyhow::bail!(
+                "Expected step {} ({})",
+                index + 1,
+                expected.step.description
+            );
+        }
+        if !expected.pending {
+            self.next = index + 1;
+        }
+        Ok(expected.ticket.clone())
+    }
+
+    /// Record the separate decision on the pending step returned by
+    /// [`next_step`](Self::next_step)
+    ///
+    /// An approved step is claimed; a denied one is skipped. Deferred
+    /// decisions leave the step pending.
+    pub fn settle(&mut self, ticket: ApprovalTicket) {
+        let Some(index) = self.upcoming().filter(|i| self.steps[*i].pending) else {
+            return;
+        };
+        let step = &mut self.steps[index];
+        match ticket.decision {
+            ApprovalDecision::DeferredToLater => step.ticket = ticket,
+            ApprovalDecision::Denied => {
+                step.ticket = ticket;
+                step.pending = false;
+            }
+            _ => {
+                step.ticket = ticket;
+                step.pending = false;
+                self.next = index + 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edit(path: &str) -> PlanStep {
+        PlanStep::new(
+            ActionType::EditFile,
+            format!("Edit {}", path),
+            vec![Change::file_edit(path, "a", "b")],
+        )
+    }
+
+    fn ticket(steps: &[(PlanStep, ApprovalDecision)]) -> PlanTicket {
+        PlanTicket::new(
+            "plan",
+            steps
+                .iter()
+                .map(|(step, decision)| PlanStepTicket {
+                    step: step.clone(),
+                    ticket: ApprovalTicket::unrecorded(decision.clone()),
+                    note: None,
+                    pending: *decision == ApprovalDecision::DeferredToLater,
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_step_selection() {
+        assert!(StepSelection::All.includes(7));
+        assert!(StepSelection::Through(2).includes(2));
+        assert!(!StepSelection::Through(2).includes(3));
+        assert!(!StepSelection::Excluding(vec![2, 4]).includes(4));
+        assert!(StepSelection::Excluding(vec![2, 4]).includes(3));
+        assert_eq!(
+            StepSelection::Excluding(vec![2, 4]).to_string(),
+            "all steps except 2, 4"
+        );
+
+        assert_eq!(parse_step_numbers("2, 4").unwrap(), [2, 4]);
+        assert_eq!(parse_step_numbers("3 1").unwrap(), [3, 1]);
+        assert!(parse_step_numbers("two").is_err());
+        assert!(parse_step_numbers(" ").is_err());
+    }
+
+    #[test]
+    fn test_combined_card_lists_steps() {
+        let steps = [edit("/tmp/a.rs"), edit("/tmp/b.rs")]
+            .into_iter()
+            .enumerate()
+            .map(|(i, step)| {
+                PlanStepCard::new(
+                    i + 1,
+                    DiffCard::new(step.action_type, step.description, step.changes),
+                )
+            })
+            .chain([PlanStepCard::new(
+                3,
+                DiffCard::new(ActionType::DeleteFile, "Delete /tmp/c".to_string(), vec![]),
+            )])
+            .collect();
+
+        let request = PlanRequest::new("Refactor", steps).unwrap();
+        let card = &request.request.diff_card;
+
+        assert_eq!(card.description, "Plan: Refactor (3 steps)");
+        assert_eq!(card.action_type, ActionType::DeleteFile);
+        assert_eq!(card.risk_level, ActionType::DeleteFile.risk_level());
+        assert_eq!(card.changes.len(), 2);
+        assert_eq!(card.risk_reasons.len(), 3);
+        assert!(card.risk_reasons[0].starts_with("Step 1 ("));
+        assert!(card.risk_reasons[0].ends_with("EditFile): Edit /tmp/a.rs"));
+
+        // Approvers sign the steps' records
+        let numbers: Vec<usize> = request.request.plan.iter().map(|s| s.number).collect();
+        assert_eq!(numbers, [1, 2, 3]);
+        assert_ne!(
+            request.request.digest().unwrap(),
+            request.request.record.digest().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_steps_run_in_order_as_approved() {
+        let (a, b, c) = (edit("/tmp/a"), edit("/tmp/b"), edit("/tmp/c"));
+        let mut plan = ticket(&[
+            (a.clone(), ApprovalDecision::Approved),
+            (b.clone(), ApprovalDecision::Denied),
+            (c.clone(), ApprovalDecision::Approved),
+        ]);
+        assert_eq!(plan.approved_steps(), [1, 3]);
+
+        // Out of order
+        assert!(plan.next_step(&c).is_err());
+        assert!(plan.next_step(&a).is_ok());
+        // Excluded, or already run
+        assert!(plan.next_step(&b).is_err());
+        assert!(plan.next_step(&a).is_err());
+        // Changed action
+        assert!(plan.next_step(&edit("/tmp/d")).is_err());
+
+        let described = PlanStep {
+            description: "Update c".to_string(),
+            ..c.clone()
+        };
+        assert!(plan.next_step(&described).is_ok());
+        assert!(plan.next_step(&c).is_err());
+    }
+
+    #[test]
+    fn test_tool_call_arguments_must_match() {
+        let call = PlanStep::tool_call("fs", "write_file", serde_json::json!({"path": "/tmp/x"}));
+        let mut plan = ticket(&[(call.clone(), ApprovalDecision::Approved)]);
+
+        let changed =
+            PlanStep::tool_call("fs", "write_file", serde_json::json!({"path": "/etc/x"}));
+        assert!(plan.next_step(&changed).is_err());
+        assert!(plan.next_step(&call).is_ok());
+    }
+
+    #[test]
+    fn test_pending_step_blocks_until_settled() {
+        let (a, b, c) = (edit("/tmp/a"), edit("/tmp/b"), edit("/tmp/c"));
+        let mut plan = ticket(&[
+            (a.clone(), ApprovalDecision::Approved),
+            (b.clone(), ApprovalDecision::DeferredToLater),
+            (c.clone(), ApprovalDecision::Approved),
+        ]);
+        assert_eq!(plan.pending_steps(), [2]);
+        assert!(plan.next_step(&a).is_ok());
+
+        // The pending step cannot be skipped, and stays next until approved
+        assert!(plan.next_step(&c).is_err());
+        let pending = plan.next_step(&b).unwrap();
+        assert_eq!(pending.decision, ApprovalDecision::DeferredToLater);
+        plan.settle(ApprovalTicket::unrecorded(
+            ApprovalDecision::DeferredToLater,
+        ));
+        assert!(plan.next_step(&b).is_ok());
+        plan.settle(ApprovalTicket::unrecorded(ApprovalDecision::Approved));
+        assert!(plan.pending_steps().is_empty());
+        assert!(plan.next_step(&b).is_err());
+
+        assert!(!plan.is_finished());
+        assert!(plan.next_step(&c).is_ok());
+        assert!(plan.is_finished());
+    }
+
+    #[test]
+    fn test_denied_pending_step_skipped() {
+        let (a, b) = (edit("/tmp/a"), edit("/tmp/b"));
+        let mut plan = ticket(&[
+            (a.clone(), ApprovalDecision::DeferredToLater),
+            (b.clone(), ApprovalDecision::Approved),
+        ]);
+
+        assert!(plan.next_step(&a).is_ok());
+        plan.settle(ApprovalTicket::unrecorded(ApprovalDecision::Denied));
+        assert!(plan.next_step(&a).is_err());
+        assert!(plan.next_step(&b).is_ok());
+    }
+}
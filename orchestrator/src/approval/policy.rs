@@ -0,0 +1,260 @@
+//! Approval Policy Rules
+//!
+//! Reviewed rules that decide Red actions without prompting. Unlike
+//! standing grants (see [`grants`](super::grants)), rules are not bound to
+//! a session and never expire; they are kept in a JSON file that is
+//! reviewed and committed like code:
+//!
+//! ```json
+//! {
+//!   "rules": [
+//!     {
+//!       "effect": "allow",
+//!       "action_type": "CreateFile",
+//!       "changes": { "kind": "path_prefix", "prefix": "/home/agent/out" },
+//!       "note": "Agent output directory"
+//!     },
+//!     { "effect": "deny", "action_type": "TransferAsset" }
+//!   ]
+//! }
+//! ```
+//!
+//! Deny rules are checked first and apply to every action. Allow rules never
+//! apply to actions that need multi-party approval. Decisions made by a rule
+//! are recorded with the rule as justification, as made by "system".
+//!
+//! With a trust list configured, allow rules must be signed by a trusted
+//! approver (`luminaguard keys sign-policy`, which adds a `"signature"` to
+//! each allow rule). Decisions made by a signed rule are recorded as made
+//! by its signer and carry the rule's signature; unsigned rules are refused
+//! like any other unsigned approval.
+//!
+//! `luminaguard audit suggest-policy` proposes rules mined from the approval
+//! history (see [`suggest`](super::suggest)); they are never applied
+//! automatically.
+
+use super::action::ActionType;
+use super::diff::Change;
+use super::grants::ChangeMatcher;
+use super::signing::RecordSignature;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// What a matching rule decides
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyEffect {
+    /// Approve without prompting
+    Allow,
+
+    /// Deny without prompting
+    Deny,
+}
+
+impl fmt::Display for PolicyEffect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolicyEffect::Allow => write!(f, "allow"),
+            PolicyEffect::Deny => write!(f, "deny"),
+        }
+    }
+}
+
+/// A rule deciding actions of one type
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PolicyRule {
+    /// Decision for matching actions
+    pub effect: PolicyEffect,
+
+    /// Action type covered
+    pub action_type: ActionType,
+
+    /// Changes covered (any if omitted)
+    #[serde(default = "any_changes")]
+    pub changes: ChangeMatcher,
+
+    /// Why the rule exists (for reviewers)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+
+    /// Approver's signature over the rule (allow rules, see
+    /// [`Keyring::sign_rule`](super::signing::Keyring::sign_rule))
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<RecordSignature>,
+}
+
+fn any_changes() -> ChangeMatcher {
+    ChangeMatcher::Any
+}
+
+impl PolicyRule {
+    /// Create a rule without a note
+    pub fn new(effect: PolicyEffect, action_type: ActionType, changes: ChangeMatcher) -> Self {
+        Self {
+            effect,
+            action_type,
+            changes,
+            note: None,
+            signature: None,
+        }
+    }
+
+    /// Attach a note for reviewers
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.note = Some(note.into());
+        self
+    }
+
+    /// Check whether the rule covers an action
+    pub fn covers(&self, action_type: ActionType, changes: &[Change]) -> bool {
+        self.action_type == action_type && self.changes.matches(changes)
+    }
+}
+
+impl fmt::Display for PolicyRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.effect, self.action_type)?;
+        if self.changes != ChangeMatcher::Any {
+            write!(f, ", {}", self.changes)?;
+        }
+        Ok(())
+    }
+}
+
+/// Approval policy (an ordered list of rules)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ApprovalPolicy {
+    /// Rules, in file order
+    pub rules: Vec<PolicyRule>,
+}
+
+impl ApprovalPolicy {
+    /// Create an empty policy (every Red action is prompted for)
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load a policy from a JSON file
+    pub fn from_json_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let data = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read approval policy from {}", path.display()))?;
+        serde_json::from_str(&data)
+            .with_context(|| format!("Failed to parse approval policy from {}", path.display()))
+    }
+
+    /// Default policy location (`<data dir>/approval_policy.json`)
+    pub fn default_path() -> PathBuf {
+        super::data_dir().join("approval_policy.json")
+    }
+
+    /// Add a rule
+    pub fn with_rule(mut self, rule: PolicyRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Serialize to pretty JSON (the policy file format)
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// First rule deciding an action (deny rules take precedence)
+    pub fn rule_for(&self, action_type: ActionType, changes: &[Change]) -> Option<&PolicyRule> {
+        let mut matching = self.rules.iter().filter(|r| r.covers(action_type, changes));
+        let first = matching.clone().next()?;
+        Some(
+            matching
+                .find(|r| r.effect == PolicyEffect::Deny)
+                .unwrap_or(first),
+        )
+    }
+
+    /// Whether the policy already has a rule with the same effect and scope
+    pub fn contains(&self, rule: &PolicyRule) -> bool {
+        self.rules.iter().any(|r| {
+            r.effect == rule.effect
+                && r.action_type == rule.action_type
+                && r.changes == rule.changes
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn out_dir() -> ChangeMatcher {
+        ChangeMatcher::PathPrefix {
+            prefix: "/home/agent/out".to_string(),
+        }
+    }
+
+    fn create(path: &str) -> Vec<Change> {
+        vec![Change::file_create(path, "x")]
+    }
+
+    #[test]
+    fn test_parse_policy_file() {
+        let policy: ApprovalPolicy = serde_json::from_str(
+            r#"{
+                "rules": [
+                    {
+                        "effect": "allow",
+                        "action_type": "CreateFile",
+                        "changes": { "kind": "path_prefix", "prefix": "/home/agent/out" },
+                        "note": "Agent output directory"
+                    },
+                    { "effect": "deny", "action_type": "TransferAsset" }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(policy.rules.len(), 2);
+        assert_eq!(policy.rules[0].changes, out_dir());
+        assert_eq!(policy.rules[1].changes, ChangeMatcher::Any);
+        assert_eq!(
+            policy.rules[0].to_string(),
+            "allow CreateFile, path under /home/agent/out"
+        );
+        assert_eq!(policy.rules[1].to_string(), "deny TransferAsset");
+
+        let reparsed: ApprovalPolicy = serde_json::from_str(&policy.to_json().unwrap()).unwrap();
+        assert_eq!(reparsed, policy);
+    }
+
+    #[test]
+    fn test_deny_rules_take_precedence() {
+        let policy = ApprovalPolicy::new()
+            .with_rule(PolicyRule::new(
+                PolicyEffect::Allow,
+                ActionType::CreateFile,
+                out_dir(),
+            ))
+            .with_rule(PolicyRule::new(
+                PolicyEffect::Deny,
+                ActionType::CreateFile,
+                ChangeMatcher::PathPrefix {
+                    prefix: "/home/agent/out/secrets".to_string(),
+                },
+            ));
+
+        let rule = |path| policy.rule_for(ActionType::CreateFile, &create(path));
+        assert_eq!(
+            rule("/home/agent/out/a.txt").map(|r| r.effect),
+            Some(PolicyEffect::Allow)
+        );
+        assert_eq!(
+            rule("/home/agent/out/secrets/key").map(|r| r.effect),
+            Some(PolicyEffect::Deny)
+        );
+        assert!(rule("/etc/passwd").is_none());
+        assert!(policy
+            .rule_for(ActionType::EditFile, &create("/home/agent/out/a.txt"))
+            .is_none());
+    }
+}
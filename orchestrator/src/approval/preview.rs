@@ -0,0 +1,631 @@
+//! Dry-run Previews of Red Actions
+//!
+//! Diff Cards normally describe what the agent says an action will do. In
+//! preview mode the action first runs against an [`Overlay`]: a disposable
+//! copy of the workspace. The files it created, edited or deleted there are
+//! captured as `FileCreate`/`FileEdit`/`FileDelete` changes (with content)
+//! and shown on the Diff Card, so the approver sees what the action actually
+//! does rather than what was promised.
+//!
+//! After approval, the action either runs again for real or the overlay's
+//! changes are committed to the workspace (see [`PreviewCommit`]). The
+//! overlay is deleted when dropped.
+//!
+//! The overlay is a plain copy of the workspace directory on the host, made
+//! by the orchestrator; it is not a VM snapshot. Only the path arguments of
+//! the call are pointed at it, so the preview is only as faithful as the
+//! tool's confinement to those paths.
+//!
+//! The preview is a real run of an unapproved action, so only file tools
+//! whose path arguments all resolve inside the workspace are previewed (see
+//! [`PreviewConfig::check_previewable`]). Anything else would touch the host
+//! outside the overlay; its Diff Card says the preview is unavailable.
+
+use super::action::ActionType;
+use super::diff::Change;
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Largest workspace (total file size) that can be previewed
+pub const MAX_WORKSPACE_BYTES: u64 = 64 * 1024 * 1024;
+
+/// What happens after a previewed action is approved
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PreviewCommit {
+    /// Run the action again against the real workspace
+    #[default]
+    Execute,
+
+    /// Copy the overlay's changes to the workspace (the action does not run
+    /// again). Amended actions still run for real.
+    CommitOverlay,
+}
+
+/// Preview mode configuration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreviewConfig {
+    /// Workspace the action operates on
+    pub workspace: PathBuf,
+
+    /// What happens after approval
+    pub on_approval: PreviewCommit,
+}
+
+impl PreviewConfig {
+    /// Preview actions on `workspace`, running them again once approved
+    ///
+    /// The workspace path is canonicalized if it exists, so tool calls must
+    /// spell paths under its canonical form to be previewed.
+    pub fn new(workspace: impl Into<PathBuf>) -> Self {
+        let workspace = workspace.into();
+        Self {
+            workspace: workspace.canonicalize().unwrap_or(workspace),
+            on_approval: PreviewCommit::default(),
+        }
+    }
+
+    /// Set what happens after approval
+    pub fn with_commit(mut self, on_approval: PreviewCommit) -> Self {
+        self.on_approval = on_approval;
+        self
+    }
+
+    /// Check that a tool call can be run against an overlay
+    ///
+    /// The tool must be a file tool (create, edit or delete), name at least
+    /// one path, and every path must start with the workspace path (without
+    /// `..`) and resolve, following symlinks, inside the workspace.
+    ///
+    /// # Errors
+    ///
+    /// Returns the reason the call cannot be previewed.
+    pub fn check_previewable(&self, tool_name: &str, arguments: &serde_json::Value) -> Result<()> {
+        if !matches!(
+            ActionType::from_description(tool_name),
+            ActionType::CreateFile | ActionType::EditFile | ActionType::DeleteFile
+        ) {
+            anyhow::bail!("{} is not a file tool", tool_name);
+        }
+
+        let paths = argument_paths(arguments);
+        if paths.is_empty() {
+            anyhow::bail!("{} names no paths", tool_name);
+        }
+
+        let workspace = self
+            .workspace
+            .canonicalize()
+            .with_context(|| format!("Failed to resolve {}", self.workspace.display()))?;
+        for path in paths {
+            // The overlay rewrites the workspace prefix, so the path must be
+            // spelled under it as well as resolve inside it
+            let spelled = Path::new(&path);
+            if !spelled.starts_with(&self.workspace)
+                || spelled
+                    .components()
+                    .any(|c| c == std::path::Component::ParentDir)
+            {
+                anyhow::bail!("{} is not a plain path in the workspace", path);
+            }
+            if !resolve(spelled)?.starts_with(&workspace) {
+                anyhow::bail!("{} is outside the workspace", path);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Argument names (besides any containing "path") that hold paths
+const PATH_ARGUMENTS: &[&str] = &[
+    "file",
+    "filename",
+    "dir",
+    "directory",
+    "source",
+    "destination",
+    "target",
+];
+
+/// Paths named in tool call arguments
+///
+/// Collects strings (or arrays of strings) under path-like argument names,
+/// at any depth.
+pub fn argument_paths(arguments: &serde_json::Value) -> Vec<String> {
+    let mut paths = Vec::new();
+    collect_paths(arguments, &mut paths);
+    paths
+}
+
+fn is_path_argument(key: &str) -> bool {
+    let key = key.to_lowercase();
+    key.contains("path") || PATH_ARGUMENTS.contains(&key.as_str())
+}
+
+fn collect_paths(value: &serde_json::Value, paths: &mut Vec<String>) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, item) in map {
+                if is_path_argument(key) {
+                    match item {
+                        serde_json::Value::String(s) => paths.push(s.clone()),
+                        serde_json::Value::Array(items) => paths
+                            .extend(items.iter().filter_map(|i| i.as_str().map(str::to_string))),
+                        other => collect_paths(other, paths),
+                    }
+                } else {
+                    collect_paths(item, paths);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for item in items {
+                collect_paths(item, paths);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Resolve an absolute path that may not exist yet
+///
+/// The nearest existing ancestor is canonicalized and the rest appended.
+fn resolve(path: &Path) -> Result<PathBuf> {
+    let mut existing = path;
+    let mut rest = Vec::new();
+    while existing.symlink_metadata().is_err() {
+        let (Some(parent), Some(name)) = (existing.parent(), existing.file_name()) else {
+            anyhow::bail!("Failed to resolve {}", path.display());
+        };
+        rest.push(name);
+        existing = parent;
+    }
+
+    let mut resolved = existing
+        .canonicalize()
+        .with_context(|| format!("Failed to resolve {}", existing.display()))?;
+    resolved.extend(rest.iter().rev());
+    Ok(resolved)
+}
+
+/// Contents of every file under a directory
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WorkspaceSnapshot {
+    /// File contents by path relative to the root
+    files: BTreeMap<PathBuf, Vec<u8>>,
+}
+
+impl WorkspaceSnapshot {
+    /// Read every regular file under `root` (symlinks are not followed)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a file cannot be read or the files exceed
+    /// [`MAX_WORKSPACE_BYTES`].
+    pub fn capture(root: &Path) -> Result<Self> {
+        let mut snapshot = Self::default();
+        let mut total = 0u64;
+        let mut dirs = vec![root.to_path_buf()];
+        while let Some(dir) = dirs.pop() {
+            for entry in std::fs::read_dir(&dir)
+                .with_context(|| format!("Failed to read {}", dir.display()))?
+            {
+                let entry = entry?;
+                let file_type = entry.file_type()?;
+                let path = entry.path();
+                if file_type.is_dir() {
+                    dirs.push(path);
+                } else if file_type.is_file() {
+                    let content = std::fs::read(&path)
+                        .with_context(|| format!("Failed to read {}", path.display()))?;
+                    total += content.len() as u64;
+                    if total > MAX_WORKSPACE_BYTES {
+                        anyhow::bail!(
+                            "Workspace {} is too large to preview (over {} bytes)",
+                            root.display(),
+                            MAX_WORKSPACE_BYTES
+                        );
+                    }
+                    let relative = path.strip_prefix(root).unwrap_or(&path).to_path_buf();
+                    snapshot.files.insert(relative, content);
+                }
+            }
+        }
+        Ok(snapshot)
+    }
+
+    /// Number of files
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Whether there are no files
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Changes turning this snapshot into `after`, with paths under `root`
+    pub fn changes(&self, after: &WorkspaceSnapshot, root: &Path) -> Vec<Change> {
+        let display = |relative: &Path| root.join(relative).to_string_lossy().into_owned();
+
+        let mut changes = Vec::new();
+        for (relative, content) in &after.files {
+            match self.files.get(relative) {
+                None => changes.push(Change::file_create(
+                    display(relative),
+                    &String::from_utf8_lossy(content),
+                )),
+                Some(before) if before != content => changes.push(Change::file_edit(
+                    display(relative),
+                    &String::from_utf8_lossy(before),
+                    &String::from_utf8_lossy(content),
+                )),
+                Some(_) => {}
+            }
+        }
+        for (relative, content) in &self.files {
+            if !after.files.contains_key(relative) {
+                changes.push(Change::FileDelete {
+                    path: display(relative),
+                    size_bytes: content.len() as u64,
+                });
+            }
+        }
+        changes
+    }
+}
+
+/// Disposable copy of a workspace that actions can be previewed in
+#[derive(Debug)]
+pub struct Overlay {
+    /// The real workspace
+    workspace: PathBuf,
+
+    /// Root of the copy
+    root: PathBuf,
+
+    /// Workspace contents when the overlay was created
+    base: WorkspaceSnapshot,
+}
+
+impl Overlay {
+    /// Copy `workspace` into a new temporary directory
+    pub fn create(workspace: &Path) -> Result<Self> {
+        let base = WorkspaceSnapshot::capture(workspace)?;
+        let root =
+            std::env::temp_dir().join(format!("luminaguard-preview-{}", uuid::Uuid::new_v4()));
+        let overlay = Self {
+            workspace: workspace.to_path_buf(),
+            root,
+            base,
+        };
+
+        std::fs::create_dir_all(&overlay.root)
+            .with_context(|| format!("Failed to create {}", overlay.root.display()))?;
+        for (relative, content) in &overlay.base.files {
+            write_file(&overlay.root.join(relative), content)?;
+        }
+        Ok(overlay)
+    }
+
+    /// Root of the copy
+    pub fn path(&self) -> &Path {
+        &self.root
+    }
+
+    /// The real workspace
+    pub fn workspace(&self) -> &Path {
+        &self.workspace
+    }
+
+    /// Changes made in the overlay, with paths in the real workspace
+    pub fn changes(&self) -> Result<Vec<Change>> {
+        let after = WorkspaceSnapshot::capture(&self.root)?;
+        Ok(self.base.changes(&after, &self.workspace))
+    }
+
+    /// Point a path in the workspace at the overlay
+    ///
+    /// Paths are compared by component (`/ws` does not prefix `/wsx`); any
+    /// other string is returned unchanged.
+    pub fn rewrite(&self, s: &str) -> String {
+        match Path::new(s).strip_prefix(&self.workspace) {
+            Ok(rest) if rest.as_os_str().is_empty() => self.root.to_string_lossy().into_owned(),
+            Ok(rest) => self.root.join(rest).to_string_lossy().into_owned(),
+            Err(_) => s.to_string(),
+        }
+    }
+
+    /// Rewrite the paths in tool call arguments (see
+    /// [`argument_paths`] and [`rewrite`](Self::rewrite))
+    ///
+    /// Other values, such as file content, are left as they are.
+    pub fn rewrite_json(&self, value: &serde_json::Value) -> serde_json::Value {
+        replace_paths(value, &|s| self.rewrite(s))
+    }
+
+    /// Point overlay paths in a JSON value back at the real workspace
+    pub fn restore_json(&self, value: &serde_json::Value) -> serde_json::Value {
+        replace_json(value, &|s| {
+            s.replace(
+                &*self.root.to_string_lossy(),
+                &self.workspace.to_string_lossy(),
+            )
+        })
+    }
+
+    /// Apply the overlay's changes to the real workspace
+    ///
+    /// Returns the number of files written or removed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the workspace changed since the overlay was
+    /// created, or a file cannot be written.
+    pub fn commit(&self) -> Result<usize> {
+        if WorkspaceSnapshot::capture(&self.workspace)? != self.base {
+            anyhow::bail!(
+                "Workspace {} changed since the preview; not committing",
+                self.workspace.display()
+            );
+        }
+
+        let after = WorkspaceSnapshot::capture(&self.root)?;
+        let mut applied = 0;
+        for (relative, content) in &after.files {
+            if self.base.files.get(relative) != Some(content) {
+                write_file(&self.workspace.join(relative), content)?;
+                applied += 1;
+            }
+        }
+        for relative in self.base.files.keys() {
+            if !after.files.contains_key(relative) {
+                let path = self.workspace.join(relative);
+                std::fs::remove_file(&path)
+                    .with_context(|| format!("Failed to remove {}", path.display()))?;
+                applied += 1;
+            }
+        }
+        Ok(applied)
+    }
+}
+
+impl Drop for Overlay {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.root);
+    }
+}
+
+/// Apply `f` to the strings [`argument_paths`] collects
+fn replace_paths(value: &serde_json::Value, f: &dyn Fn(&str) -> String) -> serde_json::Value {
+    let replace = |item: &serde_json::Value| match item {
+        serde_json::Value::String(s) => serde_json::Value::String(f(s)),
+        other => other.clone(),
+    };
+    match value {
+        serde_json::Value::Object(map) => map
+            .iter()
+            .map(|(key, item)| {
+                let item = match item {
+                    serde_json::Value::String(_) if is_path_argument(key) => replace(item),
+                    serde_json::Value::Array(items) if is_path_argument(key) => {
+                        items.iter().map(replace).collect()
+                    }
+                    other => replace_paths(other, f),
+                };
+                (key.clone(), item)
+            })
+            .collect(),
+        serde_json::Value::Array(items) => {
+            items.iter().map(|item| replace_paths(item, f)).collect()
+        }
+        other => other.clone(),
+    }
+}
+
+/// Apply `f` to every string in a JSON value
+fn replace_json(value: &serde_json::Value, f: &dyn Fn(&str) -> String) -> serde_json::Value {
+    match value {
+        serde_json::Value::String(s) => serde_json::Value::String(f(s)),
+        serde_json::Value::Array(items) => items.iter().map(|item| replace_json(item, f)).collect(),
+        serde_json::Value::Object(map) => map
+            .iter()
+            .map(|(key, item)| (key.clone(), replace_json(item, f)))
+            .collect(),
+        other => other.clone(),
+    }
+}
+
+fn write_file(path: &Path, content: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    std::fs::write(path, content).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workspace() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("keep.txt"), "same\n").unwrap();
+        std::fs::write(dir.path().join("edit.txt"), "old\n").unwrap();
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        std::fs::write(dir.path().join("sub").join("gone.txt"), "bye").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_overlay_captures_changes() {
+        let dir = workspace();
+        let overlay = Overlay::create(dir.path()).unwrap();
+
+        std::fs::write(overlay.path().join("edit.txt"), "new\n").unwrap();
+        std::fs::write(overlay.path().join("sub").join("new.txt"), "hello").unwrap();
+        std::fs::remove_file(overlay.path().join("sub").join("gone.txt")).unwrap();
+
+        let changes = overlay.changes().unwrap();
+        assert_eq!(changes.len(), 3);
+        let path = |p: &str| dir.path().join(p).to_string_lossy().into_owned();
+        assert!(changes.contains(&Change::file_edit(path("edit.txt"), "old\n", "new\n")));
+        assert!(changes.contains(&Change::file_create(path("sub/new.txt"), "hello")));
+        assert!(changes.contains(&Change::FileDelete {
+            path: path("sub/gone.txt"),
+            size_bytes: 3,
+        }));
+
+        // The workspace itself is untouched until committed
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("edit.txt")).unwrap(),
+            "old\n"
+        );
+        assert_eq!(overlay.commit().unwrap(), 3);
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("edit.txt")).unwrap(),
+            "new\n"
+        );
+        assert!(!dir.path().join("sub").join("gone.txt").exists());
+
+        let root = overlay.path().to_path_buf();
+        drop(overlay);
+        assert!(!root.exists());
+    }
+
+    #[test]
+    fn test_commit_refused_if_workspace_changed() {
+        let dir = workspace();
+        let overlay = Overlay::create(dir.path()).unwrap();
+        std::fs::write(overlay.path().join("edit.txt"), "new\n").unwrap();
+        std::fs::write(dir.path().join("keep.txt"), "changed\n").unwrap();
+
+        assert!(overlay.commit().is_err());
+    }
+
+    #[test]
+    fn test_rewrite_paths() {
+        let dir = workspace();
+        let overlay = Overlay::create(dir.path()).unwrap();
+        let file = dir.path().join("edit.txt");
+        let args = serde_json::json!({"path": file.to_string_lossy(), "n": 1});
+
+        let rewritten = overlay.rewrite_json(&args);
+        assert_eq!(
+            rewritten["path"],
+            overlay.path().join("edit.txt").to_string_lossy().as_ref()
+        );
+        assert_eq!(rewritten["n"], 1);
+        assert_eq!(overlay.restore_json(&rewritten), args);
+    }
+
+    #[test]
+    fn test_rewrite_only_path_arguments() {
+        let dir = workspace();
+        let overlay = Overlay::create(dir.path()).unwrap();
+        let ws = dir.path().to_string_lossy().into_owned();
+        let sibling = format!("{}x/a.txt", ws);
+        let content = format!("Copied from {}/keep.txt", ws);
+        let args = serde_json::json!({
+            "path": format!("{}/edit.txt", ws),
+            "content": content,
+            "edits": [{"file": sibling, "newText": ws}],
+        });
+
+        let rewritten = overlay.rewrite_json(&args);
+        assert_eq!(
+            rewritten["path"],
+            overlay.path().join("edit.txt").to_string_lossy().as_ref()
+        );
+        // File content naming the workspace is written as given
+        assert_eq!(rewritten["content"], content);
+        assert_eq!(rewritten["edits"][0]["newText"], ws);
+        // A path merely sharing the workspace's prefix is not in it
+        assert_eq!(rewritten["edits"][0]["file"], sibling);
+        assert_eq!(overlay.rewrite(&ws), overlay.path().to_string_lossy());
+    }
+
+    #[test]
+    fn test_argument_paths() {
+        let args = serde_json::json!({
+            "path": "/a",
+            "content": "/not/a/path",
+            "edits": [{"file": "/b"}],
+            "paths": ["/c", "/d"],
+            "Destination": "/e",
+        });
+        let mut paths = argument_paths(&args);
+        paths.sort();
+        assert_eq!(paths, vec!["/a", "/b", "/c", "/d", "/e"]);
+    }
+
+    #[test]
+    fn test_only_confined_file_tools_previewable() {
+        let dir = workspace();
+        let config = PreviewConfig::new(dir.path());
+        let inside = |p: &str| dir.path().join(p).to_string_lossy().into_owned();
+
+        for (tool, args) in [
+            (
+                "write_file",
+                serde_json::json!({"path": inside("edit.txt")}),
+            ),
+            (
+                "write_file",
+                serde_json::json!({"path": inside("new/dir/x.txt")}),
+            ),
+            (
+                "delete_file",
+                serde_json::json!({"path": inside("sub/gone.txt")}),
+            ),
+            (
+                "edit_files",
+                serde_json::json!({"paths": [inside("keep.txt"), inside("k.txt")]}),
+            ),
+        ] {
+            assert!(
+                config.check_previewable(tool, &args).is_ok(),
+                "{tool} {args}"
+            );
+        }
+
+        let outside = tempfile::tempdir().unwrap();
+        let elsewhere = outside.path().join("x.txt").to_string_lossy().into_owned();
+        for (tool, args) in [
+            ("send_email", serde_json::json!({"to": "a@b.c"})),
+            ("write_file", serde_json::json!({"content": "x"})),
+            ("write_file", serde_json::json!({"path": "edit.txt"})),
+            ("write_file", serde_json::json!({"path": elsewhere})),
+            (
+                "write_file",
+                serde_json::json!({"path": inside("../x.txt")}),
+            ),
+            (
+                "write_file",
+                serde_json::json!({"path": inside("sub/../keep.txt")}),
+            ),
+            (
+                "edit_files",
+                serde_json::json!({"paths": [inside("keep.txt"), elsewhere]}),
+            ),
+        ] {
+            assert!(
+                config.check_previewable(tool, &args).is_err(),
+                "{tool} {args}"
+            );
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_out_of_workspace_not_previewable() {
+        let dir = workspace();
+        let outside = tempfile::tempdir().unwrap();
+        std::os::unix::fs::symlink(outside.path(), dir.path().join("link")).unwrap();
+        let config = PreviewConfig::new(dir.path());
+
+        let path = dir.path().join("link").join("x.txt");
+        let args = serde_json::json!({"path": path.to_string_lossy()});
+        assert!(config.check_previewable("write_file", &args).is_err());
+    }
+}
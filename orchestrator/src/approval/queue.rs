@@ -0,0 +1,678 @@
+//! Deferred Approval Queue
+//!
+//! A Red action the approver defers ("decide later") is parked in a
+//! [`DeferredQueue`] instead of being treated as denied. A file-backed queue
+//! is shared with the CLI, so deferred actions can be reviewed and decided
+//! later with `luminaguard approvals list|show|approve|deny`.
+//!
+//! When the same action is requested again (e.g. the agent retries a tool
+//! call), [`ApprovalManager`](super::ApprovalManager) consults the queue: a
+//! pending item stays pending without prompting again, and a decided item
+//! returns its decision and leaves the queue. Items not picked up before
+//! they expire (including decided ones) are marked expired, so a stale
+//! approval is never used.
+//!
+//! Decisions and expiries are reported once to the requesting session via
+//! [`DeferredQueue::take_notifications`].
+//!
+//! A decision is recorded as the record [`DeferredAction::record_header`]
+//! describes; the CLI signs it with the approver's local key, so it holds
+//! under a trust list.
+//!
+//! Actions waiting for multi-party approval (see [`quorum`](super::quorum))
+//! are queued too, so they are listed with the rest; they are decided by
+//! the votes, not with `luminaguard approvals approve|deny`.
+
+use super::action::ActionType;
+use super::diff::{Change, DiffCard};
+use super::history::ApprovalDecision;
+use super::signing::{RecordHeader, RecordSignature};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// How long a deferred action waits for a decision by default
+pub const DEFAULT_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// A Red action waiting in the queue
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeferredAction {
+    /// Unique ID (UUID)
+    pub id: String,
+
+    /// Action type being performed
+    pub action_type: ActionType,
+
+    /// Human-readable description of the action
+    pub description: String,
+
+    /// Changes the action will make
+    pub changes: Vec<Change>,
+
+    /// Tool call arguments (tool calls only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub arguments: Option<serde_json::Value>,
+
+    /// Session that requested the action
+    pub session_id: String,
+
+    /// User whose session requested the action
+    pub requested_by: String,
+
+    /// When the action was deferred
+    pub deferred_at: DateTime<Utc>,
+
+    /// When the item expires if not picked up
+    pub expires_at: DateTime<Utc>,
+
+    /// Where the item stands
+    pub status: DeferredStatus,
+
+    /// Pending multi-party approval whose votes decide the item
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quorum_id: Option<String>,
+
+    /// Whether the requesting session was told about the outcome
+    #[serde(default)]
+    pub notified: bool,
+}
+
+/// State of a deferred action
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum DeferredStatus {
+    /// Waiting for a decision
+    Pending,
+
+    /// Decided, waiting to be picked up by the requesting session
+    Decided {
+        /// The decision
+        decision: ApprovalDecision,
+        /// Who decided
+        decided_by: String,
+        /// Optional reason given by the approver
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        justification: Option<String>,
+        /// When the decision was made
+        decided_at: DateTime<Utc>,
+        /// The approver's signature over the decision
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        signature: Option<Box<RecordSignature>>,
+    },
+
+    /// Not decided or picked up in time
+    Expired,
+}
+
+impl DeferredStatus {
+    /// Short label: `pending`, `approved`, `denied` or `expired`
+    pub fn label(&self) -> &'static str {
+        match self {
+            DeferredStatus::Pending => "pending",
+            DeferredStatus::Decided { decision, .. } if decision.is_approved() => "approved",
+            DeferredStatus::Decided { .. } => "denied",
+            DeferredStatus::Expired => "expired",
+        }
+    }
+}
+
+impl DeferredAction {
+    /// Create a pending item (the queue sets the expiry when enqueued)
+    pub fn new(
+        action_type: ActionType,
+        description: impl Into<String>,
+        changes: Vec<Change>,
+        session_id: impl Into<String>,
+        requested_by: impl Into<String>,
+    ) -> Self {
+        let now = Utc::now();
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            action_type,
+            description: description.into(),
+            changes,
+            arguments: None,
+            session_id: session_id.into(),
+            requested_by: requested_by.into(),
+            deferred_at: now,
+            expires_at: now,
+            status: DeferredStatus::Pending,
+            quorum_id: None,
+            notified: false,
+        }
+    }
+
+    /// Attach tool call arguments
+    pub fn with_arguments(mut self, arguments: Option<serde_json::Value>) -> Self {
+        self.arguments = arguments;
+        self
+    }
+
+    /// Mark as waiting for the votes on a pending multi-party approval
+    pub fn with_quorum(mut self, quorum_id: impl Into<String>) -> Self {
+        self.quorum_id = Some(quorum_id.into());
+        self
+    }
+
+    /// Diff Card for reviewing the action
+    pub fn diff_card(&self) -> DiffCard {
+        DiffCard {
+            timestamp: self.deferred_at,
+            ..DiffCard::new(
+                self.action_type,
+                self.description.clone(),
+                self.changes.clone(),
+            )
+        }
+    }
+
+    /// Header of the record a decision on this item is recorded as
+    pub fn record_header(&self) -> RecordHeader {
+        RecordHeader {
+            id: self.id.clone(),
+            timestamp: self.deferred_at,
+            session_id: Some(self.session_id.clone()),
+            ..RecordHeader::for_card(&self.diff_card())
+        }
+    }
+
+    /// Whether this item is the same action requested by the same session
+    fn is_request(
+        &self,
+        action_type: ActionType,
+        description: &str,
+        changes: &[Change],
+        arguments: Option<&serde_json::Value>,
+        session_id: &str,
+    ) -> bool {
+        self.session_id == session_id
+            && self.action_type == action_type
+            && self.description == description
+            && self.changes == changes
+            && self.arguments.as_ref() == arguments
+    }
+}
+
+/// Persistent queue of deferred actions
+#[derive(Debug)]
+pub struct DeferredQueue {
+    path: Option<PathBuf>,
+    ttl: Duration,
+    items: Vec<DeferredAction>,
+}
+
+impl DeferredQueue {
+    /// Queue that is not persisted
+    pub fn in_memory() -> Self {
+        Self {
+            path: None,
+            ttl: DEFAULT_TTL,
+            items: Vec::new(),
+        }
+    }
+
+    /// Open (or create) a queue backed by a JSON file
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let mut queue = Self {
+            path: Some(path.into()),
+            ..Self::in_memory()
+        };
+        queue.reload()?;
+        Ok(queue)
+    }
+
+    /// Default queue location (`<data dir>/deferred.json`)
+    pub fn default_path() -> PathBuf {
+        super::data_dir().join("deferred.json")
+    }
+
+    /// Set how long new items wait for a decision
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// All items, after marking stale ones expired
+    pub fn list(&mut self) -> Result<&[DeferredAction]> {
+        let _lock = self.lock()?;
+        self.refresh()?;
+        Ok(&self.items)
+    }
+
+    /// Get an item by ID (or unique ID prefix)
+    pub fn get(&mut self, id: &str) -> Result<Option<DeferredAction>> {
+        let _lock = self.lock()?;
+        self.refresh()?;
+        Ok(self.position(id)?.map(|i| self.items[i].clone()))
+    }
+
+    /// Park an action until it is decided; returns the stored item
+    pub fn enqueue(&mut self, mut item: DeferredAction) -> Result<DeferredAction> {
+        let _lock = self.lock()?;
+        self.reload()?;
+        item.expires_at = item.deferred_at
+            + chrono::Duration::from_std(self.ttl).context("Deferred queue TTL too large")?;
+        self.items.push(item.clone());
+        self.save()?;
+        Ok(item)
+    }
+
+    /// Decide a pending item by ID (or unique ID prefix)
+    ///
+    /// `signature` is the approver's, over the item's
+    /// [`record_header`](DeferredAction::record_header).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no pending item matches, the item waits for a
+    /// quorum, or `decision` is not a final decision.
+    pub fn decide(
+        &mut self,
+        id: &str,
+        decision: ApprovalDecision,
+        decided_by: impl Into<String>,
+        justification: Option<String>,
+        signature: Option<RecordSignature>,
+    ) -> Result<DeferredAction> {
+        if decision == ApprovalDecision::DeferredToLater {
+            anyhow::bail!("A deferred action cannot be deferred again");
+        }
+
+        let _lock = self.lock()?;
+        self.refresh()?;
+        let index = self
+            .position(id)?
+            .with_context(|| format!("No deferred action with ID {}", id))?;
+        let item = &mut self.items[index];
+        if item.status != DeferredStatus::Pending {
+            anyhow::bail!(
+                "Deferred action {} is already {}",
+                item.id,
+                item.status.label()
+            );
+        }
+        if let Some(quorum_id) = &item.quorum_id {
+            anyhow::bail!(
+                "Deferred action {} is decided by the votes on pending approval {}",
+                item.id,
+                quorum_id
+            );
+        }
+
+        item.status = DeferredStatus::Decided {
+            decision,
+            decided_by: decided_by.into(),
+            justification,
+            decided_at: Utc::now(),
+            signature: signature.map(Box::new),
+        };
+        let item = item.clone();
+        self.save()?;
+        Ok(item)
+    }
+
+    /// Look up a queued request for an action
+    ///
+    /// A pending item is returned and stays queued. Decided and expired
+    /// items are removed from the queue and returned.
+    pub fn claim(
+        &mut self,
+        action_type: ActionType,
+        description: &str,
+        changes: &[Change],
+        arguments: Option<&serde_json::Value>,
+        session_id: &str,
+    ) -> Result<Option<DeferredAction>> {
+        let _lock = self.lock()?;
+        self.refresh()?;
+        let Some(index) = self.items.iter().position(|i| {
+            i.quorum_id.is_none()
+                && i.is_request(action_type, description, changes, arguments, session_id)
+        }) else {
+            return Ok(None);
+        };
+
+        if self.items[index].status == DeferredStatus::Pending {
+            return Ok(Some(self.items[index].clone()));
+        }
+        let item = self.items.remove(index);
+        self.save()?;
+        Ok(Some(item))
+    }
+
+    /// Pending item waiting for the votes on a multi-party approval
+    pub fn quorum_item(&mut self, quorum_id: &str) -> Result<Option<DeferredAction>> {
+        let _lock = self.lock()?;
+        self.refresh()?;
+        Ok(self
+            .items
+            .iter()
+            .find(|i| {
+                i.quorum_id.as_deref() == Some(quorum_id) && i.status == DeferredStatus::Pending
+            })
+            .cloned())
+    }
+
+    /// Remove an item by ID (e.g. once its quorum is decided)
+    pub fn withdraw(&mut self, id: &str) -> Result<Option<DeferredAction>> {
+        let _lock = self.lock()?;
+        self.reload()?;
+        let Some(index) = self.items.iter().position(|i| i.id == id) else {
+            return Ok(None);
+        };
+        let item = self.items.remove(index);
+        self.save()?;
+        Ok(Some(item))
+    }
+
+    /// Decided and expired items a session has not been told about yet
+    ///
+    /// Each item is returned once. Expired items are dropped from the queue
+    /// once reported.
+    pub fn take_notifications(&mut self, session_id: &str) -> Result<Vec<DeferredAction>> {
+        let _lock = self.lock()?;
+        self.refresh()?;
+        let mut taken = Vec::new();
+        for item in &mut self.items {
+            if item.session_id == session_id
+                && !item.notified
+                && item.status != DeferredStatus::Pending
+            {
+                item.notified = true;
+                taken.push(item.clone());
+            }
+        }
+
+        if !taken.is_empty() {
+            self.items
+                .retain(|i| !(i.notified && i.status == DeferredStatus::Expired));
+            self.save()?;
+        }
+        Ok(taken)
+    }
+
+    /// Reload the items and mark stale ones expired (with the store locked)
+    fn refresh(&mut self) -> Result<()> {
+        self.reload()?;
+        if self.expire_stale() > 0 {
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    /// Lock the backing file (if any) until the guard is dropped
+    fn lock(&self) -> Result<Option<File>> {
+        self.path.as_deref().map(super::lock_store).transpose()
+    }
+
+    /// Mark items past their expiry as expired
+    fn expire_stale(&mut self) -> usize {
+        let now = Utc::now();
+        let mut expired = 0;
+        for item in &mut self.items {
+            if item.status != DeferredStatus::Expired && item.expires_at <= now {
+                item.status = DeferredStatus::Expired;
+                item.notified = false;
+                expired += 1;
+            }
+        }
+        expired
+    }
+
+    /// Index of the item with this ID (or unique ID prefix)
+    fn position(&self, id: &str) -> Result<Option<usize>> {
+        let matching: Vec<usize> = self
+            .items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| item.id.starts_with(id))
+            .map(|(i, _)| i)
+            .collect();
+
+        match matching.as_slice() {
+            [] => Ok(None),
+            [index] => Ok(Some(*index)),
+            _ => anyhow::bail!("Deferred action ID prefix '{}' is ambiguous", id),
+        }
+    }
+
+    fn reload(&mut self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        self.items = if path.exists() {
+            let json = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read deferred queue {}", path.display()))?;
+            serde_json::from_str(&json)
+                .with_context(|| format!("Failed to parse deferred queue {}", path.display()))?
+        } else {
+            Vec::new()
+        };
+        Ok(())
+    }
+
+    fn save(&self) -> Result<()> {
+        match &self.path {
+            Some(path) => super::write_json_atomic(path, &self.items),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Default for DeferredQueue {
+    fn default() -> Self {
+        Self::in_memory()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn delete(path: &str) -> Vec<Change> {
+        vec![Change::FileDelete {
+            path: path.to_string(),
+            size_bytes: 1,
+        }]
+    }
+
+    fn deferred(path: &str) -> DeferredAction {
+        DeferredAction::new(
+            ActionType::DeleteFile,
+            format!("Delete {}", path),
+            delete(path),
+            "session-1",
+            "alice",
+        )
+    }
+
+    #[test]
+    fn test_pending_item_stays_until_decided() {
+        let mut queue = DeferredQueue::in_memory();
+        let item = queue.enqueue(deferred("/tmp/a")).unwrap();
+        assert!(item.expires_at > item.deferred_at);
+
+        let claimed = queue
+            .claim(
+                ActionType::DeleteFile,
+                "Delete /tmp/a",
+                &delete("/tmp/a"),
+                None,
+                "session-1",
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(claimed.status, DeferredStatus::Pending);
+        assert_eq!(queue.list().unwrap().len(), 1);
+
+        // Other sessions and other actions do not match
+        assert!(queue
+            .claim(
+                ActionType::DeleteFile,
+                "Delete /tmp/a",
+                &delete("/tmp/a"),
+                None,
+                "session-2",
+            )
+            .unwrap()
+            .is_none());
+        assert!(queue
+            .claim(
+                ActionType::DeleteFile,
+                "Delete /tmp/a",
+                &delete("/etc/passwd"),
+                None,
+                "session-1",
+            )
+            .unwrap()
+            .is_none());
+
+        queue
+            .decide(&item.id[..8], ApprovalDecision::Approved, "bob", None, None)
+            .unwrap();
+        let claimed = queue
+            .claim(
+                ActionType::DeleteFile,
+                "Delete /tmp/a",
+                &delete("/tmp/a"),
+                None,
+                "session-1",
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(claimed.status.label(), "approved");
+        assert!(queue.list().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_decide_rejects_non_pending() {
+        let mut queue = DeferredQueue::in_memory();
+        let item = queue.enqueue(deferred("/tmp/a")).unwrap();
+
+        assert!(queue
+            .decide(
+                &item.id,
+                ApprovalDecision::DeferredToLater,
+                "bob",
+                None,
+                None
+            )
+            .is_err());
+        queue
+            .decide(&item.id, ApprovalDecision::Denied, "bob", None, None)
+            .unwrap();
+        assert!(queue
+            .decide(&item.id, ApprovalDecision::Approved, "bob", None, None)
+            .is_err());
+        assert!(queue
+            .decide("missing", ApprovalDecision::Approved, "bob", None, None)
+            .is_err());
+    }
+
+    #[test]
+    fn test_quorum_items_decided_by_votes() {
+        let mut queue = DeferredQueue::in_memory();
+        let item = queue
+            .enqueue(deferred("/tmp/a").with_quorum("quorum-1"))
+            .unwrap();
+
+        // Neither decided by hand nor claimed as a plain deferral
+        assert!(queue
+            .decide(&item.id, ApprovalDecision::Approved, "bob", None, None)
+            .is_err());
+        assert!(queue
+            .claim(
+                ActionType::DeleteFile,
+                "Delete /tmp/a",
+                &delete("/tmp/a"),
+                None,
+                "session-1",
+            )
+            .unwrap()
+            .is_none());
+
+        assert_eq!(queue.quorum_item("quorum-1").unwrap(), Some(item.clone()));
+        assert!(queue.quorum_item("quorum-2").unwrap().is_none());
+        assert_eq!(queue.withdraw(&item.id).unwrap(), Some(item));
+        assert!(queue.list().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_stale_items_expire() {
+        let mut queue = DeferredQueue::in_memory().with_ttl(Duration::ZERO);
+        let item = queue.enqueue(deferred("/tmp/a")).unwrap();
+
+        assert_eq!(queue.list().unwrap()[0].status, DeferredStatus::Expired);
+        assert!(queue
+            .decide(&item.id, ApprovalDecision::Approved, "bob", None, None)
+            .is_err());
+
+        // Reported once, then dropped
+        let notifications = queue.take_notifications("session-1").unwrap();
+        assert_eq!(notifications.len(), 1);
+        assert_eq!(notifications[0].status.label(), "expired");
+        assert!(queue.take_notifications("session-1").unwrap().is_empty());
+        assert!(queue.list().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_notifications_once_per_decision() {
+        let mut queue = DeferredQueue::in_memory();
+        let item = queue.enqueue(deferred("/tmp/a")).unwrap();
+        assert!(queue.take_notifications("session-1").unwrap().is_empty());
+
+        queue
+            .decide(
+                &item.id,
+                ApprovalDecision::Denied,
+                "bob",
+                Some("not now".to_string()),
+                None,
+            )
+            .unwrap();
+        assert!(queue.take_notifications("session-2").unwrap().is_empty());
+        let notifications = queue.take_notifications("session-1").unwrap();
+        assert_eq!(notifications.len(), 1);
+        assert_eq!(notifications[0].status.label(), "denied");
+        assert!(queue.take_notifications("session-1").unwrap().is_empty());
+
+        // Still claimable after the notification
+        assert_eq!(queue.list().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_file_queue_shared_with_cli() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("deferred.json");
+        let mut orchestrator = DeferredQueue::open(&path).unwrap();
+        let item = orchestrator.enqueue(deferred("/tmp/a")).unwrap();
+
+        let mut cli = DeferredQueue::open(&path).unwrap();
+        assert_eq!(
+            cli.get(&item.id).unwrap().unwrap().description,
+            "Delete /tmp/a"
+        );
+        cli.decide(&item.id, ApprovalDecision::Approved, "bob", None, None)
+            .unwrap();
+
+        let claimed = orchestrator
+            .claim(
+                ActionType::DeleteFile,
+                "Delete /tmp/a",
+                &delete("/tmp/a"),
+                None,
+                "session-1",
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(claimed.status.label(), "approved");
+        assert!(DeferredQueue::open(&path)
+            .unwrap()
+            .list()
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,702 @@
+//! Multi-Party (N-of-M) Approvals
+//!
+//! Critical actions can require sign-off from several members of an
+//! approver group instead of a single user:
+//! - [`ApproverGroup`]: named set of approvers
+//! - [`QuorumRule`]: which actions need how many approvals from which group
+//! - [`PendingQuorum`]: votes collected so far for one action
+//! - [`QuorumStore`]: pending approvals, persisted to disk so votes survive
+//!   orchestrator restarts
+//!
+//! Separation of duties: the session owner may vote, but a quorum is never
+//! reached on the session owner's approval alone.
+//!
+//! Quorum rules need a trust list: [`ApprovalManager`](super::ApprovalManager)
+//! only counts votes signed by the voter with a trusted key, and one key
+//! cannot vote twice under different names. While a quorum is pending, the
+//! action waits in the deferred queue.
+
+use super::action::{ActionType, RiskLevel};
+use super::backend::BackendDecision;
+use super::diff::{Change, DiffCard};
+use super::history::ApprovalDecision;
+use super::signing::{RecordHeader, RecordSignature};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::path::PathBuf;
+use tracing::debug;
+
+/// Named set of approvers
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApproverGroup {
+    /// Group name (referenced by rules)
+    pub name: String,
+
+    /// Usernames allowed to vote
+    pub members: Vec<String>,
+}
+
+impl ApproverGroup {
+    /// Create a group
+    pub fn new<I, S>(name: impl Into<String>, members: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            name: name.into(),
+            members: members.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Check whether a user belongs to the group
+    pub fn contains(&self, user: &str) -> bool {
+        self.members.iter().any(|m| m == user)
+    }
+}
+
+/// Approvals required for actions at or above a risk level
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuorumRule {
+    /// Lowest risk level the rule applies to
+    pub min_risk: RiskLevel,
+
+    /// Group whose members may vote
+    pub group: String,
+
+    /// Number of approvals required (N)
+    pub required: usize,
+}
+
+impl QuorumRule {
+    /// Create a rule
+    pub fn new(min_risk: RiskLevel, group: impl Into<String>, required: usize) -> Self {
+        Self {
+            min_risk,
+            group: group.into(),
+            required,
+        }
+    }
+}
+
+/// Approver groups and quorum rules
+///
+/// With no rules, every Red action needs a single approval.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuorumConfig {
+    /// Approver groups
+    #[serde(default)]
+    pub groups: Vec<ApproverGroup>,
+
+    /// Quorum rules
+    #[serde(default)]
+    pub rules: Vec<QuorumRule>,
+}
+
+impl QuorumConfig {
+    /// Create an empty configuration
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an approver group
+    pub fn with_group(mut self, group: ApproverGroup) -> Self {
+        self.groups.push(group);
+        self
+    }
+
+    /// Add a quorum rule
+    pub fn with_rule(mut self, rule: QuorumRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Look up a group by name
+    pub fn group(&self, name: &str) -> Option<&ApproverGroup> {
+        self.groups.iter().find(|g| g.name == name)
+    }
+
+    /// Strictest rule that applies to an action at `risk` (the Diff Card's
+    /// assessed risk level), if any
+    pub fn rule_for(&self, risk: RiskLevel) -> Option<&QuorumRule> {
+        self.rules
+            .iter()
+            .filter(|r| risk >= r.min_risk)
+            .max_by_key(|r| r.required)
+    }
+
+    /// Check that every rule can be satisfied
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a rule references an unknown group, requires
+    /// no approvals, or requires more approvals than the group has members.
+    pub fn validate(&self) -> Result<()> {
+        for rule in &self.rules {
+            let group = self.group(&rule.group).with_context(|| {
+                format!("Quorum rule references unknown group '{}'", rule.group)
+            })?;
+            if rule.required == 0 {
+                anyhow::bail!(
+                    "Quorum rule for group '{}' requires 0 approvals",
+                    rule.group
+                );
+            }
+            if rule.required > group.members.len() {
+                anyhow::bail!(
+                    "Quorum rule requires {} approvals but group '{}' has {} members",
+                    rule.required,
+                    rule.group,
+                    group.members.len()
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A single approver's vote
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Vote {
+    /// Who voted
+    pub approver: String,
+
+    /// Their decision
+    pub decision: ApprovalDecision,
+
+    /// Optional reason
+    pub justification: Option<String>,
+
+    /// When the vote was cast (UTC)
+    pub timestamp: DateTime<Utc>,
+
+    /// The voter's signature (moved to the record once the vote is decided)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<RecordSignature>,
+}
+
+impl Vote {
+    /// Create a vote cast now
+    pub fn new(approver: impl Into<String>, decision: ApprovalDecision) -> Self {
+        Self {
+            approver: approver.into(),
+            decision,
+            justification: None,
+            timestamp: Utc::now(),
+            signature: None,
+        }
+    }
+
+    /// Attach the voter's signature
+    pub fn with_signature(mut self, signature: RecordSignature) -> Self {
+        self.signature = Some(signature);
+        self
+    }
+
+    /// Key the vote was signed with (hex)
+    fn public_key(&self) -> Option<&str> {
+        self.signature.as_ref().map(|s| s.public_key.as_str())
+    }
+
+    /// Attach a justification
+    pub fn with_justification(mut self, justification: impl Into<String>) -> Self {
+        self.justification = Some(justification.into());
+        self
+    }
+}
+
+impl From<BackendDecision> for Vote {
+    fn from(decision: BackendDecision) -> Self {
+        Self {
+            approver: decision.approved_by,
+            decision: decision.decision,
+            justification: decision.justification,
+            timestamp: Utc::now(),
+            signature: decision.signature,
+        }
+    }
+}
+
+/// State of a multi-party approval
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuorumStatus {
+    /// Still collecting votes
+    Pending {
+        /// Approvals so far
+        approvals: usize,
+        /// Approvals required
+        required: usize,
+    },
+
+    /// Quorum reached
+    Approved,
+
+    /// Quorum can no longer be reached
+    Denied,
+}
+
+/// Votes collected for one action
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingQuorum {
+    /// Unique ID
+    pub id: String,
+
+    /// Action awaiting approval
+    pub action_type: ActionType,
+
+    /// Action description
+    pub description: String,
+
+    /// What the action does
+    #[serde(default)]
+    pub changes: Vec<Change>,
+
+    /// Tool call arguments, for tool calls
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub arguments: Option<serde_json::Value>,
+
+    /// User whose session requested the action
+    pub session_owner: String,
+
+    /// Rule being applied
+    pub rule: QuorumRule,
+
+    /// Group members at the time the request was opened
+    pub eligible: Vec<String>,
+
+    /// Votes cast so far
+    pub votes: Vec<Vote>,
+
+    /// When the request was opened (UTC)
+    pub created_at: DateTime<Utc>,
+
+    /// Record the outcome will be recorded as (what voters sign)
+    #[serde(default)]
+    pub record: RecordHeader,
+}
+
+impl PendingQuorum {
+    /// Current state of the vote
+    pub fn status(&self) -> QuorumStatus {
+        let approvers = self.approvers();
+        let approvals = approvers.len();
+        let independent = approvers.iter().any(|a| *a != self.session_owner);
+
+        if approvals >= self.rule.required && independent {
+            return QuorumStatus::Approved;
+        }
+
+        // Deny once the outstanding voters can no longer make up the quorum
+        let undecided: Vec<&String> = self
+            .eligible
+            .iter()
+            .filter(|m| !self.has_voted(m))
+            .collect();
+        let reachable = approvals + undecided.len() >= self.rule.required
+            && (independent || undecided.iter().any(|m| **m != self.session_owner));
+
+        if reachable {
+            QuorumStatus::Pending {
+                approvals,
+                required: self.rule.required,
+            }
+        } else {
+            QuorumStatus::Denied
+        }
+    }
+
+    /// Record a vote
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the vote is already decided, the approver is not
+    /// in the group, or the approver (or their signing key, under another
+    /// name) has already voted.
+    pub fn cast_vote(&mut self, vote: Vote) -> Result<QuorumStatus> {
+        if !matches!(self.status(), QuorumStatus::Pending { .. }) {
+            anyhow::bail!("Approval {} is already decided", self.id);
+        }
+        if !self.eligible.contains(&vote.approver) {
+            anyhow::bail!(
+                "{} is not in approver group '{}'",
+                vote.approver,
+                self.rule.group
+            );
+        }
+        if self.has_voted(&vote.approver) {
+            anyhow::bail!("{} has already voted on {}", vote.approver, self.id);
+        }
+        if let Some(key) = vote.public_key() {
+            if self
+                .votes
+                .iter()
+                .any(|v| v.public_key().is_some_and(|k| k.eq_ignore_ascii_case(key)))
+            {
+                anyhow::bail!("Key of {} has already voted on {}", vote.approver, self.id);
+            }
+        }
+
+        debug!(
+            "Vote on {} by {}: {}",
+            self.id, vote.approver, vote.decision
+        );
+        self.votes.push(vote);
+        Ok(self.status())
+    }
+
+    /// Users who approved
+    pub fn approvers(&self) -> Vec<&str> {
+        self.votes
+            .iter()
+            .filter(|v| v.decision == ApprovalDecision::Approved)
+            .map(|v| v.approver.as_str())
+            .collect()
+    }
+
+    fn has_voted(&self, user: &str) -> bool {
+        self.votes.iter().any(|v| v.approver == user)
+    }
+}
+
+/// Pending multi-party approvals, optionally persisted to a JSON file
+#[derive(Debug, Default)]
+pub struct QuorumStore {
+    path: Option<PathBuf>,
+    pending: Vec<PendingQuorum>,
+}
+
+impl QuorumStore {
+    /// Store that is not persisted
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    /// Open (or create) a store backed by a JSON file
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let mut store = Self {
+            path: Some(path.into()),
+            pending: Vec::new(),
+        };
+        store.reload()?;
+        Ok(store)
+    }
+
+    /// All pending approvals
+    pub fn pending(&self) -> &[PendingQuorum] {
+        &self.pending
+    }
+
+    /// Look up a pending approval
+    pub fn get(&self, id: &str) -> Option<&PendingQuorum> {
+        self.pending.iter().find(|p| p.id == id)
+    }
+
+    /// Open a pending approval for the action a Diff Card describes
+    ///
+    /// If the same session already has one open for the same action, with
+    /// the same changes and arguments (e.g. before a restart), it is reused
+    /// so earlier votes still count, along with its `record` header (which
+    /// votes are signed over).
+    pub fn open_request(
+        &mut self,
+        config: &QuorumConfig,
+        rule: &QuorumRule,
+        card: &DiffCard,
+        arguments: Option<&serde_json::Value>,
+        session_owner: &str,
+        record: RecordHeader,
+    ) -> Result<String> {
+        let _lock = self.lock()?;
+        self.reload()?;
+        if let Some(existing) = self.pending.iter().find(|p| {
+            p.action_type == card.action_type
+                && p.description == card.description
+                && p.changes == card.changes
+                && p.arguments.as_ref() == arguments
+                && p.session_owner == session_owner
+        }) {
+            return Ok(existing.id.clone());
+        }
+
+        let group = config
+            .group(&rule.group)
+            .with_context(|| format!("Unknown approver group '{}'", rule.group))?;
+
+        let pending = PendingQuorum {
+            id: uuid::Uuid::new_v4().to_string(),
+            action_type: card.action_type,
+            description: card.description.clone(),
+            changes: card.changes.clone(),
+            arguments: arguments.cloned(),
+            session_owner: session_owner.to_string(),
+            rule: rule.clone(),
+            eligible: group.members.clone(),
+            votes: Vec::new(),
+            created_at: Utc::now(),
+            record,
+        };
+        let id = pending.id.clone();
+        self.pending.push(pending);
+        self.save()?;
+        Ok(id)
+    }
+
+    /// Record a vote and persist it
+    pub fn vote(&mut self, id: &str, vote: Vote) -> Result<QuorumStatus> {
+        let _lock = self.lock()?;
+        self.reload()?;
+        let pending = self
+            .pending
+            .iter_mut()
+            .find(|p| p.id == id)
+            .with_context(|| format!("No pending approval with id {}", id))?;
+        let status = pending.cast_vote(vote)?;
+        self.save()?;
+        Ok(status)
+    }
+
+    /// Remove a pending approval once it is decided
+    pub fn resolve(&mut self, id: &str) -> Result<Option<PendingQuorum>> {
+        let _lock = self.lock()?;
+        self.reload()?;
+        let Some(index) = self.pending.iter().position(|p| p.id == id) else {
+            return Ok(None);
+        };
+        let pending = self.pending.remove(index);
+        self.save()?;
+        Ok(Some(pending))
+    }
+
+    /// Lock the backing file (if any) until the guard is dropped
+    fn lock(&self) -> Result<Option<File>> {
+        self.path.as_deref().map(super::lock_store).transpose()
+    }
+
+    /// Re-read the store, so changes made by other processes apply
+    fn reload(&mut self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        self.pending = if path.exists() {
+            let json = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read quorum store {}", path.display()))?;
+            serde_json::from_str(&json)
+                .with_context(|| format!("Failed to parse quorum store {}", path.display()))?
+        } else {
+            Vec::new()
+        };
+        Ok(())
+    }
+
+    /// Write the store to disk
+    fn save(&self) -> Result<()> {
+        match &self.path {
+            Some(path) => super::write_json_atomic(path, &self.pending),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> QuorumConfig {
+        QuorumConfig::new()
+            .with_group(ApproverGroup::new("finance", ["alice", "bob", "carol"]))
+            .with_rule(QuorumRule::new(RiskLevel::Critical, "finance", 2))
+    }
+
+    fn open(store: &mut QuorumStore, owner: &str) -> String {
+        let config = config();
+        let rule = config.rule_for(RiskLevel::Critical).unwrap().clone();
+        store
+            .open_request(
+                &config,
+                &rule,
+                &DiffCard::new(ActionType::TransferAsset, "Send 1 BTC".to_string(), vec![]),
+                None,
+                owner,
+                RecordHeader::default(),
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn test_rule_for_risk_level() {
+        let config = config().with_rule(QuorumRule::new(RiskLevel::High, "finance", 1));
+
+        assert_eq!(config.rule_for(RiskLevel::Critical).unwrap().required, 2);
+        assert_eq!(config.rule_for(RiskLevel::High).unwrap().required, 1);
+        assert!(config.rule_for(RiskLevel::Medium).is_none());
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(config().validate().is_ok());
+        assert!(config()
+            .with_rule(QuorumRule::new(RiskLevel::High, "ops", 1))
+            .validate()
+            .is_err());
+        assert!(config()
+            .with_rule(QuorumRule::new(RiskLevel::High, "finance", 4))
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn test_two_of_three() {
+        let mut store = QuorumStore::in_memory();
+        let id = open(&mut store, "dave");
+
+        let status = store
+            .vote(&id, Vote::new("alice", ApprovalDecision::Approved))
+            .unwrap();
+        assert_eq!(
+            status,
+            QuorumStatus::Pending {
+                approvals: 1,
+                required: 2
+            }
+        );
+
+        // A single denial does not block 2-of-3
+        let status = store
+            .vote(&id, Vote::new("bob", ApprovalDecision::Denied))
+            .unwrap();
+        assert!(matches!(status, QuorumStatus::Pending { .. }));
+
+        let status = store
+            .vote(&id, Vote::new("carol", ApprovalDecision::Approved))
+            .unwrap();
+        assert_eq!(status, QuorumStatus::Approved);
+    }
+
+    #[test]
+    fn test_denied_when_unreachable() {
+        let mut store = QuorumStore::in_memory();
+        let id = open(&mut store, "dave");
+
+        store
+            .vote(&id, Vote::new("alice", ApprovalDecision::Denied))
+            .unwrap();
+        let status = store
+            .vote(&id, Vote::new("bob", ApprovalDecision::DeferredToLater))
+            .unwrap();
+
+        assert_eq!(status, QuorumStatus::Denied);
+    }
+
+    #[test]
+    fn test_rejects_outsiders_and_double_votes() {
+        let mut store = QuorumStore::in_memory();
+        let id = open(&mut store, "dave");
+
+        let signed = |approver: &str| {
+            Vote::new(approver, ApprovalDecision::Approved).with_signature(RecordSignature {
+                signer: approver.to_string(),
+                public_key: "aa".to_string(),
+                plan: Vec::new(),
+                steps: None,
+                modifications: Vec::new(),
+                grant: None,
+                rule: None,
+                signature: "00".to_string(),
+            })
+        };
+
+        assert!(store
+            .vote(&id, Vote::new("mallory", ApprovalDecision::Approved))
+            .is_err());
+        store.vote(&id, signed("alice")).unwrap();
+        assert!(store
+            .vote(&id, Vote::new("alice", ApprovalDecision::Approved))
+            .is_err());
+
+        // Same key under another name
+        assert!(store.vote(&id, signed("bob")).is_err());
+        assert_eq!(store.get(&id).unwrap().votes.len(), 1);
+    }
+
+    #[test]
+    fn test_separation_of_duties() {
+        let config = QuorumConfig::new()
+            .with_group(ApproverGroup::new("ops", ["alice", "bob"]))
+            .with_rule(QuorumRule::new(RiskLevel::Critical, "ops", 1));
+        let rule = config.rule_for(RiskLevel::Critical).unwrap().clone();
+        let mut store = QuorumStore::in_memory();
+        let id = store
+            .open_request(
+                &config,
+                &rule,
+                &DiffCard::new(ActionType::DeleteFile, "rm -rf".to_string(), vec![]),
+                None,
+                "alice",
+                RecordHeader::default(),
+            )
+            .unwrap();
+
+        // The owner's approval alone is not enough
+        let status = store
+            .vote(&id, Vote::new("alice", ApprovalDecision::Approved))
+            .unwrap();
+        assert!(matches!(status, QuorumStatus::Pending { .. }));
+
+        let status = store
+            .vote(&id, Vote::new("bob", ApprovalDecision::Approved))
+            .unwrap();
+        assert_eq!(status, QuorumStatus::Approved);
+    }
+
+    #[test]
+    fn test_owner_only_group_is_denied() {
+        let config = QuorumConfig::new()
+            .with_group(ApproverGroup::new("solo", ["alice"]))
+            .with_rule(QuorumRule::new(RiskLevel::Critical, "solo", 1));
+        let rule = config.rule_for(RiskLevel::Critical).unwrap().clone();
+        let mut store = QuorumStore::in_memory();
+        let id = store
+            .open_request(
+                &config,
+                &rule,
+                &DiffCard::new(ActionType::DeleteFile, "rm -rf".to_string(), vec![]),
+                None,
+                "alice",
+                RecordHeader::default(),
+            )
+            .unwrap();
+
+        assert_eq!(store.get(&id).unwrap().status(), QuorumStatus::Denied);
+    }
+
+    #[test]
+    fn test_persists_across_restarts() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("approvals/pending.json");
+
+        let id = {
+            let mut store = QuorumStore::open(&path).unwrap();
+            let id = open(&mut store, "dave");
+            store
+                .vote(&id, Vote::new("alice", ApprovalDecision::Approved))
+                .unwrap();
+            id
+        };
+
+        let mut store = QuorumStore::open(&path).unwrap();
+        assert_eq!(store.pending().len(), 1);
+        // Reopening the same action resumes the existing request
+        assert_eq!(open(&mut store, "dave"), id);
+
+        let status = store
+            .vote(&id, Vote::new("bob", ApprovalDecision::Approved))
+            .unwrap();
+        assert_eq!(status, QuorumStatus::Approved);
+
+        let resolved = store.resolve(&id).unwrap().unwrap();
+        assert_eq!(resolved.approvers(), vec!["alice", "bob"]);
+        assert!(QuorumStore::open(&path).unwrap().pending().is_empty());
+    }
+}
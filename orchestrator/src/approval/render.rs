@@ -0,0 +1,421 @@
+//! Diff Card Rendering for Web and Chat Channels
+//!
+//! [`DiffCard::to_human_readable`] targets terminals. This module adds:
+//! - [`DiffCard::to_markdown`] for chat channels; diffs and long content are
+//!   fenced code blocks inside `<details>` (collapsible where supported)
+//! - [`DiffCard::to_html`], a self-contained page (inline styles, no scripts
+//!   or external resources), and [`DiffCard::to_html_fragment`] for
+//!   embedding in an existing page
+//!
+//! The risk level is color-coded. All card content is escaped, so a card
+//! built from agent-supplied text cannot inject markup.
+
+use super::action::RiskLevel;
+use super::diff::{risk_badge, Change, DiffCard};
+use super::line_diff::{DiffLineKind, UnifiedDiff};
+use std::fmt::Write as _;
+
+/// Background and text colors for a risk level (CSS)
+pub fn risk_colors(level: RiskLevel) -> (&'static str, &'static str) {
+    match level {
+        RiskLevel::None => ("#2e7d32", "#fff"),
+        RiskLevel::Low => ("#f9a825", "#000"),
+        RiskLevel::Medium => ("#ef6c00", "#fff"),
+        RiskLevel::High => ("#c62828", "#fff"),
+        RiskLevel::Critical => ("#6a0000", "#fff"),
+    }
+}
+
+/// Extra detail shown under a change's summary
+enum Detail<'a> {
+    /// Short labeled value
+    Field(&'static str, String),
+
+    /// Longer content, collapsed by default (label, content, code language)
+    Block(&'static str, &'a str, &'static str),
+
+    /// Line-level diff, collapsed by default
+    Diff(&'a UnifiedDiff),
+}
+
+/// Details for a change (the same information as the CLI output)
+fn details(change: &Change) -> Vec<Detail<'_>> {
+    match change {
+        Change::FileCreate {
+            content_preview, ..
+        } => vec![Detail::Block("Content", content_preview, "")],
+        Change::FileEdit {
+            diff: Some(diff), ..
+        } => vec![Detail::Diff(diff)],
+        Change::FileEdit { before, after, .. } => vec![
+            Detail::Block("Before", before, ""),
+            Detail::Block("After", after, ""),
+        ],
+        Change::FileDelete { size_bytes, .. } => {
+            vec![Detail::Field("Size", format!("{} bytes", size_bytes))]
+        }
+        Change::CommandExec { args, .. } if !args.is_empty() => {
+            vec![Detail::Field("Args", args.join(" "))]
+        }
+        Change::EmailSend { preview, .. } => vec![Detail::Block("Message", preview, "")],
+        Change::ExternalCall {
+            payload_preview, ..
+        } => vec![Detail::Block("Payload", payload_preview, "")],
+        Change::ConfigChange {
+            old_value,
+            new_value,
+            ..
+        } => vec![
+            Detail::Field("Old", old_value.clone()),
+            Detail::Field("New", new_value.clone()),
+        ],
+        Change::GitOperation { commits, .. } => commits
+            .iter()
+            .map(|commit| Detail::Field("Commit", commit.clone()))
+            .collect(),
+        Change::DatabaseStatement { statement, .. } => {
+            vec![Detail::Block("Statement", statement, "sql")]
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// One-line description of a diff (used as the collapsed label)
+fn diff_label(diff: &UnifiedDiff) -> String {
+    if diff.binary {
+        "Binary file changed".to_string()
+    } else {
+        format!(
+            "{} line(s) added, {} line(s) removed",
+            diff.added, diff.removed
+        )
+    }
+}
+
+impl DiffCard {
+    /// Render as Markdown (for chat channels)
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "### {} {} Action\n",
+            risk_badge(self.risk_level),
+            self.action_type
+        );
+        let _ = writeln!(out, "**Risk score:** {}/100", self.risk_score);
+        for reason in &self.risk_reasons {
+            let _ = writeln!(out, "- {}", escape_markdown(reason));
+        }
+        for anomaly in &self.anomalies {
+            let _ = writeln!(out, "- ⚠ **Anomaly:** {}", escape_markdown(anomaly));
+        }
+        let _ = writeln!(
+            out,
+            "\n**Description:** {}\n",
+            escape_markdown(&self.description)
+        );
+        let _ = writeln!(
+            out,
+            "**Time:** {}",
+            self.timestamp.format("%Y-%m-%d %H:%M:%S UTC")
+        );
+
+        if !self.changes.is_empty() {
+            out.push_str("\n#### Changes\n");
+        }
+        for (i, change) in self.changes.iter().enumerate() {
+            let _ = writeln!(
+                out,
+                "\n**{}. {}** - {}",
+                i + 1,
+                change.change_type(),
+                escape_markdown(&change.summary())
+            );
+            for detail in details(change) {
+                match detail {
+                    Detail::Field(label, value) => {
+                        let _ = writeln!(out, "- {}: {}", label, escape_markdown(&value));
+                    }
+                    Detail::Block(label, content, language) => {
+                        push_markdown_block(&mut out, label, content, language);
+                    }
+                    Detail::Diff(diff) if diff.binary => {
+                        let _ = writeln!(out, "- {}", diff_label(diff));
+                    }
+                    Detail::Diff(diff) => {
+                        push_markdown_block(&mut out, &diff_label(diff), &diff_text(diff), "diff");
+                    }
+                }
+            }
+        }
+
+        out
+    }
+
+    /// Render as a self-contained HTML page
+    pub fn to_html(&self) -> String {
+        format!(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>{} Action - LuminaGuard Diff Card</title>\n</head>\n\
+             <body style=\"max-width:960px;margin:2em auto;padding:0 1em\">\n{}</body>\n</html>\n",
+            self.action_type,
+            self.to_html_fragment()
+        )
+    }
+
+    /// Render as an HTML fragment (an `<article>` with inline styles)
+    pub fn to_html_fragment(&self) -> String {
+        let (background, foreground) = risk_colors(self.risk_level);
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "<article style=\"font-family:sans-serif;border:2px solid {};border-radius:6px;\
+             padding:1em;margin-bottom:1em\">",
+            background
+        );
+        let _ = writeln!(
+            out,
+            "<header style=\"background:{};color:{};padding:0.4em 0.8em;border-radius:4px;\
+             font-weight:bold\">{} {} Action</header>",
+            background,
+            foreground,
+            risk_badge(self.risk_level),
+            self.action_type
+        );
+        let _ = writeln!(
+            out,
+            "<p><strong>Risk score:</strong> {}/100</p>",
+            self.risk_score
+        );
+        if !self.risk_reasons.is_empty() {
+            out.push_str("<ul>\n");
+            for reason in &self.risk_reasons {
+                let _ = writeln!(out, "<li>{}</li>", escape_html(reason));
+            }
+            out.push_str("</ul>\n");
+        }
+        for anomaly in &self.anomalies {
+            let _ = writeln!(
+                out,
+                "<p style=\"background:#fff3cd;padding:0.3em 0.6em\">\
+                 ⚠ <strong>Anomaly:</strong> {}</p>",
+                escape_html(anomaly)
+            );
+        }
+        let _ = writeln!(
+            out,
+            "<p><strong>Description:</strong> {}</p>",
+            escape_html(&self.description)
+        );
+        let _ = writeln!(
+            out,
+            "<p><strong>Time:</strong> {}</p>",
+            self.timestamp.format("%Y-%m-%d %H:%M:%S UTC")
+        );
+
+        if !self.changes.is_empty() {
+            out.push_str("<h3>Changes</h3>\n<ol>\n");
+            for change in &self.changes {
+                let _ = writeln!(
+                    out,
+                    "<li><strong>{}</strong> - {}",
+                    change.change_type(),
+                    escape_html(&change.summary())
+                );
+                for detail in details(change) {
+                    match detail {
+                        Detail::Field(label, value) => {
+                            let _ = writeln!(out, "<div>{}: {}</div>", label, escape_html(&value));
+                        }
+                        Detail::Block(label, content, _) => {
+                            let _ = writeln!(
+                                out,
+                                "<details><summary>{}</summary><pre style=\"{}\">{}</pre></details>",
+                                label,
+                                PRE_STYLE,
+                                escape_html(content)
+                            );
+                        }
+                        Detail::Diff(diff) if diff.binary => {
+                            let _ = writeln!(out, "<div>{}</div>", diff_label(diff));
+                        }
+                        Detail::Diff(diff) => push_html_diff(&mut out, diff),
+                    }
+                }
+                out.push_str("</li>\n");
+            }
+            out.push_str("</ol>\n");
+        }
+
+        out.push_str("</article>\n");
+        out
+    }
+}
+
+/// Style for preformatted content
+const PRE_STYLE: &str = "background:#f6f6f6;padding:0.5em;overflow-x:auto";
+
+/// Unified diff text (hunk headers and prefixed lines)
+fn diff_text(diff: &UnifiedDiff) -> String {
+    let mut text = String::new();
+    for hunk in &diff.hunks {
+        let _ = writeln!(text, "{}", hunk.header());
+        for line in &hunk.lines {
+            let _ = writeln!(text, "{}{}", line.kind.prefix(), line.text);
+        }
+    }
+    if diff.omitted_hunks > 0 {
+        let _ = writeln!(text, "... {} more hunk(s) not shown", diff.omitted_hunks);
+    }
+    text
+}
+
+/// Append a collapsible fenced code block
+fn push_markdown_block(out: &mut String, label: &str, content: &str, language: &str) {
+    // The fence must be longer than any backtick run in the content
+    let longest = content.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest.max(2) + 1);
+    let _ = writeln!(
+        out,
+        "\n<details><summary>{}</summary>\n\n{}{}\n{}",
+        escape_html(label),
+        fence,
+        language,
+        content.trim_end_matches('\n')
+    );
+    let _ = writeln!(out, "{}\n\n</details>", fence);
+}
+
+/// Append a collapsible, colored diff
+fn push_html_diff(out: &mut String, diff: &UnifiedDiff) {
+    let _ = write!(
+        out,
+        "<details><summary>{}</summary><pre style=\"{}\">",
+        diff_label(diff),
+        PRE_STYLE
+    );
+    for hunk in &diff.hunks {
+        let _ = writeln!(
+            out,
+            "<span style=\"color:#6a737d\">{}</span>",
+            hunk.header()
+        );
+        for line in &hunk.lines {
+            let style = match line.kind {
+                DiffLineKind::Context => "",
+                DiffLineKind::Added => " style=\"background:#e6ffed\"",
+                DiffLineKind::Removed => " style=\"background:#ffeef0\"",
+            };
+            let _ = writeln!(
+                out,
+                "<span{}>{}{}</span>",
+                style,
+                line.kind.prefix(),
+                escape_html(&line.text)
+            );
+        }
+    }
+    if diff.omitted_hunks > 0 {
+        let _ = writeln!(out, "... {} more hunk(s) not shown", diff.omitted_hunks);
+    }
+    out.push_str("</pre></details>\n");
+}
+
+/// Escape text for HTML content and attribute values
+fn escape_html(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Escape inline Markdown (and HTML, which most Markdown renderers allow)
+///
+/// Newlines become spaces so text cannot start new blocks.
+fn escape_markdown(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|' | '~' | '!' | '&' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' | '\r' => escaped.push(' '),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::approval::action::ActionType;
+
+    fn edit_card() -> DiffCard {
+        DiffCard::new(
+            ActionType::EditFile,
+            "Update <config> & *notes*".to_string(),
+            vec![
+                Change::file_edit("/srv/app.toml", "a = 1\nb = 2\n", "a = 1\nb = 3\n"),
+                Change::FileDelete {
+                    path: "/srv/old.txt".to_string(),
+                    size_bytes: 12,
+                },
+            ],
+        )
+    }
+
+    #[test]
+    fn test_markdown_rendering() {
+        let markdown = edit_card().to_markdown();
+
+        assert!(markdown.starts_with("### 🔴 [HIGH] EditFile Action"));
+        assert!(markdown.contains("Update \\<config\\> \\& \\*notes\\*"));
+        assert!(markdown.contains("**1. File Edit** - Edit: /srv/app.toml"));
+        assert!(markdown.contains(
+            "<details><summary>1 line(s) added, 1 line(s) removed</summary>\n\n```diff\n@@"
+        ));
+        assert!(markdown.contains("\n-b = 2\n+b = 3\n```\n\n</details>"));
+        assert!(markdown.contains("- Size: 12 bytes"));
+    }
+
+    #[test]
+    fn test_markdown_fence_longer_than_content_backticks() {
+        let card = DiffCard::new(
+            ActionType::CreateFile,
+            "Create README".to_string(),
+            vec![Change::file_create(
+                "/tmp/README.md",
+                "```rust\nfn main() {}\n```",
+            )],
+        );
+
+        assert!(card
+            .to_markdown()
+            .contains("````\n```rust\nfn main() {}\n```\n````"));
+    }
+
+    #[test]
+    fn test_html_rendering_is_escaped_and_self_contained() {
+        let html = edit_card().to_html();
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("background:#c62828"));
+        assert!(html.contains("Update &lt;config&gt; &amp; *notes*"));
+        assert!(!html.contains("<config>"));
+        assert!(html.contains("<details><summary>1 line(s) added, 1 line(s) removed</summary>"));
+        assert!(html.contains("<span style=\"background:#ffeef0\">-b = 2</span>"));
+        assert!(html.contains("<span style=\"background:#e6ffed\">+b = 3</span>"));
+        assert!(html.contains("<div>Size: 12 bytes</div>"));
+        assert!(!html.contains("<script") && !html.contains("<link") && !html.contains("src="));
+    }
+}
@@ -0,0 +1,669 @@
+//! Context-aware Risk Scoring for Diff Cards
+//!
+//! An action's type gives its baseline risk ([`ActionType::risk_level`]),
+//! but deleting one temporary file and deleting thousands of files in a git
+//! repository share a type. The [`RiskConfig`] scorer adjusts the baseline
+//! with features of the changes themselves:
+//!
+//! - Number of files and bytes affected
+//! - Sensitive paths (`.ssh`, `.git`, `/etc`, ...) and temporary paths
+//! - Transfer amounts, against per-currency thresholds
+//! - Email recipients outside the internal domains
+//! - Dangerous command patterns (`rm -rf`, `mkfs`, ...)
+//! - Force pushes and pushes to protected branches
+//! - Destructive or unbounded database statements and affected rows
+//! - Packages from outside the default registry, unpinned versions
+//! - World-writable or setuid modes and ownership given to root
+//!
+//! The result is a score from 0 to 100, the risk level it falls in, and the
+//! reasons for every adjustment, which are shown on the Diff Card.
+//! Adjustments never turn a Red action Green: whether an action needs
+//! approval still depends on its type alone.
+
+use super::action::{ActionType, RiskLevel};
+use super::diff::Change;
+use super::grants::is_under;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Component, Path};
+
+/// Score added for touching a sensitive path
+const SENSITIVE_PATH_WEIGHT: i32 = 25;
+
+/// Score added for reaching `bulk_files`
+const BULK_FILES_WEIGHT: i32 = 10;
+
+/// Score added for reaching `mass_files`
+const MASS_FILES_WEIGHT: i32 = 25;
+
+/// Score added for reaching `large_bytes`
+const LARGE_BYTES_WEIGHT: i32 = 10;
+
+/// Score removed when every path is temporary
+const TEMPORARY_PATHS_WEIGHT: i32 = -10;
+
+/// Score added for a transfer at or over the large threshold
+const LARGE_TRANSFER_WEIGHT: i32 = 20;
+
+/// Score removed for a transfer under the small threshold
+const SMALL_TRANSFER_WEIGHT: i32 = -15;
+
+/// Score added for a transfer amount that cannot be parsed
+const UNPARSED_AMOUNT_WEIGHT: i32 = 10;
+
+/// Score added for an email to an external domain
+const EXTERNAL_RECIPIENT_WEIGHT: i32 = 15;
+
+/// Score added for a dangerous command pattern
+const DANGEROUS_COMMAND_WEIGHT: i32 = 30;
+
+/// Score added for a git operation rewriting history
+const FORCE_PUSH_WEIGHT: i32 = 25;
+
+/// Score added for a push to a protected branch
+const PROTECTED_BRANCH_WEIGHT: i32 = 15;
+
+/// Score added for `DROP`/`TRUNCATE` statements
+const DESTRUCTIVE_SQL_WEIGHT: i32 = 30;
+
+/// Score added for `DELETE`/`UPDATE` without a `WHERE` clause
+const UNBOUNDED_SQL_WEIGHT: i32 = 25;
+
+/// Score added for reaching `large_row_count`
+const LARGE_ROWS_WEIGHT: i32 = 15;
+
+/// Score added for a package from an untrusted source
+const UNTRUSTED_SOURCE_WEIGHT: i32 = 15;
+
+/// Score added for a package without a pinned version
+const UNPINNED_VERSION_WEIGHT: i32 = 5;
+
+/// Score added for a world-writable mode
+const WORLD_WRITABLE_WEIGHT: i32 = 20;
+
+/// Score added for a setuid/setgid mode
+const SETUID_WEIGHT: i32 = 25;
+
+/// Score added for ownership given to root
+const ROOT_OWNER_WEIGHT: i32 = 15;
+
+/// Lowest score of an action that requires approval
+const RED_FLOOR: i32 = 10;
+
+/// Transfer amount thresholds for one currency
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AmountThresholds {
+    /// Transfers below this amount lower the score
+    pub small: f64,
+
+    /// Transfers of at least this amount raise the score
+    pub large: f64,
+}
+
+impl AmountThresholds {
+    /// Thresholds for small and large transfers
+    pub fn new(small: f64, large: f64) -> Self {
+        Self { small, large }
+    }
+}
+
+/// Thresholds and patterns used to score Diff Cards
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RiskConfig {
+    /// Number of files from which the score is raised
+    pub bulk_files: usize,
+
+    /// Number of files from which the score is raised further
+    pub mass_files: usize,
+
+    /// Bytes affected (approximate) from which the score is raised
+    pub large_bytes: u64,
+
+    /// Paths that mark a path as sensitive: absolute directories (`/etc/`),
+    /// directory names (`.git/`) or fragments (`id_rsa`)
+    pub sensitive_paths: Vec<String>,
+
+    /// Directories of temporary locations
+    pub temporary_paths: Vec<String>,
+
+    /// Transfer thresholds by currency code (case-insensitive)
+    pub transfer_thresholds: BTreeMap<String, AmountThresholds>,
+
+    /// Email domains considered internal (no recipient check if empty)
+    pub internal_domains: Vec<String>,
+
+    /// Command patterns considered dangerous (case-insensitive)
+    pub dangerous_commands: Vec<String>,
+
+    /// Branches pushes to which raise the score
+    pub protected_branches: Vec<String>,
+
+    /// Estimated affected rows from which the score is raised
+    pub large_row_count: u64,
+
+    /// Package sources treated like the default registry
+    pub trusted_package_sources: Vec<String>,
+}
+
+impl Default for RiskConfig {
+    fn default() -> Self {
+        let strings = |items: &[&str]| items.iter().map(|s| s.to_string()).collect();
+        let thresholds = [
+            ("USD", AmountThresholds::new(10.0, 1_000.0)),
+            ("EUR", AmountThresholds::new(10.0, 1_000.0)),
+            ("GBP", AmountThresholds::new(10.0, 1_000.0)),
+            ("BTC", AmountThresholds::new(0.000_2, 0.02)),
+            ("ETH", AmountThresholds::new(0.005, 0.5)),
+        ];
+
+        Self {
+            bulk_files: 20,
+            mass_files: 1_000,
+            large_bytes: 10 * 1024 * 1024,
+            sensitive_paths: strings(&[
+                ".ssh",
+                ".gnupg",
+                ".aws",
+                ".kube",
+                ".git/",
+                ".env",
+                "/etc/",
+                "id_rsa",
+                "id_ed25519",
+            ]),
+            temporary_paths: strings(&["/tmp/", "/var/tmp/"]),
+            transfer_thresholds: thresholds
+                .into_iter()
+                .map(|(currency, t)| (currency.to_string(), t))
+                .collect(),
+            internal_domains: Vec::new(),
+            dangerous_commands: strings(&[
+                "rm -rf",
+                "rm -fr",
+                "mkfs",
+                "dd if=",
+                "chmod -r 777",
+                "> /dev/sd",
+                "| sh",
+                "| bash",
+                ":(){",
+                "shutdown",
+                "push --force",
+                "drop table",
+            ]),
+            protected_branches: strings(&["main", "master"]),
+            large_row_count: 10_000,
+            trusted_package_sources: Vec::new(),
+        }
+    }
+}
+
+/// Score of a Diff Card and why
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RiskAssessment {
+    /// Score from 0 (harmless) to 100
+    pub score: u8,
+
+    /// Risk level the score falls in
+    pub level: RiskLevel,
+
+    /// Human-readable reasons for each adjustment of the baseline
+    pub reasons: Vec<String>,
+}
+
+impl RiskConfig {
+    /// Score an action from its type and changes
+    pub fn assess(&self, action_type: ActionType, changes: &[Change]) -> RiskAssessment {
+        let mut score = baseline(action_type.risk_level());
+        let mut reasons = Vec::new();
+        let mut adjust = |weight: i32, reason: String| {
+            score += weight;
+            reasons.push(format!("{} ({:+})", reason, weight));
+        };
+
+        let paths: Vec<&str> = changes.iter().filter_map(touched_path).collect();
+        if paths.len() >= self.mass_files {
+            adjust(MASS_FILES_WEIGHT, format!("{} files affected", paths.len()));
+        } else if paths.len() >= self.bulk_files {
+            adjust(BULK_FILES_WEIGHT, format!("{} files affected", paths.len()));
+        }
+
+        let bytes: u64 = changes.iter().map(bytes_affected).sum();
+        if bytes >= self.large_bytes {
+            adjust(
+                LARGE_BYTES_WEIGHT,
+                format!("About {} bytes affected", bytes),
+            );
+        }
+
+        let sensitive: Vec<&str> = paths
+            .iter()
+            .copied()
+            .filter(|path| self.sensitive_paths.iter().any(|s| is_sensitive(path, s)))
+            .collect();
+        if let Some(first) = sensitive.first() {
+            let reason = match sensitive.len() {
+                1 => format!("Sensitive path {}", first),
+                n => format!("Sensitive paths {} and {} more", first, n - 1),
+            };
+            adjust(SENSITIVE_PATH_WEIGHT, reason);
+        } else if !paths.is_empty()
+            && paths.iter().all(|path| {
+                self.temporary_paths
+                    .iter()
+                    .any(|t| is_under(Path::new(path), Path::new(t)))
+            })
+        {
+            adjust(
+                TEMPORARY_PATHS_WEIGHT,
+                "Only temporary paths affected".to_string(),
+            );
+        }
+
+        for change in changes {
+            match change {
+                Change::AssetTransfer {
+                    amount, currency, ..
+                } => {
+                    let thresholds = self
+                        .transfer_thresholds
+                        .iter()
+                        .find(|(code, _)| code.eq_ignore_ascii_case(currency))
+                        .map(|(_, t)| t);
+                    match (parse_amount(amount), thresholds) {
+                        (None, _) => adjust(
+                            UNPARSED_AMOUNT_WEIGHT,
+                            format!("Unrecognized transfer amount '{}'", amount),
+                        ),
+                        (Some(value), Some(t)) if value >= t.large => adjust(
+                            LARGE_TRANSFER_WEIGHT,
+                            format!("Transfer of {} {} is large", amount, currency),
+                        ),
+                        (Some(value), Some(t)) if value < t.small => adjust(
+                            SMALL_TRANSFER_WEIGHT,
+                            format!("Transfer of {} {} is small", amount, currency),
+                        ),
+                        _ => {}
+                    }
+                }
+                Change::EmailSend { to, .. } if !self.internal_domains.is_empty() => {
+                    let domain = to.rsplit_once('@').map_or("", |(_, d)| d);
+                    let internal = self
+                        .internal_domains
+                        .iter()
+                        .any(|d| domain.eq_ignore_ascii_case(d));
+                    if !internal {
+                        adjust(
+                            EXTERNAL_RECIPIENT_WEIGHT,
+                            format!("External recipient {}", to),
+                        );
+                    }
+                }
+                Change::CommandExec { command, args, .. } => {
+                    let line = std::iter::once(command)
+                        .chain(args)
+                        .map(String::as_str)
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                        .to_lowercase();
+                    if let Some(pattern) = self
+                        .dangerous_commands
+                        .iter()
+                        .find(|p| line.contains(&p.to_lowercase()))
+                    {
+                        adjust(
+                            DANGEROUS_COMMAND_WEIGHT,
+                            format!("Dangerous command pattern '{}'", pattern),
+                        );
+                    }
+                }
+                Change::GitOperation {
+                    operation,
+                    branch,
+                    force,
+                    ..
+                } => {
+                    if *force {
+                        adjust(
+                            FORCE_PUSH_WEIGHT,
+                            format!("git {} rewrites history of {}", operation, branch),
+                        );
+                    }
+                    if operation == "push" && self.protected_branches.contains(branch) {
+                        adjust(
+                            PROTECTED_BRANCH_WEIGHT,
+                            format!("Push to protected branch {}", branch),
+                        );
+                    }
+                }
+                Change::DatabaseStatement {
+                    statement,
+                    estimated_rows,
+                    ..
+                } => {
+                    let upper = statement.to_uppercase();
+                    let words: Vec<&str> = upper.split_whitespace().collect();
+                    match words.first().copied() {
+                        Some(verb @ ("DROP" | "TRUNCATE")) => adjust(
+                            DESTRUCTIVE_SQL_WEIGHT,
+                            format!("Destructive {} statement", verb),
+                        ),
+                        Some(verb @ ("DELETE" | "UPDATE")) if !words.contains(&"WHERE") => adjust(
+                            UNBOUNDED_SQL_WEIGHT,
+                            format!("{} without a WHERE clause", verb),
+                        ),
+                        _ => {}
+                    }
+                    if let Some(rows) = estimated_rows.filter(|n| *n >= self.large_row_count) {
+                        adjust(LARGE_ROWS_WEIGHT, format!("About {} rows affected", rows));
+                    }
+                }
+                Change::PackageInstall {
+                    package,
+                    version,
+                    source,
+                    ..
+                } => {
+                    if let Some(source) = source
+                        .as_ref()
+                        .filter(|s| !self.trusted_package_sources.contains(s))
+                    {
+                        adjust(
+                            UNTRUSTED_SOURCE_WEIGHT,
+                            format!("{} installed from {}", package, source),
+                        );
+                    }
+                    if version.is_none() {
+                        adjust(
+                            UNPINNED_VERSION_WEIGHT,
+                            format!("Version of {} not pinned", package),
+                        );
+                    }
+                }
+                Change::PermissionChange {
+                    path,
+                    new_mode,
+                    new_owner,
+                    ..
+                } => {
+                    let mode = new_mode.as_deref().and_then(parse_mode);
+                    if let (Some(mode), Some(text)) = (mode, new_mode) {
+                        if mode & 0o6000 != 0 {
+                            adjust(
+                                SETUID_WEIGHT,
+                                format!("Setuid/setgid mode {} on {}", text, path),
+                            );
+                        }
+                        if mode & 0o002 != 0 {
+                            adjust(
+                                WORLD_WRITABLE_WEIGHT,
+                                format!("World-writable mode {} on {}", text, path),
+                            );
+                        }
+                    }
+                    let owner = new_owner
+                        .as_deref()
+                        .map(|o| o.split(':').next().unwrap_or(o));
+                    if owner == Some("root") {
+                        adjust(ROOT_OWNER_WEIGHT, format!("{} given to root", path));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let floor = if action_type.requires_approval() {
+            RED_FLOOR
+        } else {
+            0
+        };
+        let score = score.clamp(floor, 100) as u8;
+        RiskAssessment {
+            score,
+            level: level_for(score),
+            reasons,
+        }
+    }
+}
+
+/// Score of an action type's baseline risk level
+fn baseline(level: RiskLevel) -> i32 {
+    match level {
+        RiskLevel::None => 0,
+        RiskLevel::Low => 20,
+        RiskLevel::Medium => 45,
+        RiskLevel::High => 65,
+        RiskLevel::Critical => 85,
+    }
+}
+
+/// Risk level a score falls in
+pub fn level_for(score: u8) -> RiskLevel {
+    match score {
+        0..=9 => RiskLevel::None,
+        10..=34 => RiskLevel::Low,
+        35..=59 => RiskLevel::Medium,
+        60..=79 => RiskLevel::High,
+        _ => RiskLevel::Critical,
+    }
+}
+
+/// Path whose content or permissions a change affects
+fn touched_path(change: &Change) -> Option<&str> {
+    match change {
+        Change::PermissionChange { path, .. } => Some(path),
+        _ => change.path(),
+    }
+}
+
+/// Whether `path` is at or under a sensitive path entry
+///
+/// Paths climbing with `..` could be anywhere, so they count as sensitive.
+fn is_sensitive(path: &str, entry: &str) -> bool {
+    let mut components = Path::new(path).components();
+    if components.any(|c| c == Component::ParentDir) {
+        return true;
+    }
+    if entry.starts_with('/') {
+        return is_under(Path::new(path), Path::new(entry));
+    }
+    match entry.strip_suffix('/') {
+        Some(name) => Path::new(path).components().any(|c| c.as_os_str() == name),
+        None => path.contains(entry),
+    }
+}
+
+/// Parse an octal mode like "755" or "0o4755"
+fn parse_mode(mode: &str) -> Option<u32> {
+    u32::from_str_radix(mode.trim_start_matches("0o"), 8).ok()
+}
+
+/// Bytes a change affects (content previews count as their length)
+fn bytes_affected(change: &Change) -> u64 {
+    match change {
+        Change::FileCreate {
+            content_preview, ..
+        } => content_preview.len() as u64,
+        Change::FileEdit { after, .. } => after.len() as u64,
+        Change::FileDelete { size_bytes, .. } => *size_bytes,
+        _ => 0,
+    }
+}
+
+/// Parse an amount like "1,250.00" (negative amounts are not amounts)
+fn parse_amount(amount: &str) -> Option<f64> {
+    let cleaned: String = amount
+        .trim()
+        .chars()
+        .filter(|c| *c != ',' && *c != '_')
+        .collect();
+    cleaned
+        .parse::<f64>()
+        .ok()
+        .filter(|v| v.is_finite() && *v >= 0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn delete(path: &str) -> Change {
+        Change::FileDelete {
+            path: path.to_string(),
+            size_bytes: 10,
+        }
+    }
+
+    fn transfer(amount: &str, currency: &str) -> Change {
+        Change::AssetTransfer {
+            from: "a".to_string(),
+            to: "b".to_string(),
+            amount: amount.to_string(),
+            currency: currency.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_file_count_and_paths() {
+        let config = RiskConfig::default();
+
+        let temp = config.assess(ActionType::DeleteFile, &[delete("/tmp/a.txt")]);
+        assert_eq!(temp.level, RiskLevel::High);
+        assert_eq!(temp.reasons, vec!["Only temporary paths affected (-10)"]);
+
+        // Climbing out of a temporary path is not temporary
+        let escape = config.assess(ActionType::DeleteFile, &[delete("/tmp/../etc/passwd")]);
+        assert_eq!(
+            escape.reasons,
+            vec!["Sensitive path /tmp/../etc/passwd (+25)"]
+        );
+
+        // Sensitive directories themselves are sensitive
+        for path in ["/etc", "/src/repo/.git", "/src/repo/.git/config"] {
+            let sensitive = config.assess(ActionType::DeleteFile, &[delete(path)]);
+            assert!(sensitive.reasons[0].starts_with("Sensitive path"), "{path}");
+        }
+        let ignore = config.assess(ActionType::DeleteFile, &[delete("/src/repo/.gitignore")]);
+        assert!(ignore.reasons.is_empty());
+
+        let repo: Vec<Change> = (0..5000)
+            .map(|i| delete(&format!("/src/repo/.git/objects/{}", i)))
+            .collect();
+        let repo = config.assess(ActionType::DeleteFile, &repo);
+        assert_eq!(repo.score, 100);
+        assert_eq!(repo.level, RiskLevel::Critical);
+        assert!(repo.reasons[0].starts_with("5000 files affected"));
+        assert!(repo.reasons[1].contains("and 4999 more"));
+    }
+
+    #[test]
+    fn test_transfer_thresholds() {
+        let config = RiskConfig::default();
+
+        let cent = config.assess(ActionType::TransferAsset, &[transfer("0.01", "usd")]);
+        let million = config.assess(ActionType::TransferAsset, &[transfer("1,000,000", "USD")]);
+        assert!(cent.score < million.score);
+        assert_eq!(cent.level, RiskLevel::High);
+        assert_eq!(million.level, RiskLevel::Critical);
+        assert!(million.reasons[0].contains("is large"));
+
+        let unknown = config.assess(ActionType::TransferAsset, &[transfer("lots", "USD")]);
+        assert!(unknown.reasons[0].contains("Unrecognized"));
+        let negative = config.assess(ActionType::TransferAsset, &[transfer("-1000000", "USD")]);
+        assert!(negative.reasons[0].contains("Unrecognized"));
+    }
+
+    #[test]
+    fn test_recipients_and_commands() {
+        let config = RiskConfig {
+            internal_domains: vec!["example.com".to_string()],
+            ..RiskConfig::default()
+        };
+        let email = |to: &str| Change::EmailSend {
+            to: to.to_string(),
+            subject: String::new(),
+            preview: String::new(),
+        };
+        assert!(config
+            .assess(ActionType::SendEmail, &[email("bob@example.com")])
+            .reasons
+            .is_empty());
+        assert_eq!(
+            config
+                .assess(ActionType::SendEmail, &[email("eve@evil.test")])
+                .reasons,
+            vec!["External recipient eve@evil.test (+15)"]
+        );
+
+        let command = Change::CommandExec {
+            command: "rm".to_string(),
+            args: vec!["-rf".to_string(), "/".to_string()],
+            env_vars: None,
+        };
+        let assessment = config.assess(ActionType::ExecuteCommand, &[command]);
+        assert!(assessment.reasons[0].contains("'rm -rf'"));
+    }
+
+    #[test]
+    fn test_git_database_package_permission() {
+        let config = RiskConfig::default();
+        let reasons = |action_type, change| config.assess(action_type, &[change]).reasons;
+
+        let push = Change::GitOperation {
+            operation: "push".to_string(),
+            branch: "main".to_string(),
+            remote: Some("origin".to_string()),
+            commits: vec!["abc123 Fix".to_string()],
+            force: true,
+        };
+        assert_eq!(
+            reasons(ActionType::Push, push),
+            vec![
+                "git push rewrites history of main (+25)",
+                "Push to protected branch main (+15)"
+            ]
+        );
+
+        let delete = Change::DatabaseStatement {
+            connection: "postgres://db/app".to_string(),
+            statement: "delete from users".to_string(),
+            estimated_rows: Some(50_000),
+        };
+        assert_eq!(
+            reasons(ActionType::Query, delete),
+            vec![
+                "DELETE without a WHERE clause (+25)",
+                "About 50000 rows affected (+15)"
+            ]
+        );
+
+        let package = Change::PackageInstall {
+            manager: "pip".to_string(),
+            package: "requests".to_string(),
+            version: Some("2.32.0".to_string()),
+            source: Some("git+https://example.test/requests".to_string()),
+        };
+        assert_eq!(
+            reasons(ActionType::Install, package),
+            vec!["requests installed from git+https://example.test/requests (+15)"]
+        );
+
+        let chmod = Change::PermissionChange {
+            path: "/usr/local/bin/tool".to_string(),
+            old_mode: Some("755".to_string()),
+            new_mode: Some("4777".to_string()),
+            old_owner: None,
+            new_owner: Some("root:root".to_string()),
+        };
+        assert_eq!(reasons(ActionType::ModifySystem, chmod).len(), 3);
+    }
+
+    #[test]
+    fn test_red_actions_stay_red() {
+        let config = RiskConfig::default();
+        let assessment = config.assess(ActionType::Install, &[transfer("0.01", "USD")]);
+        assert_eq!(assessment.score, 10);
+        assert_eq!(assessment.level, RiskLevel::Low);
+    }
+}
@@ -0,0 +1,283 @@
+//! Published Diff Card JSON Schema
+//!
+//! The JSON Schema for [`DiffCard`] (and [`Change`]) is generated from the
+//! Rust types and checked in at `docs/schemas/diff-card.schema.json`;
+//! regenerate it with
+//! `luminaguard schema --output docs/schemas/diff-card.schema.json`. The
+//! Python agent validates the cards it sends against the same file, and a
+//! test keeps the file in sync with the types.
+//!
+//! # Versioning
+//!
+//! Cards carry a `schema_version` ([`SCHEMA_VERSION`]). [`parse_diff_card`]
+//! upgrades older cards before deserializing:
+//! - Version 1 cards have no `schema_version`. Cards serialized by the
+//!   orchestrator already match version 2; cards in the Python agent's old
+//!   format (`{"change_type", "summary", "details"}` changes, lowercase risk
+//!   level, tool name as the action type) are converted
+//! - Cards with a newer version than this build supports are rejected
+
+use super::action::{ActionType, RiskLevel};
+use super::diff::{Change, DiffCard, SCHEMA_VERSION};
+use anyhow::{Context, Result};
+use serde_json::Value;
+
+/// `$id` of the published schema
+pub const SCHEMA_ID: &str = "https://luminaguard.dev/schemas/diff-card.schema.json";
+
+/// JSON Schema for Diff Cards (current version)
+pub fn diff_card_schema() -> Value {
+    let mut schema = schemars::schema_for!(DiffCard).to_value();
+    if let Some(object) = schema.as_object_mut() {
+        object.insert("$id".to_string(), Value::from(SCHEMA_ID));
+        object.insert("title".to_string(), Value::from("LuminaGuard Diff Card"));
+        object.insert(
+            "description".to_string(),
+            Value::String(format!(
+                "Diff Card shown to approvers before a Red action runs, schema version {}. \
+                 Generated from orchestrator/src/approval/diff.rs.",
+                SCHEMA_VERSION
+            )),
+        );
+    }
+    schema
+}
+
+/// Parse a Diff Card of any supported schema version
+///
+/// # Errors
+///
+/// Returns an error if the JSON is invalid, the card is from a newer schema
+/// version, or it does not match its version's format.
+pub fn parse_diff_card(json: &str) -> Result<DiffCard> {
+    let mut card: Value = serde_json::from_str(json).context("Failed to parse Diff Card JSON")?;
+    let version = match card.get("schema_version") {
+        None => 1,
+        Some(version) => version
+            .as_u64()
+            .context("Diff Card schema_version must be a positive integer")?,
+    };
+    if version > u64::from(SCHEMA_VERSION) {
+        anyhow::bail!(
+            "Diff Card schema version {} is newer than supported (version {})",
+            version,
+            SCHEMA_VERSION
+        );
+    }
+    if version < 2 {
+        upgrade_v1(&mut card)?;
+    }
+
+    let mut card: DiffCard =
+        serde_json::from_value(card).context("Diff Card does not match the schema")?;
+
+    // Senders without a diff engine (e.g. the Python agent) send full file
+    // content; compute the line diff and keep only previews
+    for change in &mut card.changes {
+        if let Change::FileEdit {
+            path,
+            before,
+            after,
+            diff: None,
+        } = change
+        {
+            *change = Change::file_edit(path.as_str(), before, after);
+        }
+    }
+    Ok(card)
+}
+
+/// Upgrade a version 1 card to version 2
+fn upgrade_v1(card: &mut Value) -> Result<()> {
+    let object = card
+        .as_object_mut()
+        .context("Diff Card must be a JSON object")?;
+    object.insert("schema_version".to_string(), Value::from(SCHEMA_VERSION));
+
+    // The Python agent sent tool names and lowercase risk levels
+    let action_type = match object.get("action_type").and_then(Value::as_str) {
+        Some(s) => s
+            .parse::<ActionType>()
+            .unwrap_or_else(|_| ActionType::from_description(s)),
+        None => ActionType::Unknown,
+    };
+    object.insert(
+        "action_type".to_string(),
+        serde_json::to_value(action_type)?,
+    );
+    let risk_level = object
+        .get("risk_level")
+        .and_then(Value::as_str)
+        .and_then(|s| s.parse::<RiskLevel>().ok())
+        .unwrap_or(RiskLevel::Medium);
+    object.insert("risk_level".to_string(), serde_json::to_value(risk_level)?);
+
+    object
+        .entry("description")
+        .or_insert_with(|| Value::String(String::new()));
+    object
+        .entry("timestamp")
+        .or_insert_with(|| serde_json::json!(chrono::Utc::now()));
+
+    if let Some(Value::Array(changes)) = object.get_mut("changes") {
+        for change in changes.iter_mut() {
+            if change.get("change_type").is_some() {
+                *change = serde_json::to_value(legacy_change(change))?;
+            }
+        }
+    } else {
+        object.insert("changes".to_string(), Value::Array(Vec::new()));
+    }
+    Ok(())
+}
+
+/// Convert a change in the Python agent's old format
+fn legacy_change(change: &Value) -> Change {
+    let change_type = change["change_type"].as_str().unwrap_or("Custom");
+    let summary = change["summary"].as_str().unwrap_or("");
+    let details = &change["details"];
+    let path = details["path"].as_str().unwrap_or("");
+
+    match change_type {
+        "FileCreate" => Change::file_create(path, details["content"].as_str().unwrap_or("")),
+        "FileEdit" => Change::file_edit(
+            path,
+            details["before"].as_str().unwrap_or(""),
+            details["after"].as_str().unwrap_or(""),
+        ),
+        "FileDelete" => Change::FileDelete {
+            path: path.to_string(),
+            size_bytes: details["size_bytes"].as_u64().unwrap_or(0),
+        },
+        "CommandExec" => Change::CommandExec {
+            command: summary.replace("Execute: ", ""),
+            args: details["args"]
+                .as_array()
+                .map(|args| {
+                    args.iter()
+                        .filter_map(|a| a.as_str().map(String::from))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            env_vars: None,
+        },
+        _ => Change::Custom {
+            description: summary.to_string(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_published_schema_is_current() {
+        let published: Value =
+            serde_json::from_str(include_str!("../../../docs/schemas/diff-card.schema.json"))
+                .unwrap();
+
+        assert_eq!(
+            published,
+            diff_card_schema(),
+            "docs/schemas/diff-card.schema.json is out of date; regenerate it with \
+             `luminaguard schema --output docs/schemas/diff-card.schema.json`"
+        );
+    }
+
+    #[test]
+    fn test_parse_current_version() {
+        let card = DiffCard::new(
+            ActionType::DeleteFile,
+            "Delete file".to_string(),
+            vec![Change::FileDelete {
+                path: "/tmp/a".to_string(),
+                size_bytes: 3,
+            }],
+        );
+        let parsed = parse_diff_card(&card.to_json().unwrap()).unwrap();
+
+        assert_eq!(parsed.schema_version, SCHEMA_VERSION);
+        assert_eq!(parsed.changes, card.changes);
+        assert_eq!(parsed.risk_score, card.risk_score);
+    }
+
+    #[test]
+    fn test_file_edit_diff_computed_if_missing() {
+        let json = serde_json::json!({
+            "schema_version": SCHEMA_VERSION,
+            "action_type": "EditFile",
+            "description": "write_file",
+            "risk_level": "High",
+            "changes": [{"FileEdit": {"path": "/tmp/a", "before": "a\n", "after": "b\n"}}],
+            "timestamp": "2024-01-01T00:00:00Z"
+        });
+        let card = parse_diff_card(&json.to_string()).unwrap();
+
+        assert_eq!(card.changes[0], Change::file_edit("/tmp/a", "a\n", "b\n"));
+    }
+
+    #[test]
+    fn test_parse_legacy_python_card() {
+        let json = serde_json::json!({
+            "action_type": "write_file",
+            "description": "write_file",
+            "risk_level": "high",
+            "changes": [
+                {
+                    "change_type": "FileEdit",
+                    "summary": "write_file: Write to /tmp/a",
+                    "details": {"path": "/tmp/a", "before": "", "after": "hello\n"}
+                },
+                {"change_type": "FileRead", "summary": "read_file: Read /tmp/b", "details": {}}
+            ],
+            "timestamp": "2024-01-01T00:00:00Z"
+        });
+        let card = parse_diff_card(&json.to_string()).unwrap();
+
+        assert_eq!(card.schema_version, SCHEMA_VERSION);
+        assert_eq!(card.action_type, ActionType::EditFile);
+        assert_eq!(card.risk_level, RiskLevel::High);
+        assert_eq!(card.changes[0], Change::file_edit("/tmp/a", "", "hello\n"));
+        assert_eq!(
+            card.changes[1],
+            Change::Custom {
+                description: "read_file: Read /tmp/b".to_string()
+            }
+        );
+        assert_eq!(card.timestamp.to_rfc3339(), "2024-01-01T00:00:00+00:00");
+    }
+
+    #[test]
+    fn test_parse_legacy_rust_card() {
+        // Serialized before risk scores and schema versions existed
+        let json = serde_json::json!({
+            "action_type": "EditFile",
+            "description": "Edit config",
+            "risk_level": "High",
+            "changes": [{"Custom": {"description": "tweak"}}],
+            "timestamp": "2024-01-01T00:00:00Z"
+        });
+        let card = parse_diff_card(&json.to_string()).unwrap();
+
+        assert_eq!(card.action_type, ActionType::EditFile);
+        assert_eq!(card.risk_level, RiskLevel::High);
+        assert_eq!(card.risk_score, 0);
+        assert_eq!(card.changes.len(), 1);
+    }
+
+    #[test]
+    fn test_newer_version_rejected() {
+        let json = serde_json::json!({
+            "schema_version": SCHEMA_VERSION + 1,
+            "action_type": "EditFile",
+            "description": "",
+            "risk_level": "High",
+            "changes": [],
+            "timestamp": "2024-01-01T00:00:00Z"
+        });
+
+        let err = parse_diff_card(&json.to_string()).unwrap_err();
+        assert!(err.to_string().contains("newer than supported"));
+    }
+}
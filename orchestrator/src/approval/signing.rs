@@ -0,0 +1,1096 @@
+//! Signed Approval Records
+//!
+//! Approvers sign their decisions themselves, with Ed25519 keys held in
+//! their own [`Keyring`] (one key file per approver, under `<data dir>/keys`
+//! on the approver's machine). A [`RecordSignature`] covers the digest of
+//! the record that was decided ([`RecordHeader`]: its ID, time, action,
+//! paths, command, session and the digest of its Diff Card), the approver,
+//! the verdict and any modifications, so none of them can be altered (or a
+//! decision fabricated, or a signature moved to another record) without the
+//! approver's key.
+//! Verification recomputes the record digest from the record itself. The
+//! orchestrator never holds approver keys: signatures arrive with the
+//! decision (`"signature"` in web and webhook decisions, produced with
+//! `luminaguard keys sign` over the request's `digest`) and are stored on
+//! the record.
+//!
+//! A plan is approved with one signature over the digests of its steps'
+//! records ([`PlanStepDigest`]) and the steps approved
+//! ([`StepSelection`]), which holds for each selected step's record.
+//!
+//! Decisions made without prompting are verified through what authorized
+//! them:
+//! - Standing grants: the grant's origin record, whose approver must have
+//!   signed the grant scope along with the approval, and which the granted
+//!   record must fall within ([`TrustList::verify_with`])
+//! - Policy allow rules: the rule's signature (`luminaguard keys
+//!   sign-policy`), copied onto each record the rule decides
+//!
+//! Decisions made at the orchestrator's terminal (the stdin prompt, the
+//! TUI, `luminaguard approvals approve`) are signed with the local user's
+//! key from the local keyring.
+//!
+//! A [`TrustList`] names the public keys allowed to sign for each approver.
+//! When one is configured, [`ApprovalManager`](super::ApprovalManager)
+//! refuses an approval unless every approver signed it with a trusted key,
+//! and [`verify_audit_log`] checks an exported audit log offline
+//! (`luminaguard audit verify`).
+//!
+//! Keys are managed with `luminaguard keys generate|trust|list|sign|sign-policy`.
+
+use super::action::ActionType;
+use super::amend::Modification;
+use super::diff::DiffCard;
+use super::grants::{is_under, ChangeMatcher, GrantScope};
+use super::hex::{hex_decode, hex_encode};
+use super::history::{ApprovalDecision, ApprovalRecord};
+use super::plan::StepSelection;
+use super::policy::{PolicyEffect, PolicyRule};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Domain separator of signed decisions
+const SIGNATURE_CONTEXT: &str = "luminaguard-decision-v2";
+
+/// The fields of an approval record a signature covers
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordHeader {
+    /// Record ID
+    pub id: String,
+
+    /// When approval was requested
+    pub timestamp: DateTime<Utc>,
+
+    /// Description of the action
+    pub action_description: String,
+
+    /// Type of the action
+    pub action_type: Option<ActionType>,
+
+    /// Files the action touches
+    pub paths: Vec<String>,
+
+    /// Digest of the Diff Card that was decided (`sha256:<hex>`)
+    pub diff_card_digest: Option<String>,
+
+    /// Agent session the action was requested in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+
+    /// Command every change of the action runs, if they all run the same one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+}
+
+impl RecordHeader {
+    /// Header of a new record deciding a Diff Card
+    pub fn for_card(diff_card: &DiffCard) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            timestamp: Utc::now(),
+            action_description: diff_card.description.clone(),
+            action_type: Some(diff_card.action_type),
+            paths: diff_card
+                .changes
+                .iter()
+                .filter_map(|change| change.path().map(String::from))
+                .collect(),
+            diff_card_digest: diff_card_digest(diff_card).ok(),
+            session_id: None,
+            command: match ChangeMatcher::similar_to(&diff_card.changes) {
+                ChangeMatcher::Command { command } => Some(command),
+                _ => None,
+            },
+        }
+    }
+
+    /// Header of an existing record
+    pub fn of(record: &ApprovalRecord) -> Self {
+        Self {
+            id: record.id.clone(),
+            timestamp: record.timestamp,
+            action_description: record.action_description.clone(),
+            action_type: record.action_type,
+            paths: record.paths.clone(),
+            diff_card_digest: record.diff_card_digest.clone(),
+            session_id: record.session_id.clone(),
+            command: record.command.clone(),
+        }
+    }
+
+    /// Record with this header, without any other details
+    pub fn into_record(self, decision: ApprovalDecision, approved_by: String) -> ApprovalRecord {
+        ApprovalRecord {
+            id: self.id,
+            timestamp: self.timestamp,
+            action_description: self.action_description,
+            action_type: self.action_type,
+            decision,
+            approved_by,
+            justification: None,
+            execution_result: None,
+            votes: Vec::new(),
+            grant_id: None,
+            origin_record_id: None,
+            deferred_id: None,
+            plan_id: None,
+            requested_by: None,
+            session_id: self.session_id,
+            paths: self.paths,
+            command: self.command,
+            latency_ms: None,
+            anomalies: Vec::new(),
+            timeout: None,
+            diff_card_digest: self.diff_card_digest,
+            signatures: Vec::new(),
+        }
+    }
+
+    /// Digest of the header (`sha256:<hex>`), as covered by signatures
+    pub fn digest(&self) -> Result<String> {
+        sha256_digest(&serde_json::to_vec(self)?)
+    }
+}
+
+/// A plan step's record, as covered by a plan signature
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlanStepDigest {
+    /// Position in the plan (from 1)
+    pub number: usize,
+
+    /// Digest of the step's record header
+    pub record_digest: String,
+}
+
+/// Digest of a plan's steps (`sha256:<hex>`), as covered by signatures
+pub fn plan_digest(steps: &[PlanStepDigest]) -> Result<String> {
+    sha256_digest(&serde_json::to_vec(steps)?)
+}
+
+/// An approver's signature over their decision
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordSignature {
+    /// Approver who signed
+    pub signer: String,
+
+    /// Signing public key (hex)
+    pub public_key: String,
+
+    /// Steps of the plan signed as a whole (plan steps only)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub plan: Vec<PlanStepDigest>,
+
+    /// Steps of the plan approved, if not all of them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub steps: Option<StepSelection>,
+
+    /// Modifications the approver made (approvals only)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub modifications: Vec<Modification>,
+
+    /// Standing grant the approver created along with the approval
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grant: Option<GrantScope>,
+
+    /// Policy rule signed, for records the rule decided
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule: Option<Box<PolicyRule>>,
+
+    /// Ed25519 signature (hex)
+    pub signature: String,
+}
+
+impl RecordSignature {
+    /// Check the signature over `decision` on the record with digest
+    /// `record_digest` (not whether the key is trusted)
+    pub fn verify(&self, record_digest: &str, decision: &ApprovalDecision) -> Result<()> {
+        let key = parse_public_key(&self.public_key)?;
+        let signature = hex_decode(&self.signature)
+            .and_then(|bytes| Signature::from_slice(&bytes).ok())
+            .context("Malformed signature")?;
+        let message = decision_message(
+            &self.signed_digest(record_digest)?,
+            &self.signer,
+            decision,
+            &self.modifications,
+            self.grant.as_ref(),
+            self.steps.as_ref(),
+        )?;
+        key.verify(&message, &signature).map_err(|_| {
+            anyhow::anyhow!("Signature of {} does not match the decision", self.signer)
+        })
+    }
+
+    /// Check the signature against the record (not whether the key is trusted)
+    ///
+    /// The signer must have made the record's decision, or cast one of its
+    /// votes.
+    pub fn verify_record(&self, record: &ApprovalRecord) -> Result<()> {
+        let decision = if record.votes.is_empty() {
+            (record.approved_by == self.signer).then_some(&record.decision)
+        } else {
+            record
+                .votes
+                .iter()
+                .find(|v| v.approver == self.signer)
+                .map(|v| &v.decision)
+        };
+        let Some(decision) = decision else {
+            anyhow::bail!("{} signed but did not decide", self.signer);
+        };
+        if let Some(rule) = &self.rule {
+            check_rule_covers(rule, record)?;
+        }
+        self.verify(&RecordHeader::of(record).digest()?, decision)
+    }
+
+    /// Digest the signer signed for a record: the record's own, its plan's
+    /// (if the record's step was selected), or the policy rule's
+    fn signed_digest(&self, record_digest: &str) -> Result<String> {
+        if let Some(rule) = &self.rule {
+            return rule_digest(rule);
+        }
+        if self.plan.is_empty() {
+            if self.steps.is_some() {
+                anyhow::bail!("{} signed a step selection without a plan", self.signer);
+            }
+            return Ok(record_digest.to_string());
+        }
+        let Some(step) = self.plan.iter().find(|s| s.record_digest == record_digest) else {
+            anyhow::bail!("{} signed a plan without this record", self.signer);
+        };
+        if let Some(steps) = self.steps.as_ref().filter(|s| !s.includes(step.number)) {
+            anyhow::bail!(
+                "{} approved {}, not step {}",
+                self.signer,
+                steps,
+                step.number
+            );
+        }
+        plan_digest(&self.plan)
+    }
+}
+
+fn decision_message(
+    digest: &str,
+    signer: &str,
+    decision: &ApprovalDecision,
+    modifications: &[Modification],
+    grant: Option<&GrantScope>,
+    steps: Option<&StepSelection>,
+) -> Result<Vec<u8>> {
+    let verdict = match decision {
+        d if d.is_approved() => "approve",
+        ApprovalDecision::DeferredToLater => "defer",
+        _ => "deny",
+    };
+    Ok(format!(
+        "{}\n{}\n{}\n{}\n{}\n{}\n{}",
+        SIGNATURE_CONTEXT,
+        digest,
+        signer,
+        verdict,
+        serde_json::to_string(modifications)?,
+        serde_json::to_string(&grant)?,
+        serde_json::to_string(&steps)?
+    )
+    .into_bytes())
+}
+
+/// Digest of a policy rule (`sha256:<hex>`), as covered by its signature
+pub fn rule_digest(rule: &PolicyRule) -> Result<String> {
+    let unsigned = PolicyRule {
+        signature: None,
+        ..rule.clone()
+    };
+    sha256_digest(&serde_json::to_vec(&unsigned)?)
+}
+
+/// The signature of a signed allow rule, as stored on records it decides
+pub fn rule_signature(rule: &PolicyRule) -> Option<RecordSignature> {
+    let mut signature = rule.signature.clone()?;
+    signature.rule = Some(Box::new(PolicyRule {
+        signature: None,
+        ..rule.clone()
+    }));
+    Some(signature)
+}
+
+/// Check that a signed rule allows the action a record decided
+fn check_rule_covers(rule: &PolicyRule, record: &ApprovalRecord) -> Result<()> {
+    if rule.effect != PolicyEffect::Allow || record.action_type != Some(rule.action_type) {
+        anyhow::bail!("Policy rule ({}) does not allow this action", rule);
+    }
+    if let ChangeMatcher::PathPrefix { prefix } = &rule.changes {
+        let prefix = Path::new(prefix);
+        if record.paths.is_empty() || !record.paths.iter().all(|p| is_under(Path::new(p), prefix)) {
+            anyhow::bail!("Policy rule ({}) does not cover the paths", rule);
+        }
+    }
+    Ok(())
+}
+
+/// Check that a grant-approved record is within the grant its origin
+/// record created: requested in the same session, while the grant was in
+/// force, and covered by the grant's matcher
+fn check_granted(record: &ApprovalRecord, origin: &ApprovalRecord) -> Result<()> {
+    if record.grant_id.is_none() || origin.grant_id != record.grant_id {
+        anyhow::bail!("Origin record {} did not create this grant", origin.id);
+    }
+    if origin.action_type != record.action_type {
+        anyhow::bail!("Grant of {} covers another action type", origin.id);
+    }
+    let scope = origin
+        .signatures
+        .iter()
+        .find(|s| s.signer == record.approved_by)
+        .and_then(|s| s.grant.as_ref())
+        .with_context(|| format!("{} did not sign a grant", record.approved_by))?;
+    if origin.session_id.is_none() || origin.session_id != record.session_id {
+        anyhow::bail!("Grant of {} belongs to another session", origin.id);
+    }
+    if record.timestamp < origin.timestamp {
+        anyhow::bail!("Record predates the grant of {}", origin.id);
+    }
+    if let GrantScope::Minutes(minutes) = scope {
+        let expires = origin.timestamp + chrono::Duration::minutes(*minutes as i64);
+        if record.timestamp > expires {
+            anyhow::bail!("Grant of {} had expired", origin.id);
+        }
+    }
+
+    // The grant's matcher, as derived from the origin's changes
+    let matcher = match scope {
+        GrantScope::PathPrefix(prefix) => ChangeMatcher::PathPrefix {
+            prefix: prefix.clone(),
+        },
+        _ => origin
+            .command
+            .clone()
+            .map_or(ChangeMatcher::Any, |command| ChangeMatcher::Command {
+                command,
+            }),
+    };
+    let covered = match &matcher {
+        ChangeMatcher::Any => true,
+        ChangeMatcher::PathPrefix { prefix } => {
+            !record.paths.is_empty()
+                && record
+                    .paths
+                    .iter()
+                    .all(|p| is_under(Path::new(p), Path::new(prefix)))
+        }
+        ChangeMatcher::Command { command } => record.command.as_ref() == Some(command),
+    };
+    if !covered {
+        anyhow::bail!(
+            "Grant of {} ({}) does not cover the action",
+            origin.id,
+            matcher
+        );
+    }
+    Ok(())
+}
+
+/// Digest of a Diff Card (`sha256:<hex>`), as covered by signatures
+pub fn diff_card_digest(diff_card: &DiffCard) -> Result<String> {
+    sha256_digest(&serde_json::to_vec(diff_card)?)
+}
+
+fn sha256_digest(bytes: &[u8]) -> Result<String> {
+    Ok(format!("sha256:{}", hex_encode(&Sha256::digest(bytes))))
+}
+
+fn parse_public_key(hex: &str) -> Result<VerifyingKey> {
+    let bytes: [u8; 32] = hex_decode(hex)
+        .and_then(|bytes| bytes.try_into().ok())
+        .context("Malformed public key")?;
+    VerifyingKey::from_bytes(&bytes).context("Invalid public key")
+}
+
+/// Approvers whose signatures an approval needs
+///
+/// Every approving voter for multi-party approvals, otherwise the approver.
+fn required_signers(record: &ApprovalRecord) -> Vec<&str> {
+    if record.votes.is_empty() {
+        vec![record.approved_by.as_str()]
+    } else {
+        record
+            .votes
+            .iter()
+            .filter(|v| v.decision.is_approved())
+            .map(|v| v.approver.as_str())
+            .collect()
+    }
+}
+
+/// Approver signing keys stored in a local directory
+#[derive(Debug, Clone)]
+pub struct Keyring {
+    dir: PathBuf,
+}
+
+impl Keyring {
+    /// Keyring in `dir` (created when the first key is generated)
+    pub fn open(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Default keyring location (`<data dir>/keys`)
+    pub fn default_path() -> PathBuf {
+        super::data_dir().join("keys")
+    }
+
+    /// Generate a signing key for an approver, returning its public key (hex)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the approver already has a key.
+    pub fn generate(&self, approver: &str) -> Result<String> {
+        let path = self.key_path(approver)?;
+        if path.exists() {
+            anyhow::bail!("{} already has a key ({})", approver, path.display());
+        }
+        std::fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create {}", self.dir.display()))?;
+
+        let key = SigningKey::from_bytes(&rand::random::<[u8; 32]>());
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options
+            .open(&path)
+            .with_context(|| format!("Failed to create {}", path.display()))?;
+        std::io::Write::write_all(&mut file, hex_encode(&key.to_bytes()).as_bytes())
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+
+        Ok(hex_encode(key.verifying_key().as_bytes()))
+    }
+
+    /// Public key (hex) of an approver, if they have a key
+    pub fn public_key(&self, approver: &str) -> Result<Option<String>> {
+        Ok(self
+            .signing_key(approver)?
+            .map(|key| hex_encode(key.verifying_key().as_bytes())))
+    }
+
+    /// All approvers with a key, with their public keys
+    pub fn list(&self) -> Result<Vec<(String, String)>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut keys = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)
+            .with_context(|| format!("Failed to read keyring {}", self.dir.display()))?
+        {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "key") {
+                if let Some(approver) = path.file_stem().and_then(|s| s.to_str()) {
+                    if let Some(public_key) = self.public_key(approver)? {
+                        keys.push((approver.to_string(), public_key));
+                    }
+                }
+            }
+        }
+        keys.sort();
+        Ok(keys)
+    }
+
+    /// Sign a decision as `signer`, if the keyring holds their key
+    ///
+    /// `digest` is the digest of the request (see
+    /// [`ApprovalRequest::digest`](super::backend::ApprovalRequest::digest)).
+    pub fn sign(
+        &self,
+        signer: &str,
+        digest: &str,
+        decision: &ApprovalDecision,
+        modifications: &[Modification],
+        grant: Option<&GrantScope>,
+    ) -> Result<Option<RecordSignature>> {
+        self.sign_decision(signer, digest, decision, modifications, grant, None)
+    }
+
+    /// Sign a decision on the selected steps of a plan as `signer`, if the
+    /// keyring holds their key
+    ///
+    /// `digest` is the plan request's digest.
+    pub fn sign_steps(
+        &self,
+        signer: &str,
+        digest: &str,
+        decision: &ApprovalDecision,
+        steps: &StepSelection,
+    ) -> Result<Option<RecordSignature>> {
+        let steps = Some(steps).filter(|s| **s != StepSelection::All);
+        self.sign_decision(signer, digest, decision, &[], None, steps)
+    }
+
+    fn sign_decision(
+        &self,
+        signer: &str,
+        digest: &str,
+        decision: &ApprovalDecision,
+        modifications: &[Modification],
+        grant: Option<&GrantScope>,
+        steps: Option<&StepSelection>,
+    ) -> Result<Option<RecordSignature>> {
+        let Some(key) = self.signing_key(signer)? else {
+            return Ok(None);
+        };
+        let message = decision_message(digest, signer, decision, modifications, grant, steps)?;
+        Ok(Some(RecordSignature {
+            signer: signer.to_string(),
+            public_key: hex_encode(key.verifying_key().as_bytes()),
+            plan: Vec::new(),
+            steps: steps.cloned(),
+            modifications: modifications.to_vec(),
+            grant: grant.cloned(),
+            rule: None,
+            signature: hex_encode(&key.sign(&message).to_bytes()),
+        }))
+    }
+
+    /// Sign a policy allow rule as `signer`, if the keyring holds their key
+    pub fn sign_rule(&self, signer: &str, rule: &PolicyRule) -> Result<Option<RecordSignature>> {
+        if rule.effect != PolicyEffect::Allow {
+            anyhow::bail!("Only allow rules are signed ({})", rule);
+        }
+        self.sign(
+            signer,
+            &rule_digest(rule)?,
+            &ApprovalDecision::Approved,
+            &[],
+            None,
+        )
+    }
+
+    fn signing_key(&self, approver: &str) -> Result<Option<SigningKey>> {
+        let path = self.key_path(approver)?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        let hex = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read key {}", path.display()))?;
+        let bytes: [u8; 32] = hex_decode(hex.trim())
+            .and_then(|bytes| bytes.try_into().ok())
+            .with_context(|| format!("Malformed key {}", path.display()))?;
+        Ok(Some(SigningKey::from_bytes(&bytes)))
+    }
+
+    fn key_path(&self, approver: &str) -> Result<PathBuf> {
+        let valid = !approver.is_empty()
+            && !approver.starts_with('.')
+            && approver
+                .chars()
+                .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | '@'));
+        if !valid {
+            anyhow::bail!("Invalid approver name '{}' for a key", approver);
+        }
+        Ok(self.dir.join(format!("{}.key", approver)))
+    }
+}
+
+/// A public key trusted to sign for an approver
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrustedKey {
+    /// Approver the key signs for
+    pub approver: String,
+
+    /// Public key (hex)
+    pub public_key: String,
+}
+
+/// Public keys trusted to sign approvals
+#[derive(Debug, Clone, Default)]
+pub struct TrustList {
+    path: Option<PathBuf>,
+    keys: Vec<TrustedKey>,
+}
+
+impl TrustList {
+    /// Empty trust list kept in memory
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    /// Open (or create) a trust list backed by a JSON file
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let keys = if path.exists() {
+            let json = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read trust list {}", path.display()))?;
+            serde_json::from_str(&json)
+                .with_context(|| format!("Failed to parse trust list {}", path.display()))?
+        } else {
+            Vec::new()
+        };
+        Ok(Self {
+            path: Some(path),
+            keys,
+        })
+    }
+
+    /// Default trust list location (`<data dir>/trusted_keys.json`)
+    pub fn default_path() -> PathBuf {
+        super::data_dir().join("trusted_keys.json")
+    }
+
+    /// Trust a public key (hex) to sign for an approver
+    pub fn add(&mut self, approver: impl Into<String>, public_key: &str) -> Result<()> {
+        parse_public_key(public_key)?;
+        let key = TrustedKey {
+            approver: approver.into(),
+            public_key: public_key.to_ascii_lowercase(),
+        };
+        if !self.keys.contains(&key) {
+            self.keys.push(key);
+        }
+        match &self.path {
+            Some(path) => super::write_json_atomic(path, &self.keys),
+            None => Ok(()),
+        }
+    }
+
+    /// All trusted keys
+    pub fn keys(&self) -> &[TrustedKey] {
+        &self.keys
+    }
+
+    /// Whether `public_key` may sign for `approver`
+    pub fn is_trusted(&self, approver: &str, public_key: &str) -> bool {
+        self.keys
+            .iter()
+            .any(|k| k.approver == approver && k.public_key.eq_ignore_ascii_case(public_key))
+    }
+
+    /// Check that `approver` signed `decision` on the record with digest
+    /// `record_digest`, with a key trusted for them
+    pub fn authenticate(
+        &self,
+        approver: &str,
+        record_digest: &str,
+        decision: &ApprovalDecision,
+        signature: Option<&RecordSignature>,
+    ) -> Result<()> {
+        let signature = signature.with_context(|| format!("{} did not sign", approver))?;
+        if signature.signer != approver {
+            anyhow::bail!("Signed by {}, not {}", signature.signer, approver);
+        }
+        signature.verify(record_digest, decision)?;
+        if !self.is_trusted(approver, &signature.public_key) {
+            anyhow::bail!("Key of {} is not in the trust list", approver);
+        }
+        Ok(())
+    }
+
+    /// Verify a record's signatures
+    ///
+    /// Every signature must match the decision its signer made on this
+    /// record and come from a key trusted for the signer. Approvals must
+    /// also be signed by every approver, and amendments by an approver who
+    /// made them. Approvals by a standing grant need the grant's origin
+    /// record (see [`verify_with`](Self::verify_with)).
+    pub fn verify(&self, record: &ApprovalRecord) -> Result<()> {
+        self.verify_with(record, None)
+    }
+
+    /// Verify a record's signatures, given the origin record of the grant
+    /// that approved it (if any)
+    ///
+    /// A grant-approved record is valid if its origin record verifies, and
+    /// the origin's approver signed a grant scope that covers the record.
+    pub fn verify_with(
+        &self,
+        record: &ApprovalRecord,
+        origin: Option<&ApprovalRecord>,
+    ) -> Result<()> {
+        if let (true, Some(origin_id)) = (record.decision.is_approved(), &record.origin_record_id) {
+            let origin = origin
+                .filter(|o| &o.id == origin_id)
+                .with_context(|| format!("Grant origin record {} not found", origin_id))?;
+            self.verify(origin)
+                .with_context(|| format!("Grant origin record {}", origin_id))?;
+            return check_granted(record, origin);
+        }
+
+        for signature in &record.signatures {
+            signature.verify_record(record)?;
+            if !self.is_trusted(&signature.signer, &signature.public_key) {
+                anyhow::bail!("Key of {} is not in the trust list", signature.signer);
+            }
+        }
+
+        if record.decision.is_approved() {
+            for signer in required_signers(record) {
+                if !record.signatures.iter().any(|s| s.signer == signer) {
+                    anyhow::bail!("Approval is not signed by {}", signer);
+                }
+            }
+        }
+        if let Some(amendment) = record.decision.amendment() {
+            if !record
+                .signatures
+                .iter()
+                .any(|s| s.modifications == amendment.modifications)
+            {
+                anyhow::bail!("Modifications are not signed by an approver");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Result of verifying an exported audit log
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LogVerification {
+    /// Records checked
+    pub checked: usize,
+
+    /// Records that failed, with the reason
+    pub failures: Vec<(String, String)>,
+}
+
+impl LogVerification {
+    /// Whether every record verified
+    pub fn is_valid(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// Verify every record of an exported audit log against a trust list
+///
+/// Accepts the JSON array of `export_audit_log`, the JSON lines of
+/// `luminaguard audit export --format jsonl`, or a history log file.
+pub fn verify_audit_log(log: &str, trust: &TrustList) -> Result<LogVerification> {
+    let values: Vec<serde_json::Value> = if log.trim_start().starts_with('[') {
+        serde_json::from_str(log).context("Failed to parse audit log")?
+    } else {
+        log.lines()
+            .filter(|line| !line.trim().is_empty())
+            .enumerate()
+            .map(|(n, line)| {
+                serde_json::from_str(line)
+                    .with_context(|| format!("Failed to parse audit log line {}", n + 1))
+            })
+            .collect::<Result<_>>()?
+    };
+
+    // History logs interleave execution outcomes, which are not signed
+    let records = values
+        .into_iter()
+        .filter(|value| value.get("entry").and_then(|e| e.as_str()) != Some("outcome"))
+        .map(|value| serde_json::from_value(value).context("Malformed record in audit log"))
+        .collect::<Result<Vec<ApprovalRecord>>>()?;
+    let by_id: HashMap<&str, &ApprovalRecord> =
+        records.iter().map(|r| (r.id.as_str(), r)).collect();
+
+    let mut verification = LogVerification::default();
+    for record in &records {
+        verification.checked += 1;
+        let origin = record
+            .origin_record_id
+            .as_deref()
+            .and_then(|id| by_id.get(id).copied());
+        if let Err(e) = trust.verify_with(record, origin) {
+            verification
+                .failures
+                .push((record.id.clone(), format!("{:#}", e)));
+        }
+    }
+    Ok(verification)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(approved_by: &str, decision: ApprovalDecision) -> ApprovalRecord {
+        ApprovalRecord {
+            id: uuid::Uuid::new_v4().to_string(),
+            timestamp: chrono::Utc::now(),
+            action_description: "Delete /tmp/a".to_string(),
+            action_type: None,
+            decision,
+            approved_by: approved_by.to_string(),
+            justification: Some("cleanup".to_string()),
+            execution_result: None,
+            votes: Vec::new(),
+            grant_id: None,
+            origin_record_id: None,
+            deferred_id: None,
+            plan_id: None,
+            requested_by: None,
+            session_id: None,
+            paths: Vec::new(),
+            command: None,
+            latency_ms: None,
+            anomalies: Vec::new(),
+            timeout: None,
+            diff_card_digest: Some("sha256:abc".to_string()),
+            signatures: Vec::new(),
+        }
+    }
+
+    fn keyring_with(approver: &str) -> (tempfile::TempDir, Keyring, TrustList) {
+        let dir = tempfile::tempdir().unwrap();
+        let keyring = Keyring::open(dir.path().join("keys"));
+        let public_key = keyring.generate(approver).unwrap();
+        let mut trust = TrustList::in_memory();
+        trust.add(approver, &public_key).unwrap();
+        (dir, keyring, trust)
+    }
+
+    fn signed(keyring: &Keyring, mut record: ApprovalRecord) -> ApprovalRecord {
+        let digest = RecordHeader::of(&record).digest().unwrap();
+        let signature = keyring
+            .sign(&record.approved_by, &digest, &record.decision, &[], None)
+            .unwrap()
+            .unwrap();
+        record.signatures.push(signature);
+        record
+    }
+
+    #[test]
+    fn test_signed_record_verifies() {
+        let (_dir, keyring, trust) = keyring_with("alice");
+        let approval = signed(&keyring, record("alice", ApprovalDecision::Approved));
+
+        assert_eq!(approval.signatures.len(), 1);
+        trust.verify(&approval).unwrap();
+
+        // The signature only holds for the decision that was signed
+        let mut tampered = approval.clone();
+        tampered.decision = ApprovalDecision::DeferredToLater;
+        assert!(trust.verify(&tampered).is_err());
+        let mut tampered = approval.clone();
+        tampered.approved_by = "bob".to_string();
+        assert!(trust.verify(&tampered).is_err());
+
+        // Or the record it was made on
+        let mut tampered = approval.clone();
+        tampered.diff_card_digest = Some("sha256:abd".to_string());
+        assert!(trust.verify(&tampered).is_err());
+        let mut tampered = approval.clone();
+        tampered.paths = vec!["/etc/passwd".to_string()];
+        assert!(trust.verify(&tampered).is_err());
+        let mut tampered = approval.clone();
+        tampered.timestamp += chrono::Duration::seconds(1);
+        assert!(trust.verify(&tampered).is_err());
+    }
+
+    #[test]
+    fn test_transplanted_signature_rejected() {
+        let (_dir, keyring, trust) = keyring_with("alice");
+        let approval = signed(&keyring, record("alice", ApprovalDecision::Approved));
+
+        // The same signature on a fabricated record for another action
+        let mut fabricated = record("alice", ApprovalDecision::Approved);
+        fabricated.action_description = "Delete /etc".to_string();
+        fabricated.signatures = approval.signatures.clone();
+        assert!(trust.verify(&fabricated).is_err());
+
+        // Even with the signed card digest, on a record with another ID
+        let mut fabricated = approval.clone();
+        fabricated.id = uuid::Uuid::new_v4().to_string();
+        assert!(trust.verify(&fabricated).is_err());
+    }
+
+    #[test]
+    fn test_plan_signature_covers_each_step() {
+        let (_dir, keyring, trust) = keyring_with("alice");
+        let steps = [
+            record("alice", ApprovalDecision::Approved),
+            record("alice", ApprovalDecision::Approved),
+        ];
+        let plan: Vec<PlanStepDigest> = steps
+            .iter()
+            .enumerate()
+            .map(|(i, step)| PlanStepDigest {
+                number: i + 1,
+                record_digest: RecordHeader::of(step).digest().unwrap(),
+            })
+            .collect();
+        let mut signature = keyring
+            .sign(
+                "alice",
+                &plan_digest(&plan).unwrap(),
+                &ApprovalDecision::Approved,
+                &[],
+                None,
+            )
+            .unwrap()
+            .unwrap();
+        signature.plan = plan;
+
+        for mut step in steps {
+            step.signatures.push(signature.clone());
+            trust.verify(&step).unwrap();
+        }
+        // Not for a record outside the plan
+        let mut other = record("alice", ApprovalDecision::Approved);
+        other.signatures.push(signature);
+        assert!(trust.verify(&other).is_err());
+    }
+
+    #[test]
+    fn test_step_selection_signed() {
+        let (_dir, keyring, trust) = keyring_with("alice");
+        let steps = [
+            record("alice", ApprovalDecision::Approved),
+            record("alice", ApprovalDecision::Approved),
+        ];
+        let plan: Vec<PlanStepDigest> = steps
+            .iter()
+            .enumerate()
+            .map(|(i, step)| PlanStepDigest {
+                number: i + 1,
+                record_digest: RecordHeader::of(step).digest().unwrap(),
+            })
+            .collect();
+        let mut signature = keyring
+            .sign_steps(
+                "alice",
+                &plan_digest(&plan).unwrap(),
+                &ApprovalDecision::Approved,
+                &StepSelection::Through(1),
+            )
+            .unwrap()
+            .unwrap();
+        signature.plan = plan;
+
+        let [mut first, mut second] = steps;
+        first.signatures.push(signature.clone());
+        trust.verify(&first).unwrap();
+        second.signatures.push(signature.clone());
+        let err = trust.verify(&second).unwrap_err();
+        assert!(err.to_string().contains("not step 2"), "{err:#}");
+
+        // Widening the selection breaks the signature
+        second.signatures[0].steps = None;
+        assert!(trust.verify(&second).is_err());
+        signature.steps = Some(StepSelection::All);
+        first.signatures[0] = signature;
+        assert!(trust.verify(&first).is_err());
+    }
+
+    #[test]
+    fn test_unsigned_or_untrusted_approval_rejected() {
+        let (_dir, keyring, trust) = keyring_with("alice");
+
+        // Fabricated approval without a signature
+        assert!(trust
+            .verify(&record("alice", ApprovalDecision::Approved))
+            .is_err());
+        // Unsigned denials are fine
+        trust
+            .verify(&record("system", ApprovalDecision::Denied))
+            .unwrap();
+
+        // Signed by a key not trusted for the approver
+        keyring.generate("mallory").unwrap();
+        let forged = signed(&keyring, record("mallory", ApprovalDecision::Approved));
+        assert!(trust.verify(&forged).is_err());
+    }
+
+    #[test]
+    fn test_amendment_must_be_signed() {
+        use crate::approval::amend::Amendment;
+
+        let (_dir, keyring, trust) = keyring_with("alice");
+        let drop = vec![Modification::DropChange { index: 0 }];
+        let mut approval = record("alice", ApprovalDecision::Approved);
+        approval.decision = ApprovalDecision::ApprovedWithModifications(Box::new(Amendment {
+            modifications: drop.clone(),
+            original_changes: Vec::new(),
+            changes: Vec::new(),
+            original_arguments: None,
+            arguments: None,
+        }));
+
+        // Signed without the modifications
+        let unsigned = signed(&keyring, approval.clone());
+        assert!(trust.verify(&unsigned).is_err());
+
+        let digest = RecordHeader::of(&approval).digest().unwrap();
+        let signature = keyring
+            .sign("alice", &digest, &approval.decision, &drop, None)
+            .unwrap()
+            .unwrap();
+        approval.signatures.push(signature);
+        trust.verify(&approval).unwrap();
+    }
+
+    #[test]
+    fn test_granted_record_within_grant() {
+        let (_dir, keyring, trust) = keyring_with("alice");
+        let mut origin = record("alice", ApprovalDecision::Approved);
+        origin.action_type = Some(ActionType::ExecuteCommand);
+        origin.session_id = Some("session-1".to_string());
+        origin.command = Some("cargo".to_string());
+        origin.grant_id = Some("grant-1".to_string());
+        let digest = RecordHeader::of(&origin).digest().unwrap();
+        let scope = GrantScope::Minutes(10);
+        let signature = keyring
+            .sign("alice", &digest, &origin.decision, &[], Some(&scope))
+            .unwrap()
+            .unwrap();
+        origin.signatures.push(signature);
+
+        let granted = ApprovalRecord {
+            id: uuid::Uuid::new_v4().to_string(),
+            timestamp: origin.timestamp + chrono::Duration::minutes(1),
+            origin_record_id: Some(origin.id.clone()),
+            signatures: Vec::new(),
+            ..origin.clone()
+        };
+        trust.verify_with(&granted, Some(&origin)).unwrap();
+
+        // Another command than the one granted
+        let mut forged = granted.clone();
+        forged.command = Some("rm".to_string());
+        assert!(trust.verify_with(&forged, Some(&origin)).is_err());
+        let mut forged = granted.clone();
+        forged.command = None;
+        assert!(trust.verify_with(&forged, Some(&origin)).is_err());
+
+        // Another session
+        let mut forged = granted.clone();
+        forged.session_id = Some("session-2".to_string());
+        assert!(trust.verify_with(&forged, Some(&origin)).is_err());
+
+        // Before the grant was created, or after it expired
+        let mut forged = granted.clone();
+        forged.timestamp = origin.timestamp - chrono::Duration::minutes(1);
+        assert!(trust.verify_with(&forged, Some(&origin)).is_err());
+        let mut forged = granted.clone();
+        forged.timestamp = origin.timestamp + chrono::Duration::minutes(11);
+        assert!(trust.verify_with(&forged, Some(&origin)).is_err());
+    }
+
+    #[test]
+    fn test_verify_exported_log() {
+        let (_dir, keyring, trust) = keyring_with("alice");
+        let signed = signed(&keyring, record("alice", ApprovalDecision::Approved));
+        let forged = record("alice", ApprovalDecision::Approved);
+
+        let log = serde_json::to_string(&vec![signed, forged.clone()]).unwrap();
+        let verification = verify_audit_log(&log, &trust).unwrap();
+        assert_eq!(verification.checked, 2);
+        assert_eq!(verification.failures.len(), 1);
+        assert_eq!(verification.failures[0].0, forged.id);
+    }
+
+    #[test]
+    fn test_key_names_validated() {
+        let keyring = Keyring::open("/nonexistent");
+        assert!(keyring.generate("../etc/passwd").is_err());
+        assert_eq!(keyring.public_key("nobody").unwrap(), None);
+    }
+}
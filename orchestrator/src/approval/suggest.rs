@@ -0,0 +1,450 @@
+//! Policy Suggestions Mined from Approval History
+//!
+//! Looks for patterns that approvers always decide the same way and proposes
+//! [`PolicyRule`]s for them, with the statistics behind each one. Nothing is
+//! applied: `luminaguard audit suggest-policy` prints the suggestions in the
+//! policy file format for a human to review and commit.
+//!
+//! Only decisions made by people count (standing grants, policy rules,
+//! timeouts and other automatic decisions are skipped), and deferrals are
+//! not decisions. For each action type, decisions are grouped by the deepest
+//! directory containing the files each action touched, and uniform groups get
+//! a path prefix rule. If decisions on the action type are uniform but not
+//! all covered by those rules (e.g. actions without files), one rule covers
+//! the whole action type instead; allow rules are never suggested this
+//! broadly for Critical action types.
+//!
+//! A pattern needs [`SuggestOptions::min_samples`] decisions, and
+//! [`SuggestOptions::min_rate`] of them must be plain approvals (for allow
+//! rules) or denials (for deny rules). Approvals with modifications count
+//! against allow rules.
+
+use super::action::{ActionType, RiskLevel};
+use super::grants::{common_directory, ChangeMatcher};
+use super::history::{ApprovalDecision, ApprovalRecord};
+use super::policy::{ApprovalPolicy, PolicyEffect, PolicyRule};
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+/// Thresholds for suggesting a rule
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SuggestOptions {
+    /// Decisions a pattern needs before a rule is suggested
+    pub min_samples: usize,
+
+    /// Share of decisions (0.0 to 1.0) that must agree with the rule
+    pub min_rate: f64,
+}
+
+impl Default for SuggestOptions {
+    fn default() -> Self {
+        Self {
+            min_samples: 10,
+            min_rate: 1.0,
+        }
+    }
+}
+
+/// Decisions made on one pattern
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternStats {
+    /// Decisions counted
+    pub total: usize,
+
+    /// Approved as requested
+    pub approved: usize,
+
+    /// Approved with modifications
+    pub modified: usize,
+
+    /// Denied
+    pub denied: usize,
+
+    /// Users who decided
+    pub approvers: BTreeSet<String>,
+
+    /// Earliest decision
+    pub first_seen: DateTime<Utc>,
+
+    /// Latest decision
+    pub last_seen: DateTime<Utc>,
+}
+
+impl PatternStats {
+    fn new(record: &ApprovalRecord) -> Self {
+        Self {
+            total: 0,
+            approved: 0,
+            modified: 0,
+            denied: 0,
+            approvers: BTreeSet::new(),
+            first_seen: record.timestamp,
+            last_seen: record.timestamp,
+        }
+    }
+
+    fn add(&mut self, record: &ApprovalRecord) {
+        self.total += 1;
+        match record.decision {
+            ApprovalDecision::Approved => self.approved += 1,
+            ApprovalDecision::ApprovedWithModifications(_) => self.modified += 1,
+            _ => self.denied += 1,
+        }
+        if record.votes.is_empty() {
+            self.approvers.insert(record.approved_by.clone());
+        } else {
+            self.approvers
+                .extend(record.votes.iter().map(|v| v.approver.clone()));
+        }
+        self.first_seen = self.first_seen.min(record.timestamp);
+        self.last_seen = self.last_seen.max(record.timestamp);
+    }
+
+    /// Effect the decisions agree on, if any
+    fn effect(&self, options: &SuggestOptions) -> Option<PolicyEffect> {
+        if self.total < options.min_samples {
+            return None;
+        }
+        let rate = |n: usize| n as f64 / self.total as f64;
+        if rate(self.approved) >= options.min_rate {
+            Some(PolicyEffect::Allow)
+        } else if rate(self.denied) >= options.min_rate {
+            Some(PolicyEffect::Deny)
+        } else {
+            None
+        }
+    }
+
+    /// Number of decisions agreeing with an effect
+    pub fn agreeing(&self, effect: PolicyEffect) -> usize {
+        match effect {
+            PolicyEffect::Allow => self.approved,
+            PolicyEffect::Deny => self.denied,
+        }
+    }
+}
+
+/// A proposed rule and the decisions behind it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    /// Proposed rule (its note summarizes the statistics)
+    pub rule: PolicyRule,
+
+    /// Decisions on the rule's pattern
+    pub stats: PatternStats,
+}
+
+impl fmt::Display for Suggestion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {}/{} {}",
+            self.rule,
+            self.stats.agreeing(self.rule.effect),
+            self.stats.total,
+            past_tense(self.rule.effect)
+        )?;
+        if self.stats.modified > 0 {
+            write!(f, ", {} modified", self.stats.modified)?;
+        }
+        write!(f, " by {}", join(&self.stats.approvers))
+    }
+}
+
+/// Suggest rules for patterns in `records` not already in `existing`
+///
+/// Suggestions are ordered by action type name, then by directory.
+pub fn suggest_policy<'a>(
+    records: impl IntoIterator<Item = &'a ApprovalRecord>,
+    options: &SuggestOptions,
+    existing: &ApprovalPolicy,
+) -> Vec<Suggestion> {
+    // Per action type: all decisions, and decisions per directory
+    let mut patterns: BTreeMap<String, (ActionType, PatternStats, BTreeMap<String, PatternStats>)> =
+        BTreeMap::new();
+    for record in records.into_iter().filter(|r| is_user_decision(r)) {
+        let Some(action_type) = record.action_type else {
+            continue;
+        };
+        let (_, overall, directories) = patterns
+            .entry(action_type.to_string())
+            .or_insert_with(|| (action_type, PatternStats::new(record), BTreeMap::new()));
+        overall.add(record);
+        if let Some(directory) = common_directory(record.paths.iter().map(String::as_str)) {
+            directories
+                .entry(directory)
+                .or_insert_with(|| PatternStats::new(record))
+                .add(record);
+        }
+    }
+
+    let mut suggestions = Vec::new();
+    for (action_type, overall, directories) in patterns.into_values() {
+        let concrete: Vec<Suggestion> = directories
+            .into_iter()
+            .filter_map(|(prefix, stats)| {
+                let effect = stats.effect(options)?;
+                let matcher = ChangeMatcher::PathPrefix { prefix };
+                Some(suggestion(effect, action_type, matcher, stats))
+            })
+            .collect();
+
+        let broad = overall.effect(options).filter(|effect| {
+            *effect == PolicyEffect::Deny || action_type.risk_level() < RiskLevel::Critical
+        });
+        let covered = |effect| -> usize {
+            concrete
+                .iter()
+                .filter(|s| s.rule.effect == effect)
+                .map(|s| s.stats.total)
+                .sum()
+        };
+        match broad {
+            Some(effect) if covered(effect) < overall.total => {
+                suggestions.push(suggestion(effect, action_type, ChangeMatcher::Any, overall));
+            }
+            _ => suggestions.extend(concrete),
+        }
+    }
+    suggestions.retain(|s| !existing.contains(&s.rule));
+    suggestions
+}
+
+/// Policy file holding the suggested rules
+pub fn suggested_policy(suggestions: &[Suggestion]) -> ApprovalPolicy {
+    ApprovalPolicy {
+        rules: suggestions.iter().map(|s| s.rule.clone()).collect(),
+    }
+}
+
+fn suggestion(
+    effect: PolicyEffect,
+    action_type: ActionType,
+    changes: ChangeMatcher,
+    stats: PatternStats,
+) -> Suggestion {
+    let note = format!(
+        "Suggested from history: {} of {} decisions {} by {} between {} and {}",
+        stats.agreeing(effect),
+        stats.total,
+        past_tense(effect),
+        join(&stats.approvers),
+        stats.first_seen.format("%Y-%m-%d"),
+        stats.last_seen.format("%Y-%m-%d")
+    );
+    Suggestion {
+        rule: PolicyRule::new(effect, action_type, changes).with_note(note),
+        stats,
+    }
+}
+
+/// Whether a person made the decision (and it is final)
+fn is_user_decision(record: &ApprovalRecord) -> bool {
+    record.origin_record_id.is_none()
+        && record.approved_by != "system"
+        && record.decision != ApprovalDecision::DeferredToLater
+}
+
+fn past_tense(effect: PolicyEffect) -> &'static str {
+    match effect {
+        PolicyEffect::Allow => "approved",
+        PolicyEffect::Deny => "denied",
+    }
+}
+
+fn join(names: &BTreeSet<String>) -> String {
+    names
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(
+        action_type: ActionType,
+        path: Option<&str>,
+        decision: ApprovalDecision,
+        approved_by: &str,
+    ) -> ApprovalRecord {
+        ApprovalRecord {
+            id: uuid::Uuid::new_v4().to_string(),
+            timestamp: Utc::now(),
+            action_description: format!("{} {:?}", action_type, path),
+            action_type: Some(action_type),
+            decision,
+            approved_by: approved_by.to_string(),
+            justification: None,
+            execution_result: None,
+            votes: Vec::new(),
+            grant_id: None,
+            origin_record_id: None,
+            deferred_id: None,
+            plan_id: None,
+            requested_by: Some("agent".to_string()),
+            session_id: None,
+            paths: path.map(String::from).into_iter().collect(),
+            command: None,
+            latency_ms: Some(4000),
+            anomalies: Vec::new(),
+            timeout: None,
+            diff_card_digest: None,
+            signatures: Vec::new(),
+        }
+    }
+
+    fn repeat(n: usize, record: impl Fn(usize) -> ApprovalRecord) -> Vec<ApprovalRecord> {
+        (0..n).map(record).collect()
+    }
+
+    #[test]
+    fn test_always_approved_directory_suggested() {
+        let mut records = repeat(12, |i| {
+            record(
+                ActionType::CreateFile,
+                Some(&format!("/home/agent/out/{}.txt", i)),
+                ApprovalDecision::Approved,
+                if i % 2 == 0 { "alice" } else { "bob" },
+            )
+        });
+        // Mixed elsewhere, so no rule covers every CreateFile
+        records.extend(repeat(3, |i| {
+            record(
+                ActionType::CreateFile,
+                Some(&format!("/etc/{}.conf", i)),
+                ApprovalDecision::Denied,
+                "alice",
+            )
+        }));
+
+        let suggestions =
+            suggest_policy(&records, &SuggestOptions::default(), &ApprovalPolicy::new());
+
+        assert_eq!(suggestions.len(), 1);
+        let rule = &suggestions[0].rule;
+        assert_eq!(rule.effect, PolicyEffect::Allow);
+        assert_eq!(rule.action_type, ActionType::CreateFile);
+        assert_eq!(
+            rule.changes,
+            ChangeMatcher::PathPrefix {
+                prefix: "/home/agent/out".to_string()
+            }
+        );
+        assert!(rule
+            .note
+            .as_deref()
+            .unwrap()
+            .starts_with("Suggested from history: 12 of 12 decisions approved by alice, bob"));
+        assert_eq!(
+            suggestions[0].to_string(),
+            "allow CreateFile, path under /home/agent/out: 12/12 approved by alice, bob"
+        );
+    }
+
+    #[test]
+    fn test_always_denied_action_type_suggested() {
+        let records = repeat(10, |_| {
+            record(
+                ActionType::TransferAsset,
+                None,
+                ApprovalDecision::Denied,
+                "alice",
+            )
+        });
+
+        let suggestions =
+            suggest_policy(&records, &SuggestOptions::default(), &ApprovalPolicy::new());
+
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].rule.effect, PolicyEffect::Deny);
+        assert_eq!(suggestions[0].rule.changes, ChangeMatcher::Any);
+
+        // Already in the policy: nothing new to suggest
+        let policy = suggested_policy(&suggestions);
+        assert!(suggest_policy(&records, &SuggestOptions::default(), &policy).is_empty());
+    }
+
+    #[test]
+    fn test_thresholds_and_automatic_decisions() {
+        let mut records = repeat(9, |i| {
+            record(
+                ActionType::EditFile,
+                Some(&format!("/srv/app/{}.rs", i)),
+                ApprovalDecision::Approved,
+                "alice",
+            )
+        });
+        // Automatic decisions do not count towards the sample size
+        records.push(record(
+            ActionType::EditFile,
+            Some("/srv/app/x.rs"),
+            ApprovalDecision::Approved,
+            "system",
+        ));
+        assert!(
+            suggest_policy(&records, &SuggestOptions::default(), &ApprovalPolicy::new()).is_empty()
+        );
+
+        records.push(record(
+            ActionType::EditFile,
+            Some("/srv/app/y.rs"),
+            ApprovalDecision::Denied,
+            "bob",
+        ));
+        assert!(
+            suggest_policy(&records, &SuggestOptions::default(), &ApprovalPolicy::new()).is_empty()
+        );
+
+        let lenient = SuggestOptions {
+            min_samples: 10,
+            min_rate: 0.9,
+        };
+        let suggestions = suggest_policy(&records, &lenient, &ApprovalPolicy::new());
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(
+            suggestions[0].rule.changes,
+            ChangeMatcher::PathPrefix {
+                prefix: "/srv/app".to_string()
+            }
+        );
+        assert_eq!(suggestions[0].stats.total, 10);
+
+        // Actions without files need a rule for the whole action type
+        records.push(record(
+            ActionType::EditFile,
+            None,
+            ApprovalDecision::Approved,
+            "alice",
+        ));
+        let suggestions = suggest_policy(&records, &lenient, &ApprovalPolicy::new());
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].rule.changes, ChangeMatcher::Any);
+    }
+
+    #[test]
+    fn test_critical_action_types_not_allowed_broadly() {
+        let records = repeat(10, |i| {
+            record(
+                ActionType::DeleteFile,
+                Some(&format!("/tmp/build/{}.o", i)),
+                ApprovalDecision::Approved,
+                "alice",
+            )
+        });
+
+        let suggestions =
+            suggest_policy(&records, &SuggestOptions::default(), &ApprovalPolicy::new());
+
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(
+            suggestions[0].rule.changes,
+            ChangeMatcher::PathPrefix {
+                prefix: "/tmp/build".to_string()
+            }
+        );
+    }
+}
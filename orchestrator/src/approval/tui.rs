@@ -1,29 +1,50 @@
 //! Approval Cliff Terminal UI (TUI) - Phase 2 Implementation
 //!
-//! This module provides a rich terminal user interface for approval decisions.
-//! Features:
-//! - Scrollable diff card display with side-by-side diff view
-//! - Color-coded risk levels (green/yellow/orange/red/critical red)
-//! - Interactive approval/rejection/cancel buttons
-//! - Keyboard navigation (↑↓ scroll, Y approve, N reject, Esc cancel)
-//! - Timeout mechanism (auto-reject after 5 minutes)
-//! - Audit logging of all decisions
+//! This module provides a full-screen terminal user interface for approval
+//! decisions, drawn with ratatui.
 //!
-//! Works over SSH and requires no GUI dependencies.
+//! Layout:
+//! - Header: risk level, action type, description and a timeout countdown
+//! - Left pane: list of changes
+//! - Right pane: details of the selected change, with a unified or
+//!   side-by-side diff for edits
+//! - Footer: keybinding hints (`?` opens a help overlay)
+//!
+//! Keys: ↑↓/jk select change, PgUp/PgDn scroll, Enter expand, Tab toggle
+//! diff view, Y approve, N reject, Esc cancel. When the timeout expires the
+//! screen closes with [`TuiResult::TimedOut`] (`luminaguard approve` reports
+//! it as a rejection; [`TuiBackend`](super::backend::TuiBackend) as an
+//! [`ApprovalTimedOut`](super::backend::ApprovalTimedOut) error).
+//!
+//! The terminal is restored on normal exit, on error and on panic. Works over
+//! SSH and requires no GUI dependencies. Rendering and key handling are
+//! backend-agnostic, so the screen can be tested with ratatui's `TestBackend`.
 
+use crate::approval::action::RiskLevel;
 use crate::approval::diff::{Change, DiffCard};
-use anyhow::{Context, Result};
+use crate::approval::line_diff::{DiffHunk, DiffLine, DiffLineKind, DiffOptions, UnifiedDiff};
+use crate::approval::ui::DEFAULT_TIMEOUT_SECS;
+use anyhow::Result;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
 use ratatui::{
-    layout::{Alignment, Constraint, Direction, Layout},
+    backend::{Backend, CrosstermBackend},
+    layout::{Alignment, Constraint, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Paragraph, Wrap},
-    Terminal,
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap},
+    Frame, Terminal,
 };
-use std::io as _;  // io::stdout is used via crossterm
+use std::borrow::Cow;
+use std::sync::Once;
 use std::time::{Duration, Instant};
 use tracing::{info, warn};
 
+/// Number of detail lines shown for a collapsed change
+const COLLAPSED_LINES: usize = 12;
+
+/// Lines scrolled by PgUp/PgDn
+const PAGE_SCROLL: u16 = 10;
+
 /// Result of TUI approval prompt
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TuiResult {
@@ -31,186 +52,745 @@ pub enum TuiResult {
     Approved,
     /// User rejected the action
     Rejected,
-    /// User cancelled (or timeout occurred)
+    /// User cancelled
     Cancelled,
+    /// No decision before the timeout
+    TimedOut,
 }
 
-/// Truncate text to maximum length
-fn truncate_text(s: &str, max_len: usize) -> String {
-    if s.len() > max_len {
-        format!("{}...", &s[..max_len])
-    } else {
-        s.to_string()
+/// How edits are shown in the detail pane
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffMode {
+    /// Single column with `-`/`+` lines
+    Unified,
+    /// Before and after in two columns
+    SideBySide,
+}
+
+/// State of the approval screen
+#[derive(Debug, Clone)]
+pub struct ApprovalScreen<'a> {
+    diff_card: &'a DiffCard,
+    selected: usize,
+    scroll: u16,
+    expanded: Vec<bool>,
+    diff_mode: DiffMode,
+    show_help: bool,
+}
+
+impl<'a> ApprovalScreen<'a> {
+    /// Create a screen for a diff card
+    pub fn new(diff_card: &'a DiffCard) -> Self {
+        Self {
+            diff_card,
+            selected: 0,
+            scroll: 0,
+            expanded: vec![false; diff_card.changes.len()],
+            diff_mode: DiffMode::Unified,
+            show_help: false,
+        }
+    }
+
+    /// Index of the selected change
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// Current diff view
+    pub fn diff_mode(&self) -> DiffMode {
+        self.diff_mode
+    }
+
+    /// Whether the help overlay is open
+    pub fn show_help(&self) -> bool {
+        self.show_help
+    }
+
+    /// Whether a change is expanded
+    pub fn is_expanded(&self, index: usize) -> bool {
+        self.expanded.get(index).copied().unwrap_or(false)
+    }
+
+    /// Handle a key press
+    ///
+    /// Returns the decision once the user has made one.
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<TuiResult> {
+        if key.kind != KeyEventKind::Press {
+            return None;
+        }
+
+        // The help overlay swallows keys until it is closed
+        if self.show_help {
+            if matches!(
+                key.code,
+                KeyCode::Esc | KeyCode::Char('?') | KeyCode::Char('q')
+            ) {
+                self.show_help = false;
+            }
+            return None;
+        }
+
+        match key.code {
+            KeyCode::Char('y') | KeyCode::Char('Y') => return Some(TuiResult::Approved),
+            KeyCode::Char('n') | KeyCode::Char('N') => return Some(TuiResult::Rejected),
+            KeyCode::Esc | KeyCode::Char('q') => return Some(TuiResult::Cancelled),
+            KeyCode::Char('?') => self.show_help = true,
+            KeyCode::Up | KeyCode::Char('k') => self.select(self.selected.saturating_sub(1)),
+            KeyCode::Down | KeyCode::Char('j') => self.select(self.selected + 1),
+            KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(PAGE_SCROLL),
+            KeyCode::PageDown => self.scroll = self.scroll.saturating_add(PAGE_SCROLL),
+            KeyCode::Home => self.scroll = 0,
+            KeyCode::Enter | KeyCode::Char(' ') => {
+                if let Some(expanded) = self.expanded.get_mut(self.selected) {
+                    *expanded = !*expanded;
+                }
+            }
+            KeyCode::Tab | KeyCode::Char('d') => {
+                self.diff_mode = match self.diff_mode {
+                    DiffMode::Unified => DiffMode::SideBySide,
+                    DiffMode::SideBySide => DiffMode::Unified,
+                };
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn select(&mut self, index: usize) {
+        let last = self.diff_card.changes.len().saturating_sub(1);
+        let index = index.min(last);
+        if index != self.selected {
+            self.selected = index;
+            self.scroll = 0;
+        }
+    }
+
+    /// Draw the screen
+    pub fn render(&self, frame: &mut Frame, remaining: Duration) {
+        // Room for a line of risk reasons and one of anomalies, if any
+        let header_height = 4
+            + u16::from(!self.diff_card.risk_reasons.is_empty())
+            + u16::from(!self.diff_card.anomalies.is_empty());
+        let [header, body, footer] = Layout::vertical([
+            Constraint::Length(header_height),
+            Constraint::Min(3),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        self.render_header(frame, header, remaining);
+
+        let [list, detail] =
+            Layout::horizontal([Constraint::Percentage(35), Constraint::Percentage(65)])
+                .areas(body);
+        self.render_change_list(frame, list);
+        self.render_detail(frame, detail);
+
+        frame.render_widget(
+            Paragraph::new(Line::from(vec![
+                key_hint("Y", "approve"),
+                key_hint("N", "reject"),
+                key_hint("Esc", "cancel"),
+                key_hint("↑↓", "select"),
+                key_hint("Enter", "expand"),
+                key_hint("Tab", "diff view"),
+                key_hint("?", "help"),
+            ])),
+            footer,
+        );
+
+        if self.show_help {
+            render_help(frame);
+        }
+    }
+
+    fn render_header(&self, frame: &mut Frame, area: Rect, remaining: Duration) {
+        let (risk_text, color) = risk_style(self.diff_card.risk_level);
+        let secs = remaining.as_secs();
+        let countdown_style = if secs < 30 {
+            Style::new().fg(Color::Red).add_modifier(Modifier::BOLD)
+        } else {
+            Style::new()
+        };
+
+        let mut lines = vec![
+            Line::from(vec![
+                Span::styled(
+                    format!(" {} ", risk_text),
+                    Style::new()
+                        .fg(Color::Black)
+                        .bg(color)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(format!(
+                    " {}  score {}/100",
+                    self.diff_card.action_type, self.diff_card.risk_score
+                )),
+                Span::raw("   "),
+                Span::styled(
+                    format!("⏱ {}:{:02} remaining", secs / 60, secs % 60),
+                    countdown_style,
+                ),
+            ]),
+            Line::from(self.diff_card.description.clone()),
+        ];
+        if !self.diff_card.risk_reasons.is_empty() {
+            lines.push(Line::styled(
+                format!("Why: {}", self.diff_card.risk_reasons.join("; ")),
+                Style::new().fg(color),
+            ));
+        }
+        if !self.diff_card.anomalies.is_empty() {
+            lines.push(Line::styled(
+                format!("⚠ Anomaly: {}", self.diff_card.anomalies.join("; ")),
+                Style::new().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+            ));
+        }
+
+        frame.render_widget(
+            Paragraph::new(lines).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::new().fg(color))
+                    .title(" LuminaGuard Approval "),
+            ),
+            area,
+        );
+    }
+
+    fn render_change_list(&self, frame: &mut Frame, area: Rect) {
+        let items: Vec<ListItem> = self
+            .diff_card
+            .changes
+            .iter()
+            .enumerate()
+            .map(|(i, change)| {
+                let marker = if self.is_expanded(i) { "▾" } else { "▸" };
+                ListItem::new(Line::from(format!(
+                    "{} {} {}",
+                    marker,
+                    change.change_type(),
+                    change.summary()
+                )))
+            })
+            .collect();
+
+        let title = format!(" Changes ({}) ", self.diff_card.changes.len());
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+            .highlight_symbol("> ");
+
+        let mut state = ListState::default().with_selected(if self.diff_card.changes.is_empty() {
+            None
+        } else {
+            Some(self.selected)
+        });
+        frame.render_stateful_widget(list, area, &mut state);
+    }
+
+    fn render_detail(&self, frame: &mut Frame, area: Rect) {
+        let Some(change) = self.diff_card.changes.get(self.selected) else {
+            frame.render_widget(
+                Paragraph::new("No changes listed for this action.")
+                    .block(Block::default().borders(Borders::ALL).title(" Details ")),
+                area,
+            );
+            return;
+        };
+
+        let expanded = self.is_expanded(self.selected);
+        let title = format!(" {} ", change.summary());
+
+        if let (DiffMode::SideBySide, Some(diff)) = (self.diff_mode, change_diff(change)) {
+            let [left, right] =
+                Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                    .areas(area);
+            let (before, after) = side_by_side_lines(&diff);
+            let side = |lines, title| {
+                Paragraph::new(collapse(lines, expanded))
+                    .block(Block::default().borders(Borders::ALL).title(title))
+                    .scroll((self.scroll, 0))
+            };
+            frame.render_widget(side(before, " Before "), left);
+            frame.render_widget(side(after, " After "), right);
+            return;
+        }
+
+        frame.render_widget(
+            Paragraph::new(collapse(detail_lines(change), expanded))
+                .block(Block::default().borders(Borders::ALL).title(title))
+                .wrap(Wrap { trim: false })
+                .scroll((self.scroll, 0)),
+            area,
+        );
     }
 }
 
-/// Present an approval decision to the user via interactive terminal UI
-///
-/// This is a simplified TUI implementation that presents a clear approval UI.
-/// For full interactive TUI with mouse support, use the enhanced version.
-///
-/// Features:
-/// - Color-coded risk levels (green/yellow/orange/red/critical red)
-/// - Clear display of changes
-/// - Keyboard input (Y approve, N reject, Esc cancel)
-/// - Timeout mechanism (auto-reject after 5 minutes by default)
-///
-/// # Arguments
-/// * `diff_card` - The DiffCard to display
+/// Risk label and color
+fn risk_style(risk: RiskLevel) -> (&'static str, Color) {
+    match risk {
+        RiskLevel::None => ("GREEN ACTION", Color::Green),
+        RiskLevel::Low => ("LOW RISK", Color::Yellow),
+        RiskLevel::Medium => ("MEDIUM RISK", Color::LightYellow),
+        RiskLevel::High => ("HIGH RISK", Color::Red),
+        RiskLevel::Critical => ("CRITICAL RISK", Color::LightRed),
+    }
+}
+
+fn key_hint(key: &'static str, action: &'static str) -> Span<'static> {
+    Span::raw(format!(" [{}] {} ", key, action))
+}
+
+/// Line-level diff for changes that have before/after content
 ///
-/// # Returns
-/// * `Ok(TuiResult::Approved)` if user approved
-/// * `Ok(TuiResult::Rejected)` if user rejected or timeout occurred
-/// * `Ok(TuiResult::Cancelled)` if user cancelled
-/// * `Err` if TUI operations fail
-pub async fn present_tui_approval(diff_card: &DiffCard) -> Result<TuiResult> {
-    info!(
-        "Presenting TUI approval for action: {}",
-        diff_card.description
-    );
+/// Uses the diff carried by the card when present, otherwise diffs the
+/// previews.
+fn change_diff(change: &Change) -> Option<Cow<'_, UnifiedDiff>> {
+    match change {
+        Change::FileEdit {
+            diff: Some(diff), ..
+        } => Some(Cow::Borrowed(diff)),
+        Change::FileEdit { before, after, .. }
+        | Change::ConfigChange {
+            old_value: before,
+            new_value: after,
+            ..
+        } => Some(Cow::Owned(UnifiedDiff::compute(
+            before,
+            after,
+            &DiffOptions::default(),
+        ))),
+        _ => None,
+    }
+}
+
+fn line_number(n: Option<usize>) -> String {
+    n.map_or_else(|| "    ".to_string(), |n| format!("{:>4}", n))
+}
+
+fn diff_line_style(kind: DiffLineKind) -> Style {
+    match kind {
+        DiffLineKind::Context => Style::new(),
+        DiffLineKind::Added => Style::new().fg(Color::Green),
+        DiffLineKind::Removed => Style::new().fg(Color::Red),
+    }
+}
+
+fn hunk_header_line(hunk: &DiffHunk) -> Line<'static> {
+    Line::styled(hunk.header(), Style::new().fg(Color::Cyan))
+}
+
+/// Unified diff lines with old/new line numbers
+fn unified_lines(diff: &UnifiedDiff) -> Vec<Line<'static>> {
+    if diff.binary {
+        return vec![Line::from("Binary file changed")];
+    }
+
+    let mut lines = vec![Line::styled(
+        format!(
+            "{} line(s) added, {} line(s) removed",
+            diff.added, diff.removed
+        ),
+        Style::new().add_modifier(Modifier::BOLD),
+    )];
+    for hunk in &diff.hunks {
+        lines.push(hunk_header_line(hunk));
+        lines.extend(hunk.lines.iter().map(|line| {
+            Line::styled(
+                format!(
+                    "{} {} {}{}",
+                    line_number(line.old_line),
+                    line_number(line.new_line),
+                    line.kind.prefix(),
+                    line.text
+                ),
+                diff_line_style(line.kind),
+            )
+        }));
+    }
+    if diff.omitted_hunks > 0 {
+        lines.push(Line::from(format!(
+            "… {} more hunk(s) not shown",
+            diff.omitted_hunks
+        )));
+    }
+    lines
+}
 
-    // Clear screen and display approval UI
-    println!("\n{}", "=".repeat(80));
-
-    // Header with risk level
-    use crate::approval::action::RiskLevel;
-    let (emoji, risk_text, _color) = match diff_card.risk_level {
-        RiskLevel::None => ("🟢", "GREEN ACTION", Color::Green),
-        RiskLevel::Low => ("🟡", "LOW RISK", Color::Yellow),
-        RiskLevel::Medium => ("🟠", "MEDIUM RISK", Color::LightYellow),
-        RiskLevel::High => ("🔴", "HIGH RISK", Color::Red),
-        RiskLevel::Critical => ("🔴🔴", "CRITICAL RISK", Color::Red),
+/// Before/after columns, with removed and added runs aligned row by row
+fn side_by_side_lines(diff: &UnifiedDiff) -> (Vec<Line<'static>>, Vec<Line<'static>>) {
+    if diff.binary {
+        let line = || vec![Line::from("Binary file")];
+        return (line(), line());
+    }
+
+    let side_line = |line: &DiffLine, number: Option<usize>| {
+        Line::styled(
+            format!("{} {}", line_number(number), line.text),
+            diff_line_style(line.kind),
+        )
     };
 
-    println!("{} {} - {}", emoji, risk_text, diff_card.action_type);
-    println!("{}", "━".repeat(80));
-    println!("Description: {}", diff_card.description);
-    println!(
-        "Time: {}",
-        diff_card.timestamp.format("%Y-%m-%d %H:%M:%S UTC")
-    );
-    println!("{}", "━".repeat(80));
-
-    // Display changes
-    if !diff_card.changes.is_empty() {
-        println!("\nChanges:");
-        for (i, change) in diff_card.changes.iter().enumerate() {
-            println!(
-                "  {}. {} - {}",
-                i + 1,
-                change.change_type(),
-                change.summary()
-            );
+    let (mut left, mut right) = (Vec::new(), Vec::new());
+    for hunk in &diff.hunks {
+        left.push(hunk_header_line(hunk));
+        right.push(hunk_header_line(hunk));
 
-            // Show preview based on change type
-            match change {
-                Change::FileEdit { before, after, .. } => {
-                    println!("     Before: {}", truncate_text(before, 60));
-                    println!("     After:  {}", truncate_text(after, 60));
-                }
-                Change::FileCreate {
-                    content_preview, ..
-                } => {
-                    println!("     Content: {}", truncate_text(content_preview, 60));
-                }
-                Change::FileDelete { size_bytes, .. } => {
-                    println!("     Size: {} bytes (permanent deletion)", size_bytes);
-                }
-                Change::CommandExec { args, .. } => {
-                    if !args.is_empty() {
-                        println!("     Args: {}", args.join(" "));
-                    }
-                }
-                Change::EmailSend { subject, .. } => {
-                    println!("     Subject: {}", subject);
-                }
-                Change::ExternalCall {
-                    payload_preview, ..
-                } => {
-                    println!("     Payload: {}", truncate_text(payload_preview, 60));
-                }
-                Change::AssetTransfer {
-                    amount, currency, ..
-                } => {
-                    println!("     Amount: {} {}", amount, currency);
-                }
-                Change::ConfigChange {
-                    old_value,
-                    new_value,
-                    ..
-                } => {
-                    println!("     Old: {}", old_value);
-                    println!("     New: {}", new_value);
+        let mut i = 0;
+        while i < hunk.lines.len() {
+            let line = &hunk.lines[i];
+            if line.kind == DiffLineKind::Context {
+                left.push(side_line(line, line.old_line));
+                right.push(side_line(line, line.new_line));
+                i += 1;
+                continue;
+            }
+
+            // A run of removed lines followed by a run of added lines
+            let removed: Vec<&DiffLine> = hunk.lines[i..]
+                .iter()
+                .take_while(|l| l.kind == DiffLineKind::Removed)
+                .collect();
+            let added: Vec<&DiffLine> = hunk.lines[i + removed.len()..]
+                .iter()
+                .take_while(|l| l.kind == DiffLineKind::Added)
+                .collect();
+            for row in 0..removed.len().max(added.len()) {
+                left.push(
+                    removed
+                        .get(row)
+                        .map_or_else(Line::default, |l| side_line(l, l.old_line)),
+                );
+                right.push(
+                    added
+                        .get(row)
+                        .map_or_else(Line::default, |l| side_line(l, l.new_line)),
+                );
+            }
+            i += removed.len() + added.len();
+        }
+    }
+    (left, right)
+}
+
+/// Detail lines for a change (unified view)
+fn detail_lines(change: &Change) -> Vec<Line<'static>> {
+    if let Some(diff) = change_diff(change) {
+        return unified_lines(&diff);
+    }
+
+    let text = match change {
+        Change::FileCreate {
+            content_preview, ..
+        } => format!("Content:\n{}", content_preview),
+        Change::FileDelete { path, size_bytes } => format!(
+            "Path: {}\nSize: {} bytes (permanent deletion)",
+            path, size_bytes
+        ),
+        Change::CommandExec {
+            command,
+            args,
+            env_vars,
+        } => {
+            let mut text = format!("Command: {}", command);
+            if !args.is_empty() {
+                text.push_str(&format!("\nArgs: {}", args.join(" ")));
+            }
+            if let Some(vars) = env_vars {
+                for (key, value) in vars {
+                    text.push_str(&format!("\nEnv: {}={}", key, value));
                 }
-                Change::Custom { description } => {
-                    println!("     {}", description);
+            }
+            text
+        }
+        Change::EmailSend {
+            to,
+            subject,
+            preview,
+        } => format!("To: {}\nSubject: {}\n\n{}", to, subject, preview),
+        Change::ExternalCall {
+            method,
+            endpoint,
+            payload_preview,
+        } => format!("{} {}\n\n{}", method, endpoint, payload_preview),
+        Change::AssetTransfer {
+            from,
+            to,
+            amount,
+            currency,
+        } => format!(
+            "From: {}\nTo: {}\nAmount: {} {}",
+            from, to, amount, currency
+        ),
+        Change::GitOperation {
+            operation,
+            branch,
+            remote,
+            commits,
+            force,
+        } => {
+            let mut text = format!("Operation: git {}\nBranch: {}", operation, branch);
+            if let Some(remote) = remote {
+                text.push_str(&format!("\nRemote: {}", remote));
+            }
+            if *force {
+                text.push_str("\nForce: rewrites history");
+            }
+            if !commits.is_empty() {
+                text.push_str("\n\nCommits:");
+                for commit in commits {
+                    text.push_str(&format!("\n  {}", commit));
                 }
             }
+            text
         }
-    }
+        Change::DatabaseStatement {
+            connection,
+            statement,
+            estimated_rows,
+        } => {
+            let rows = estimated_rows.map_or("unknown".to_string(), |n| n.to_string());
+            format!(
+                "Connection: {}\nEstimated rows: {}\n\n{}",
+                connection, rows, statement
+            )
+        }
+        Change::PackageInstall {
+            manager,
+            package,
+            version,
+            source,
+        } => format!(
+            "Manager: {}\nPackage: {}\nVersion: {}\nSource: {}",
+            manager,
+            package,
+            version.as_deref().unwrap_or("latest"),
+            source.as_deref().unwrap_or("default registry")
+        ),
+        Change::PermissionChange {
+            path,
+            old_mode,
+            new_mode,
+            old_owner,
+            new_owner,
+        } => {
+            let mut text = format!("Path: {}", path);
+            if let Some(mode) = new_mode {
+                let old = old_mode.as_deref().unwrap_or("?");
+                text.push_str(&format!("\nMode: {} -> {}", old, mode));
+            }
+            if let Some(owner) = new_owner {
+                let old = old_owner.as_deref().unwrap_or("?");
+                text.push_str(&format!("\nOwner: {} -> {}", old, owner));
+            }
+            text
+        }
+        Change::Custom { description } => description.clone(),
+        Change::FileEdit { .. } | Change::ConfigChange { .. } => String::new(),
+    };
 
-    println!("\n{}", "=".repeat(80));
+    text.lines().map(|l| Line::from(l.to_string())).collect()
+}
 
-    // Instructions
-    println!("\nOptions:");
-    println!("  [Y] Approve this action");
-    println!("  [N] Reject this action");
-    println!("  [Esc] Cancel");
-    println!("\nPress Y to approve, N to reject, or Esc to cancel: ");
+/// Limit detail lines for a collapsed change
+fn collapse(mut lines: Vec<Line<'static>>, expanded: bool) -> Vec<Line<'static>> {
+    if !expanded && lines.len() > COLLAPSED_LINES {
+        let hidden = lines.len() - COLLAPSED_LINES;
+        lines.truncate(COLLAPSED_LINES);
+        lines.push(Line::styled(
+            format!("… {} more lines (Enter to expand)", hidden),
+            Style::new().add_modifier(Modifier::DIM),
+        ));
+    }
+    lines
+}
 
-    // Read timeout from environment variable (default: 300 seconds = 5 minutes)
-    let timeout_seconds = std::env::var("LUMINAGUARD_APPROVAL_TIMEOUT")
-        .ok()
-        .and_then(|s| s.parse::<u64>().ok())
-        .unwrap_or(300);
+fn render_help(frame: &mut Frame) {
+    let area = centered_rect(frame.area(), 50, 14);
+    let lines: Vec<Line> = [
+        ("Y", "Approve this action"),
+        ("N", "Reject this action"),
+        ("Esc / q", "Cancel"),
+        ("↑ ↓ / k j", "Select change"),
+        ("PgUp PgDn", "Scroll details"),
+        ("Home", "Scroll to top"),
+        ("Enter", "Expand / collapse change"),
+        ("Tab / d", "Toggle unified / side-by-side"),
+        ("?", "Close this help"),
+    ]
+    .into_iter()
+    .map(|(key, action)| {
+        Line::from(vec![
+            Span::styled(
+                format!("{:>10}  ", key),
+                Style::new().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(action),
+        ])
+    })
+    .collect();
 
-    let start_time = Instant::now();
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(lines)
+            .block(Block::default().borders(Borders::ALL).title(" Help "))
+            .alignment(Alignment::Left),
+        area,
+    );
+}
 
-    // Simple CLI input with timeout
-    use crossterm::event::{self, Event, KeyCode};
-    use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
+/// A rectangle of at most `width` x `height` centered in `area`
+fn centered_rect(area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
 
-    enable_raw_mode()?;
+/// Truncate text to maximum length (in characters)
+fn truncate_text(s: &str, max_len: usize) -> String {
+    if s.chars().count() > max_len {
+        format!("{}...", s.chars().take(max_len).collect::<String>())
+    } else {
+        s.to_string()
+    }
+}
 
-    let mut result = TuiResult::Cancelled;  // Will be set in the loop
+/// Run the approval screen until a decision is made or the timeout expires
+///
+/// `next_event` waits up to the given duration for an input event.
+pub fn run_approval_screen<B, F>(
+    terminal: &mut Terminal<B>,
+    diff_card: &DiffCard,
+    timeout: Duration,
+    mut next_event: F,
+) -> Result<TuiResult>
+where
+    B: Backend,
+    B::Error: Send + Sync + 'static,
+    F: FnMut(Duration) -> Result<Option<Event>>,
+{
+    let mut screen = ApprovalScreen::new(diff_card);
+    let start_time = Instant::now();
 
     loop {
-        // Check timeout
-        let elapsed = start_time.elapsed().as_secs();
-        if elapsed >= timeout_seconds {
-            warn!("Approval timeout after {} seconds", elapsed);
-            println!("\n⚠️  TIMEOUT EXCEEDED - Action automatically rejected");
-            result = TuiResult::Rejected;
-            break;
+        let remaining = timeout.saturating_sub(start_time.elapsed());
+        terminal.draw(|frame| screen.render(frame, remaining))?;
+
+        if remaining.is_zero() {
+            warn!("Approval timeout after {} seconds", timeout.as_secs());
+            return Ok(TuiResult::TimedOut);
         }
 
-        // Poll for events
-        if event::poll(Duration::from_millis(100))? {
-            if let Event::Key(key) = event::read()? {
-                match key.code {
-                    KeyCode::Char('y') | KeyCode::Char('Y') => {
-                        info!("User approved action: {}", diff_card.description);
-                        result = TuiResult::Approved;
-                        break;
-                    }
-                    KeyCode::Char('n') | KeyCode::Char('N') => {
-                        info!("User rejected action: {}", diff_card.description);
-                        result = TuiResult::Rejected;
-                        break;
-                    }
-                    KeyCode::Esc => {
-                        info!("User cancelled approval: {}", diff_card.description);
-                        result = TuiResult::Cancelled;
-                        break;
-                    }
-                    _ => {}
-                }
+        if let Some(Event::Key(key)) = next_event(remaining.min(Duration::from_millis(250)))? {
+            if let Some(result) = screen.handle_key(key) {
+                return Ok(result);
             }
         }
     }
+}
+
+/// Restores the terminal when dropped
+struct TerminalGuard;
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore_terminal();
+    }
+}
+
+/// Leave the alternate screen and raw mode (best effort)
+fn restore_terminal() {
+    let _ = crossterm::terminal::disable_raw_mode();
+    let _ = crossterm::execute!(
+        std::io::stdout(),
+        crossterm::terminal::LeaveAlternateScreen,
+        crossterm::cursor::Show
+    );
+}
+
+/// Restore the terminal before the panic message is printed
+fn install_panic_hook() {
+    static HOOK: Once = Once::new();
+    HOOK.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |panic_info| {
+            restore_terminal();
+            previous(panic_info);
+        }));
+    });
+}
+
+/// Default TUI timeout
+///
+/// Read from `LUMINAGUARD_APPROVAL_TIMEOUT` (seconds), default
+/// [`DEFAULT_TIMEOUT_SECS`] (5 minutes).
+pub fn approval_timeout() -> Duration {
+    let seconds = std::env::var("LUMINAGUARD_APPROVAL_TIMEOUT")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_TIMEOUT_SECS);
+    Duration::from_secs(seconds)
+}
 
-    disable_raw_mode()?;
-    println!();
+/// Present an approval decision to the user via interactive terminal UI
+///
+/// Takes over the terminal (alternate screen, raw mode) until the user
+/// approves (Y), rejects (N) or cancels (Esc), or the default timeout
+/// ([`approval_timeout`]) expires.
+///
+/// # Arguments
+/// * `diff_card` - The DiffCard to display
+///
+/// # Returns
+/// * `Ok(TuiResult::Approved)` if user approved
+/// * `Ok(TuiResult::Rejected)` if user rejected
+/// * `Ok(TuiResult::Cancelled)` if user cancelled
+/// * `Ok(TuiResult::TimedOut)` if the timeout expired
+/// * `Err` if TUI operations fail
+pub async fn present_tui_approval(diff_card: &DiffCard) -> Result<TuiResult> {
+    present_tui_approval_with_timeout(diff_card, approval_timeout()).await
+}
+
+/// Present the terminal UI with an explicit timeout
+///
+/// See [`present_tui_approval`].
+pub async fn present_tui_approval_with_timeout(
+    diff_card: &DiffCard,
+    timeout: Duration,
+) -> Result<TuiResult> {
+    info!(
+        "Presenting TUI approval for action: {}",
+        truncate_text(&diff_card.description, 80)
+    );
+
+    install_panic_hook();
+    crossterm::terminal::enable_raw_mode()?;
+    let _guard = TerminalGuard;
+    crossterm::execute!(std::io::stdout(), crossterm::terminal::EnterAlternateScreen)?;
+
+    let mut terminal = Terminal::new(CrosstermBackend::new(std::io::stdout()))?;
+    terminal.hide_cursor()?;
+
+    let result = run_approval_screen(&mut terminal, diff_card, timeout, |wait| {
+        if event::poll(wait)? {
+            Ok(Some(event::read()?))
+        } else {
+            Ok(None)
+        }
+    })?;
+
+    match result {
+        TuiResult::Approved => info!("User approved action: {}", diff_card.description),
+        TuiResult::Rejected => info!("Action rejected: {}", diff_card.description),
+        TuiResult::Cancelled => info!("User cancelled approval: {}", diff_card.description),
+        TuiResult::TimedOut => info!("Approval timed out: {}", diff_card.description),
+    }
 
     Ok(result)
 }
@@ -219,13 +799,21 @@ pub async fn present_tui_approval(diff_card: &DiffCard) -> Result<TuiResult> {
 mod tests {
     use super::*;
     use crate::approval::action::{ActionType, RiskLevel};
+    use crate::approval::diff::SCHEMA_VERSION;
     use chrono::Utc;
+    use crossterm::event::KeyModifiers;
+    use ratatui::backend::TestBackend;
+    use std::collections::VecDeque;
 
     fn create_test_diff_card() -> DiffCard {
         DiffCard {
+            schema_version: SCHEMA_VERSION,
             action_type: ActionType::DeleteFile,
             description: "Delete test file".to_string(),
             risk_level: RiskLevel::Critical,
+            risk_score: 85,
+            risk_reasons: Vec::new(),
+            anomalies: Vec::new(),
             changes: vec![Change::FileDelete {
                 path: "/tmp/test.txt".to_string(),
                 size_bytes: 1024,
@@ -234,6 +822,48 @@ mod tests {
         }
     }
 
+    fn create_edit_diff_card() -> DiffCard {
+        DiffCard {
+            schema_version: SCHEMA_VERSION,
+            action_type: ActionType::EditFile,
+            description: "Edit config".to_string(),
+            risk_level: RiskLevel::High,
+            risk_score: 65,
+            risk_reasons: Vec::new(),
+            anomalies: Vec::new(),
+            changes: vec![
+                Change::file_edit("/etc/app.conf", "port = 80", "port = 8080"),
+                Change::FileDelete {
+                    path: "/tmp/old.conf".to_string(),
+                    size_bytes: 12,
+                },
+            ],
+            timestamp: Utc::now(),
+        }
+    }
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn buffer_text(terminal: &Terminal<TestBackend>) -> String {
+        let buffer = terminal.backend().buffer();
+        buffer
+            .content()
+            .chunks(buffer.area.width as usize)
+            .map(|row| row.iter().map(|c| c.symbol()).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn render(screen: &ApprovalScreen, remaining: Duration) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(100, 24)).unwrap();
+        terminal
+            .draw(|frame| screen.render(frame, remaining))
+            .unwrap();
+        buffer_text(&terminal)
+    }
+
     #[test]
     fn test_tui_result_approved() {
         assert_eq!(TuiResult::Approved, TuiResult::Approved);
@@ -269,4 +899,201 @@ mod tests {
         assert!(truncated.len() <= 23); // 20 + "..."
         assert!(truncated.ends_with("..."));
     }
+
+    #[test]
+    fn test_truncate_text_multibyte() {
+        assert_eq!(truncate_text("ééééé", 2), "éé...");
+    }
+
+    #[test]
+    fn test_decision_keys() {
+        let card = create_test_diff_card();
+        let mut screen = ApprovalScreen::new(&card);
+
+        assert_eq!(
+            screen.handle_key(key(KeyCode::Char('Y'))),
+            Some(TuiResult::Approved)
+        );
+        assert_eq!(
+            screen.handle_key(key(KeyCode::Char('n'))),
+            Some(TuiResult::Rejected)
+        );
+        assert_eq!(
+            screen.handle_key(key(KeyCode::Esc)),
+            Some(TuiResult::Cancelled)
+        );
+    }
+
+    #[test]
+    fn test_navigation_and_expansion() {
+        let card = create_edit_diff_card();
+        let mut screen = ApprovalScreen::new(&card);
+
+        assert_eq!(screen.handle_key(key(KeyCode::Down)), None);
+        assert_eq!(screen.selected(), 1);
+        screen.handle_key(key(KeyCode::Down));
+        assert_eq!(screen.selected(), 1); // clamped
+
+        screen.handle_key(key(KeyCode::Enter));
+        assert!(screen.is_expanded(1));
+        assert!(!screen.is_expanded(0));
+
+        screen.handle_key(key(KeyCode::Char('k')));
+        assert_eq!(screen.selected(), 0);
+
+        screen.handle_key(key(KeyCode::Tab));
+        assert_eq!(screen.diff_mode(), DiffMode::SideBySide);
+    }
+
+    #[test]
+    fn test_help_overlay_swallows_keys() {
+        let card = create_test_diff_card();
+        let mut screen = ApprovalScreen::new(&card);
+
+        screen.handle_key(key(KeyCode::Char('?')));
+        assert!(screen.show_help());
+        assert!(render(&screen, Duration::from_secs(60)).contains("Help"));
+
+        // Y must not approve while help is open; Esc only closes help
+        assert_eq!(screen.handle_key(key(KeyCode::Char('y'))), None);
+        assert_eq!(screen.handle_key(key(KeyCode::Esc)), None);
+        assert!(!screen.show_help());
+    }
+
+    #[test]
+    fn test_render_header_and_countdown() {
+        let mut card = create_test_diff_card();
+        card.risk_reasons = vec!["Only temporary paths affected (-10)".to_string()];
+        let screen = ApprovalScreen::new(&card);
+
+        let text = render(&screen, Duration::from_secs(125));
+
+        assert!(text.contains("CRITICAL RISK"));
+        assert!(text.contains("score 85/100"));
+        assert!(text.contains("Why: Only temporary paths affected (-10)"));
+        assert!(!text.contains("Anomaly"));
+        assert!(text.contains("Delete test file"));
+        assert!(text.contains("2:05 remaining"));
+        assert!(text.contains("Changes (1)"));
+        assert!(text.contains("1024 bytes"));
+
+        card.anomalies = vec!["First-ever DeleteFile action".to_string()];
+        let screen = ApprovalScreen::new(&card);
+        let text = render(&screen, Duration::from_secs(60));
+        assert!(text.contains("⚠ Anomaly: First-ever DeleteFile action"));
+        assert!(text.contains("Why: Only temporary paths affected (-10)"));
+    }
+
+    #[test]
+    fn test_render_unified_and_side_by_side() {
+        let card = create_edit_diff_card();
+        let mut screen = ApprovalScreen::new(&card);
+
+        let unified = render(&screen, Duration::from_secs(60));
+        assert!(unified.contains("1 line(s) added, 1 line(s) removed"));
+        assert!(unified.contains("@@ -1,1 +1,1 @@"));
+        assert!(unified.contains("   1      -port = 80"));
+        assert!(unified.contains("        1 +port = 8080"));
+
+        screen.handle_key(key(KeyCode::Tab));
+        let side_by_side = render(&screen, Duration::from_secs(60));
+        assert!(side_by_side.contains("Before"));
+        assert!(side_by_side.contains("After"));
+        assert!(side_by_side.contains("   1 port = 8080"));
+        assert!(!side_by_side.contains("+port = 8080"));
+    }
+
+    #[test]
+    fn test_collapsed_change_hides_lines() {
+        let before: Vec<String> = (0..30).map(|i| format!("line {}", i)).collect();
+        let card = DiffCard {
+            schema_version: SCHEMA_VERSION,
+            action_type: ActionType::EditFile,
+            description: "Big edit".to_string(),
+            risk_level: RiskLevel::Medium,
+            risk_score: 45,
+            risk_reasons: Vec::new(),
+            anomalies: Vec::new(),
+            changes: vec![Change::file_edit("/tmp/big", &before.join("\n"), "")],
+            timestamp: Utc::now(),
+        };
+        let mut screen = ApprovalScreen::new(&card);
+
+        assert!(render(&screen, Duration::from_secs(60)).contains("20 more lines"));
+
+        screen.handle_key(key(KeyCode::Enter));
+        assert!(!render(&screen, Duration::from_secs(60)).contains("more lines"));
+    }
+
+    #[test]
+    fn test_run_approval_screen_with_scripted_keys() {
+        let card = create_edit_diff_card();
+        let mut terminal = Terminal::new(TestBackend::new(100, 24)).unwrap();
+        let mut events: VecDeque<Event> = [KeyCode::Down, KeyCode::Char('?'), KeyCode::Esc]
+            .into_iter()
+            .chain(std::iter::once(KeyCode::Char('y')))
+            .map(|code| Event::Key(key(code)))
+            .collect();
+
+        let result = run_approval_screen(&mut terminal, &card, Duration::from_secs(60), |_| {
+            Ok(events.pop_front())
+        })
+        .unwrap();
+
+        assert_eq!(result, TuiResult::Approved);
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn test_run_approval_screen_timeout() {
+        let card = create_test_diff_card();
+        let mut terminal = Terminal::new(TestBackend::new(100, 24)).unwrap();
+
+        let result =
+            run_approval_screen(&mut terminal, &card, Duration::ZERO, |_| Ok(None)).unwrap();
+
+        assert_eq!(result, TuiResult::TimedOut);
+        assert!(buffer_text(&terminal).contains("0:00 remaining"));
+    }
+
+    #[test]
+    fn test_render_structured_changes() {
+        let card = DiffCard {
+            changes: vec![
+                Change::DatabaseStatement {
+                    connection: "postgres://db/app".to_string(),
+                    statement: "DROP TABLE users".to_string(),
+                    estimated_rows: None,
+                },
+                Change::PackageInstall {
+                    manager: "pip".to_string(),
+                    package: "requests".to_string(),
+                    version: None,
+                    source: None,
+                },
+            ],
+            ..create_test_diff_card()
+        };
+        let mut screen = ApprovalScreen::new(&card);
+
+        let text = render(&screen, Duration::from_secs(60));
+        assert!(text.contains("Estimated rows: unknown"));
+        assert!(text.contains("DROP TABLE users"));
+
+        screen.handle_key(key(KeyCode::Down));
+        let text = render(&screen, Duration::from_secs(60));
+        assert!(text.contains("Version: latest"));
+        assert!(text.contains("Source: default registry"));
+    }
+
+    #[test]
+    fn test_render_no_changes() {
+        let card = DiffCard {
+            changes: vec![],
+            ..create_test_diff_card()
+        };
+        let screen = ApprovalScreen::new(&card);
+
+        assert!(render(&screen, Duration::from_secs(60)).contains("No changes listed"));
+    }
 }
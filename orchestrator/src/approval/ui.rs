@@ -2,12 +2,125 @@
 //!
 //! This module handles user interaction for approval decisions.
 //! Supports both interactive (CLI) and non-interactive (mock) modes.
-
+//!
+//! # Timeouts
+//!
+//! [`ApprovalPromptConfig`] sets how long a Red action waits for a decision
+//! per risk level ([`ApprovalTimeouts`]), what happens when nobody answers
+//! ([`TimeoutAction`]) and when approvers are reminded before that. The
+//! [`ApprovalManager`](super::ApprovalManager) applies them to every
+//! backend, including this prompt: stdin is read by one long-lived helper
+//! thread, so an unattended prompt expires instead of blocking forever, and
+//! the next prompt still gets the next line typed. Lines typed while no
+//! prompt was shown are discarded before it.
+
+use super::action::RiskLevel;
+use super::amend::Modification;
+use super::backend::BackendDecision;
 use super::diff::DiffCard;
+use super::grants::{common_parent, GrantScope};
 use super::history::ApprovalDecision;
-use std::io::{self, Write};
+use super::plan::{parse_step_numbers, PlanRequest, PlanVerdict, StepSelection};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::sync::{mpsc, Mutex};
 use tracing::{debug, warn};
 
+/// Duration of a timed standing grant offered by the prompt
+pub const GRANT_MINUTES: u64 = 15;
+
+/// Default time to wait for a decision (seconds)
+pub const DEFAULT_TIMEOUT_SECS: u64 = 300;
+
+/// What happens to an action nobody decided in time
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeoutAction {
+    /// Deny the action
+    #[default]
+    Deny,
+
+    /// Park the action in the deferred queue for a later decision
+    Defer,
+
+    /// Ask the escalation backend (see
+    /// [`ApprovalManager::with_escalation`](super::ApprovalManager::with_escalation));
+    /// denied if there is none or it times out too
+    Escalate,
+}
+
+impl fmt::Display for TimeoutAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimeoutAction::Deny => write!(f, "deny"),
+            TimeoutAction::Defer => write!(f, "defer"),
+            TimeoutAction::Escalate => write!(f, "escalate"),
+        }
+    }
+}
+
+/// How long to wait for a decision, per risk level (None: indefinitely)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ApprovalTimeouts {
+    /// Low risk actions (and risk-free ones, which are rarely prompted for)
+    pub low: Option<Duration>,
+
+    /// Medium risk actions
+    pub medium: Option<Duration>,
+
+    /// High risk actions
+    pub high: Option<Duration>,
+
+    /// Critical risk actions
+    pub critical: Option<Duration>,
+}
+
+impl ApprovalTimeouts {
+    /// The same timeout for every risk level
+    pub fn uniform(timeout: Option<Duration>) -> Self {
+        Self {
+            low: timeout,
+            medium: timeout,
+            high: timeout,
+            critical: timeout,
+        }
+    }
+
+    /// Set the timeout of one risk level
+    pub fn with_level(mut self, level: RiskLevel, timeout: Option<Duration>) -> Self {
+        *self.slot(level) = timeout;
+        self
+    }
+
+    /// Timeout for a risk level
+    pub fn for_level(&self, level: RiskLevel) -> Option<Duration> {
+        match level {
+            RiskLevel::None | RiskLevel::Low => self.low,
+            RiskLevel::Medium => self.medium,
+            RiskLevel::High => self.high,
+            RiskLevel::Critical => self.critical,
+        }
+    }
+
+    fn slot(&mut self, level: RiskLevel) -> &mut Option<Duration> {
+        match level {
+            RiskLevel::None | RiskLevel::Low => &mut self.low,
+            RiskLevel::Medium => &mut self.medium,
+            RiskLevel::High => &mut self.high,
+            RiskLevel::Critical => &mut self.critical,
+        }
+    }
+}
+
+impl Default for ApprovalTimeouts {
+    fn default() -> Self {
+        Self::uniform(Some(Duration::from_secs(DEFAULT_TIMEOUT_SECS)))
+    }
+}
+
 /// Configuration for approval prompts
 #[derive(Debug, Clone)]
 pub struct ApprovalPromptConfig {
@@ -19,6 +132,15 @@ pub struct ApprovalPromptConfig {
 
     /// Default decision for mocked prompts
     pub default_decision: ApprovalDecision,
+
+    /// How long to wait for a decision, per risk level
+    pub timeouts: ApprovalTimeouts,
+
+    /// What happens when the timeout expires
+    pub on_timeout: TimeoutAction,
+
+    /// Remind approvers this long before the timeout expires
+    pub reminders: Vec<Duration>,
 }
 
 impl Default for ApprovalPromptConfig {
@@ -27,10 +149,20 @@ impl Default for ApprovalPromptConfig {
             interactive: true,
             auto_approve_green: true,
             default_decision: ApprovalDecision::Denied,
+            timeouts: ApprovalTimeouts::default(),
+            on_timeout: TimeoutAction::default(),
+            reminders: vec![Duration::from_secs(60)],
         }
     }
 }
 
+/// Standing grants and edits offered alongside a prompt
+#[derive(Debug, Clone, Copy)]
+struct PromptOffer<'a> {
+    /// Directory offered for a path-prefix grant
+    path_prefix: Option<&'a str>,
+}
+
 /// Approval prompt UI manager
 pub struct ApprovalPrompt {
     config: ApprovalPromptConfig,
@@ -61,37 +193,182 @@ impl ApprovalPrompt {
         );
 
         if self.config.interactive {
-            self.prompt_user_interactive(diff_card).await
+            let answer = self.prompt_user_interactive(diff_card, None).await?;
+            Ok(answer.decision)
         } else {
             // Mock mode: return default decision
             debug!("Mock approval prompt: using default decision");
-            Ok(self.config.default_decision)
+            Ok(self.config.default_decision.clone())
+        }
+    }
+
+    /// Ask user for a decision, also offering standing grants and edits
+    ///
+    /// Besides approve/deny, the user can approve similar actions for the
+    /// session, for [`GRANT_MINUTES`] minutes, or under the common parent
+    /// directory of the changed paths, or approve the action with
+    /// modifications (see [`Modification::parse`] for the syntax).
+    pub async fn ask_for_decision(&self, diff_card: &DiffCard) -> anyhow::Result<BackendDecision> {
+        if self.config.interactive {
+            let prefix = common_parent(&diff_card.changes);
+            let offer = PromptOffer {
+                path_prefix: prefix.as_deref(),
+            };
+            self.prompt_user_interactive(diff_card, Some(offer)).await
+        } else {
+            debug!("Mock approval prompt: using default decision");
+            Ok(BackendDecision::local_user(
+                self.config.default_decision.clone(),
+            ))
         }
     }
 
+    /// Ask user for a decision on a plan
+    ///
+    /// The user can approve every step, the steps up to a given one, or
+    /// every step except some (see [`StepSelection`]), or deny the plan.
+    pub async fn ask_for_plan_decision(&self, plan: &PlanRequest) -> anyhow::Result<PlanVerdict> {
+        if !self.config.interactive {
+            debug!("Mock approval prompt: using default decision");
+            return Ok(PlanVerdict::whole(BackendDecision::local_user(
+                self.config.default_decision.clone(),
+            )));
+        }
+
+        stdin_lines().discard_pending().await;
+        println!("\n{}\n", plan.request.diff_card);
+        self.print_plan_options();
+
+        let mut verdict = loop {
+            let Some(input) = read_line().await? else {
+                warn!("No input provided (EOF), denying by default");
+                return Ok(PlanVerdict::whole(BackendDecision::local_user(
+                    ApprovalDecision::Denied,
+                )));
+            };
+            let input = input.trim().to_lowercase();
+            let (choice, rest) = input.split_once(' ').unwrap_or((input.as_str(), ""));
+
+            let steps = match choice {
+                "a" | "approve" => Ok(StepSelection::All),
+                "p" | "prefix" => parse_step_numbers(rest).and_then(|numbers| match numbers[..] {
+                    [last] if plan.has_step(last) => Ok(StepSelection::Through(last)),
+                    _ => Err(anyhow::anyhow!("Give one step number of the plan")),
+                }),
+                "x" | "exclude" => parse_step_numbers(rest).and_then(|numbers| {
+                    match numbers.iter().find(|n| !plan.has_step(**n)) {
+                        Some(n) => Err(anyhow::anyhow!("No step {} in the plan", n)),
+                        None => Ok(StepSelection::Excluding(numbers)),
+                    }
+                }),
+                "d" | "deny" => {
+                    break PlanVerdict::whole(BackendDecision::local_user(ApprovalDecision::Denied))
+                }
+                "q" | "quit" | "exit" => {
+                    return Err(anyhow::anyhow!("User canceled approval prompt"))
+                }
+                "?" | "help" => {
+                    self.print_plan_options();
+                    continue;
+                }
+                _ => Err(anyhow::anyhow!("Invalid choice")),
+            };
+            match steps {
+                Ok(steps) => {
+                    break PlanVerdict {
+                        decision: BackendDecision::local_user(ApprovalDecision::Approved),
+                        steps,
+                    }
+                }
+                Err(e) => {
+                    println!(
+                        "{}. Enter 'a', 'p <step>', 'x <steps>', 'd' or '?' (help)",
+                        e
+                    );
+                    print!("Your choice: ");
+                    let _ = io::stdout().flush();
+                }
+            }
+        };
+
+        verdict.decision.justification = self.read_justification().await?;
+        if verdict.decision.decision.is_approved() {
+            println!("You chose: Approve {}\n", verdict.steps);
+        } else {
+            println!("You chose: {}\n", verdict.decision.decision);
+        }
+        Ok(verdict)
+    }
+
+    /// Print available options for a plan
+    fn print_plan_options(&self) {
+        println!("Please choose an action:");
+        println!("  (a) Approve          - Allow every step to proceed");
+        println!("  (p <step>) Prefix    - Approve steps up to <step> only");
+        println!("  (x <steps>) Exclude  - Approve every step except <steps> (e.g. x 2,4)");
+        println!("  (d) Deny             - Block the whole plan");
+        println!("  (q) Quit             - Exit without deciding");
+        println!("  (?) Help             - Show this help");
+        print!("\nYour choice: ");
+        let _ = io::stdout().flush();
+    }
+
     /// Interactive prompt (async-compatible)
+    ///
+    /// `offer` is set when standing grants and edits are offered; the user
+    /// is then also asked for an optional justification.
     async fn prompt_user_interactive(
         &self,
         diff_card: &DiffCard,
-    ) -> anyhow::Result<ApprovalDecision> {
-        // Print the diff card
+        offer: Option<PromptOffer<'_>>,
+    ) -> anyhow::Result<BackendDecision> {
+        // Print the diff card (earlier input was not meant for it)
+        stdin_lines().discard_pending().await;
         println!("\n{}\n", diff_card);
 
         // Show approval options
-        self.print_approval_options();
+        self.print_approval_options(offer);
 
-        // Get user input (blocking)
-        let decision = self.get_user_input()?;
+        // Get user input
+        let mut answer = self.get_user_input(diff_card, offer).await?;
+        if offer.is_some() {
+            answer.justification = self.read_justification().await?;
+        }
 
-        println!("You chose: {}\n", decision);
+        match &answer.grant {
+            Some(scope) => println!(
+                "You chose: {} (similar actions: {})\n",
+                answer.decision, scope
+            ),
+            None if !answer.modifications.is_empty() => println!(
+                "You chose: {} with {} modification(s)\n",
+                answer.decision,
+                answer.modifications.len()
+            ),
+            None => println!("You chose: {}\n", answer.decision),
+        }
 
-        Ok(decision)
+        Ok(answer)
     }
 
     /// Print available options for user
-    fn print_approval_options(&self) {
+    fn print_approval_options(&self, offer: Option<PromptOffer<'_>>) {
         println!("Please choose an action:");
         println!("  (a) Approve  - Allow this action to proceed");
+        if let Some(offer) = offer {
+            println!("  (e) Edit     - Approve with modifications");
+            println!("  (s) Session  - Approve similar actions for this session");
+            println!(
+                "  (t) Timed    - Approve similar actions for {} minutes",
+                GRANT_MINUTES
+            );
+            if let Some(prefix) = offer.path_prefix {
+                println!(
+                    "  (p) Prefix   - Approve actions under {} for this session",
+                    prefix
+                );
+            }
+        }
         println!("  (d) Deny     - Block this action");
         println!("  (q) Quit     - Exit without deciding");
         println!("  (?) Help     - Show this help");
@@ -100,29 +377,59 @@ impl ApprovalPrompt {
     }
 
     /// Get user input from stdin
-    fn get_user_input(&self) -> anyhow::Result<ApprovalDecision> {
-        let mut input = String::new();
-
+    async fn get_user_input(
+        &self,
+        diff_card: &DiffCard,
+        offer: Option<PromptOffer<'_>>,
+    ) -> anyhow::Result<BackendDecision> {
         loop {
-            input.clear();
-
-            match io::stdin().read_line(&mut input) {
-                Ok(0) => {
+            match read_line().await {
+                Ok(None) => {
                     // EOF reached
                     warn!("No input provided (EOF), denying by default");
-                    return Ok(ApprovalDecision::Denied);
+                    return Ok(BackendDecision::local_user(ApprovalDecision::Denied));
                 }
-                Ok(_) => {
+                Ok(Some(input)) => {
                     let input = input.trim().to_lowercase();
 
-                    match input.as_str() {
-                        "a" | "approve" => return Ok(ApprovalDecision::Approved),
-                        "d" | "deny" => return Ok(ApprovalDecision::Denied),
-                        "q" | "quit" | "exit" => {
+                    let approved = |grant| {
+                        Ok(BackendDecision {
+                            grant,
+                            ..BackendDecision::local_user(ApprovalDecision::Approved)
+                        })
+                    };
+                    let prefix = offer.and_then(|o| o.path_prefix);
+                    match (input.as_str(), offer) {
+                        ("a" | "approve", _) => return approved(None),
+                        ("e" | "edit", Some(_)) => {
+                            match self.read_modifications(diff_card).await? {
+                                Some(modifications) => {
+                                    return Ok(BackendDecision {
+                                        modifications,
+                                        ..BackendDecision::local_user(ApprovalDecision::Approved)
+                                    })
+                                }
+                                None => {
+                                    self.print_approval_options(offer);
+                                    continue;
+                                }
+                            }
+                        }
+                        ("s" | "session", Some(_)) => return approved(Some(GrantScope::Session)),
+                        ("t" | "timed", Some(_)) => {
+                            return approved(Some(GrantScope::Minutes(GRANT_MINUTES)))
+                        }
+                        ("p" | "prefix", Some(_)) if prefix.is_some() => {
+                            return approved(prefix.map(|p| GrantScope::PathPrefix(p.to_string())))
+                        }
+                        ("d" | "deny", _) => {
+                            return Ok(BackendDecision::local_user(ApprovalDecision::Denied))
+                        }
+                        ("q" | "quit" | "exit", _) => {
                             return Err(anyhow::anyhow!("User canceled approval prompt"))
                         }
-                        "?" | "help" => {
-                            self.print_approval_options();
+                        ("?" | "help", _) => {
+                            self.print_approval_options(offer);
                             continue;
                         }
                         _ => {
@@ -133,13 +440,68 @@ impl ApprovalPrompt {
                         }
                     }
                 }
-                Err(e) => {
-                    return Err(anyhow::anyhow!("Failed to read input: {}", e));
-                }
+                Err(e) => return Err(e),
             }
         }
     }
 
+    /// Read modifications, one per line, until an empty line
+    ///
+    /// Returns `None` if no modifications were entered.
+    async fn read_modifications(
+        &self,
+        diff_card: &DiffCard,
+    ) -> anyhow::Result<Option<Vec<Modification>>> {
+        println!("\nChanges:");
+        for (i, change) in diff_card.changes.iter().enumerate() {
+            println!("  #{} {}", i, change.summary());
+        }
+        println!("\nEnter modifications, one per line (empty line to finish):");
+        println!("  drop <#>              - Drop a change");
+        println!("  args <#> [arg...]     - Keep only these command arguments");
+        println!("  to <#> <recipient>    - Change a message recipient");
+        println!("  amount <#> <amount>   - Reduce a transfer amount");
+        println!("  set <pointer> <json>  - Set a tool call argument (e.g. set /limit 10)");
+        println!("  unset <pointer>       - Remove a tool call argument");
+
+        let mut modifications = Vec::new();
+        loop {
+            print!("> ");
+            let _ = io::stdout().flush();
+            let Some(line) = read_line().await?.filter(|l| !l.trim().is_empty()) else {
+                break;
+            };
+            match Modification::parse(&line) {
+                Ok(modification) => modifications.push(modification),
+                Err(e) => println!("Invalid modification: {}", e),
+            }
+        }
+
+        Ok((!modifications.is_empty()).then_some(modifications))
+    }
+
+    /// Read an optional justification for the decision
+    async fn read_justification(&self) -> anyhow::Result<Option<String>> {
+        print!("Justification (optional, Enter to skip): ");
+        let _ = io::stdout().flush();
+
+        let line = read_line().await?.unwrap_or_default();
+        let line = line.trim();
+        Ok((!line.is_empty()).then(|| line.to_string()))
+    }
+
+    /// Remind the user that the prompt expires in `remaining`
+    pub fn remind(&self, remaining: Duration) {
+        if self.config.interactive {
+            print!(
+                "\n⏰ {}s left to decide ({} when the time is up)\nYour choice: ",
+                remaining.as_secs(),
+                self.config.on_timeout
+            );
+            let _ = io::stdout().flush();
+        }
+    }
+
     /// Create a mock prompt for testing
     pub fn mock(decision: ApprovalDecision) -> Self {
         Self {
@@ -147,6 +509,7 @@ impl ApprovalPrompt {
                 interactive: false,
                 auto_approve_green: false,
                 default_decision: decision,
+                ..ApprovalPromptConfig::default()
             },
         }
     }
@@ -158,6 +521,7 @@ impl ApprovalPrompt {
                 interactive: false,
                 auto_approve_green: true,
                 default_decision: ApprovalDecision::Approved,
+                ..ApprovalPromptConfig::default()
             },
         }
     }
@@ -169,11 +533,77 @@ impl ApprovalPrompt {
                 interactive: false,
                 auto_approve_green: true,
                 default_decision: ApprovalDecision::Denied,
+                ..ApprovalPromptConfig::default()
             },
         }
     }
 }
 
+/// Lines read from an input by a helper thread
+///
+/// The thread reads for as long as the input is open, so a caller that stops
+/// waiting (e.g. the approval timed out) loses no line.
+#[derive(Debug)]
+struct InputLines {
+    lines: Mutex<mpsc::UnboundedReceiver<io::Result<String>>>,
+}
+
+impl InputLines {
+    /// Read the input `open` returns on a new thread until EOF
+    fn spawn<R: BufRead>(open: impl FnOnce() -> R + Send + 'static) -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        std::thread::spawn(move || {
+            let mut input = open();
+            loop {
+                let mut line = String::new();
+                let read = match input.read_line(&mut line) {
+                    Ok(0) => break,
+                    Ok(_) => Ok(line),
+                    Err(e) => Err(e),
+                };
+                let failed = read.is_err();
+                if tx.send(read).is_err() || failed {
+                    break;
+                }
+            }
+        });
+        Self {
+            lines: Mutex::new(rx),
+        }
+    }
+
+    /// Next line, or `None` at EOF
+    async fn read_line(&self) -> anyhow::Result<Option<String>> {
+        match self.lines.lock().await.recv().await {
+            Some(line) => line
+                .map(Some)
+                .map_err(|e| anyhow::anyhow!("Failed to read input: {}", e)),
+            None => Ok(None),
+        }
+    }
+
+    /// Drop lines typed before now
+    async fn discard_pending(&self) {
+        let mut lines = self.lines.lock().await;
+        while let Ok(line) = lines.try_recv() {
+            debug!("Discarding input typed before the prompt: {:?}", line);
+        }
+    }
+}
+
+/// The process's stdin, read on a helper thread started on first use
+fn stdin_lines() -> &'static InputLines {
+    static STDIN: OnceLock<InputLines> = OnceLock::new();
+    STDIN.get_or_init(|| InputLines::spawn(|| io::stdin().lock()))
+}
+
+/// Read a line from stdin without blocking the async runtime
+///
+/// Returns `None` at EOF.
+async fn read_line() -> anyhow::Result<Option<String>> {
+    stdin_lines().read_line().await
+}
+
 impl Default for ApprovalPrompt {
     fn default() -> Self {
         Self::new()
@@ -191,6 +621,28 @@ mod tests {
         assert!(config.interactive);
         assert!(config.auto_approve_green);
         assert_eq!(config.default_decision, ApprovalDecision::Denied);
+        assert_eq!(config.on_timeout, TimeoutAction::Deny);
+        assert_eq!(
+            config.timeouts.for_level(RiskLevel::Critical),
+            Some(Duration::from_secs(DEFAULT_TIMEOUT_SECS))
+        );
+    }
+
+    #[test]
+    fn test_timeouts_per_risk_level() {
+        let timeouts = ApprovalTimeouts::uniform(Some(Duration::from_secs(600)))
+            .with_level(RiskLevel::Critical, Some(Duration::from_secs(60)))
+            .with_level(RiskLevel::Low, None);
+
+        assert_eq!(timeouts.for_level(RiskLevel::Low), None);
+        assert_eq!(
+            timeouts.for_level(RiskLevel::High),
+            Some(Duration::from_secs(600))
+        );
+        assert_eq!(
+            timeouts.for_level(RiskLevel::Critical),
+            Some(Duration::from_secs(60))
+        );
     }
 
     #[test]
@@ -205,6 +657,7 @@ mod tests {
             interactive: false,
             auto_approve_green: false,
             default_decision: ApprovalDecision::Approved,
+            ..ApprovalPromptConfig::default()
         };
 
         let prompt = ApprovalPrompt::with_config(config.clone());
@@ -268,6 +721,37 @@ mod tests {
         assert_eq!(decision, ApprovalDecision::Denied);
     }
 
+    /// Input fed a chunk at a time from a channel, like a terminal
+    struct Typed(std::sync::mpsc::Receiver<&'static str>);
+
+    impl io::Read for Typed {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let Ok(chunk) = self.0.recv() else {
+                return Ok(0);
+            };
+            buf[..chunk.len()].copy_from_slice(chunk.as_bytes());
+            Ok(chunk.len())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_answer_after_timed_out_prompt_not_lost() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let input = InputLines::spawn(move || io::BufReader::new(Typed(rx)));
+
+        // Nobody answers the first prompt
+        let unanswered = tokio::time::timeout(Duration::from_millis(50), input.read_line()).await;
+        assert!(unanswered.is_err());
+
+        // The next prompt gets the next line typed
+        input.discard_pending().await;
+        tx.send("a\n").unwrap();
+        assert_eq!(input.read_line().await.unwrap().as_deref(), Some("a\n"));
+
+        drop(tx);
+        assert_eq!(input.read_line().await.unwrap(), None);
+    }
+
     #[test]
     fn test_approval_prompt_default() {
         let prompt = ApprovalPrompt::default();
@@ -0,0 +1,884 @@
+//! Web Approval Backend (localhost HTTP)
+//!
+//! Serves pending Diff Cards on a loopback HTTP endpoint so a reviewer who is
+//! not at the orchestrator's terminal (e.g. on a laptop, via an SSH tunnel)
+//! can approve or deny Red actions.
+//!
+//! # Endpoints
+//!
+//! - `GET /` - minimal HTML page (open `/#<token>` to authenticate)
+//! - `GET /api/pending` - pending requests (Diff Card JSON, plus text and
+//!   HTML renderings)
+//! - `GET /api/pending/{id}` - a single pending request
+//! - `POST /api/pending/{id}/decision` - decide a request; body
+//!   `{"decision": "approve" | "deny" | "defer", "justification": "..."}`,
+//!   optionally with a standing grant (`"grant": "session"`,
+//!   `{"minutes": 15}` or `{"path_prefix": "/tmp/build"}`) or with
+//!   modifications (`"modifications": ["drop 1", "amount 0 5"]`, or as
+//!   [`Modification`] objects), and with the approver's
+//!   [`RecordSignature`] as `"signature"` (see `luminaguard keys sign`)
+//!
+//! # Security
+//!
+//! - The server only binds to loopback addresses
+//! - Every API call must carry an approver's token, either as
+//!   `Authorization: Bearer <token>` or `X-LuminaGuard-Token: <token>`
+//! - Each approver has their own token (the local user, plus any
+//!   [`WebApprover`]s configured), random per backend instance unless
+//!   configured explicitly; decisions are attributed to the token's
+//!   approver, and a body naming another `"approver"` is refused
+
+use super::amend::Modification;
+use super::backend::{ApprovalBackend, ApprovalRequest, ApprovalTimedOut, BackendDecision};
+use super::grants::GrantScope;
+use super::history::ApprovalDecision;
+use super::local_user;
+use super::signing::RecordSignature;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full, Limited};
+use hyper::body::Incoming;
+use hyper::header::{HeaderValue, AUTHORIZATION, CONTENT_TYPE};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use rand::RngCore;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer};
+use serde_json::json;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+/// Maximum accepted request body size
+pub(super) const MAX_BODY_BYTES: usize = 64 * 1024;
+
+/// Header carrying the session token (alternative to `Authorization`)
+const TOKEN_HEADER: &str = "x-luminaguard-token";
+
+/// Web approval backend configuration
+#[derive(Debug, Clone)]
+pub struct WebApprovalConfig {
+    /// Address to bind (must be loopback)
+    pub bind_addr: SocketAddr,
+
+    /// How long to wait for a decision
+    pub timeout: Duration,
+
+    /// Token of the local user (`$USER`) (random if None)
+    pub token: Option<String>,
+
+    /// Further approvers, each with their own token
+    pub approvers: Vec<WebApprover>,
+}
+
+impl Default for WebApprovalConfig {
+    fn default() -> Self {
+        Self {
+            bind_addr: SocketAddr::from(([127, 0, 0, 1], 8765)),
+            timeout: Duration::from_secs(300),
+            token: None,
+            approvers: Vec::new(),
+        }
+    }
+}
+
+/// An approver with their own token
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebApprover {
+    /// Name decisions with the token are attributed to
+    pub name: String,
+
+    /// Token (random if None)
+    pub token: Option<String>,
+}
+
+impl WebApprover {
+    /// Approver with a random token
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            token: None,
+        }
+    }
+}
+
+/// A request waiting for a web decision
+struct Pending {
+    request: ApprovalRequest,
+    responder: oneshot::Sender<BackendDecision>,
+}
+
+/// State shared with the HTTP server
+struct Shared {
+    /// (approver, token) pairs
+    tokens: Vec<(String, String)>,
+    pending: Mutex<HashMap<String, Pending>>,
+}
+
+/// Approval backend served over localhost HTTP
+pub struct WebApprovalBackend {
+    addr: SocketAddr,
+    token: String,
+    timeout: Duration,
+    shared: Arc<Shared>,
+    server: JoinHandle<()>,
+}
+
+impl std::fmt::Debug for WebApprovalBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // The token is deliberately omitted
+        f.debug_struct("WebApprovalBackend")
+            .field("addr", &self.addr)
+            .field("timeout", &self.timeout)
+            .finish_non_exhaustive()
+    }
+}
+
+impl WebApprovalBackend {
+    /// Bind the HTTP server and start serving
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the address is not loopback or cannot be bound,
+    /// or if approver names are empty or repeated.
+    pub async fn start(config: WebApprovalConfig) -> Result<Self> {
+        if !config.bind_addr.ip().is_loopback() {
+            anyhow::bail!(
+                "Web approval backend must bind to a loopback address, got {}",
+                config.bind_addr
+            );
+        }
+
+        let token = config.token.unwrap_or_else(generate_token);
+        let mut tokens = vec![(local_user(), token.clone())];
+        for approver in config.approvers {
+            if approver.name.trim().is_empty() || tokens.iter().any(|(n, _)| *n == approver.name) {
+                anyhow::bail!("Invalid or repeated web approver '{}'", approver.name);
+            }
+            let token = approver.token.unwrap_or_else(generate_token);
+            tokens.push((approver.name, token));
+        }
+
+        let listener = TcpListener::bind(config.bind_addr).await.with_context(|| {
+            format!("Failed to bind web approval server to {}", config.bind_addr)
+        })?;
+        let addr = listener.local_addr()?;
+
+        let shared = Arc::new(Shared {
+            tokens,
+            pending: Mutex::new(HashMap::new()),
+        });
+
+        let server = tokio::spawn(serve(listener, shared.clone()));
+        info!("Web approval server listening on http://{}", addr);
+
+        Ok(Self {
+            addr,
+            token,
+            timeout: config.timeout,
+            shared,
+            server,
+        })
+    }
+
+    /// Address the server is bound to
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Token of the local user
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    /// URL of the approval page, including the local user's token
+    pub fn url(&self) -> String {
+        format!("http://{}/#{}", self.addr, self.token)
+    }
+
+    /// URL of the approval page for a configured approver
+    pub fn approver_url(&self, name: &str) -> Option<String> {
+        self.shared
+            .tokens
+            .iter()
+            .find(|(approver, _)| approver == name)
+            .map(|(_, token)| format!("http://{}/#{}", self.addr, token))
+    }
+
+    /// Number of requests waiting for a decision
+    pub fn pending_count(&self) -> usize {
+        self.shared.pending.lock().map(|p| p.len()).unwrap_or(0)
+    }
+}
+
+impl Drop for WebApprovalBackend {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+#[async_trait]
+impl ApprovalBackend for WebApprovalBackend {
+    fn name(&self) -> &str {
+        "web"
+    }
+
+    async fn request_approval(&self, request: &ApprovalRequest) -> Result<BackendDecision> {
+        let (tx, rx) = oneshot::channel();
+        self.shared
+            .pending
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Pending approvals lock poisoned"))?
+            .insert(
+                request.id.clone(),
+                Pending {
+                    request: request.clone(),
+                    responder: tx,
+                },
+            );
+        info!(
+            "Waiting for web approval of '{}' at {}",
+            request.diff_card.description,
+            self.url()
+        );
+
+        let wait = request.wait_limit(self.timeout);
+        let outcome = tokio::time::timeout(wait, rx).await;
+
+        // Drop the request if it is still pending (timeout)
+        if let Ok(mut pending) = self.shared.pending.lock() {
+            pending.remove(&request.id);
+        }
+
+        match outcome {
+            Ok(Ok(decision)) => Ok(decision),
+            Ok(Err(_)) => anyhow::bail!("Web approval server stopped before a decision"),
+            Err(_) => {
+                warn!("Web approval timed out for request {}", request.id);
+                Err(ApprovalTimedOut {
+                    timeout_secs: wait.as_secs(),
+                }
+                .into())
+            }
+        }
+    }
+}
+
+/// Random 256-bit hex token
+fn generate_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::rngs::OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Accept connections until the task is aborted
+async fn serve(listener: TcpListener, shared: Arc<Shared>) {
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                warn!("Web approval server accept failed: {}", e);
+                continue;
+            }
+        };
+        debug!("Web approval connection from {}", peer);
+
+        let shared = shared.clone();
+        tokio::spawn(async move {
+            let service = service_fn(move |req| handle(req, shared.clone()));
+            if let Err(e) = http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .await
+            {
+                debug!("Web approval connection error: {}", e);
+            }
+        });
+    }
+}
+
+/// Decision submitted by an approver
+#[derive(Debug, Deserialize)]
+pub(super) struct DecisionBody {
+    decision: DecisionInput,
+    #[serde(default)]
+    approver: Option<String>,
+    #[serde(default)]
+    justification: Option<String>,
+    #[serde(default)]
+    grant: Option<GrantScope>,
+    #[serde(default, deserialize_with = "modifications")]
+    modifications: Vec<Modification>,
+    #[serde(default)]
+    signature: Option<RecordSignature>,
+}
+
+/// Accept modifications as objects or in prompt syntax (`"drop 1"`)
+fn modifications<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Vec<Modification>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Input {
+        Text(String),
+        Structured(Modification),
+    }
+
+    Vec::<Input>::deserialize(deserializer)?
+        .into_iter()
+        .map(|input| match input {
+            Input::Text(text) => Modification::parse(&text).map_err(D::Error::custom),
+            Input::Structured(modification) => Ok(modification),
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum DecisionInput {
+    Approve,
+    Deny,
+    Defer,
+}
+
+impl DecisionBody {
+    /// Convert to a decision by `approver`, whose credential it came with
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the body names another approver.
+    pub(super) fn into_decision_by(mut self, approver: &str) -> Result<BackendDecision> {
+        if let Some(named) = self.approver.take().filter(|a| !a.trim().is_empty()) {
+            if named != approver {
+                anyhow::bail!(
+                    "decision names approver '{}' but the token is {}'s",
+                    named,
+                    approver
+                );
+            }
+        }
+        Ok(self.into_relayed_decision(approver))
+    }
+
+    /// Convert to a decision relayed by an integration, attributing it to
+    /// the approver the integration named (`default_approver` if none)
+    pub(super) fn into_relayed_decision(self, default_approver: &str) -> BackendDecision {
+        let modifications = match self.decision {
+            DecisionInput::Approve => self.modifications,
+            DecisionInput::Deny | DecisionInput::Defer => Vec::new(),
+        };
+        BackendDecision {
+            decision: self.decision.into(),
+            approved_by: self
+                .approver
+                .filter(|a| !a.trim().is_empty())
+                .unwrap_or_else(|| default_approver.to_string()),
+            justification: self.justification.filter(|j| !j.trim().is_empty()),
+            grant: self.grant,
+            modifications,
+            signature: self.signature,
+        }
+    }
+}
+
+impl From<DecisionInput> for ApprovalDecision {
+    fn from(input: DecisionInput) -> Self {
+        match input {
+            DecisionInput::Approve => ApprovalDecision::Approved,
+            DecisionInput::Deny => ApprovalDecision::Denied,
+            DecisionInput::Defer => ApprovalDecision::DeferredToLater,
+        }
+    }
+}
+
+async fn handle(
+    req: Request<Incoming>,
+    shared: Arc<Shared>,
+) -> std::result::Result<Response<Full<Bytes>>, Infallible> {
+    let path = req.uri().path().to_string();
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+    if req.method() == Method::GET && path == "/" {
+        return Ok(html_response(INDEX_HTML));
+    }
+
+    if segments.first() != Some(&"api") {
+        return Ok(json_error(StatusCode::NOT_FOUND, "not found"));
+    }
+    let Some(approver) = authenticate(&req, &shared.tokens) else {
+        return Ok(json_error(
+            StatusCode::UNAUTHORIZED,
+            "missing or invalid token",
+        ));
+    };
+
+    let response = match (req.method().clone(), segments.as_slice()) {
+        (Method::GET, ["api", "pending"]) => list_pending(&shared, &approver),
+        (Method::GET, ["api", "pending", id]) => get_pending(&shared, id),
+        (Method::POST, ["api", "pending", id, "decision"]) => {
+            let id = id.to_string();
+            match Limited::new(req.into_body(), MAX_BODY_BYTES)
+                .collect()
+                .await
+            {
+                Ok(body) => decide(&shared, &id, &approver, &body.to_bytes()),
+                Err(_) => json_error(StatusCode::PAYLOAD_TOO_LARGE, "request body too large"),
+            }
+        }
+        _ => json_error(StatusCode::NOT_FOUND, "not found"),
+    };
+    Ok(response)
+}
+
+/// Approver whose token the request carries
+fn authenticate(req: &Request<Incoming>, tokens: &[(String, String)]) -> Option<String> {
+    let presented = req
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .or_else(|| {
+            req.headers()
+                .get(TOKEN_HEADER)
+                .and_then(|v| v.to_str().ok())
+        });
+
+    let presented = presented?;
+    tokens
+        .iter()
+        .find(|(_, token)| constant_time_eq(presented.as_bytes(), token.as_bytes()))
+        .map(|(approver, _)| approver.clone())
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn pending_json(request: &ApprovalRequest) -> serde_json::Value {
+    json!({
+        "id": request.id,
+        "requested_at": request.requested_at,
+        "diff_card": request.diff_card,
+        "record": request.record,
+        "digest": request.digest().ok(),
+        "rendered": request.diff_card.to_human_readable(),
+        "html": request.diff_card.to_html_fragment(),
+    })
+}
+
+fn list_pending(shared: &Shared, approver: &str) -> Response<Full<Bytes>> {
+    let Ok(pending) = shared.pending.lock() else {
+        return json_error(StatusCode::INTERNAL_SERVER_ERROR, "lock poisoned");
+    };
+    let mut requests: Vec<&ApprovalRequest> = pending.values().map(|p| &p.request).collect();
+    requests.sort_by_key(|r| r.requested_at);
+    let items: Vec<serde_json::Value> = requests.into_iter().map(pending_json).collect();
+    json_response(
+        StatusCode::OK,
+        &json!({ "approver": approver, "pending": items }),
+    )
+}
+
+fn get_pending(shared: &Shared, id: &str) -> Response<Full<Bytes>> {
+    let Ok(pending) = shared.pending.lock() else {
+        return json_error(StatusCode::INTERNAL_SERVER_ERROR, "lock poisoned");
+    };
+    match pending.get(id) {
+        Some(p) => json_response(StatusCode::OK, &pending_json(&p.request)),
+        None => json_error(StatusCode::NOT_FOUND, "no pending request with this id"),
+    }
+}
+
+fn decide(shared: &Shared, id: &str, approver: &str, body: &[u8]) -> Response<Full<Bytes>> {
+    let body: DecisionBody = match serde_json::from_slice(body) {
+        Ok(body) => body,
+        Err(e) => return json_error(StatusCode::BAD_REQUEST, &format!("invalid body: {}", e)),
+    };
+    let decision = match body.into_decision_by(approver) {
+        Ok(decision) => decision,
+        Err(e) => return json_error(StatusCode::FORBIDDEN, &format!("{:#}", e)),
+    };
+
+    let Some(pending) = shared.pending.lock().ok().and_then(|mut p| p.remove(id)) else {
+        return json_error(StatusCode::NOT_FOUND, "no pending request with this id");
+    };
+
+    info!(
+        "Web decision for {}: {} by {}",
+        id, decision.decision, decision.approved_by
+    );
+
+    if pending.responder.send(decision.clone()).is_err() {
+        return json_error(StatusCode::GONE, "request is no longer waiting");
+    }
+    json_response(
+        StatusCode::OK,
+        &json!({ "id": id, "decision": decision.decision }),
+    )
+}
+
+pub(super) fn json_response(
+    status: StatusCode,
+    value: &serde_json::Value,
+) -> Response<Full<Bytes>> {
+    let mut response = Response::new(Full::new(Bytes::from(value.to_string())));
+    *response.status_mut() = status;
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    response
+}
+
+pub(super) fn json_error(status: StatusCode, message: &str) -> Response<Full<Bytes>> {
+    json_response(status, &json!({ "error": message }))
+}
+
+fn html_response(html: &'static str) -> Response<Full<Bytes>> {
+    let mut response = Response::new(Full::new(Bytes::from_static(html.as_bytes())));
+    let headers = response.headers_mut();
+    headers.insert(
+        CONTENT_TYPE,
+        HeaderValue::from_static("text/html; charset=utf-8"),
+    );
+    headers.insert(
+        "content-security-policy",
+        HeaderValue::from_static(
+            "default-src 'self'; script-src 'unsafe-inline'; style-src 'unsafe-inline'",
+        ),
+    );
+    response
+}
+
+/// Approval page; the token is read from the URL fragment (never sent to the
+/// server in the URL) and passed in a header on API calls.
+const INDEX_HTML: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>LuminaGuard Approvals</title>
+<style>
+body { font-family: sans-serif; max-width: 960px; margin: 2em auto; padding: 0 1em; }
+.card { border: 1px solid #ccc; border-radius: 6px; padding: 1em; margin-bottom: 1em; }
+pre { background: #f6f6f6; padding: 0.5em; overflow-x: auto; }
+button { margin-right: 0.5em; padding: 0.4em 1em; }
+.approve { background: #2e7d32; color: #fff; }
+.deny { background: #c62828; color: #fff; }
+</style>
+</head>
+<body>
+<h1>LuminaGuard Approvals</h1>
+<p id="approver"></p>
+<div id="status"></div>
+<div id="pending"></div>
+<script>
+const token = decodeURIComponent(location.hash.slice(1));
+const headers = { "Authorization": "Bearer " + token, "Content-Type": "application/json" };
+const status = document.getElementById("status");
+
+async function load() {
+  const res = await fetch("/api/pending", { headers });
+  if (!res.ok) { status.textContent = "Error: " + (await res.json()).error; return; }
+  const { approver, pending } = await res.json();
+  document.getElementById("approver").textContent = "Deciding as " + approver;
+  const list = document.getElementById("pending");
+  list.replaceChildren();
+  status.textContent = pending.length ? "" : "No pending approvals.";
+  for (const item of pending) {
+    const card = document.createElement("div");
+    card.className = "card";
+    // Card content is escaped by the server
+    const rendered = document.createElement("div");
+    rendered.innerHTML = item.html;
+    const reason = document.createElement("input");
+    reason.placeholder = "justification (optional)";
+    const scope = document.createElement("select");
+    for (const [label, value] of [["Only this action", ""], ["Similar actions for this session", "\"session\""], ["Similar actions for 15 minutes", "{\"minutes\":15}"]]) {
+      scope.append(new Option(label, value));
+    }
+    const edits = document.createElement("textarea");
+    edits.placeholder = "modifications, one per line (e.g. drop 1, amount 0 5, set /limit 10)";
+    edits.rows = 2;
+    edits.cols = 60;
+    // Signed approvals are needed when the orchestrator has a trust list
+    const hint = document.createElement("p");
+    hint.textContent = "Sign with: luminaguard keys sign " + item.digest + " --as " + approver + " --decision approve (plus --modify/--grant as chosen)";
+    const signature = document.createElement("textarea");
+    signature.placeholder = "signature (output of luminaguard keys sign)";
+    signature.rows = 2;
+    signature.cols = 60;
+    card.append(rendered, reason, scope, document.createElement("br"), edits, hint, signature, document.createElement("br"));
+    for (const [label, decision, cls] of [["Approve", "approve", "approve"], ["Deny", "deny", "deny"], ["Defer", "defer", ""]]) {
+      const button = document.createElement("button");
+      button.textContent = label;
+      button.className = cls;
+      button.onclick = () => decide(item.id, decision, reason.value, decision === "approve" && scope.value ? JSON.parse(scope.value) : null, edits.value.split("\n").filter(l => l.trim()), signature.value.trim() ? JSON.parse(signature.value) : null);
+      card.append(button);
+    }
+    list.append(card);
+  }
+}
+
+async function decide(id, decision, justification, grant, modifications, signature) {
+  const res = await fetch("/api/pending/" + encodeURIComponent(id) + "/decision", {
+    method: "POST", headers, body: JSON.stringify({ decision, justification, grant, modifications, signature })
+  });
+  status.textContent = res.ok ? "Recorded: " + decision : "Error: " + (await res.json()).error;
+  load();
+}
+
+load();
+setInterval(load, 3000);
+</script>
+</body>
+</html>
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::approval::action::ActionType;
+    use crate::approval::diff::DiffCard;
+
+    fn local_config(timeout: Duration) -> WebApprovalConfig {
+        WebApprovalConfig {
+            bind_addr: SocketAddr::from(([127, 0, 0, 1], 0)),
+            timeout,
+            token: Some("test-token".to_string()),
+            approvers: vec![WebApprover {
+                name: "alice".to_string(),
+                token: Some("alice-token".to_string()),
+            }],
+        }
+    }
+
+    fn request() -> ApprovalRequest {
+        ApprovalRequest::new(DiffCard::new(
+            ActionType::DeleteFile,
+            "Delete /tmp/report.txt".to_string(),
+            vec![],
+        ))
+    }
+
+    fn client() -> reqwest::Client {
+        reqwest::Client::builder().no_proxy().build().unwrap()
+    }
+
+    async fn wait_for_pending(backend: &WebApprovalBackend) {
+        for _ in 0..100 {
+            if backend.pending_count() > 0 {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("request never became pending");
+    }
+
+    #[tokio::test]
+    async fn test_rejects_non_loopback_bind() {
+        let config = WebApprovalConfig {
+            bind_addr: SocketAddr::from(([0, 0, 0, 0], 0)),
+            ..WebApprovalConfig::default()
+        };
+        assert!(WebApprovalBackend::start(config).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_generated_token_is_random() {
+        assert_eq!(generate_token().len(), 64);
+        assert_ne!(generate_token(), generate_token());
+    }
+
+    #[tokio::test]
+    async fn test_approver_tokens() {
+        let backend = WebApprovalBackend::start(WebApprovalConfig {
+            approvers: vec![WebApprover::new("alice"), WebApprover::new("bob")],
+            ..local_config(Duration::from_secs(1))
+        })
+        .await
+        .unwrap();
+        let alice = backend.approver_url("alice").unwrap();
+        let bob = backend.approver_url("bob").unwrap();
+        assert_ne!(alice, bob);
+        assert_ne!(alice, backend.url());
+        assert!(backend.approver_url("mallory").is_none());
+
+        // Names must be unique
+        assert!(WebApprovalBackend::start(WebApprovalConfig {
+            approvers: vec![WebApprover::new("alice"), WebApprover::new("alice")],
+            ..local_config(Duration::from_secs(1))
+        })
+        .await
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_approve_via_http() {
+        let backend = Arc::new(
+            WebApprovalBackend::start(local_config(Duration::from_secs(10)))
+                .await
+                .unwrap(),
+        );
+        let base = format!("http://{}", backend.local_addr());
+        let waiter = {
+            let backend = backend.clone();
+            tokio::spawn(async move { backend.request_approval(&request()).await })
+        };
+        wait_for_pending(&backend).await;
+
+        let pending: serde_json::Value = client()
+            .get(format!("{}/api/pending", base))
+            .bearer_auth("alice-token")
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(pending["approver"], "alice");
+        let item = &pending["pending"][0];
+        assert!(item["rendered"]
+            .as_str()
+            .unwrap()
+            .contains("Delete /tmp/report.txt"));
+        assert!(item["html"].as_str().unwrap().starts_with("<article"));
+        let id = item["id"].as_str().unwrap();
+        let decide = |token: &'static str, body: serde_json::Value| {
+            client()
+                .post(format!("{}/api/pending/{}/decision", base, id))
+                .header(TOKEN_HEADER, token)
+                .json(&body)
+                .send()
+        };
+
+        // Decisions are attributed to the token's approver, not a claimed name
+        let response = decide(
+            "test-token",
+            json!({"decision": "approve", "approver": "alice"}),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+        assert_eq!(backend.pending_count(), 1);
+
+        let response = decide(
+            "alice-token",
+            json!({"decision": "approve", "justification": "expected"}),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+
+        let decision = waiter.await.unwrap().unwrap();
+        assert_eq!(decision.decision, ApprovalDecision::Approved);
+        assert_eq!(decision.approved_by, "alice");
+        assert_eq!(decision.justification.as_deref(), Some("expected"));
+        assert_eq!(backend.pending_count(), 0);
+    }
+
+    #[test]
+    fn test_decision_body_modifications() {
+        let body: DecisionBody = serde_json::from_value(json!({
+            "decision": "approve",
+            "modifications": ["drop 1", {"op": "reduce_amount", "index": 0, "amount": "5"}]
+        }))
+        .unwrap();
+        assert_eq!(
+            body.into_decision_by("alice").unwrap().modifications,
+            vec![
+                Modification::DropChange { index: 1 },
+                Modification::ReduceAmount {
+                    index: 0,
+                    amount: "5".to_string()
+                }
+            ]
+        );
+
+        let denied: DecisionBody =
+            serde_json::from_value(json!({"decision": "deny", "modifications": ["drop 1"]}))
+                .unwrap();
+        assert!(denied
+            .into_decision_by("alice")
+            .unwrap()
+            .modifications
+            .is_empty());
+
+        assert!(serde_json::from_value::<DecisionBody>(
+            json!({"decision": "approve", "modifications": ["explode 1"]})
+        )
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_requires_token() {
+        let backend = WebApprovalBackend::start(local_config(Duration::from_secs(1)))
+            .await
+            .unwrap();
+        let url = format!("http://{}/api/pending", backend.local_addr());
+
+        let missing = client().get(&url).send().await.unwrap();
+        assert_eq!(missing.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+        let wrong = client().get(&url).bearer_auth("nope").send().await.unwrap();
+        assert_eq!(wrong.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+        // The page itself carries no data and needs no token
+        let page = client()
+            .get(format!("http://{}/", backend.local_addr()))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(page.status(), reqwest::StatusCode::OK);
+        let page = page.text().await.unwrap();
+        assert!(page.contains("luminaguard keys sign"));
+        assert!(page.contains("modifications, signature })"));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_request_and_bad_body() {
+        let backend = WebApprovalBackend::start(local_config(Duration::from_secs(1)))
+            .await
+            .unwrap();
+        let url = format!(
+            "http://{}/api/pending/missing/decision",
+            backend.local_addr()
+        );
+
+        let bad = client()
+            .post(&url)
+            .bearer_auth("test-token")
+            .body("{\"decision\": \"maybe\"}")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(bad.status(), reqwest::StatusCode::BAD_REQUEST);
+
+        let unknown = client()
+            .post(&url)
+            .bearer_auth("test-token")
+            .json(&json!({"decision": "deny"}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(unknown.status(), reqwest::StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_timeout() {
+        let backend = WebApprovalBackend::start(local_config(Duration::from_millis(50)))
+            .await
+            .unwrap();
+
+        let err = backend.request_approval(&request()).await.unwrap_err();
+
+        assert!(err.downcast_ref::<ApprovalTimedOut>().is_some());
+        assert_eq!(backend.pending_count(), 0);
+    }
+}
@@ -0,0 +1,968 @@
+//! Approval Webhooks (chat/ticket integration)
+//!
+//! Routes pending Red actions through external systems such as Slack bots
+//! or ticket trackers. Each pending Diff Card is POSTed to a configured
+//! webhook, and the decision comes back either through a callback endpoint
+//! served by the orchestrator or by polling the integration.
+//!
+//! # Payload
+//!
+//! The webhook body is the [`DiffCard::to_json`](super::diff::DiffCard::to_json)
+//! object with six extra fields:
+//! - `request_id` - pending request ID
+//! - `record` - the record the decision will be recorded as
+//! - `digest` - what approvers sign (`luminaguard keys sign`)
+//! - `decision_url` - where the decision is submitted (callback) or fetched (polling)
+//! - `expires_at` - RFC 3339 time after which the action is denied (the
+//!   earlier of the request's own deadline and the configured expiry)
+//! - `markdown` - the card rendered for chat messages
+//!   ([`DiffCard::to_markdown`](super::diff::DiffCard::to_markdown))
+//!
+//! Reminders before a request expires are POSTed to the same webhook as
+//! `{"event": "reminder", "request_id": "...", "decision_url": "...", "remaining_secs": 60}`.
+//!
+//! # Signatures
+//!
+//! Webhooks, callbacks, poll requests and poll responses are all signed
+//! with the shared secret:
+//! - `X-LuminaGuard-Timestamp: <unix seconds>`
+//! - `X-LuminaGuard-Signature: sha256=<hex HMAC-SHA256 of "{timestamp}.{request_id}.{body}">`
+//!
+//! The request ID binds a signed decision to one request: a decision
+//! captured for one request cannot be replayed to another. Signatures more
+//! than [`SIGNATURE_TOLERANCE_SECS`] away from the current time are
+//! rejected, and each signature is accepted only once.
+//!
+//! # Decisions
+//!
+//! Decision bodies use the same format as the web UI:
+//! `{"decision": "approve" | "deny" | "defer", "approver": "...", "justification": "..."}`,
+//! optionally with the approver's own `"signature"`. The approver is named
+//! by the integration (which holds the shared secret), not bound to a
+//! credential of their own; under a trust list, their signature is what
+//! proves it.
+//!
+//! - Callback: the integration POSTs the decision to `decision_url`
+//! - Polling: the orchestrator GETs `decision_url` until the integration
+//!   answers `200` with a decision (`202`, `204` and `404` mean pending)
+
+use super::backend::{ApprovalBackend, ApprovalRequest, ApprovalTimedOut, BackendDecision};
+use super::hex::{hex_decode, hex_encode};
+use super::web::{json_error, json_response, DecisionBody, MAX_BODY_BYTES};
+use crate::mcp::retry::{retry_with_backoff, should_retry_status, RetryConfig};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use bytes::Bytes;
+use hmac::{Hmac, Mac};
+use http_body_util::{BodyExt, Full, Limited};
+use hyper::body::Incoming;
+use hyper::header::HeaderMap;
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use serde_json::json;
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+/// Header carrying the HMAC signature
+pub const SIGNATURE_HEADER: &str = "x-luminaguard-signature";
+
+/// Header carrying the signing timestamp (Unix seconds)
+pub const TIMESTAMP_HEADER: &str = "x-luminaguard-timestamp";
+
+/// Maximum clock difference accepted when verifying a signature
+pub const SIGNATURE_TOLERANCE_SECS: i64 = 300;
+
+/// Path prefix of the callback endpoint
+const CALLBACK_PATH: &str = "/webhook/decision";
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// How decisions come back from the integration
+#[derive(Debug, Clone)]
+pub enum DecisionChannel {
+    /// Serve a callback endpoint the integration POSTs decisions to
+    Callback {
+        /// Address to bind
+        bind_addr: SocketAddr,
+
+        /// Externally reachable base URL (e.g. behind a reverse proxy);
+        /// defaults to `http://<bound address>`
+        public_url: Option<String>,
+    },
+
+    /// Poll `{poll_url}/{request_id}` for a decision
+    Poll {
+        /// Base URL to poll
+        poll_url: String,
+
+        /// Delay between polls
+        interval: Duration,
+    },
+}
+
+/// Webhook backend configuration
+#[derive(Clone)]
+pub struct WebhookConfig {
+    /// URL pending Diff Cards are POSTed to
+    pub url: String,
+
+    /// Shared HMAC secret
+    pub secret: String,
+
+    /// How decisions come back
+    pub channel: DecisionChannel,
+
+    /// How long a request stays open before it is denied
+    pub expiry: Duration,
+
+    /// Retry policy for webhook delivery
+    pub retry: RetryConfig,
+}
+
+impl WebhookConfig {
+    /// Create a configuration with a 5 minute expiry and default retries
+    pub fn new(
+        url: impl Into<String>,
+        secret: impl Into<String>,
+        channel: DecisionChannel,
+    ) -> Self {
+        Self {
+            url: url.into(),
+            secret: secret.into(),
+            channel,
+            expiry: Duration::from_secs(300),
+            retry: RetryConfig::default(),
+        }
+    }
+
+    /// Set how long a request stays open
+    pub fn with_expiry(mut self, expiry: Duration) -> Self {
+        self.expiry = expiry;
+        self
+    }
+
+    /// Set the delivery retry policy
+    pub fn with_retry(mut self, retry: RetryConfig) -> Self {
+        self.retry = retry;
+        self
+    }
+}
+
+impl std::fmt::Debug for WebhookConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // The secret is deliberately omitted
+        f.debug_struct("WebhookConfig")
+            .field("url", &self.url)
+            .field("channel", &self.channel)
+            .field("expiry", &self.expiry)
+            .field("retry", &self.retry)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Sign a body concerning `request_id`, returning the
+/// `X-LuminaGuard-Signature` header value
+pub fn sign(secret: &[u8], timestamp: i64, request_id: &str, body: &[u8]) -> String {
+    let digest = signing_mac(secret, timestamp, request_id, body)
+        .finalize()
+        .into_bytes();
+    format!("sha256={}", hex_encode(&digest))
+}
+
+/// Verify a signature produced by [`sign`]
+///
+/// # Errors
+///
+/// Returns an error if either header is missing or malformed, the
+/// timestamp is outside [`SIGNATURE_TOLERANCE_SECS`] of `now`, or the
+/// signature does not match (e.g. it was made for another request).
+pub fn verify(
+    secret: &[u8],
+    timestamp: Option<&str>,
+    signature: Option<&str>,
+    request_id: &str,
+    body: &[u8],
+    now: i64,
+) -> Result<()> {
+    let timestamp: i64 = timestamp
+        .context("Missing signature timestamp")?
+        .trim()
+        .parse()
+        .context("Malformed signature timestamp")?;
+    if (now - timestamp).abs() > SIGNATURE_TOLERANCE_SECS {
+        anyhow::bail!("Signature timestamp is outside the allowed window");
+    }
+
+    let signature = signature
+        .context("Missing signature")?
+        .trim()
+        .strip_prefix("sha256=")
+        .context("Unsupported signature scheme")?;
+    let signature = hex_decode(signature).context("Malformed signature")?;
+
+    signing_mac(secret, timestamp, request_id, body)
+        .verify_slice(&signature)
+        .map_err(|_| anyhow::anyhow!("Signature mismatch"))
+}
+
+fn signing_mac(secret: &[u8], timestamp: i64, request_id: &str, body: &[u8]) -> HmacSha256 {
+    // HMAC accepts keys of any length
+    let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC accepts any key length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(request_id.as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    mac
+}
+
+/// Signatures seen within the tolerance window
+#[derive(Debug, Default)]
+struct ReplayGuard {
+    /// Signature header value → signing timestamp
+    seen: Mutex<HashMap<String, i64>>,
+}
+
+impl ReplayGuard {
+    /// Accept a signature once; older ones are forgotten once they are
+    /// outside the tolerance window anyway
+    fn admit(&self, signature: &str, timestamp: i64, now: i64) -> Result<()> {
+        let mut seen = self
+            .seen
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Replay guard lock poisoned"))?;
+        seen.retain(|_, ts| (now - *ts).abs() <= SIGNATURE_TOLERANCE_SECS);
+        if seen
+            .insert(signature.trim().to_string(), timestamp)
+            .is_some()
+        {
+            anyhow::bail!("Signature already used");
+        }
+        Ok(())
+    }
+}
+
+fn verify_headers(
+    secret: &[u8],
+    headers: &HeaderMap,
+    request_id: &str,
+    body: &[u8],
+    replays: &ReplayGuard,
+) -> Result<()> {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+    let now = chrono::Utc::now().timestamp();
+    let (timestamp, signature) = (header(TIMESTAMP_HEADER), header(SIGNATURE_HEADER));
+    verify(secret, timestamp, signature, request_id, body, now)?;
+
+    // Both headers parsed in `verify`
+    let timestamp = timestamp.and_then(|t| t.trim().parse().ok()).unwrap_or(now);
+    replays.admit(signature.unwrap_or_default(), timestamp, now)
+}
+
+/// State shared with the callback server
+struct CallbackShared {
+    secret: Vec<u8>,
+    replays: ReplayGuard,
+    pending: Mutex<HashMap<String, oneshot::Sender<BackendDecision>>>,
+}
+
+/// Running callback endpoint
+struct Callback {
+    shared: Arc<CallbackShared>,
+    server: JoinHandle<()>,
+}
+
+/// Approval backend that routes requests through a signed webhook
+pub struct WebhookApprovalBackend {
+    config: WebhookConfig,
+    client: reqwest::Client,
+    callback: Option<Callback>,
+    /// URL decisions are submitted to or fetched from, without the request ID
+    decisions_url: String,
+    replays: ReplayGuard,
+}
+
+impl std::fmt::Debug for WebhookApprovalBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WebhookApprovalBackend")
+            .field("config", &self.config)
+            .field("decisions_url", &self.decisions_url)
+            .finish_non_exhaustive()
+    }
+}
+
+impl WebhookApprovalBackend {
+    /// Create the backend, starting the callback server if configured
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the secret is empty or the callback address
+    /// cannot be bound.
+    pub async fn start(config: WebhookConfig) -> Result<Self> {
+        if config.secret.is_empty() {
+            anyhow::bail!("Webhook secret must not be empty");
+        }
+
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()
+            .context("Failed to build webhook HTTP client")?;
+
+        let (callback, decisions_url) = match &config.channel {
+            DecisionChannel::Callback {
+                bind_addr,
+                public_url,
+            } => {
+                let listener = TcpListener::bind(bind_addr).await.with_context(|| {
+                    format!("Failed to bind webhook callback server to {}", bind_addr)
+                })?;
+                let addr = listener.local_addr()?;
+                let shared = Arc::new(CallbackShared {
+                    secret: config.secret.as_bytes().to_vec(),
+                    replays: ReplayGuard::default(),
+                    pending: Mutex::new(HashMap::new()),
+                });
+                let server = tokio::spawn(serve(listener, shared.clone()));
+                info!("Webhook callback server listening on http://{}", addr);
+
+                let base_url = public_url
+                    .clone()
+                    .unwrap_or_else(|| format!("http://{}", addr));
+                (
+                    Some(Callback { shared, server }),
+                    format!("{}{}", base_url.trim_end_matches('/'), CALLBACK_PATH),
+                )
+            }
+            DecisionChannel::Poll { poll_url, .. } => {
+                (None, poll_url.trim_end_matches('/').to_string())
+            }
+        };
+
+        Ok(Self {
+            config,
+            client,
+            callback,
+            decisions_url,
+            replays: ReplayGuard::default(),
+        })
+    }
+
+    /// URL the decision for a request is submitted to or fetched from
+    pub fn decision_url(&self, request_id: &str) -> String {
+        format!("{}/{}", self.decisions_url, request_id)
+    }
+
+    /// Number of requests waiting for a callback
+    pub fn pending_count(&self) -> usize {
+        self.callback
+            .as_ref()
+            .and_then(|c| c.shared.pending.lock().ok().map(|p| p.len()))
+            .unwrap_or(0)
+    }
+
+    /// Build the signed webhook payload
+    fn payload(&self, request: &ApprovalRequest) -> Result<Vec<u8>> {
+        let expiry = request.requested_at
+            + chrono::Duration::from_std(self.config.expiry).context("Expiry out of range")?;
+        let expires_at = request.expires_at.map_or(expiry, |at| at.min(expiry));
+
+        let mut payload: serde_json::Value = serde_json::from_str(&request.diff_card.to_json()?)?;
+        let object = payload
+            .as_object_mut()
+            .context("Diff Card did not serialize to an object")?;
+        object.insert("request_id".to_string(), json!(request.id));
+        object.insert("record".to_string(), json!(request.record));
+        object.insert("digest".to_string(), json!(request.digest()?));
+        object.insert(
+            "decision_url".to_string(),
+            json!(self.decision_url(&request.id)),
+        );
+        object.insert("expires_at".to_string(), json!(expires_at));
+        object.insert(
+            "markdown".to_string(),
+            json!(request.diff_card.to_markdown()),
+        );
+
+        Ok(serde_json::to_vec(&payload)?)
+    }
+
+    /// POST the payload once
+    ///
+    /// Error messages are worded so [`RetryConfig::should_retry_error`]
+    /// retries connection failures and retryable HTTP statuses only.
+    async fn deliver(&self, request_id: &str, body: &[u8]) -> Result<()> {
+        let timestamp = chrono::Utc::now().timestamp();
+        let response = self
+            .client
+            .post(&self.config.url)
+            .header("content-type", "application/json")
+            .header(TIMESTAMP_HEADER, timestamp.to_string())
+            .header(
+                SIGNATURE_HEADER,
+                sign(self.config.secret.as_bytes(), timestamp, request_id, body),
+            )
+            .body(body.to_vec())
+            .send()
+            .await
+            .map_err(|e| anyhow::anyhow!("Webhook connection failed: {}", e))?;
+
+        let status = response.status();
+        if status.is_success() {
+            Ok(())
+        } else if should_retry_status(status.as_u16()) {
+            anyhow::bail!("Webhook delivery hit a temporary failure: HTTP {}", status)
+        } else {
+            anyhow::bail!("Webhook delivery rejected: HTTP {}", status)
+        }
+    }
+
+    /// Fetch the decision once (polling mode)
+    async fn poll_once(&self, request_id: &str) -> Result<Option<BackendDecision>> {
+        let url = self.decision_url(request_id);
+        let timestamp = chrono::Utc::now().timestamp();
+        let response = self
+            .client
+            .get(&url)
+            .header(TIMESTAMP_HEADER, timestamp.to_string())
+            .header(
+                SIGNATURE_HEADER,
+                sign(self.config.secret.as_bytes(), timestamp, request_id, b""),
+            )
+            .send()
+            .await?;
+
+        match response.status() {
+            StatusCode::OK => {}
+            StatusCode::ACCEPTED | StatusCode::NO_CONTENT | StatusCode::NOT_FOUND => {
+                return Ok(None)
+            }
+            status => anyhow::bail!("Unexpected poll response: HTTP {}", status),
+        }
+
+        let headers = response.headers().clone();
+        let body = response.bytes().await?;
+        verify_headers(
+            self.config.secret.as_bytes(),
+            &headers,
+            request_id,
+            &body,
+            &self.replays,
+        )
+        .context("Poll response signature rejected")?;
+
+        let body: DecisionBody = serde_json::from_slice(&body).context("Invalid decision body")?;
+        Ok(Some(body.into_relayed_decision("webhook")))
+    }
+
+    /// Poll until a decision arrives or the deadline passes
+    async fn poll_until(
+        &self,
+        request_id: &str,
+        interval: Duration,
+        deadline: tokio::time::Instant,
+    ) -> Option<BackendDecision> {
+        loop {
+            match self.poll_once(request_id).await {
+                Ok(Some(decision)) => return Some(decision),
+                Ok(None) => {}
+                Err(e) => warn!("Webhook poll for {} failed: {:#}", request_id, e),
+            }
+            if tokio::time::Instant::now() + interval >= deadline {
+                return None;
+            }
+            tokio::time::sleep(interval).await;
+        }
+    }
+}
+
+impl Drop for WebhookApprovalBackend {
+    fn drop(&mut self) {
+        if let Some(callback) = &self.callback {
+            callback.server.abort();
+        }
+    }
+}
+
+#[async_trait]
+impl ApprovalBackend for WebhookApprovalBackend {
+    fn name(&self) -> &str {
+        "webhook"
+    }
+
+    async fn request_approval(&self, request: &ApprovalRequest) -> Result<BackendDecision> {
+        let wait = request.wait_limit(self.config.expiry);
+        let deadline = tokio::time::Instant::now() + wait;
+        let body = self.payload(request)?;
+
+        // Register before delivery so an immediate callback is not lost
+        let receiver = match &self.callback {
+            Some(callback) => {
+                let (tx, rx) = oneshot::channel();
+                callback
+                    .shared
+                    .pending
+                    .lock()
+                    .map_err(|_| anyhow::anyhow!("Pending approvals lock poisoned"))?
+                    .insert(request.id.clone(), tx);
+                Some(rx)
+            }
+            None => None,
+        };
+
+        let delivered =
+            retry_with_backoff(&self.config.retry, || self.deliver(&request.id, &body)).await;
+        if let Err(e) = delivered {
+            self.forget(&request.id);
+            return Err(e.context(format!(
+                "Failed to deliver approval webhook to {}",
+                self.config.url
+            )));
+        }
+        info!(
+            "Approval webhook delivered for '{}' (request {})",
+            request.diff_card.description, request.id
+        );
+
+        let decision = match (receiver, &self.config.channel) {
+            (Some(rx), _) => tokio::time::timeout_at(deadline, rx)
+                .await
+                .ok()
+                .and_then(|r| r.ok()),
+            (None, DecisionChannel::Poll { interval, .. }) => {
+                self.poll_until(&request.id, *interval, deadline).await
+            }
+            (None, DecisionChannel::Callback { .. }) => None,
+        };
+        self.forget(&request.id);
+
+        decision.ok_or_else(|| {
+            warn!("Webhook approval expired for request {}", request.id);
+            ApprovalTimedOut {
+                timeout_secs: wait.as_secs(),
+            }
+            .into()
+        })
+    }
+
+    async fn remind(&self, request: &ApprovalRequest, remaining: Duration) -> Result<()> {
+        let body = serde_json::to_vec(&json!({
+            "event": "reminder",
+            "request_id": request.id,
+            "decision_url": self.decision_url(&request.id),
+            "remaining_secs": remaining.as_secs(),
+        }))?;
+        retry_with_backoff(&self.config.retry, || self.deliver(&request.id, &body))
+            .await
+            .with_context(|| format!("Failed to deliver reminder to {}", self.config.url))
+    }
+}
+
+impl WebhookApprovalBackend {
+    /// Drop a request from the callback table
+    fn forget(&self, request_id: &str) {
+        if let Some(callback) = &self.callback {
+            if let Ok(mut pending) = callback.shared.pending.lock() {
+                pending.remove(request_id);
+            }
+        }
+    }
+}
+
+/// Accept callback connections until the task is aborted
+async fn serve(listener: TcpListener, shared: Arc<CallbackShared>) {
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                warn!("Webhook callback server accept failed: {}", e);
+                continue;
+            }
+        };
+        debug!("Webhook callback connection from {}", peer);
+
+        let shared = shared.clone();
+        tokio::spawn(async move {
+            let service = service_fn(move |req| handle_callback(req, shared.clone()));
+            if let Err(e) = http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .await
+            {
+                debug!("Webhook callback connection error: {}", e);
+            }
+        });
+    }
+}
+
+async fn handle_callback(
+    req: Request<Incoming>,
+    shared: Arc<CallbackShared>,
+) -> std::result::Result<Response<Full<Bytes>>, Infallible> {
+    let id = match req.uri().path().strip_prefix(CALLBACK_PATH) {
+        Some(rest) if req.method() == Method::POST => rest.trim_matches('/').to_string(),
+        _ => return Ok(json_error(StatusCode::NOT_FOUND, "not found")),
+    };
+
+    let (parts, body) = req.into_parts();
+    let body = match Limited::new(body, MAX_BODY_BYTES).collect().await {
+        Ok(body) => body.to_bytes(),
+        Err(_) => {
+            return Ok(json_error(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                "request body too large",
+            ))
+        }
+    };
+
+    if let Err(e) = verify_headers(&shared.secret, &parts.headers, &id, &body, &shared.replays) {
+        warn!("Rejected webhook callback for {}: {}", id, e);
+        return Ok(json_error(StatusCode::UNAUTHORIZED, "invalid signature"));
+    }
+
+    let body: DecisionBody = match serde_json::from_slice(&body) {
+        Ok(body) => body,
+        Err(e) => {
+            return Ok(json_error(
+                StatusCode::BAD_REQUEST,
+                &format!("invalid body: {}", e),
+            ))
+        }
+    };
+
+    let Some(responder) = shared.pending.lock().ok().and_then(|mut p| p.remove(&id)) else {
+        return Ok(json_error(
+            StatusCode::NOT_FOUND,
+            "no pending request with this id",
+        ));
+    };
+
+    let decision = body.into_relayed_decision("webhook");
+    info!(
+        "Webhook decision for {}: {} by {}",
+        id, decision.decision, decision.approved_by
+    );
+
+    if responder.send(decision.clone()).is_err() {
+        return Ok(json_error(StatusCode::GONE, "request is no longer waiting"));
+    }
+    Ok(json_response(
+        StatusCode::OK,
+        &json!({ "id": id, "decision": decision.decision }),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::approval::action::ActionType;
+    use crate::approval::diff::DiffCard;
+    use crate::approval::history::ApprovalDecision;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const SECRET: &str = "test-secret";
+
+    type Handler = dyn Fn(&Method, &str, &[u8]) -> Response<Full<Bytes>> + Send + Sync;
+
+    /// Local HTTP stand-in for the chat/ticket integration
+    async fn spawn_stand_in(handler: Arc<Handler>) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let handler = handler.clone();
+                tokio::spawn(async move {
+                    let service = service_fn(move |req: Request<Incoming>| {
+                        let handler = handler.clone();
+                        async move {
+                            let method = req.method().clone();
+                            let path = req.uri().path().to_string();
+                            let body = req.into_body().collect().await.unwrap().to_bytes();
+                            Ok::<_, Infallible>(handler(&method, &path, &body))
+                        }
+                    });
+                    let _ = http1::Builder::new()
+                        .serve_connection(TokioIo::new(stream), service)
+                        .await;
+                });
+            }
+        });
+        addr
+    }
+
+    fn signed_response(
+        status: StatusCode,
+        request_id: &str,
+        body: serde_json::Value,
+    ) -> Response<Full<Bytes>> {
+        let body = body.to_string();
+        let timestamp = chrono::Utc::now().timestamp();
+        let mut response = json_response(status, &serde_json::from_str(&body).unwrap());
+        let headers = response.headers_mut();
+        headers.insert(TIMESTAMP_HEADER, timestamp.to_string().parse().unwrap());
+        headers.insert(
+            SIGNATURE_HEADER,
+            sign(SECRET.as_bytes(), timestamp, request_id, body.as_bytes())
+                .parse()
+                .unwrap(),
+        );
+        response
+    }
+
+    fn fast_retry() -> RetryConfig {
+        RetryConfig::default()
+            .max_attempts(3)
+            .base_delay(Duration::from_millis(10))
+            .jitter(0.0)
+    }
+
+    fn request() -> ApprovalRequest {
+        ApprovalRequest::new(DiffCard::new(
+            ActionType::DeleteFile,
+            "Delete /tmp/x".to_string(),
+            vec![],
+        ))
+    }
+
+    fn http() -> reqwest::Client {
+        reqwest::Client::builder().no_proxy().build().unwrap()
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let now = 1_700_000_000;
+        let signature = sign(b"secret", now, "req-1", b"body");
+        assert!(signature.starts_with("sha256="));
+
+        let ts = now.to_string();
+        let check = |secret: &[u8], ts: Option<&str>, sig: Option<&str>, id, body, now| {
+            verify(secret, ts, sig, id, body, now)
+        };
+        let (ts, sig) = (Some(ts.as_str()), Some(signature.as_str()));
+        assert!(check(b"secret", ts, sig, "req-1", b"body", now).is_ok());
+        assert!(check(b"secret", ts, sig, "req-1", b"body", now + 60).is_ok());
+
+        // Tampered body, other request, wrong secret, stale timestamp,
+        // missing headers
+        assert!(check(b"secret", ts, sig, "req-1", b"bodx", now).is_err());
+        assert!(check(b"secret", ts, sig, "req-2", b"body", now).is_err());
+        assert!(check(b"other", ts, sig, "req-1", b"body", now).is_err());
+        assert!(check(b"secret", ts, sig, "req-1", b"body", now + 301).is_err());
+        assert!(check(b"secret", None, sig, "req-1", b"body", now).is_err());
+        assert!(check(b"secret", ts, None, "req-1", b"body", now).is_err());
+        assert!(check(b"secret", ts, Some("sha256=zz"), "req-1", b"body", now).is_err());
+    }
+
+    #[test]
+    fn test_signature_accepted_once() {
+        let guard = ReplayGuard::default();
+        let now = 1_700_000_000;
+
+        assert!(guard.admit("sha256=ab", now, now).is_ok());
+        assert!(guard.admit("sha256=ab", now, now + 10).is_err());
+        assert!(guard.admit("sha256=cd", now, now + 10).is_ok());
+
+        // Forgotten once outside the window (and rejected by `verify` then)
+        assert!(guard
+            .admit("sha256=ab", now, now + SIGNATURE_TOLERANCE_SECS + 1)
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_empty_secret_rejected() {
+        let config = WebhookConfig::new(
+            "http://127.0.0.1:1/hook",
+            "",
+            DecisionChannel::Poll {
+                poll_url: "http://127.0.0.1:1/decisions".to_string(),
+                interval: Duration::from_millis(10),
+            },
+        );
+        assert!(WebhookApprovalBackend::start(config).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_callback_flow_with_retry() {
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let (payload_tx, mut payload_rx) = tokio::sync::mpsc::unbounded_channel();
+
+        let counter = attempts.clone();
+        let stand_in = spawn_stand_in(Arc::new(move |_: &Method, _: &str, body: &[u8]| {
+            // First delivery fails transiently
+            if counter.fetch_add(1, Ordering::SeqCst) == 0 {
+                return json_error(StatusCode::SERVICE_UNAVAILABLE, "busy");
+            }
+            payload_tx.send(body.to_vec()).unwrap();
+            json_response(StatusCode::OK, &json!({}))
+        }))
+        .await;
+
+        let config = WebhookConfig::new(
+            format!("http://{}/hook", stand_in),
+            SECRET,
+            DecisionChannel::Callback {
+                bind_addr: "127.0.0.1:0".parse().unwrap(),
+                public_url: None,
+            },
+        )
+        .with_retry(fast_retry());
+        let backend = Arc::new(WebhookApprovalBackend::start(config).await.unwrap());
+
+        let waiter = tokio::spawn({
+            let backend = backend.clone();
+            async move { backend.request_approval(&request()).await }
+        });
+
+        let payload: serde_json::Value =
+            serde_json::from_slice(&payload_rx.recv().await.unwrap()).unwrap();
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+        assert_eq!(payload["description"], "Delete /tmp/x");
+        assert!(payload["expires_at"].is_string());
+        assert!(payload["markdown"]
+            .as_str()
+            .unwrap()
+            .contains("**Description:** Delete /tmp/x"));
+        let decision_url = payload["decision_url"].as_str().unwrap().to_string();
+        let request_id = payload["request_id"].as_str().unwrap().to_string();
+        assert!(decision_url.ends_with(&request_id));
+
+        // Unsigned callback is rejected
+        let body = json!({"decision": "approve", "approver": "bob"}).to_string();
+        let response = http()
+            .post(&decision_url)
+            .body(body.clone())
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let timestamp = chrono::Utc::now().timestamp();
+        let callback = |signature: String| {
+            http()
+                .post(&decision_url)
+                .header(TIMESTAMP_HEADER, timestamp.to_string())
+                .header(SIGNATURE_HEADER, signature)
+                .body(body.clone())
+                .send()
+        };
+
+        // Signed for another request
+        let other = sign(SECRET.as_bytes(), timestamp, "other", body.as_bytes());
+        let response = callback(other).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let signature = sign(SECRET.as_bytes(), timestamp, &request_id, body.as_bytes());
+        let response = callback(signature.clone()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // Replayed
+        let response = callback(signature).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let decision = waiter.await.unwrap().unwrap();
+        assert_eq!(decision.decision, ApprovalDecision::Approved);
+        assert_eq!(decision.approved_by, "bob");
+        assert_eq!(backend.pending_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_poll_flow() {
+        let polls = Arc::new(AtomicUsize::new(0));
+        let counter = polls.clone();
+        let stand_in = spawn_stand_in(Arc::new(move |method: &Method, path: &str, _: &[u8]| {
+            if method == Method::POST {
+                return json_response(StatusCode::OK, &json!({}));
+            }
+            if counter.fetch_add(1, Ordering::SeqCst) == 0 {
+                return json_error(StatusCode::ACCEPTED, "pending");
+            }
+            signed_response(
+                StatusCode::OK,
+                path.rsplit('/').next().unwrap(),
+                json!({"decision": "deny", "approver": "carol", "justification": "not today"}),
+            )
+        }))
+        .await;
+
+        let config = WebhookConfig::new(
+            format!("http://{}/hook", stand_in),
+            SECRET,
+            DecisionChannel::Poll {
+                poll_url: format!("http://{}/decisions", stand_in),
+                interval: Duration::from_millis(20),
+            },
+        );
+        let backend = WebhookApprovalBackend::start(config).await.unwrap();
+
+        let decision = backend.request_approval(&request()).await.unwrap();
+
+        assert_eq!(decision.decision, ApprovalDecision::Denied);
+        assert_eq!(decision.approved_by, "carol");
+        assert_eq!(decision.justification.as_deref(), Some("not today"));
+        assert_eq!(polls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_poll_ignores_unsigned_decision_and_expires() {
+        let stand_in = spawn_stand_in(Arc::new(|method: &Method, _: &str, _: &[u8]| {
+            if method == Method::POST {
+                return json_response(StatusCode::OK, &json!({}));
+            }
+            json_response(StatusCode::OK, &json!({"decision": "approve"}))
+        }))
+        .await;
+
+        let config = WebhookConfig::new(
+            format!("http://{}/hook", stand_in),
+            SECRET,
+            DecisionChannel::Poll {
+                poll_url: format!("http://{}/decisions", stand_in),
+                interval: Duration::from_millis(20),
+            },
+        )
+        .with_expiry(Duration::from_millis(150));
+        let backend = WebhookApprovalBackend::start(config).await.unwrap();
+
+        let err = backend.request_approval(&request()).await.unwrap_err();
+        assert!(err.downcast_ref::<ApprovalTimedOut>().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_rejected_delivery_not_retried() {
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let counter = attempts.clone();
+        let stand_in = spawn_stand_in(Arc::new(move |_: &Method, _: &str, _: &[u8]| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            json_error(StatusCode::BAD_REQUEST, "nope")
+        }))
+        .await;
+
+        let config = WebhookConfig::new(
+            format!("http://{}/hook", stand_in),
+            SECRET,
+            DecisionChannel::Callback {
+                bind_addr: "127.0.0.1:0".parse().unwrap(),
+                public_url: Some("https://approvals.example.com/".to_string()),
+            },
+        )
+        .with_retry(fast_retry());
+        let backend = WebhookApprovalBackend::start(config).await.unwrap();
+        assert_eq!(
+            backend.decision_url("abc"),
+            "https://approvals.example.com/webhook/decision/abc"
+        );
+
+        assert!(backend.request_approval(&request()).await.is_err());
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+        assert_eq!(backend.pending_count(), 0);
+    }
+}
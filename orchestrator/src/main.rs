@@ -10,8 +10,15 @@
 
 use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
-use luminaguard_orchestrator::approval::diff::{Change, DiffCard};
+use luminaguard_orchestrator::approval::diff::DiffCard;
 use luminaguard_orchestrator::approval::tui::TuiResult;
+use luminaguard_orchestrator::approval::{
+    diff_card_schema, local_user, parse_diff_card, suggest_policy, suggested_policy,
+    verify_audit_log, ApprovalBackend, ApprovalDecision, ApprovalHistory, ApprovalPolicy,
+    ApprovalRequest, ApprovalTimedOut, AuditFormat, AuditQuery, CheckpointStore, DecisionKind,
+    DeferredQueue, GrantScope, GrantStore, Keyring, Modification, PolicyEffect, SuggestOptions,
+    TrustList, WebApprovalBackend, WebApprovalConfig,
+};
 use luminaguard_orchestrator::mcp::{McpClient, StdioTransport};
 use luminaguard_orchestrator::approval::action::ActionType;
 use luminaguard_orchestrator::approval::action::RiskLevel;
@@ -65,24 +72,319 @@ enum Commands {
         /// Path to JSON file containing Diff Card
         #[arg(long)]
         diff_card: String,
+
+        /// Serve the approval on a localhost web page instead of the TUI
+        /// (e.g. 127.0.0.1:8765)
+        #[arg(long, value_name = "ADDR")]
+        web: Option<std::net::SocketAddr>,
+    },
+    /// Manage standing approval grants
+    Grants {
+        /// Grant store (default: ~/.luminaguard/grants.json)
+        #[arg(long, global = true)]
+        store: Option<std::path::PathBuf>,
+
+        #[command(subcommand)]
+        action: GrantsCommand,
+    },
+    /// Review and decide deferred approvals
+    Approvals {
+        /// Deferred queue (default: ~/.luminaguard/deferred.json)
+        #[arg(long, global = true)]
+        queue: Option<std::path::PathBuf>,
+
+        #[command(subcommand)]
+        action: ApprovalsCommand,
+    },
+    /// Query, summarize and export the approval audit trail
+    Audit {
+        /// Approval history (default: ~/.luminaguard/history.jsonl)
+        #[arg(long, global = true)]
+        history: Option<std::path::PathBuf>,
+
+        #[command(subcommand)]
+        action: AuditCommand,
+    },
+    /// Manage approver signing keys and the trust list
+    Keys {
+        /// Keyring directory (default: ~/.luminaguard/keys)
+        #[arg(long, global = true)]
+        keyring: Option<std::path::PathBuf>,
+
+        /// Trust list (default: ~/.luminaguard/trusted_keys.json)
+        #[arg(long, global = true)]
+        trust: Option<std::path::PathBuf>,
+
+        #[command(subcommand)]
+        action: KeysCommand,
+    },
+    /// Restore the files changed by an approved action
+    Rollback {
+        /// Approval record ID (or unique prefix)
+        approval_id: String,
+
+        /// Checkpoint store (default: ~/.luminaguard/checkpoints)
+        #[arg(long)]
+        checkpoints: Option<std::path::PathBuf>,
+    },
+    /// Print the Diff Card JSON Schema
+    Schema {
+        /// Write the schema to a file instead of stdout
+        #[arg(long)]
+        output: Option<std::path::PathBuf>,
     },
     /// Test Firecracker feasibility prototype (requires --features vm-prototype)
     #[cfg(feature = "vm-prototype")]
     TestVmPrototype,
 }
 
+#[derive(Subcommand, Debug)]
+enum GrantsCommand {
+    /// List standing grants
+    List,
+    /// Revoke a grant by ID (or unique ID prefix)
+    Revoke {
+        /// Grant ID
+        id: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ApprovalsCommand {
+    /// List deferred actions
+    List,
+    /// Show the Diff Card of a deferred action
+    Show {
+        /// Deferred action ID (or unique ID prefix)
+        id: String,
+    },
+    /// Approve a deferred action
+    Approve {
+        /// Deferred action ID (or unique ID prefix)
+        id: String,
+
+        /// Reason for the decision
+        #[arg(long)]
+        justification: Option<String>,
+    },
+    /// Deny a deferred action
+    Deny {
+        /// Deferred action ID (or unique ID prefix)
+        id: String,
+
+        /// Reason for the decision
+        #[arg(long)]
+        justification: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum AuditCommand {
+    /// List decisions matching the filters
+    Query {
+        #[command(flatten)]
+        filter: AuditFilter,
+    },
+    /// Show decision counts per day
+    Stats {
+        #[command(flatten)]
+        filter: AuditFilter,
+    },
+    /// Export decisions for SIEM ingestion
+    Export {
+        #[command(flatten)]
+        filter: AuditFilter,
+
+        /// Output format: jsonl, csv or cef (syslog)
+        #[arg(long, default_value = "jsonl")]
+        format: AuditFormat,
+
+        /// Write to this file instead of stdout
+        #[arg(long, short)]
+        output: Option<std::path::PathBuf>,
+    },
+    /// Suggest approval policy rules for consistently decided actions
+    ///
+    /// Prints the rules in the policy file format for review; nothing is
+    /// applied.
+    SuggestPolicy {
+        #[command(flatten)]
+        filter: AuditFilter,
+
+        /// Decisions a pattern needs before a rule is suggested
+        #[arg(long, default_value_t = 10)]
+        min_samples: usize,
+
+        /// Share of decisions that must agree with a rule (0.0 to 1.0)
+        #[arg(long, default_value_t = 1.0, value_parser = parse_rate)]
+        min_rate: f64,
+
+        /// Current policy; rules it already has are not suggested
+        /// (default: ~/.luminaguard/approval_policy.json)
+        #[arg(long)]
+        policy: Option<std::path::PathBuf>,
+
+        /// Write the suggested rules to this file instead of stdout
+        #[arg(long, short)]
+        output: Option<std::path::PathBuf>,
+    },
+    /// Verify the signatures of an exported audit log (JSON or JSON lines)
+    Verify {
+        /// Exported audit log
+        file: std::path::PathBuf,
+
+        /// Trust list (default: ~/.luminaguard/trusted_keys.json)
+        #[arg(long)]
+        trust: Option<std::path::PathBuf>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum KeysCommand {
+    /// Generate a signing key for an approver (default: $USER)
+    Generate {
+        /// Approver name
+        approver: Option<String>,
+    },
+    /// Trust a public key to sign approvals for an approver
+    Trust {
+        /// Approver name
+        approver: String,
+
+        /// Public key (hex), as printed by `keys generate`
+        public_key: String,
+    },
+    /// List local signing keys and trusted keys
+    List,
+    /// Sign a decision on a request with a local key (run by approvers)
+    Sign {
+        /// Digest of the request (its `digest`)
+        digest: String,
+
+        /// Decision: approve, deny or defer
+        #[arg(long, default_value = "approve", value_parser = parse_decision)]
+        decision: ApprovalDecision,
+
+        /// Approver name (default: $USER)
+        #[arg(long = "as")]
+        approver: Option<String>,
+
+        /// Modification made along with the approval (e.g. "drop 1"), repeatable
+        #[arg(long = "modify", value_parser = parse_modification)]
+        modifications: Vec<Modification>,
+
+        /// Standing grant made along with the approval ("session",
+        /// '{"minutes":15}' or '{"path_prefix":"/dir"}')
+        #[arg(long, value_parser = parse_grant)]
+        grant: Option<GrantScope>,
+    },
+    /// Sign the allow rules of an approval policy (run by approvers)
+    SignPolicy {
+        /// Policy file, rewritten with the signatures
+        /// (default: ~/.luminaguard/approval_policy.json)
+        policy: Option<std::path::PathBuf>,
+
+        /// Approver name (default: $USER)
+        #[arg(long = "as")]
+        approver: Option<String>,
+    },
+}
+
+/// Audit trail filters
+#[derive(clap::Args, Debug)]
+struct AuditFilter {
+    /// Only decisions at or after this time (RFC 3339 or YYYY-MM-DD, UTC)
+    #[arg(long, value_parser = parse_time)]
+    since: Option<chrono::DateTime<chrono::Utc>>,
+
+    /// Only decisions before this time (RFC 3339 or YYYY-MM-DD, UTC)
+    #[arg(long, value_parser = parse_time)]
+    until: Option<chrono::DateTime<chrono::Utc>>,
+
+    /// Only decisions by (or with a vote from) this user
+    #[arg(long)]
+    user: Option<String>,
+
+    /// Only this action type (e.g. DeleteFile)
+    #[arg(long)]
+    action: Option<ActionType>,
+
+    /// Only this risk level (low, medium, high, critical)
+    #[arg(long)]
+    risk: Option<RiskLevel>,
+
+    /// Only this decision (approved, denied, deferred)
+    #[arg(long)]
+    decision: Option<DecisionKind>,
+}
+
+impl AuditFilter {
+    fn query(&self) -> AuditQuery {
+        AuditQuery {
+            since: self.since,
+            until: self.until,
+            user: self.user.clone(),
+            action_type: self.action,
+            risk_level: self.risk,
+            decision: self.decision,
+        }
+    }
+}
+
+/// Parse a share between 0.0 (exclusive) and 1.0
+fn parse_rate(s: &str) -> Result<f64> {
+    let rate: f64 = s.parse().with_context(|| format!("Invalid rate '{}'", s))?;
+    if !(rate > 0.0 && rate <= 1.0) {
+        anyhow::bail!("Rate must be greater than 0.0 and at most 1.0");
+    }
+    Ok(rate)
+}
+
+/// Parse a decision an approver signs
+fn parse_decision(s: &str) -> Result<ApprovalDecision> {
+    match s {
+        "approve" => Ok(ApprovalDecision::Approved),
+        "deny" => Ok(ApprovalDecision::Denied),
+        "defer" => Ok(ApprovalDecision::DeferredToLater),
+        _ => anyhow::bail!("Invalid decision '{}' (expected approve, deny or defer)", s),
+    }
+}
+
+/// Parse a modification in prompt syntax (e.g. "drop 1")
+fn parse_modification(s: &str) -> Result<Modification> {
+    Modification::parse(s)
+}
+
+/// Parse a grant scope (JSON, or a bare "session")
+fn parse_grant(s: &str) -> Result<GrantScope> {
+    serde_json::from_str(s)
+        .or_else(|_| serde_json::from_value(json!(s)))
+        .with_context(|| format!("Invalid grant '{}'", s))
+}
+
+/// Parse an RFC 3339 timestamp or a date (midnight UTC)
+fn parse_time(s: &str) -> Result<chrono::DateTime<chrono::Utc>> {
+    if let Ok(date) = chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return Ok(date.and_time(chrono::NaiveTime::MIN).and_utc());
+    }
+    let time = chrono::DateTime::parse_from_rfc3339(s)
+        .with_context(|| format!("Invalid time '{}' (expected RFC 3339 or YYYY-MM-DD)", s))?;
+    Ok(time.with_timezone(&chrono::Utc))
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     // Parse command-line arguments
     let args = Args::parse();
 
-    // Initialize tracing
+    // Initialize tracing (on stderr: stdout carries command output)
     let filter = if args.verbose {
         Level::DEBUG
     } else {
         Level::INFO
     };
     tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
         .with_max_level(filter)
         .with_env_filter(
             EnvFilter::builder()
@@ -111,9 +413,34 @@ async fn main() -> Result<()> {
             info!("Testing MCP connection...");
             test_mcp(command, args, list_tools).await?;
         }
-        Some(Commands::Approve { diff_card }) => {
+        Some(Commands::Approve { diff_card, web }) => {
             info!("Presenting approval TUI...");
-            present_approval(&diff_card).await?;
+            present_approval(&diff_card, web).await?;
+        }
+        Some(Commands::Grants { store, action }) => {
+            manage_grants(store, action)?;
+        }
+        Some(Commands::Approvals { queue, action }) => {
+            manage_approvals(queue, action)?;
+        }
+        Some(Commands::Audit { history, action }) => {
+            audit(history, action)?;
+        }
+        Some(Commands::Keys {
+            keyring,
+            trust,
+            action,
+        }) => {
+            manage_keys(keyring, trust, action)?;
+        }
+        Some(Commands::Rollback {
+            approval_id,
+            checkpoints,
+        }) => {
+            rollback(&approval_id, checkpoints)?;
+        }
+        Some(Commands::Schema { output }) => {
+            print_schema(output)?;
         }
         #[cfg(feature = "vm-prototype")]
         Some(Commands::TestVmPrototype) => {
@@ -302,82 +629,23 @@ async fn test_vm_prototype() -> Result<()> {
 }
 
 /// Present approval TUI for a Diff Card
-async fn present_approval(diff_card_path: &str) -> Result<()> {
+async fn present_approval(
+    diff_card_path: &str,
+    web: Option<std::net::SocketAddr>,
+) -> Result<()> {
     // Read Diff Card from JSON file
     let diff_card_json = fs::read_to_string(diff_card_path)
         .with_context(|| format!("Failed to read Diff Card from {}", diff_card_path))?;
 
-    let diff_card_data: serde_json::Value =
-        serde_json::from_str(&diff_card_json).context("Failed to parse Diff Card JSON")?;
-
-    // Convert to DiffCard structure
-    let changes: Vec<Change> = if let Some(changes_array) = diff_card_data["changes"].as_array() {
-        changes_array
-            .iter()
-            .map(|c| {
-                let change_type = c["change_type"].as_str().unwrap_or("Custom");
-                let summary = c["summary"].as_str().unwrap_or("");
-
-                match change_type {
-                    "FileCreate" => Change::FileCreate {
-                        path: c["details"]["path"].as_str().unwrap_or("").to_string(),
-                        content_preview: c["details"]["before"].as_str().unwrap_or("").to_string(),
-                    },
-                    "FileEdit" => Change::FileEdit {
-                        path: c["details"]["path"].as_str().unwrap_or("").to_string(),
-                        before: c["details"]["before"].as_str().unwrap_or("").to_string(),
-                        after: c["details"]["after"].as_str().unwrap_or("").to_string(),
-                    },
-                    "FileDelete" => Change::FileDelete {
-                        path: c["details"]["path"].as_str().unwrap_or("").to_string(),
-                        size_bytes: c["details"]["size_bytes"].as_u64().unwrap_or(0),
-                    },
-                    "CommandExec" => {
-                        let args = if let Some(arr) = c["details"]["args"].as_array() {
-                            arr.iter()
-                                .filter_map(|v| v.as_str().map(|s| s.to_string()))
-                                .collect()
-                        } else {
-                            vec![]
-                        };
-
-                        Change::CommandExec {
-                            command: summary.replace("Execute: ", ""),
-                            args,
-                            env_vars: None,
-                        }
-                    }
-                    _ => Change::Custom {
-                        description: summary.to_string(),
-                    },
-                }
-            })
-            .collect()
-    } else {
-        vec![]
-    };
+    // Older cards (including the Python agent's format) are upgraded
+    let diff_card = parse_diff_card(&diff_card_json)?;
 
-    let diff_card = DiffCard {
-        action_type: ActionType::Unknown,  // Simplified: use Unknown as placeholder
-        description: diff_card_data["description"]
-            .as_str()
-            .unwrap_or("")
-            .to_string(),
-        risk_level: match diff_card_data["risk_level"].as_str().unwrap_or("medium") {
-            "none" => RiskLevel::None,
-            "low" => RiskLevel::Low,
-            "medium" => RiskLevel::Medium,
-            "high" => RiskLevel::High,
-            "critical" => RiskLevel::Critical,
-            _ => RiskLevel::Medium,
-        },
-        changes,
-        timestamp: chrono::Utc::now(),
+    // Present TUI (or web page)
+    let result = match web {
+        Some(bind_addr) => present_web_approval(diff_card, bind_addr).await?,
+        None => luminaguard_orchestrator::approval::tui::present_tui_approval(&diff_card).await?,
     };
 
-    // Present TUI
-    let result = luminaguard_orchestrator::approval::tui::present_tui_approval(&diff_card).await?;
-
     // Print result to stdout for Python client to read
     match result {
         TuiResult::Approved => {
@@ -392,6 +660,419 @@ async fn present_approval(diff_card_path: &str) -> Result<()> {
             println!("cancelled");
             Ok(())
         }
+        TuiResult::TimedOut => {
+            eprintln!("No decision before the approval timeout, rejecting");
+            println!("rejected");
+            Ok(())
+        }
+    }
+}
+
+/// List or revoke standing grants
+fn manage_grants(store: Option<std::path::PathBuf>, action: GrantsCommand) -> Result<()> {
+    let mut store = GrantStore::open(store.unwrap_or_else(GrantStore::default_path))?;
+
+    match action {
+        GrantsCommand::List => {
+            let now = chrono::Utc::now();
+            if store.list().is_empty() {
+                println!("No standing grants");
+            }
+            for grant in store.list() {
+                let expiry = match grant.expires_at {
+                    Some(t) if t <= now => "expired".to_string(),
+                    Some(t) => format!("until {}", t.format("%Y-%m-%d %H:%M:%S UTC")),
+                    None => "session".to_string(),
+                };
+                println!(
+                    "{}  {}  {}  {}  by {}  (decision {})",
+                    grant.id,
+                    grant.action_type,
+                    grant.matcher,
+                    expiry,
+                    grant.granted_by,
+                    grant.origin_record_id
+                );
+            }
+        }
+        GrantsCommand::Revoke { id } => match store.revoke(&id)? {
+            Some(grant) => println!("Revoked grant {}", grant.id),
+            None => anyhow::bail!("No grant with ID {}", id),
+        },
+    }
+
+    Ok(())
+}
+
+/// Review and decide deferred approvals
+fn manage_approvals(queue: Option<std::path::PathBuf>, action: ApprovalsCommand) -> Result<()> {
+    let mut queue = DeferredQueue::open(queue.unwrap_or_else(DeferredQueue::default_path))?;
+    let approver = local_user();
+
+    let (id, decision, justification) = match action {
+        ApprovalsCommand::List => {
+            let items = queue.list()?;
+            if items.is_empty() {
+                println!("No deferred approvals");
+            }
+            for item in items {
+                println!(
+                    "{}  {:<8}  {}  {}  by {}  (expires {})",
+                    item.id,
+                    item.status.label(),
+                    item.action_type,
+                    item.description,
+                    item.requested_by,
+                    item.expires_at.format("%Y-%m-%d %H:%M:%S UTC")
+                );
+            }
+            return Ok(());
+        }
+        ApprovalsCommand::Show { id } => {
+            let item = queue
+                .get(&id)?
+                .with_context(|| format!("No deferred action with ID {}", id))?;
+            println!("{}", item.diff_card());
+            if let Some(arguments) = &item.arguments {
+                println!("Arguments: {}", serde_json::to_string_pretty(arguments)?);
+            }
+            println!(
+                "Status: {}  (requested by {}, expires {})",
+                item.status.label(),
+                item.requested_by,
+                item.expires_at.format("%Y-%m-%d %H:%M:%S UTC")
+            );
+            return Ok(());
+        }
+        ApprovalsCommand::Approve { id, justification } => {
+            (id, ApprovalDecision::Approved, justification)
+        }
+        ApprovalsCommand::Deny { id, justification } => {
+            (id, ApprovalDecision::Denied, justification)
+        }
+    };
+
+    // Signed with the approver's local key, if they have one
+    let item = queue
+        .get(&id)?
+        .with_context(|| format!("No deferred action with ID {}", id))?;
+    let signature = Keyring::open(Keyring::default_path()).sign(
+        &approver,
+        &item.record_header().digest()?,
+        &decision,
+        &[],
+        None,
+    )?;
+    let item = queue.decide(&id, decision, approver, justification, signature)?;
+    println!(
+        "Deferred action {} {}: {}",
+        item.id,
+        item.status.label(),
+        item.description
+    );
+    Ok(())
+}
+
+/// Query, summarize or export the approval history
+fn audit(history: Option<std::path::PathBuf>, action: AuditCommand) -> Result<()> {
+    let history = ApprovalHistory::open(history.unwrap_or_else(ApprovalHistory::default_path))?;
+
+    match action {
+        AuditCommand::Query { filter } => {
+            let records = history.query(&filter.query());
+            if records.is_empty() {
+                println!("No matching decisions");
+            }
+            for record in records {
+                let action_type = record
+                    .action_type
+                    .map_or_else(|| "-".to_string(), |a| a.to_string());
+                println!(
+                    "{}  {}  {:<8}  {}  {}  by {}",
+                    record.timestamp.format("%Y-%m-%d %H:%M:%S UTC"),
+                    record.id,
+                    DecisionKind::of(&record.decision),
+                    action_type,
+                    record.action_description,
+                    record.approved_by
+                );
+                if let Some(timeout) = &record.timeout {
+                    println!("    {}", timeout);
+                }
+                if let Some(summary) = history
+                    .outcome(&record.id)
+                    .map(|outcome| outcome.to_string())
+                {
+                    println!("    {}", summary);
+                }
+            }
+        }
+        AuditCommand::Stats { filter } => {
+            let stats = history.daily_stats(&filter.query());
+            println!(
+                "{:<10}  {:>8}  {:>8}  {:>8}  {:>8}",
+                "Day", "Approved", "Denied", "Deferred", "Total"
+            );
+            let (mut approved, mut denied, mut deferred) = (0, 0, 0);
+            for day in &stats {
+                println!(
+                    "{:<10}  {:>8}  {:>8}  {:>8}  {:>8}",
+                    day.day,
+                    day.approved,
+                    day.denied,
+                    day.deferred,
+                    day.total()
+                );
+                approved += day.approved;
+                denied += day.denied;
+                deferred += day.deferred;
+            }
+            println!(
+                "{:<10}  {:>8}  {:>8}  {:>8}  {:>8}",
+                "Total",
+                approved,
+                denied,
+                deferred,
+                approved + denied + deferred
+            );
+        }
+        AuditCommand::Export {
+            filter,
+            format,
+            output,
+        } => {
+            let exported = history.export(&filter.query(), format)?;
+            match output {
+                Some(path) => {
+                    fs::write(&path, exported)
+                        .with_context(|| format!("Failed to write {}", path.display()))?;
+                    eprintln!("Exported audit trail ({}) to {}", format, path.display());
+                }
+                None => print!("{}", exported),
+            }
+        }
+        AuditCommand::SuggestPolicy {
+            filter,
+            min_samples,
+            min_rate,
+            policy,
+            output,
+        } => {
+            let policy_path = policy.unwrap_or_else(ApprovalPolicy::default_path);
+            let current = if policy_path.exists() {
+                ApprovalPolicy::from_json_file(&policy_path)?
+            } else {
+                ApprovalPolicy::new()
+            };
+            let options = SuggestOptions {
+                min_samples,
+                min_rate,
+            };
+            let records = history.query(&filter.query());
+            let suggestions = suggest_policy(records.iter().copied(), &options, &current);
+
+            eprintln!(
+                "{} suggested rule(s) from {} decision(s)",
+                suggestions.len(),
+                records.len()
+            );
+            for suggestion in &suggestions {
+                eprintln!("  {}", suggestion);
+            }
+            let json = suggested_policy(&suggestions).to_json()?;
+            match output {
+                Some(path) if path == policy_path => {
+                    anyhow::bail!(
+                        "Refusing to overwrite the current policy {}; review the suggestions first",
+                        path.display()
+                    );
+                }
+                Some(path) => {
+                    fs::write(&path, json + "\n")
+                        .with_context(|| format!("Failed to write {}", path.display()))?;
+                    eprintln!(
+                        "Wrote suggestions to {}; review them before adding them to {}",
+                        path.display(),
+                        policy_path.display()
+                    );
+                }
+                None => println!("{}", json),
+            }
+        }
+        AuditCommand::Verify { file, trust } => verify_audit_file(&file, trust)?,
+    }
+    Ok(())
+}
+
+/// Verify the signatures of an exported audit log offline
+fn verify_audit_file(file: &std::path::Path, trust: Option<std::path::PathBuf>) -> Result<()> {
+    let trust = TrustList::open(trust.unwrap_or_else(TrustList::default_path))?;
+    let log =
+        fs::read_to_string(file).with_context(|| format!("Failed to read {}", file.display()))?;
+
+    let verification = verify_audit_log(&log, &trust)?;
+    for (id, reason) in &verification.failures {
+        println!("FAILED  {}  {}", id, reason);
+    }
+    if !verification.is_valid() {
+        anyhow::bail!(
+            "{} of {} records failed verification",
+            verification.failures.len(),
+            verification.checked
+        );
+    }
+    println!("Verified {} records", verification.checked);
+    Ok(())
+}
+
+/// Generate, trust, list or sign with approver signing keys
+fn manage_keys(
+    keyring: Option<std::path::PathBuf>,
+    trust: Option<std::path::PathBuf>,
+    action: KeysCommand,
+) -> Result<()> {
+    let keyring = Keyring::open(keyring.unwrap_or_else(Keyring::default_path));
+    let mut trust = TrustList::open(trust.unwrap_or_else(TrustList::default_path))?;
+
+    match action {
+        KeysCommand::Generate { approver } => {
+            let approver = approver.unwrap_or_else(local_user);
+            let public_key = keyring.generate(&approver)?;
+            println!("Generated signing key for {}", approver);
+            println!("Public key: {}", public_key);
+            println!(
+                "Trust it with: luminaguard keys trust {} {}",
+                approver, public_key
+            );
+        }
+        KeysCommand::Trust {
+            approver,
+            public_key,
+        } => {
+            trust.add(approver.as_str(), &public_key)?;
+            println!("Trusted key {} for {}", public_key, approver);
+        }
+        KeysCommand::List => {
+            let keys = keyring.list()?;
+            if keys.is_empty() && trust.keys().is_empty() {
+                println!("No signing keys");
+            }
+            for (approver, public_key) in keys {
+                let trusted = if trust.is_trusted(&approver, &public_key) {
+                    "trusted"
+                } else {
+                    "untrusted"
+                };
+                println!("local    {}  {}  ({})", approver, public_key, trusted);
+            }
+            for key in trust.keys() {
+                println!("trusted  {}  {}", key.approver, key.public_key);
+            }
+        }
+        KeysCommand::Sign {
+            digest,
+            decision,
+            approver,
+            modifications,
+            grant,
+        } => {
+            let approver = approver.unwrap_or_else(local_user);
+            let signature = keyring
+                .sign(
+                    &approver,
+                    &digest,
+                    &decision,
+                    &modifications,
+                    grant.as_ref(),
+                )?
+                .with_context(|| format!("No signing key for {}", approver))?;
+            println!("{}", serde_json::to_string(&signature)?);
+        }
+        KeysCommand::SignPolicy { policy, approver } => {
+            let approver = approver.unwrap_or_else(local_user);
+            let path = policy.unwrap_or_else(ApprovalPolicy::default_path);
+            let mut policy = ApprovalPolicy::from_json_file(&path)?;
+            let mut signed = 0;
+            for rule in &mut policy.rules {
+                if rule.effect != PolicyEffect::Allow {
+                    continue;
+                }
+                rule.signature = Some(
+                    keyring
+                        .sign_rule(&approver, rule)?
+                        .with_context(|| format!("No signing key for {}", approver))?,
+                );
+                signed += 1;
+            }
+            fs::write(&path, policy.to_json()? + "\n")
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            println!("Signed {} allow rules in {}", signed, path.display());
+        }
+    }
+    Ok(())
+}
+
+/// Restore the files checkpointed for an approval
+fn rollback(approval_id: &str, checkpoints: Option<std::path::PathBuf>) -> Result<()> {
+    let store = CheckpointStore::open(checkpoints.unwrap_or_else(CheckpointStore::default_path));
+    let checkpoint = store.rollback(approval_id)?;
+
+    println!("Rolled back approval {}", checkpoint.approval_id);
+    for file in &checkpoint.files {
+        let action = if file.before.is_some() {
+            "restored"
+        } else {
+            "removed "
+        };
+        println!("  {}  {}", action, file.path.display());
+    }
+    Ok(())
+}
+
+/// Print (or write) the published Diff Card JSON Schema
+fn print_schema(output: Option<std::path::PathBuf>) -> Result<()> {
+    let schema = serde_json::to_string_pretty(&diff_card_schema())? + "\n";
+    match output {
+        Some(path) => {
+            fs::write(&path, schema).with_context(|| format!("Failed to write {}", path.display()))
+        }
+        None => {
+            print!("{}", schema);
+            Ok(())
+        }
+    }
+}
+
+/// Wait for a decision from the localhost web approval page
+async fn present_web_approval(
+    diff_card: DiffCard,
+    bind_addr: std::net::SocketAddr,
+) -> Result<TuiResult> {
+    let backend = WebApprovalBackend::start(WebApprovalConfig {
+        bind_addr,
+        ..WebApprovalConfig::default()
+    })
+    .await?;
+
+    // stdout is reserved for the decision
+    eprintln!("Open {} to review this action", backend.url());
+
+    match backend
+        .request_approval(&ApprovalRequest::new(diff_card))
+        .await
+    {
+        Ok(outcome) => Ok(match outcome.decision {
+            ApprovalDecision::Approved if outcome.modifications.is_empty() => TuiResult::Approved,
+            ApprovalDecision::Approved | ApprovalDecision::ApprovedWithModifications(_) => {
+                // The decision printed here cannot carry an amended action
+                eprintln!("Modifications are not supported by this command; denying");
+                TuiResult::Rejected
+            }
+            ApprovalDecision::Denied => TuiResult::Rejected,
+            ApprovalDecision::DeferredToLater => TuiResult::Cancelled,
+        }),
+        Err(e) if e.is::<ApprovalTimedOut>() => Ok(TuiResult::Rejected),
+        Err(e) => Err(e),
     }
 }
 
@@ -406,6 +1087,262 @@ mod tests {
         assert!(matches!(args.command, Some(Commands::Run { .. })));
     }
 
+    #[test]
+    fn test_grants_args_parsing() {
+        let args = Args::parse_from([
+            "luminaguard",
+            "grants",
+            "revoke",
+            "abc123",
+            "--store",
+            "g.json",
+        ]);
+        match args.command {
+            Some(Commands::Grants {
+                store,
+                action: GrantsCommand::Revoke { id },
+            }) => {
+                assert_eq!(id, "abc123");
+                assert_eq!(store.unwrap(), std::path::PathBuf::from("g.json"));
+            }
+            other => panic!("unexpected command: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_approvals_args_parsing() {
+        let args = Args::parse_from([
+            "luminaguard",
+            "approvals",
+            "deny",
+            "abc123",
+            "--justification",
+            "not now",
+        ]);
+        match args.command {
+            Some(Commands::Approvals {
+                queue,
+                action: ApprovalsCommand::Deny { id, justification },
+            }) => {
+                assert_eq!(id, "abc123");
+                assert_eq!(justification.as_deref(), Some("not now"));
+                assert!(queue.is_none());
+            }
+            other => panic!("unexpected command: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_audit_args_parsing() {
+        let args = Args::parse_from([
+            "luminaguard",
+            "audit",
+            "export",
+            "--since",
+            "2026-03-01",
+            "--risk",
+            "critical",
+            "--decision",
+            "denied",
+            "--format",
+            "cef",
+        ]);
+        match args.command {
+            Some(Commands::Audit {
+                history,
+                action:
+                    AuditCommand::Export {
+                        filter,
+                        format,
+                        output,
+                    },
+            }) => {
+                assert!(history.is_none() && output.is_none());
+                assert_eq!(format, AuditFormat::Cef);
+                let query = filter.query();
+                assert_eq!(
+                    query.since,
+                    Some(parse_time("2026-03-01T00:00:00Z").unwrap())
+                );
+                assert_eq!(query.risk_level, Some(RiskLevel::Critical));
+                assert_eq!(query.decision, Some(DecisionKind::Denied));
+            }
+            other => panic!("unexpected command: {:?}", other),
+        }
+
+        assert!(
+            Args::try_parse_from(["luminaguard", "audit", "query", "--action", "Teleport"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_suggest_policy_args_parsing() {
+        let args = Args::parse_from([
+            "luminaguard",
+            "audit",
+            "suggest-policy",
+            "--min-samples",
+            "20",
+            "--min-rate",
+            "0.95",
+            "--action",
+            "CreateFile",
+        ]);
+        match args.command {
+            Some(Commands::Audit {
+                action:
+                    AuditCommand::SuggestPolicy {
+                        filter,
+                        min_samples,
+                        min_rate,
+                        policy,
+                        output,
+                    },
+                ..
+            }) => {
+                assert_eq!(min_samples, 20);
+                assert_eq!(min_rate, 0.95);
+                assert!(policy.is_none() && output.is_none());
+                assert_eq!(filter.query().action_type, Some(ActionType::CreateFile));
+            }
+            other => panic!("unexpected command: {:?}", other),
+        }
+
+        assert!(Args::try_parse_from([
+            "luminaguard",
+            "audit",
+            "suggest-policy",
+            "--min-rate",
+            "1.5"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_keys_args_parsing() {
+        let args = Args::parse_from([
+            "luminaguard",
+            "keys",
+            "trust",
+            "alice",
+            "abcd",
+            "--trust",
+            "t.json",
+        ]);
+        match args.command {
+            Some(Commands::Keys {
+                keyring,
+                trust,
+                action:
+                    KeysCommand::Trust {
+                        approver,
+                        public_key,
+                    },
+            }) => {
+                assert!(keyring.is_none());
+                assert_eq!(trust.unwrap(), std::path::PathBuf::from("t.json"));
+                assert_eq!((approver.as_str(), public_key.as_str()), ("alice", "abcd"));
+            }
+            other => panic!("unexpected command: {:?}", other),
+        }
+
+        let args = Args::parse_from([
+            "luminaguard",
+            "keys",
+            "sign",
+            "sha256:ab",
+            "--decision",
+            "deny",
+            "--as",
+            "alice",
+        ]);
+        match args.command {
+            Some(Commands::Keys {
+                action:
+                    KeysCommand::Sign {
+                        digest,
+                        decision,
+                        approver,
+                        modifications,
+                        grant,
+                    },
+                ..
+            }) => {
+                assert_eq!(digest, "sha256:ab");
+                assert_eq!(decision, ApprovalDecision::Denied);
+                assert_eq!(approver.as_deref(), Some("alice"));
+                assert!(modifications.is_empty());
+                assert!(grant.is_none());
+            }
+            other => panic!("unexpected command: {:?}", other),
+        }
+        assert!(
+            Args::try_parse_from(["luminaguard", "keys", "sign", "x", "--decision", "maybe"])
+                .is_err()
+        );
+
+        for (arg, scope) in [
+            ("session", GrantScope::Session),
+            (r#"{"minutes":15}"#, GrantScope::Minutes(15)),
+            (
+                r#"{"path_prefix":"/tmp/out"}"#,
+                GrantScope::PathPrefix("/tmp/out".to_string()),
+            ),
+        ] {
+            let args = Args::parse_from(["luminaguard", "keys", "sign", "x", "--grant", arg]);
+            match args.command {
+                Some(Commands::Keys {
+                    action: KeysCommand::Sign { grant, .. },
+                    ..
+                }) => assert_eq!(grant, Some(scope)),
+                other => panic!("unexpected command: {:?}", other),
+            }
+        }
+        assert!(
+            Args::try_parse_from(["luminaguard", "keys", "sign", "x", "--grant", "forever"])
+                .is_err()
+        );
+
+        let args = Args::parse_from(["luminaguard", "keys", "sign-policy", "p.json"]);
+        match args.command {
+            Some(Commands::Keys {
+                action: KeysCommand::SignPolicy { policy, approver },
+                ..
+            }) => {
+                assert_eq!(policy.unwrap(), std::path::PathBuf::from("p.json"));
+                assert!(approver.is_none());
+            }
+            other => panic!("unexpected command: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_rollback_args_parsing() {
+        let args = Args::parse_from(["luminaguard", "rollback", "abc123"]);
+        match args.command {
+            Some(Commands::Rollback {
+                approval_id,
+                checkpoints,
+            }) => {
+                assert_eq!(approval_id, "abc123");
+                assert!(checkpoints.is_none());
+            }
+            other => panic!("unexpected command: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_schema_args_parsing() {
+        let args = Args::parse_from(["luminaguard", "schema", "--output", "card.json"]);
+        match args.command {
+            Some(Commands::Schema { output }) => {
+                assert_eq!(output, Some(std::path::PathBuf::from("card.json")));
+            }
+            other => panic!("unexpected command: {:?}", other),
+        }
+    }
+
     #[tokio::test]
     async fn test_spawn_vm_integration() {
         // Skip if firecracker or resources are missing
@@ -0,0 +1,545 @@
+//! Tool Result Cache for Idempotent Green Actions
+//!
+//! Agents often call the same read-only tools (`read_file`, `list_directory`)
+//! with identical arguments. This module provides an opt-in cache between the
+//! agent RPC layer and [`McpClient`](crate::mcp::McpClient).
+//!
+//! # Rules
+//!
+//! - Only tools classified as Green (see [`ActionType::from_description`]) are
+//!   cached; Red actions always go to the server
+//! - Entries are keyed on server name, tool name and canonicalized arguments
+//!   (object keys sorted), so `{"a":1,"b":2}` and `{"b":2,"a":1}` share an entry
+//! - Error results (`"isError": true`) are never cached, so a transient
+//!   failure is retried on the next call
+//! - Entries expire after a TTL (configurable per tool)
+//! - The cache is bounded by entry count and total bytes; oldest entries are
+//!   evicted first
+//! - All entries for a server are invalidated when a Red action on that
+//!   server is approved, since it may have changed what Green tools return
+
+use crate::approval::ActionType;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Cache configuration
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CacheConfig {
+    /// Enable caching (opt-in)
+    pub enabled: bool,
+
+    /// Default time-to-live for entries, in seconds
+    pub ttl_secs: u64,
+
+    /// Per-tool TTL overrides, in seconds
+    pub tool_ttls: HashMap<String, u64>,
+
+    /// Maximum number of cached entries
+    pub max_entries: usize,
+
+    /// Results larger than this (serialized) are not cached
+    pub max_entry_bytes: usize,
+
+    /// Maximum total size of all cached results
+    pub max_total_bytes: usize,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ttl_secs: 60,
+            tool_ttls: HashMap::new(),
+            max_entries: 256,
+            max_entry_bytes: 256 * 1024,
+            max_total_bytes: 8 * 1024 * 1024,
+        }
+    }
+}
+
+impl CacheConfig {
+    /// Create an enabled cache configuration with default bounds
+    pub fn enabled() -> Self {
+        Self {
+            enabled: true,
+            ..Self::default()
+        }
+    }
+
+    /// TTL that applies to a tool
+    pub fn ttl_for(&self, tool_name: &str) -> Duration {
+        Duration::from_secs(
+            self.tool_ttls
+                .get(tool_name)
+                .copied()
+                .unwrap_or(self.ttl_secs),
+        )
+    }
+}
+
+/// Cache hit/miss metrics
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheMetrics {
+    /// Lookups served from the cache
+    pub hits: u64,
+
+    /// Lookups that went to the server
+    pub misses: u64,
+
+    /// Results stored in the cache
+    pub insertions: u64,
+
+    /// Entries removed to stay within bounds or because they expired
+    pub evictions: u64,
+
+    /// Entries removed by server invalidation
+    pub invalidations: u64,
+}
+
+/// Cache key: server, tool and canonical arguments
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+    server: String,
+    tool: String,
+    arguments: String,
+}
+
+/// A cached tool result
+#[derive(Debug, Clone)]
+struct CacheEntry {
+    result: Value,
+    size_bytes: usize,
+    inserted_at: Instant,
+    ttl: Duration,
+}
+
+impl CacheEntry {
+    fn is_expired(&self) -> bool {
+        self.inserted_at.elapsed() >= self.ttl
+    }
+}
+
+/// Bounded TTL cache for Green tool results
+#[derive(Debug, Clone, Default)]
+pub struct ToolResultCache {
+    config: CacheConfig,
+    entries: HashMap<CacheKey, CacheEntry>,
+    total_bytes: usize,
+    metrics: CacheMetrics,
+}
+
+impl ToolResultCache {
+    /// Create a new cache
+    pub fn new(config: CacheConfig) -> Self {
+        Self {
+            config,
+            entries: HashMap::new(),
+            total_bytes: 0,
+            metrics: CacheMetrics::default(),
+        }
+    }
+
+    /// Whether results of this tool may be cached
+    ///
+    /// Requires the cache to be enabled and the tool to be a Green action.
+    pub fn is_cacheable(&self, tool_name: &str) -> bool {
+        self.config.enabled && !ActionType::from_description(tool_name).requires_approval()
+    }
+
+    /// Look up a cached result
+    ///
+    /// Counts a hit or miss for cacheable tools; expired entries are removed.
+    pub fn get(&mut self, server: &str, tool_name: &str, arguments: &Value) -> Option<Value> {
+        if !self.is_cacheable(tool_name) {
+            return None;
+        }
+
+        let key = make_key(server, tool_name, arguments);
+        match self.entries.get(&key) {
+            Some(entry) if !entry.is_expired() => {
+                self.metrics.hits += 1;
+                Some(entry.result.clone())
+            }
+            Some(_) => {
+                self.remove(&key);
+                self.metrics.evictions += 1;
+                self.metrics.misses += 1;
+                None
+            }
+            None => {
+                self.metrics.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Store a result for a cacheable tool
+    ///
+    /// Non-cacheable tools, error results and oversized results are
+    /// ignored.
+    pub fn insert(&mut self, server: &str, tool_name: &str, arguments: &Value, result: Value) {
+        if !self.is_cacheable(tool_name)
+            || result["isError"] == true
+            || self.config.max_entries == 0
+        {
+            return;
+        }
+
+        let size_bytes = serde_json::to_vec(&result).map_or(usize::MAX, |v| v.len());
+        if size_bytes > self.config.max_entry_bytes || size_bytes > self.config.max_total_bytes {
+            return;
+        }
+
+        let key = make_key(server, tool_name, arguments);
+        self.remove(&key);
+        self.make_room(size_bytes);
+
+        self.total_bytes += size_bytes;
+        self.entries.insert(
+            key,
+            CacheEntry {
+                result,
+                size_bytes,
+                inserted_at: Instant::now(),
+                ttl: self.config.ttl_for(tool_name),
+            },
+        );
+        self.metrics.insertions += 1;
+    }
+
+    /// Drop all entries for a server
+    ///
+    /// Called when a Red action on the server is approved. Returns the number
+    /// of removed entries.
+    pub fn invalidate_server(&mut self, server: &str) -> usize {
+        let keys: Vec<CacheKey> = self
+            .entries
+            .keys()
+            .filter(|k| k.server == server)
+            .cloned()
+            .collect();
+        for key in &keys {
+            self.remove(key);
+        }
+        self.metrics.invalidations += keys.len() as u64;
+        keys.len()
+    }
+
+    /// Current metrics
+    pub fn metrics(&self) -> CacheMetrics {
+        self.metrics
+    }
+
+    /// Number of cached entries
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// True if the cache holds no entries
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Total size of cached results in bytes
+    pub fn total_bytes(&self) -> usize {
+        self.total_bytes
+    }
+
+    fn remove(&mut self, key: &CacheKey) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.total_bytes -= entry.size_bytes;
+        }
+    }
+
+    /// Evict expired entries, then the oldest ones, until a new entry fits
+    fn make_room(&mut self, incoming_bytes: usize) {
+        let fits = |cache: &Self| {
+            cache.entries.len() < cache.config.max_entries
+                && cache.total_bytes + incoming_bytes <= cache.config.max_total_bytes
+        };
+        if fits(self) {
+            return;
+        }
+
+        let expired: Vec<CacheKey> = self
+            .entries
+            .iter()
+            .filter(|(_, e)| e.is_expired())
+            .map(|(k, _)| k.clone())
+            .collect();
+        for key in &expired {
+            self.remove(key);
+            self.metrics.evictions += 1;
+        }
+
+        while !fits(self) {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, e)| e.inserted_at)
+                .map(|(k, _)| k.clone());
+            match oldest {
+                Some(key) => {
+                    self.remove(&key);
+                    self.metrics.evictions += 1;
+                }
+                None => break,
+            }
+        }
+    }
+}
+
+fn make_key(server: &str, tool_name: &str, arguments: &Value) -> CacheKey {
+    let mut canonical = String::new();
+    write_canonical(arguments, &mut canonical);
+    CacheKey {
+        server: server.to_string(),
+        tool: tool_name.to_string(),
+        arguments: canonical,
+    }
+}
+
+/// Serialize a JSON value with object keys sorted
+fn write_canonical(value: &Value, out: &mut String) {
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            out.push('{');
+            for (i, key) in keys.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&Value::String(key.clone()).to_string());
+                out.push(':');
+                write_canonical(&map[key], out);
+            }
+            out.push('}');
+        }
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical(item, out);
+            }
+            out.push(']');
+        }
+        v => out.push_str(&v.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn result(text: &str) -> Value {
+        json!({ "content": [{ "type": "text", "text": text }] })
+    }
+
+    #[test]
+    fn test_disabled_by_default() {
+        let mut cache = ToolResultCache::default();
+        let args = json!({"path": "/tmp/a"});
+
+        cache.insert("fs", "read_file", &args, result("a"));
+
+        assert!(cache.get("fs", "read_file", &args).is_none());
+        assert!(cache.is_empty());
+        assert_eq!(cache.metrics(), CacheMetrics::default());
+    }
+
+    #[test]
+    fn test_hit_after_insert() {
+        let mut cache = ToolResultCache::new(CacheConfig::enabled());
+        let args = json!({"path": "/tmp/a"});
+
+        assert!(cache.get("fs", "read_file", &args).is_none());
+        cache.insert("fs", "read_file", &args, result("a"));
+
+        assert_eq!(cache.get("fs", "read_file", &args), Some(result("a")));
+        let metrics = cache.metrics();
+        assert_eq!(metrics.hits, 1);
+        assert_eq!(metrics.misses, 1);
+        assert_eq!(metrics.insertions, 1);
+    }
+
+    #[test]
+    fn test_red_tools_not_cached() {
+        let mut cache = ToolResultCache::new(CacheConfig::enabled());
+        let args = json!({"path": "/tmp/a", "content": "x"});
+
+        assert!(!cache.is_cacheable("write_file"));
+        cache.insert("fs", "write_file", &args, result("ok"));
+
+        assert!(cache.is_empty());
+        assert!(cache.get("fs", "write_file", &args).is_none());
+        assert_eq!(cache.metrics().misses, 0);
+    }
+
+    #[test]
+    fn test_error_results_not_cached() {
+        let mut cache = ToolResultCache::new(CacheConfig::enabled());
+        let args = json!({"path": "/tmp/a"});
+        let mut error = result("No such file");
+        error["isError"] = json!(true);
+
+        cache.insert("fs", "read_file", &args, error);
+
+        assert!(cache.is_empty());
+        assert!(cache.get("fs", "read_file", &args).is_none());
+        assert_eq!(cache.metrics().insertions, 0);
+    }
+
+    #[test]
+    fn test_canonical_arguments() {
+        let mut cache = ToolResultCache::new(CacheConfig::enabled());
+
+        cache.insert(
+            "fs",
+            "search_files",
+            &json!({"path": "/tmp", "pattern": "*.rs", "opts": {"b": 1, "a": 2}}),
+            result("hit"),
+        );
+
+        assert!(cache
+            .get(
+                "fs",
+                "search_files",
+                &json!({"opts": {"a": 2, "b": 1}, "pattern": "*.rs", "path": "/tmp"})
+            )
+            .is_some());
+        assert!(cache
+            .get(
+                "fs",
+                "search_files",
+                &json!({"path": "/tmp", "pattern": "*.md"})
+            )
+            .is_none());
+    }
+
+    #[test]
+    fn test_key_includes_server() {
+        let mut cache = ToolResultCache::new(CacheConfig::enabled());
+        let args = json!({"path": "/tmp/a"});
+
+        cache.insert("fs1", "read_file", &args, result("a"));
+
+        assert!(cache.get("fs2", "read_file", &args).is_none());
+    }
+
+    #[test]
+    fn test_ttl_expiry() {
+        let mut config = CacheConfig::enabled();
+        config.tool_ttls.insert("read_file".to_string(), 0);
+        let mut cache = ToolResultCache::new(config);
+        let args = json!({"path": "/tmp/a"});
+
+        cache.insert("fs", "read_file", &args, result("a"));
+
+        assert!(cache.get("fs", "read_file", &args).is_none());
+        assert!(cache.is_empty());
+        assert_eq!(cache.metrics().evictions, 1);
+    }
+
+    #[test]
+    fn test_entry_count_bound_evicts_oldest() {
+        let config = CacheConfig {
+            max_entries: 2,
+            ..CacheConfig::enabled()
+        };
+        let mut cache = ToolResultCache::new(config);
+
+        for i in 0..3 {
+            cache.insert("fs", "read_file", &json!({ "path": i }), result("x"));
+            std::thread::sleep(Duration::from_millis(2));
+        }
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache
+            .get("fs", "read_file", &json!({ "path": 0 }))
+            .is_none());
+        assert!(cache
+            .get("fs", "read_file", &json!({ "path": 2 }))
+            .is_some());
+        assert_eq!(cache.metrics().evictions, 1);
+
+        // No room for any entry
+        let mut cache = ToolResultCache::new(CacheConfig {
+            max_entries: 0,
+            ..CacheConfig::enabled()
+        });
+        cache.insert("fs", "read_file", &json!({ "path": 0 }), result("x"));
+        assert!(cache.is_empty());
+        assert_eq!(cache.metrics().insertions, 0);
+    }
+
+    #[test]
+    fn test_size_bounds() {
+        let config = CacheConfig {
+            max_entry_bytes: 100,
+            max_total_bytes: 150,
+            ..CacheConfig::enabled()
+        };
+        let mut cache = ToolResultCache::new(config);
+
+        cache.insert(
+            "fs",
+            "read_file",
+            &json!({"path": "big"}),
+            result(&"x".repeat(200)),
+        );
+        assert!(cache.is_empty());
+
+        cache.insert(
+            "fs",
+            "read_file",
+            &json!({"path": "a"}),
+            result(&"a".repeat(40)),
+        );
+        cache.insert(
+            "fs",
+            "read_file",
+            &json!({"path": "b"}),
+            result(&"b".repeat(40)),
+        );
+        assert!(cache.total_bytes() <= 150);
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_invalidate_server() {
+        let mut cache = ToolResultCache::new(CacheConfig::enabled());
+        cache.insert("fs", "read_file", &json!({"path": "a"}), result("a"));
+        cache.insert("fs", "list_directory", &json!({"path": "/"}), result("b"));
+        cache.insert(
+            "github",
+            "search_repositories",
+            &json!({"q": "x"}),
+            result("c"),
+        );
+
+        assert_eq!(cache.invalidate_server("fs"), 2);
+
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.metrics().invalidations, 2);
+        assert!(cache
+            .get("github", "search_repositories", &json!({"q": "x"}))
+            .is_some());
+    }
+
+    #[test]
+    fn test_write_canonical() {
+        let mut out = String::new();
+        write_canonical(
+            &json!({"b": [1, {"d": null, "c": "x"}], "a": true}),
+            &mut out,
+        );
+        assert_eq!(out, r#"{"a":true,"b":[1,{"c":"x","d":null}]}"#);
+    }
+}
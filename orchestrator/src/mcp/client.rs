@@ -451,7 +451,9 @@ where
             let error = response
                 .error
                 .ok_or_else(|| McpError::internal_error("Tool call failed with unknown error"))?;
-            return Err(anyhow::anyhow!("Tool '{}' failed: {}", name, error));
+            // Keep the McpError in the chain so callers can inspect its code
+            let message = format!("Tool '{}' failed: {}", name, error);
+            return Err(anyhow::Error::new(error).context(message));
         }
 
         // Parse tool result
@@ -681,7 +683,8 @@ mod tests {
         // Call tool should fail
         let result = client.call_tool("unknown_tool", json!({})).await;
 
-        assert!(result.is_err());
+        let err = result.unwrap_err();
+        assert_eq!(err.downcast_ref::<McpError>().unwrap().code, -32601);
     }
 
     #[tokio::test]
@@ -33,6 +33,15 @@ pub mod client;
 // Retry logic and error resilience
 pub mod retry;
 
+// Tool result size limits and output sanitization
+pub mod sanitize;
+
+// Tool allowlists and argument constraints
+pub mod policy;
+
+// Result caching for idempotent Green tools
+pub mod cache;
+
 // Re-export commonly used types for convenience
 pub use protocol::{
     ClientCapabilities, ClientInfo, InitializeParams, McpError, McpMethod, McpRequest, McpResponse,
@@ -46,6 +55,15 @@ pub use transport::StdioTransport;
 // Re-export client types
 pub use client::{ClientState, McpClient};
 
+// Re-export cache types
+pub use cache::{CacheConfig, CacheMetrics, ToolResultCache};
+
+// Re-export policy types
+pub use policy::{ArgumentConstraint, PolicyViolation, ServerToolPolicy, ToolPolicy};
+
+// Re-export sanitization types
+pub use sanitize::{OutputLimits, SanitizeReport, SanitizerConfig, ToolResultSanitizer};
+
 // Note: Old placeholder client removed - now using client::McpClient
 
 // Integration tests module
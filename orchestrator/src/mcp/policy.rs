@@ -0,0 +1,596 @@
+//! Tool Allowlists and Argument Constraints
+//!
+//! This module restricts which tools of an MCP server the agent can see and
+//! call, and which argument values it may pass.
+//!
+//! # Configuration
+//!
+//! Policies are configured per MCP server (keyed by server name) and can be
+//! loaded from JSON:
+//!
+//! ```json
+//! {
+//!   "servers": {
+//!     "filesystem": {
+//!       "allowed_tools": ["read_file", "list_directory", "write_file"],
+//!       "hidden_tools": ["move_file"],
+//!       "argument_constraints": {
+//!         "write_file": [
+//!           { "type": "path_under", "argument": "path", "roots": ["/home/agent/out"] }
+//!         ]
+//!       },
+//!       "description_overrides": {
+//!         "write_file": "Write a file under /home/agent/out"
+//!       }
+//!     }
+//!   }
+//! }
+//! ```
+//!
+//! # Enforcement
+//!
+//! - `tools/list` results are filtered with [`ToolPolicy::filter_tools`]
+//! - `tools/call` requests are checked with [`ToolPolicy::check_call`] before
+//!   they reach `McpClient::call_tool`
+//!
+//! Servers without an entry are unrestricted.
+
+use crate::mcp::protocol::Tool;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+
+/// Tool policies for all MCP servers
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToolPolicy {
+    /// Per-server policies (keyed by server name)
+    pub servers: HashMap<String, ServerToolPolicy>,
+}
+
+/// Tool policy for a single MCP server
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServerToolPolicy {
+    /// If set, only these tools are visible and callable
+    pub allowed_tools: Option<Vec<String>>,
+
+    /// Tools that are never visible or callable
+    pub hidden_tools: Vec<String>,
+
+    /// Argument constraints per tool name
+    pub argument_constraints: HashMap<String, Vec<ArgumentConstraint>>,
+
+    /// Replacement descriptions shown to the model, per tool name
+    pub description_overrides: HashMap<String, String>,
+}
+
+impl ServerToolPolicy {
+    /// Whether a tool is visible and callable
+    pub fn is_tool_allowed(&self, tool_name: &str) -> bool {
+        if self.hidden_tools.iter().any(|t| t == tool_name) {
+            return false;
+        }
+        match &self.allowed_tools {
+            Some(allowed) => allowed.iter().any(|t| t == tool_name),
+            None => true,
+        }
+    }
+}
+
+/// A constraint on a single tool argument
+///
+/// If the argument is an array, every element must satisfy the constraint.
+/// A missing argument is not a violation (the server validates its own
+/// required arguments).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ArgumentConstraint {
+    /// Path argument must be under one of the given roots
+    PathUnder {
+        /// Argument name
+        argument: String,
+        /// Allowed root directories
+        roots: Vec<String>,
+    },
+
+    /// URL argument's host must match one of the given hosts
+    ///
+    /// A host of the form `*.example.com` matches any subdomain of
+    /// `example.com` (but not `example.com` itself).
+    UrlHost {
+        /// Argument name
+        argument: String,
+        /// Allowed hosts
+        hosts: Vec<String>,
+    },
+
+    /// Argument must equal one of the given values
+    OneOf {
+        /// Argument name
+        argument: String,
+        /// Allowed values
+        values: Vec<Value>,
+    },
+
+    /// String argument must not exceed the given length (in characters)
+    MaxLength {
+        /// Argument name
+        argument: String,
+        /// Maximum length
+        max: usize,
+    },
+}
+
+impl ArgumentConstraint {
+    /// Name of the constrained argument
+    pub fn argument(&self) -> &str {
+        match self {
+            ArgumentConstraint::PathUnder { argument, .. }
+            | ArgumentConstraint::UrlHost { argument, .. }
+            | ArgumentConstraint::OneOf { argument, .. }
+            | ArgumentConstraint::MaxLength { argument, .. } => argument,
+        }
+    }
+
+    /// Check a single argument value, returning a reason on failure
+    fn check_value(&self, value: &Value) -> std::result::Result<(), String> {
+        match self {
+            ArgumentConstraint::PathUnder { roots, .. } => {
+                let path = value
+                    .as_str()
+                    .ok_or_else(|| "expected a path string".to_string())?;
+                let normalized = normalize_path(Path::new(path))
+                    .ok_or_else(|| format!("path '{}' escapes its root", path))?;
+                let allowed = roots.iter().any(|root| {
+                    normalize_path(Path::new(root)).is_some_and(|r| normalized.starts_with(&r))
+                });
+                if allowed {
+                    Ok(())
+                } else {
+                    Err(format!(
+                        "path '{}' is not under an allowed root ({})",
+                        path,
+                        roots.join(", ")
+                    ))
+                }
+            }
+            ArgumentConstraint::UrlHost { hosts, .. } => {
+                let url = value
+                    .as_str()
+                    .ok_or_else(|| "expected a URL string".to_string())?;
+                let parsed = reqwest::Url::parse(url)
+                    .map_err(|e| format!("invalid URL '{}': {}", url, e))?;
+                let host = parsed
+                    .host_str()
+                    .ok_or_else(|| format!("URL '{}' has no host", url))?
+                    .to_lowercase();
+                if hosts.iter().any(|pattern| host_matches(&host, pattern)) {
+                    Ok(())
+                } else {
+                    Err(format!("host '{}' is not in the allowed host list", host))
+                }
+            }
+            ArgumentConstraint::OneOf { values, .. } => {
+                if values.contains(value) {
+                    Ok(())
+                } else {
+                    Err(format!("value {} is not one of the allowed values", value))
+                }
+            }
+            ArgumentConstraint::MaxLength { max, .. } => {
+                let s = value
+                    .as_str()
+                    .ok_or_else(|| "expected a string".to_string())?;
+                let len = s.chars().count();
+                if len <= *max {
+                    Ok(())
+                } else {
+                    Err(format!("length {} exceeds maximum of {}", len, max))
+                }
+            }
+        }
+    }
+}
+
+/// Why a tool call was rejected by policy
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PolicyViolation {
+    /// MCP server name
+    pub server: String,
+
+    /// Tool that was called
+    pub tool: String,
+
+    /// Argument that violated a constraint (None if the tool itself is blocked)
+    pub argument: Option<String>,
+
+    /// Human-readable reason
+    pub reason: String,
+}
+
+impl fmt::Display for PolicyViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.argument {
+            Some(arg) => write!(
+                f,
+                "tool '{}' on server '{}': argument '{}': {}",
+                self.tool, self.server, arg, self.reason
+            ),
+            None => write!(
+                f,
+                "tool '{}' on server '{}': {}",
+                self.tool, self.server, self.reason
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PolicyViolation {}
+
+impl ToolPolicy {
+    /// Create an empty (unrestricted) policy
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load a policy from a JSON file
+    pub fn from_json_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let data = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read tool policy from {}", path.display()))?;
+        serde_json::from_str(&data)
+            .with_context(|| format!("Failed to parse tool policy from {}", path.display()))
+    }
+
+    /// Set the policy for a server
+    pub fn with_server(mut self, server: impl Into<String>, policy: ServerToolPolicy) -> Self {
+        self.servers.insert(server.into(), policy);
+        self
+    }
+
+    /// Get the policy for a server (None if unrestricted)
+    pub fn for_server(&self, server: &str) -> Option<&ServerToolPolicy> {
+        self.servers.get(server)
+    }
+
+    /// Filter and rewrite a server's tool list for the model
+    ///
+    /// Hidden and non-allowlisted tools are removed; description overrides
+    /// are applied to the remaining tools.
+    pub fn filter_tools(&self, server: &str, tools: Vec<Tool>) -> Vec<Tool> {
+        let Some(policy) = self.for_server(server) else {
+            return tools;
+        };
+
+        tools
+            .into_iter()
+            .filter(|t| policy.is_tool_allowed(&t.name))
+            .map(|mut t| {
+                if let Some(description) = policy.description_overrides.get(&t.name) {
+                    t.description = description.clone();
+                }
+                t
+            })
+            .collect()
+    }
+
+    /// Check a tool call against the server's policy
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`PolicyViolation`] if the tool is hidden/not allowlisted or
+    /// an argument violates a constraint.
+    pub fn check_call(
+        &self,
+        server: &str,
+        tool: &str,
+        arguments: &Value,
+    ) -> std::result::Result<(), PolicyViolation> {
+        let Some(policy) = self.for_server(server) else {
+            return Ok(());
+        };
+
+        let violation = |argument: Option<&str>, reason: String| PolicyViolation {
+            server: server.to_string(),
+            tool: tool.to_string(),
+            argument: argument.map(str::to_string),
+            reason,
+        };
+
+        if !policy.is_tool_allowed(tool) {
+            return Err(violation(None, "tool is not permitted".to_string()));
+        }
+
+        let constraints = match policy.argument_constraints.get(tool) {
+            Some(c) => c,
+            None => return Ok(()),
+        };
+
+        for constraint in constraints {
+            let name = constraint.argument();
+            let Some(value) = arguments.get(name) else {
+                continue;
+            };
+            let values: Vec<&Value> = match value {
+                Value::Array(items) => items.iter().collect(),
+                v => vec![v],
+            };
+            for v in values {
+                constraint
+                    .check_value(v)
+                    .map_err(|reason| violation(Some(name), reason))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Lexically normalize a path, resolving `.` and `..` without touching the
+/// filesystem
+///
+/// Returns `None` if `..` would climb above the start of the path.
+fn normalize_path(path: &Path) -> Option<PathBuf> {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !out.pop() || out.as_os_str().is_empty() {
+                    return None;
+                }
+            }
+            c => out.push(c.as_os_str()),
+        }
+    }
+    Some(out)
+}
+
+/// Match a host against a pattern (`example.com` or `*.example.com`)
+fn host_matches(host: &str, pattern: &str) -> bool {
+    let pattern = pattern.to_lowercase();
+    match pattern.strip_prefix("*.") {
+        Some(suffix) => host
+            .strip_suffix(suffix)
+            .is_some_and(|prefix| prefix.ends_with('.')),
+        None => host == pattern,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn tool(name: &str) -> Tool {
+        Tool {
+            name: name.to_string(),
+            description: format!("{} description", name),
+            input_schema: json!({}),
+        }
+    }
+
+    fn fs_policy() -> ToolPolicy {
+        let mut constraints = HashMap::new();
+        constraints.insert(
+            "write_file".to_string(),
+            vec![ArgumentConstraint::PathUnder {
+                argument: "path".to_string(),
+                roots: vec!["/home/agent/out".to_string()],
+            }],
+        );
+        constraints.insert(
+            "read_multiple_files".to_string(),
+            vec![ArgumentConstraint::PathUnder {
+                argument: "paths".to_string(),
+                roots: vec!["/home/agent".to_string()],
+            }],
+        );
+
+        let mut overrides = HashMap::new();
+        overrides.insert(
+            "write_file".to_string(),
+            "Write a file under /home/agent/out".to_string(),
+        );
+
+        ToolPolicy::new().with_server(
+            "filesystem",
+            ServerToolPolicy {
+                allowed_tools: None,
+                hidden_tools: vec!["move_file".to_string()],
+                argument_constraints: constraints,
+                description_overrides: overrides,
+            },
+        )
+    }
+
+    #[test]
+    fn test_unknown_server_unrestricted() {
+        let policy = fs_policy();
+        assert!(policy.check_call("github", "move_file", &json!({})).is_ok());
+        assert_eq!(
+            policy.filter_tools("github", vec![tool("move_file")]).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_filter_hides_and_rewrites() {
+        let policy = fs_policy();
+        let tools = policy.filter_tools(
+            "filesystem",
+            vec![tool("read_file"), tool("move_file"), tool("write_file")],
+        );
+
+        let names: Vec<_> = tools.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["read_file", "write_file"]);
+        assert_eq!(tools[1].description, "Write a file under /home/agent/out");
+        assert_eq!(tools[0].description, "read_file description");
+    }
+
+    #[test]
+    fn test_allowlist() {
+        let policy = ToolPolicy::new().with_server(
+            "fetch",
+            ServerToolPolicy {
+                allowed_tools: Some(vec!["fetch".to_string()]),
+                ..ServerToolPolicy::default()
+            },
+        );
+
+        assert!(policy.check_call("fetch", "fetch", &json!({})).is_ok());
+        let err = policy.check_call("fetch", "post", &json!({})).unwrap_err();
+        assert_eq!(err.tool, "post");
+        assert!(err.argument.is_none());
+    }
+
+    #[test]
+    fn test_hidden_tool_call_rejected() {
+        let policy = fs_policy();
+        let err = policy
+            .check_call("filesystem", "move_file", &json!({}))
+            .unwrap_err();
+        assert!(err.to_string().contains("not permitted"));
+    }
+
+    #[test]
+    fn test_path_under_root() {
+        let policy = fs_policy();
+        assert!(policy
+            .check_call(
+                "filesystem",
+                "write_file",
+                &json!({"path": "/home/agent/out/report.md"})
+            )
+            .is_ok());
+
+        let err = policy
+            .check_call("filesystem", "write_file", &json!({"path": "/etc/passwd"}))
+            .unwrap_err();
+        assert_eq!(err.argument.as_deref(), Some("path"));
+    }
+
+    #[test]
+    fn test_path_traversal_rejected() {
+        let policy = fs_policy();
+        assert!(policy
+            .check_call(
+                "filesystem",
+                "write_file",
+                &json!({"path": "/home/agent/out/../../../etc/shadow"})
+            )
+            .is_err());
+        // Prefix match must be component-wise
+        assert!(policy
+            .check_call(
+                "filesystem",
+                "write_file",
+                &json!({"path": "/home/agent/output/x"})
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_path_array_argument() {
+        let policy = fs_policy();
+        assert!(policy
+            .check_call(
+                "filesystem",
+                "read_multiple_files",
+                &json!({"paths": ["/home/agent/a", "/home/agent/b"]})
+            )
+            .is_ok());
+        assert!(policy
+            .check_call(
+                "filesystem",
+                "read_multiple_files",
+                &json!({"paths": ["/home/agent/a", "/root/.ssh/id_rsa"]})
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_url_host_constraint() {
+        let constraint = ArgumentConstraint::UrlHost {
+            argument: "url".to_string(),
+            hosts: vec!["docs.rs".to_string(), "*.github.com".to_string()],
+        };
+
+        assert!(constraint
+            .check_value(&json!("https://docs.rs/serde"))
+            .is_ok());
+        assert!(constraint
+            .check_value(&json!("https://api.github.com/repos"))
+            .is_ok());
+        assert!(constraint
+            .check_value(&json!("https://github.com/"))
+            .is_err());
+        assert!(constraint
+            .check_value(&json!("https://evilgithub.com/"))
+            .is_err());
+        assert!(constraint
+            .check_value(&json!("https://docs.rs.evil.com/"))
+            .is_err());
+        assert!(constraint.check_value(&json!("not a url")).is_err());
+    }
+
+    #[test]
+    fn test_one_of_and_max_length() {
+        let one_of = ArgumentConstraint::OneOf {
+            argument: "method".to_string(),
+            values: vec![json!("GET"), json!("HEAD")],
+        };
+        assert!(one_of.check_value(&json!("GET")).is_ok());
+        assert!(one_of.check_value(&json!("POST")).is_err());
+
+        let max_len = ArgumentConstraint::MaxLength {
+            argument: "query".to_string(),
+            max: 3,
+        };
+        assert!(max_len.check_value(&json!("abc")).is_ok());
+        assert!(max_len.check_value(&json!("abcd")).is_err());
+    }
+
+    #[test]
+    fn test_missing_argument_not_violation() {
+        let policy = fs_policy();
+        assert!(policy
+            .check_call("filesystem", "write_file", &json!({"content": "x"}))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_policy_from_json() {
+        let policy: ToolPolicy = serde_json::from_value(json!({
+            "servers": {
+                "fetch": {
+                    "argument_constraints": {
+                        "fetch": [{ "type": "url_host", "argument": "url", "hosts": ["example.com"] }]
+                    }
+                }
+            }
+        }))
+        .unwrap();
+
+        assert!(policy
+            .check_call("fetch", "fetch", &json!({"url": "https://example.com/"}))
+            .is_ok());
+        assert!(policy
+            .check_call("fetch", "fetch", &json!({"url": "https://example.org/"}))
+            .is_err());
+    }
+
+    #[test]
+    fn test_normalize_path() {
+        assert_eq!(
+            normalize_path(Path::new("/a/./b/../c")),
+            Some(PathBuf::from("/a/c"))
+        );
+        assert_eq!(normalize_path(Path::new("/..")), None);
+        assert_eq!(normalize_path(Path::new("a/../..")), None);
+    }
+}